@@ -0,0 +1,70 @@
+#[cfg(feature = "remote-art")]
+pub mod remote;
+
+use eyre::Result;
+
+use crate::cache::Cache;
+#[cfg(feature = "remote-art")]
+use crate::config::ArtSource;
+
+const VERSION: u32 = 1;
+
+pub struct ArtCache {
+    cache: Cache,
+}
+
+impl ArtCache {
+    pub fn new(cache: Cache) -> Self {
+        Self { cache }
+    }
+
+    pub fn get(&self, artist: &str, album: &str) -> Option<Vec<u8>> {
+        self.cache.load(&key(artist, album), VERSION)
+    }
+
+    pub fn put(&self, artist: &str, album: &str, data: &[u8]) -> Result<()> {
+        self.cache.store(&key(artist, album), VERSION, data)
+    }
+}
+
+// covers the server has none of, from the cache when they were fetched
+// before
+#[cfg(feature = "remote-art")]
+pub struct Fallback {
+    cache: Option<ArtCache>,
+    remote: remote::RemoteArt,
+}
+
+#[cfg(feature = "remote-art")]
+impl Fallback {
+    pub fn new(source: ArtSource, cache: Option<Cache>) -> Self {
+        Self {
+            cache: cache.map(ArtCache::new),
+            remote: remote::RemoteArt::new(source),
+        }
+    }
+
+    pub async fn fetch(&self, artist: &str, album: &str) -> Result<Option<Vec<u8>>> {
+        if let Some(data) = self.cache.as_ref().and_then(|c| c.get(artist, album)) {
+            return Ok(Some(data));
+        }
+
+        let data = self.remote.fetch(artist, album).await?;
+        if let (Some(cache), Some(data)) = (&self.cache, &data) {
+            // only costs a download next time
+            let _ = cache.put(artist, album, data);
+        }
+        Ok(data)
+    }
+}
+
+// fnv-1a, stable across builds unlike the std hasher
+fn key(artist: &str, album: &str) -> String {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in artist.bytes().chain([0]).chain(album.bytes()) {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+
+    format!("art-{hash:016x}")
+}
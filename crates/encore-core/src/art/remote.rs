@@ -0,0 +1,134 @@
+use eyre::Result;
+use reqwest::StatusCode;
+use serde_json::Value;
+
+use crate::config::ArtSource;
+
+pub struct RemoteArt {
+    http: reqwest::Client,
+    source: ArtSource,
+}
+
+impl RemoteArt {
+    pub fn new(source: ArtSource) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            source,
+        }
+    }
+
+    pub async fn fetch(&self, artist: &str, album: &str) -> Result<Option<Vec<u8>>> {
+        match &self.source {
+            // e.g. https://covers.example.com/{artist}/{album}.jpg
+            ArtSource::Template { url } => {
+                let url = url
+                    .replace("{artist}", &encode(artist))
+                    .replace("{album}", &encode(album));
+                self.download(self.http.get(url)).await
+            }
+            ArtSource::Subsonic {
+                url,
+                user,
+                password,
+            } => {
+                let password = password
+                    .bytes()
+                    .map(|b| format!("{b:02x}"))
+                    .collect::<String>();
+                let auth = [
+                    ("u", user.as_str()),
+                    ("p", &format!("enc:{password}")),
+                    ("v", "1.13.0"),
+                    ("c", "encore"),
+                    ("f", "json"),
+                ];
+
+                let json: Value = self
+                    .http
+                    .get(format!("{url}/rest/search3"))
+                    .query(&auth)
+                    .query(&[("query", album), ("albumCount", "20")])
+                    .query(&[("artistCount", "0"), ("songCount", "0")])
+                    .send()
+                    .await?
+                    .error_for_status()?
+                    .json()
+                    .await?;
+
+                let albums = json.pointer("/subsonic-response/searchResult3/album");
+                let Some(id) =
+                    best_match(albums, "artist", artist).and_then(|a| a["coverArt"].as_str())
+                else {
+                    return Ok(None);
+                };
+
+                let req = self
+                    .http
+                    .get(format!("{url}/rest/getCoverArt"))
+                    .query(&auth)
+                    .query(&[("id", id)]);
+                self.download(req).await
+            }
+            ArtSource::Jellyfin { url, api_key } => {
+                let json: Value = self
+                    .http
+                    .get(format!("{url}/Items"))
+                    .header("X-Emby-Token", api_key)
+                    .query(&[("searchTerm", album), ("IncludeItemTypes", "MusicAlbum")])
+                    .query(&[("Recursive", "true"), ("Limit", "20")])
+                    .send()
+                    .await?
+                    .error_for_status()?
+                    .json()
+                    .await?;
+
+                let Some(id) = best_match(json.get("Items"), "AlbumArtist", artist)
+                    .and_then(|a| a["Id"].as_str())
+                else {
+                    return Ok(None);
+                };
+
+                let req = self
+                    .http
+                    .get(format!("{url}/Items/{id}/Images/Primary"))
+                    .header("X-Emby-Token", api_key);
+                self.download(req).await
+            }
+        }
+    }
+
+    async fn download(&self, req: reqwest::RequestBuilder) -> Result<Option<Vec<u8>>> {
+        let res = req.send().await?;
+        if res.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+
+        Ok(Some(res.error_for_status()?.bytes().await?.to_vec()))
+    }
+}
+
+fn best_match<'a>(albums: Option<&'a Value>, key: &str, artist: &str) -> Option<&'a Value> {
+    let albums = albums?.as_array()?;
+    albums
+        .iter()
+        .find(|album| {
+            album[key]
+                .as_str()
+                .is_some_and(|a| a.eq_ignore_ascii_case(artist))
+        })
+        .or(albums.first())
+}
+
+fn encode(s: &str) -> String {
+    let mut encoded = String::with_capacity(s.len());
+    for byte in s.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{byte:02X}")),
+        }
+    }
+
+    encoded
+}
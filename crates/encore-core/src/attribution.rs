@@ -0,0 +1,110 @@
+use std::{
+    collections::{HashSet, VecDeque},
+    time::{Duration, SystemTime},
+};
+
+use encore_mpd::Subsystem;
+use tokio::time::Instant;
+
+const WINDOW: Duration = Duration::from_secs(2);
+const HISTORY: usize = 100;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Origin {
+    Local,
+    External,
+}
+
+#[derive(Debug)]
+pub struct Change {
+    pub at: SystemTime,
+    pub subsystem: Subsystem,
+    pub origin: Origin,
+}
+
+// every command we issue is expected to come back as an idle event on the
+// subsystems it touches; changes nobody here asked for came from elsewhere
+#[derive(Default)]
+pub struct Attribution {
+    pending: VecDeque<(Subsystem, Instant)>,
+    pub history: VecDeque<Change>,
+}
+
+impl Attribution {
+    pub fn issued(&mut self, subsystems: &[Subsystem]) {
+        let now = Instant::now();
+        self.pending
+            .extend(subsystems.iter().map(|subsystem| (*subsystem, now)));
+    }
+
+    pub fn classify(&mut self, changed: &HashSet<Subsystem>) -> Vec<&Change> {
+        let now = Instant::now();
+        self.pending
+            .retain(|(_, at)| now.duration_since(*at) < WINDOW);
+
+        for subsystem in changed {
+            let origin = match self.pending.iter().position(|(s, _)| s == subsystem) {
+                Some(i) => {
+                    self.pending.remove(i);
+                    Origin::Local
+                }
+                None => Origin::External,
+            };
+
+            if self.history.len() == HISTORY {
+                self.history.pop_front();
+            }
+            self.history.push_back(Change {
+                at: SystemTime::now(),
+                subsystem: *subsystem,
+                origin,
+            });
+        }
+
+        self.history.iter().rev().take(changed.len()).collect()
+    }
+}
+
+// the subsystems the server reports as changed after a command
+pub fn touched(command: &str) -> &'static [Subsystem] {
+    match command {
+        "play" | "playid" | "pause" | "next" | "previous" | "stop" | "seek" | "seekid"
+        | "seekcur" => &[Subsystem::Player],
+        "setvol" | "volume" => &[Subsystem::Mixer],
+        "repeat" | "random" | "single" | "consume" | "crossfade" | "replay_gain_mode" => {
+            &[Subsystem::Options]
+        }
+        "add" | "addid" | "clear" | "delete" | "deleteid" | "move" | "moveid" | "shuffle"
+        | "load" | "findadd" | "searchadd" | "swap" | "swapid" | "prio" | "prioid" | "rangeid"
+        | "addtagid" | "cleartagid" => &[Subsystem::Playlist],
+        "save" | "rm" | "rename" | "playlistadd" | "playlistclear" | "playlistdelete"
+        | "playlistmove" => &[Subsystem::StoredPlaylist],
+        "enableoutput" | "disableoutput" | "toggleoutput" | "outputset" => &[Subsystem::Output],
+        "sticker" => &[Subsystem::Sticker],
+        "newpartition" | "delpartition" | "moveoutput" => &[Subsystem::Partition],
+        "update" | "rescan" => &[Subsystem::Update],
+        _ => &[],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::time::advance;
+
+    use super::*;
+
+    #[tokio::test(start_paused = true)]
+    async fn expects_changes_within_the_window() {
+        let mut attribution = Attribution::default();
+        let changed = HashSet::from([Subsystem::Player]);
+        attribution.issued(touched("pause"));
+        advance(WINDOW / 2).await;
+        assert_eq!(attribution.classify(&changed)[0].origin, Origin::Local);
+
+        // a change that comes too late was somebody else's
+        attribution.issued(touched("pause"));
+        advance(WINDOW).await;
+        assert_eq!(attribution.classify(&changed)[0].origin, Origin::External);
+        assert_eq!(attribution.history.len(), 2);
+    }
+}
@@ -0,0 +1,199 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use eyre::{Context, Result};
+
+use crate::config;
+
+// header: magic, schema version (u32 le), flags, uncompressed length (u64 le)
+const MAGIC: &[u8; 4] = b"ENCC";
+const HEADER_LEN: usize = 17;
+const FLAG_ZSTD: u8 = 1;
+// the most a body may claim to grow to, so a corrupt length cannot have it
+// allocate the world before zstd finds out
+const MAX_RATIO: usize = 256;
+
+pub struct Cache {
+    dir: PathBuf,
+    compress: bool,
+}
+
+impl Cache {
+    pub fn new(dir: impl Into<PathBuf>, compress: bool) -> Self {
+        Self {
+            dir: dir.into(),
+            compress,
+        }
+    }
+
+    // under the user's cache directory, unless the config turns it off
+    pub fn open(config: &config::Cache) -> Option<Self> {
+        let dir = dirs::cache_dir()?.join("encore");
+        config.enabled.then(|| Self::new(dir, config.compress))
+    }
+
+    pub fn load(&self, name: &str, version: u32) -> Option<Vec<u8>> {
+        let path = self.path(name);
+        let data = fs::read(&path).ok()?;
+        let payload = decode(&data, version);
+        if payload.is_none() {
+            let _ = fs::remove_file(&path);
+        }
+
+        payload
+    }
+
+    pub fn store(&self, name: &str, version: u32, payload: &[u8]) -> Result<()> {
+        let mut data = Vec::with_capacity(HEADER_LEN + payload.len());
+        data.extend_from_slice(MAGIC);
+        data.extend_from_slice(&version.to_le_bytes());
+        data.push(if self.compress { FLAG_ZSTD } else { 0 });
+        data.extend_from_slice(&(payload.len() as u64).to_le_bytes());
+
+        if self.compress {
+            zstd::stream::copy_encode(payload, &mut data, 0)?;
+        } else {
+            data.extend_from_slice(payload);
+        }
+
+        fs::create_dir_all(&self.dir)?;
+        let path = self.path(name);
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, data)
+            .and_then(|_| fs::rename(&tmp, &path))
+            .with_context(|| format!("Failed to write cache {}", path.display()))
+    }
+
+    // a list the server sent, for as long as its database was not updated
+    pub fn load_list(&self, name: &str, version: u32, db_update: u64) -> Option<Vec<String>> {
+        let payload = self.load(name, version)?;
+        let (stamp, items) = payload.split_first_chunk::<8>()?;
+        if u64::from_le_bytes(*stamp) != db_update {
+            return None;
+        }
+
+        let items = std::str::from_utf8(items).ok()?;
+        Some(items.split_terminator('\0').map(String::from).collect())
+    }
+
+    // the protocol has no way to send a NUL, so one ends each item
+    pub fn store_list(
+        &self,
+        name: &str,
+        version: u32,
+        db_update: u64,
+        items: &[String],
+    ) -> Result<()> {
+        let mut payload = db_update.to_le_bytes().to_vec();
+        for item in items {
+            payload.extend_from_slice(item.as_bytes());
+            payload.push(0);
+        }
+        self.store(name, version, &payload)
+    }
+
+    fn path(&self, name: &str) -> PathBuf {
+        self.dir.join(Path::new(name).with_extension("cache"))
+    }
+}
+
+fn decode(data: &[u8], version: u32) -> Option<Vec<u8>> {
+    if data.len() < HEADER_LEN || &data[..4] != MAGIC {
+        return None;
+    }
+
+    if u32::from_le_bytes(data[4..8].try_into().ok()?) != version {
+        return None;
+    }
+
+    let flags = data[8];
+    let len = usize::try_from(u64::from_le_bytes(data[9..17].try_into().ok()?)).ok()?;
+    let body = &data[HEADER_LEN..];
+
+    let payload = if flags & FLAG_ZSTD != 0 {
+        if len > body.len().saturating_mul(MAX_RATIO) {
+            return None;
+        }
+        zstd::bulk::decompress(body, len).ok()?
+    } else {
+        body.to_vec()
+    };
+
+    (payload.len() == len).then_some(payload)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use super::*;
+
+    fn cache(name: &str, compress: bool) -> Cache {
+        let dir = env::temp_dir().join(format!("encore-cache-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        Cache::new(dir, compress)
+    }
+
+    #[test]
+    fn round_trips() {
+        for compress in [false, true] {
+            let cache = cache(&format!("round-trip-{compress}"), compress);
+            let payload = b"artist\0artist\0artist".repeat(100);
+            cache.store("artists", 1, &payload).unwrap();
+            assert_eq!(cache.load("artists", 1), Some(payload));
+
+            // another schema is a miss
+            assert_eq!(cache.load("artists", 2), None);
+            assert_eq!(cache.load("albums", 1), None);
+            fs::remove_dir_all(&cache.dir).unwrap();
+        }
+    }
+
+    #[test]
+    fn lists_go_stale_with_the_database() {
+        let cache = cache("lists", true);
+        let artists = vec!["Björk".to_string(), String::new(), "Sigur Rós".into()];
+        cache.store_list("artists", 1, 1700, &artists).unwrap();
+        assert_eq!(cache.load_list("artists", 1, 1700), Some(artists));
+        assert_eq!(cache.load_list("artists", 1, 1800), None);
+
+        cache.store_list("artists", 1, 1800, &[]).unwrap();
+        assert_eq!(cache.load_list("artists", 1, 1800), Some(Vec::new()));
+        cache
+            .store_list("artists", 1, 1800, &[String::new()])
+            .unwrap();
+        assert_eq!(
+            cache.load_list("artists", 1, 1800),
+            Some(vec![String::new()])
+        );
+        fs::remove_dir_all(&cache.dir).unwrap();
+    }
+
+    #[test]
+    fn refuses_corrupt_headers() {
+        let cache = cache("corrupt", true);
+        cache.store("artists", 1, b"payload").unwrap();
+        let path = cache.path("artists");
+        let mut data = fs::read(&path).unwrap();
+
+        // a length far beyond what the body can hold is not believed
+        data[9..17].copy_from_slice(&u64::MAX.to_le_bytes());
+        assert_eq!(decode(&data, 1), None);
+        data[9..17].copy_from_slice(&(1u64 << 40).to_le_bytes());
+        assert_eq!(decode(&data, 1), None);
+        // nor one that is off
+        data[9..17].copy_from_slice(&6u64.to_le_bytes());
+        assert_eq!(decode(&data, 1), None);
+
+        assert_eq!(decode(b"ENCC", 1), None);
+        assert_eq!(decode(b"JUNKJUNKJUNKJUNKJUNK", 1), None);
+
+        // and the file that held it goes
+        fs::write(&path, &data).unwrap();
+        assert_eq!(cache.load("artists", 1), None);
+        assert!(!path.exists());
+        fs::remove_dir_all(&cache.dir).unwrap();
+    }
+}
@@ -0,0 +1,568 @@
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use eyre::{Context, Result};
+use serde::{Deserialize, Deserializer};
+
+use crate::{connection::Endpoint, fifo::Format};
+
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub host: Option<String>,
+    // servers by name, for `:connect`
+    pub hosts: BTreeMap<String, String>,
+    // settings of their own for some of those servers
+    pub profiles: Profiles,
+    pub timeout: Timeouts,
+    // percentage points per volume key press
+    pub volume_step: u8,
+    pub art: Graphics,
+    // where the server's music directory is mounted locally, for the files
+    // that sit next to the tracks
+    #[serde(deserialize_with = "dir")]
+    pub music_dir: Option<PathBuf>,
+    #[serde(deserialize_with = "dir")]
+    pub lyrics_dir: Option<PathBuf>,
+    pub visualizer: Visualizer,
+    pub theme: Theme,
+    pub queue: Queue,
+    pub layout: Layout,
+    pub notifications: Notifications,
+    pub power: Power,
+    // playback events appended as JSON lines, or sent to whoever is
+    // connected to a socket given as `unix:/path`
+    #[serde(deserialize_with = "dir")]
+    pub event_log: Option<PathBuf>,
+    pub confirm: Confirm,
+    pub seek: Seek,
+    pub cache: Cache,
+    // where to look for the covers the server has none of
+    pub remote_art: Option<ArtSource>,
+    pub remote_control: RemoteControl,
+    // where the file is, or would be, read from; themes are looked up next
+    // to it
+    #[serde(skip)]
+    pub path: Option<PathBuf>,
+}
+
+// a built-in theme or one from `themes/<name>.toml`, with single elements
+// restyled on top
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct Theme {
+    pub name: Option<String>,
+    #[serde(flatten)]
+    pub styles: BTreeMap<String, Spec>,
+}
+
+// colors by name, `#rrggbb` or palette index, and modifiers like `bold`
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Spec {
+    pub fg: Option<String>,
+    pub bg: Option<String>,
+    pub modifiers: Vec<String>,
+}
+
+// MPD's fifo output, read for the visualizer
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Visualizer {
+    #[serde(deserialize_with = "dir")]
+    pub fifo: Option<PathBuf>,
+    pub format: Format,
+    pub mode: Mode,
+    // spectrum bars, fewer when the pane is narrower
+    pub bars: usize,
+    // how much of a bar is left after each frame as it falls, 0 to 1
+    pub smoothing: f32,
+    // bottom to top
+    pub gradient: Vec<Rgb>,
+}
+
+impl Default for Visualizer {
+    fn default() -> Self {
+        Self {
+            fifo: None,
+            format: Format::default(),
+            mode: Mode::default(),
+            bars: 32,
+            smoothing: 0.8,
+            gradient: vec![
+                Rgb(0x3f, 0xb9, 0x50),
+                Rgb(0xe3, 0xb3, 0x41),
+                Rgb(0xf8, 0x51, 0x49),
+            ],
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Mode {
+    #[default]
+    Wave,
+    Spectrum,
+}
+
+// `#rrggbb`
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Rgb(pub u8, pub u8, pub u8);
+
+impl<'de> Deserialize<'de> for Rgb {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        let hex = s.strip_prefix('#').filter(|hex| hex.len() == 6);
+        let channel = |i: usize| u8::from_str_radix(hex?.get(i..i + 2)?, 16).ok();
+        match (channel(0), channel(2), channel(4)) {
+            (Some(r), Some(g), Some(b)) => Ok(Self(r, g, b)),
+            _ => Err(serde::de::Error::custom(format!(
+                "invalid color {s:?}, expected #rrggbb"
+            ))),
+        }
+    }
+}
+
+// which panes show from the start and how much room they get
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Layout {
+    pub art: bool,
+    pub visualizer: bool,
+    pub split: Split,
+    // percent of the screen for the cover, sized to fit it when unset
+    pub art_size: Option<u16>,
+    // percent of the lists' height
+    pub visualizer_size: u16,
+}
+
+impl Default for Layout {
+    fn default() -> Self {
+        Self {
+            art: true,
+            visualizer: false,
+            split: Split::default(),
+            art_size: None,
+            visualizer_size: 33,
+        }
+    }
+}
+
+// which edits that cannot be undone ask y/n first
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Confirm {
+    pub clear: bool,
+    pub delete_playlist: bool,
+    // removing at least this many tracks from the queue at once, 0 never asks
+    pub remove: usize,
+}
+
+impl Default for Confirm {
+    fn default() -> Self {
+        Self {
+            clear: true,
+            delete_playlist: true,
+            remove: 10,
+        }
+    }
+}
+
+// how far the arrow keys seek, and with shift
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Seek {
+    #[serde(deserialize_with = "secs")]
+    pub step: Duration,
+    #[serde(deserialize_with = "secs")]
+    pub long_step: Duration,
+}
+
+impl Default for Seek {
+    fn default() -> Self {
+        Self {
+            step: Duration::from_secs(5),
+            long_step: Duration::from_secs(60),
+        }
+    }
+}
+
+// what is slow to read from the server kept on disk between runs, until the
+// server's database changes
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Cache {
+    pub enabled: bool,
+    pub compress: bool,
+}
+
+impl Default for Cache {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            compress: true,
+        }
+    }
+}
+
+// a url with `{artist}` and `{album}` in it, or a media server to search
+#[derive(Clone, Debug, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase", deny_unknown_fields)]
+pub enum ArtSource {
+    Template {
+        url: String,
+    },
+    Subsonic {
+        url: String,
+        user: String,
+        password: String,
+    },
+    Jellyfin {
+        url: String,
+        api_key: String,
+    },
+}
+
+// a desktop notification whenever another song starts
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Notifications {
+    pub enabled: bool,
+    // the cover as the notification's picture, once it is fetched
+    pub cover: bool,
+    // the least time between two, skipping through songs only tells the last
+    #[serde(deserialize_with = "secs")]
+    pub every: Duration,
+}
+
+impl Default for Notifications {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            cover: true,
+            every: Duration::from_secs(3),
+        }
+    }
+}
+
+// how playback and the system's sleep get along
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct Power {
+    // holds the suspend back until the song is paused
+    pub pause_on_suspend: bool,
+    // plays again on waking what was paused for the suspend
+    pub resume_on_wake: bool,
+    // keeps the screen and the system awake while playing
+    pub inhibit_idle: bool,
+}
+
+// under the names of `[hosts]`
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(transparent)]
+pub struct Profiles(BTreeMap<String, Profile>);
+
+#[derive(Clone, Copy, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Profile {
+    // in place of `[power]`, not on top of it
+    pub power: Option<Power>,
+}
+
+impl Profiles {
+    // the power settings of whichever named server the endpoint is
+    pub fn power(&self, hosts: &BTreeMap<String, String>, endpoint: &Endpoint) -> Option<Power> {
+        hosts.iter().find_map(|(name, host)| {
+            let host = Endpoint::parse(host)?;
+            let power = self.0.get(name)?.power;
+            power.filter(|_| host.target == endpoint.target)
+        })
+    }
+}
+
+// the HTTP remote control, served once it has an address
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RemoteControl {
+    pub addr: Option<SocketAddr>,
+    // what every request has to carry, the page takes it after a `#`
+    pub token: Option<String>,
+}
+
+// the cover beside the lists or above them
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Split {
+    #[default]
+    Horizontal,
+    Vertical,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Queue {
+    // left to right, like ncmpcpp's song_columns
+    pub columns: Vec<Column>,
+}
+
+impl Default for Queue {
+    fn default() -> Self {
+        let column = |tag, width| Column {
+            tag,
+            width,
+            align: Align::Left,
+        };
+        Self {
+            columns: vec![
+                column(Tag::Title, Width::Fill(3)),
+                column(Tag::Artist, Width::Fill(2)),
+                column(Tag::Album, Width::Fill(2)),
+                column(Tag::Duration, Width::Cells(6)),
+            ],
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Column {
+    pub tag: Tag,
+    #[serde(default)]
+    pub width: Width,
+    #[serde(default)]
+    pub align: Align,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Tag {
+    // the track number without the total
+    Track,
+    // or the stream's name, or the file's
+    Title,
+    Artist,
+    AlbumArtist,
+    Album,
+    Disc,
+    Date,
+    Genre,
+    Duration,
+    File,
+    // stars from the `rating` sticker
+    Rating,
+}
+
+// cells as a number, `N%` of the table or `Nfr`, a share of what is left
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Width {
+    Cells(u16),
+    Percent(u16),
+    Fill(u16),
+}
+
+impl Default for Width {
+    fn default() -> Self {
+        Self::Fill(1)
+    }
+}
+
+impl<'de> Deserialize<'de> for Width {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Raw {
+            Cells(u16),
+            Text(String),
+        }
+
+        let s = match Raw::deserialize(deserializer)? {
+            Raw::Cells(cells) => return Ok(Self::Cells(cells)),
+            Raw::Text(s) => s,
+        };
+        let width = match (s.strip_suffix('%'), s.strip_suffix("fr")) {
+            (Some(n), _) => n.parse().ok().filter(|n| *n <= 100).map(Self::Percent),
+            (_, Some(n)) => n.parse().ok().map(Self::Fill),
+            _ => s.parse().ok().map(Self::Cells),
+        };
+        width.ok_or_else(|| {
+            serde::de::Error::custom(format!("invalid width {s:?}, expected cells, N% or Nfr"))
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Align {
+    #[default]
+    Left,
+    Center,
+    Right,
+}
+
+// how covers are drawn, auto goes by what the terminal is known to support
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Graphics {
+    #[default]
+    Auto,
+    Kitty,
+    Sixel,
+    Iterm2,
+    Ueberzug,
+    Blocks,
+    Off,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            host: None,
+            hosts: BTreeMap::new(),
+            profiles: Profiles::default(),
+            timeout: Timeouts::default(),
+            volume_step: 5,
+            art: Graphics::default(),
+            music_dir: None,
+            lyrics_dir: None,
+            visualizer: Visualizer::default(),
+            theme: Theme::default(),
+            queue: Queue::default(),
+            layout: Layout::default(),
+            notifications: Notifications::default(),
+            power: Power::default(),
+            event_log: None,
+            confirm: Confirm::default(),
+            seek: Seek::default(),
+            cache: Cache::default(),
+            remote_art: None,
+            remote_control: RemoteControl::default(),
+            path: None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Timeouts {
+    #[serde(deserialize_with = "positive_secs")]
+    pub connect: Duration,
+    #[serde(deserialize_with = "positive_secs")]
+    pub command: Duration,
+    #[serde(deserialize_with = "positive_secs")]
+    pub keepalive: Duration,
+}
+
+impl Default for Timeouts {
+    fn default() -> Self {
+        Self {
+            connect: Duration::from_secs(5),
+            command: Duration::from_secs(10),
+            keepalive: Duration::from_secs(60),
+        }
+    }
+}
+
+impl Config {
+    pub fn path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("encore").join("config.toml"))
+    }
+
+    pub fn power_for(&self, endpoint: &Endpoint) -> Power {
+        self.profiles
+            .power(&self.hosts, endpoint)
+            .unwrap_or(self.power)
+    }
+
+    // a missing file is not an error, every setting has a default
+    pub fn load(path: Option<PathBuf>) -> Result<Self> {
+        let Some(path) = path.or_else(Self::path) else {
+            return Ok(Self::default());
+        };
+
+        let text = match fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                return Ok(Self {
+                    path: Some(path),
+                    ..Self::default()
+                })
+            }
+            Err(e) => return Err(e).context(format!("Failed to read {}", path.display())),
+        };
+
+        let mut config: Self =
+            toml::from_str(&text).with_context(|| format!("Failed to parse {}", path.display()))?;
+        config.path = Some(path);
+        Ok(config)
+    }
+}
+
+// the elements a theme file restyles, the same tables as under `[theme]`
+pub fn load_theme(path: &Path) -> Result<BTreeMap<String, Spec>> {
+    let text = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read theme {}", path.display()))?;
+    toml::from_str(&text).with_context(|| format!("Failed to parse theme {}", path.display()))
+}
+
+fn secs<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+    let secs = f64::deserialize(deserializer)?;
+    Duration::try_from_secs_f64(secs).map_err(serde::de::Error::custom)
+}
+
+// a timeout of nothing fails every attempt before it is made, and a
+// keepalive of nothing pings without pause
+fn positive_secs<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+    match secs(deserializer)? {
+        Duration::ZERO => Err(serde::de::Error::custom("expected more than 0 seconds")),
+        secs => Ok(secs),
+    }
+}
+
+// a leading `~/` stands for the home directory
+fn dir<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<PathBuf>, D::Error> {
+    let path = PathBuf::deserialize(deserializer)?;
+    Ok(Some(match (path.strip_prefix("~"), dirs::home_dir()) {
+        (Ok(rest), Some(home)) => home.join(rest),
+        _ => path,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn takes_power_from_the_profile() {
+        let config: Config = toml::from_str(
+            "[power]\npause_on_suspend = true\n\
+             [hosts]\nhome = \"home:6601\"\nwork = \"work\"\n\
+             [profiles.home.power]\ninhibit_idle = true\n\
+             [profiles.work]",
+        )
+        .unwrap();
+        let power = |host| config.power_for(&Endpoint::parse(host).unwrap());
+
+        assert!(power("home:6601").inhibit_idle && !power("home:6601").pause_on_suspend);
+        assert_eq!(power("work:6600"), config.power);
+        assert_eq!(power("home"), config.power);
+    }
+
+    #[test]
+    fn refuses_zero_timeouts() {
+        let config: Config = toml::from_str("[timeout]\nconnect = 0.5\ncommand = 2").unwrap();
+        assert_eq!(config.timeout.connect, Duration::from_millis(500));
+        assert_eq!(config.timeout.command, Duration::from_secs(2));
+
+        for field in ["connect", "command", "keepalive"] {
+            let error = toml::from_str::<Config>(&format!("[timeout]\n{field} = 0"));
+            let error = error.unwrap_err().to_string();
+            assert!(error.contains("expected more than 0 seconds"), "{error}");
+        }
+    }
+}
@@ -0,0 +1,367 @@
+use std::{env, fmt, net::SocketAddr, path::PathBuf, time::Duration};
+
+#[cfg(unix)]
+use encore_mpd::connect_unix;
+use encore_mpd::Client;
+use eyre::{bail, Result};
+use tokio::{
+    io::{AsyncRead, AsyncWrite},
+    net::{lookup_host, TcpStream},
+    task::JoinSet,
+    time::sleep,
+};
+
+use crate::ssh;
+#[cfg(feature = "tls")]
+use crate::tls;
+
+const ATTEMPT_DELAY: Duration = Duration::from_millis(250);
+
+pub type Connection = Client<Box<dyn AsyncRead + Send + Unpin>, Box<dyn AsyncWrite + Send + Unpin>>;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Target {
+    Tcp { host: String, port: u16 },
+    Unix(PathBuf),
+    // MPD at `port` on the loopback of an ssh destination
+    Ssh { destination: String, port: u16 },
+    Tls { host: String, port: u16 },
+}
+
+#[derive(Clone, Default, Eq, PartialEq)]
+pub struct Endpoint {
+    pub target: Target,
+    pub password: Option<String>,
+}
+
+impl Default for Target {
+    fn default() -> Self {
+        Target::Tcp {
+            host: "localhost".into(),
+            port: 6600,
+        }
+    }
+}
+
+impl Target {
+    // `host`, `host:port`, `[v6]:port`, an absolute socket path, an abstract
+    // `@name`, `ssh://[user@]host[:ssh port][/port]` or `mpds://host[:port]`
+    pub fn parse(s: &str) -> Option<Self> {
+        let s = s.trim();
+        if s.is_empty() {
+            return None;
+        }
+
+        if let Some(rest) = s.strip_prefix("mpds://") {
+            return match Target::parse(rest)? {
+                Target::Tcp { host, port } => Some(Target::Tls { host, port }),
+                _ => None,
+            };
+        }
+
+        if let Some(rest) = s.strip_prefix("ssh://") {
+            let (host, port) = match rest.split_once('/') {
+                Some((host, port)) => (host, port.parse().ok()?),
+                None => (rest, 6600),
+            };
+
+            if host.is_empty() {
+                return None;
+            }
+
+            return Some(Target::Ssh {
+                destination: format!("ssh://{host}"),
+                port,
+            });
+        }
+
+        if s.starts_with(['/', '@']) {
+            return Some(Target::Unix(s.into()));
+        }
+
+        if let Some(rest) = s.strip_prefix('[') {
+            let (host, port) = rest.split_once(']')?;
+            let port = match port {
+                "" => 6600,
+                port => port.strip_prefix(':')?.parse().ok()?,
+            };
+
+            return Some(Target::Tcp {
+                host: host.into(),
+                port,
+            });
+        }
+
+        // a bare IPv6 literal has no room for a port
+        if s.matches(':').count() > 1 {
+            return Some(Target::Tcp {
+                host: s.into(),
+                port: 6600,
+            });
+        }
+
+        Some(match s.rsplit_once(':') {
+            Some((host, port)) => Target::Tcp {
+                host: host.into(),
+                port: port.parse().ok()?,
+            },
+            None => Target::Tcp {
+                host: s.into(),
+                port: 6600,
+            },
+        })
+    }
+
+    // the MPD port, for targets that have one
+    pub fn port_mut(&mut self) -> Option<&mut u16> {
+        match self {
+            Target::Tcp { port, .. } | Target::Ssh { port, .. } | Target::Tls { port, .. } => {
+                Some(port)
+            }
+            Target::Unix(_) => None,
+        }
+    }
+}
+
+impl Endpoint {
+    // `[password@]target` like MPD_HOST for mpc, where the target may be left
+    // out after a password and a leading `~/` in a socket path is expanded
+    pub fn parse(s: &str) -> Option<Self> {
+        let (password, target) = match s.trim().split_once('@') {
+            Some((password, target)) if !password.is_empty() && !password.contains("://") => {
+                (Some(password.into()), target)
+            }
+            _ => (None, s),
+        };
+
+        let target = match target.trim() {
+            "" if password.is_some() => Target::default(),
+            target => match target.strip_prefix("~/") {
+                Some(path) => Target::Unix(dirs::home_dir()?.join(path)),
+                None => Target::parse(target)?,
+            },
+        };
+
+        Some(Self { target, password })
+    }
+
+    // MPD_HOST and MPD_PORT as understood by mpc, on top of `fallback`
+    pub fn from_env(fallback: Self) -> Self {
+        let mut endpoint = env::var("MPD_HOST")
+            .ok()
+            .and_then(|host| Self::parse(&host))
+            .unwrap_or(fallback);
+
+        let port = env::var("MPD_PORT").ok().and_then(|port| port.parse().ok());
+        if let (Some(p), Some(port)) = (endpoint.target.port_mut(), port) {
+            *p = port;
+        }
+
+        endpoint
+    }
+}
+
+// keeps the password out of logs and error reports
+impl fmt::Debug for Endpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Endpoint")
+            .field("target", &self.target)
+            .field("password", &self.password.as_ref().map(|_| "***"))
+            .finish()
+    }
+}
+
+impl fmt::Display for Endpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.target.fmt(f)
+    }
+}
+
+impl fmt::Display for Target {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Target::Tcp { host, port } if host.contains(':') => write!(f, "[{host}]:{port}"),
+            Target::Tcp { host, port } => write!(f, "{host}:{port}"),
+            Target::Unix(path) => write!(f, "{}", path.display()),
+            Target::Ssh {
+                destination,
+                port: 6600,
+            } => write!(f, "{destination}"),
+            Target::Ssh { destination, port } => write!(f, "{destination}/{port}"),
+            Target::Tls { host, port } if host.contains(':') => write!(f, "mpds://[{host}]:{port}"),
+            Target::Tls { host, port } => write!(f, "mpds://{host}:{port}"),
+        }
+    }
+}
+
+pub async fn connect(endpoint: &Endpoint) -> Result<Connection> {
+    let mut client = open(&endpoint.target).await?;
+    if let Some(password) = &endpoint.password {
+        client.password(password).await?;
+    }
+
+    Ok(client)
+}
+
+async fn open(target: &Target) -> Result<Connection> {
+    match target {
+        Target::Tcp { host, port } => {
+            let (r, w) = connect_tcp(host, *port).await?.into_split();
+            Client::init_client(Box::new(r) as _, Box::new(w) as _).await
+        }
+        #[cfg(unix)]
+        Target::Unix(path) => {
+            let (r, w) = connect_unix(path).await?.into_split();
+            Client::init_client(Box::new(r) as _, Box::new(w) as _).await
+        }
+        #[cfg(not(unix))]
+        Target::Unix(path) => bail!(
+            "unix sockets are not supported on this platform: {}",
+            path.display()
+        ),
+        Target::Ssh { destination, port } => ssh::connect(destination, *port).await,
+        #[cfg(feature = "tls")]
+        Target::Tls { host, port } => tls::connect(host, *port).await,
+        #[cfg(not(feature = "tls"))]
+        Target::Tls { .. } => bail!("encore was built without TLS support"),
+    }
+}
+
+// tries every address the host resolves to, starting the next one when the
+// previous fails or has not answered within ATTEMPT_DELAY, so a dead IPv6
+// route does not eat the whole connect timeout (RFC 8305, minus the racing
+// DNS lookups)
+pub(crate) async fn connect_tcp(host: &str, port: u16) -> Result<TcpStream> {
+    let (v6, v4): (Vec<SocketAddr>, Vec<SocketAddr>) = lookup_host((host, port))
+        .await?
+        .partition(SocketAddr::is_ipv6);
+
+    // alternate families so one broken stack costs a single delay
+    let mut addrs = Vec::with_capacity(v6.len() + v4.len());
+    let (mut v6, mut v4) = (v6.into_iter(), v4.into_iter());
+    loop {
+        match (v6.next(), v4.next()) {
+            (None, None) => break,
+            (a, b) => addrs.extend(a.into_iter().chain(b)),
+        }
+    }
+
+    let mut addrs = addrs.into_iter();
+    let mut attempts = JoinSet::new();
+    let mut error = None;
+
+    loop {
+        if let Some(addr) = addrs.next() {
+            attempts.spawn(TcpStream::connect(addr));
+        } else if attempts.is_empty() {
+            break;
+        }
+
+        tokio::select! {
+            Some(result) = attempts.join_next() => match result {
+                Ok(Ok(stream)) => return Ok(stream),
+                Ok(Err(e)) => error = Some(e),
+                Err(e) => error = Some(e.into()),
+            },
+            _ = sleep(ATTEMPT_DELAY), if addrs.len() > 0 => {}
+        }
+    }
+
+    match error {
+        Some(e) => Err(e.into()),
+        None => bail!("{host} did not resolve to any address"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tcp(host: &str, port: u16) -> Target {
+        Target::Tcp {
+            host: host.into(),
+            port,
+        }
+    }
+
+    #[test]
+    fn parse_tcp_targets() {
+        assert_eq!(Target::parse("music"), Some(tcp("music", 6600)));
+        assert_eq!(Target::parse(" music:6601 "), Some(tcp("music", 6601)));
+        assert_eq!(Target::parse("[::1]:6601"), Some(tcp("::1", 6601)));
+        assert_eq!(Target::parse("[::1]"), Some(tcp("::1", 6600)));
+        assert_eq!(Target::parse("fe80::1"), Some(tcp("fe80::1", 6600)));
+        assert_eq!(Target::parse("music:port"), None);
+        assert_eq!(Target::parse("[::1]6601"), None);
+        assert_eq!(Target::parse(""), None);
+    }
+
+    #[test]
+    fn parse_other_targets() {
+        assert_eq!(
+            Target::parse("/run/mpd/socket"),
+            Some(Target::Unix("/run/mpd/socket".into()))
+        );
+        assert_eq!(Target::parse("@mpd"), Some(Target::Unix("@mpd".into())));
+        assert_eq!(
+            Target::parse("ssh://me@music:2222/6601"),
+            Some(Target::Ssh {
+                destination: "ssh://me@music:2222".into(),
+                port: 6601,
+            })
+        );
+        assert_eq!(
+            Target::parse("ssh://music"),
+            Some(Target::Ssh {
+                destination: "ssh://music".into(),
+                port: 6600,
+            })
+        );
+        assert_eq!(Target::parse("ssh://"), None);
+        assert_eq!(
+            Target::parse("mpds://music:6601"),
+            Some(Target::Tls {
+                host: "music".into(),
+                port: 6601,
+            })
+        );
+        assert_eq!(Target::parse("mpds:///run/mpd/socket"), None);
+    }
+
+    #[test]
+    fn targets_display_as_parsed() {
+        for s in [
+            "music:6601",
+            "[::1]:6600",
+            "ssh://music/6601",
+            "mpds://music:6600",
+        ] {
+            assert_eq!(Target::parse(s).unwrap().to_string(), s);
+        }
+    }
+
+    #[test]
+    fn parse_endpoints() {
+        let endpoint = Endpoint::parse("secret@music:6601").unwrap();
+        assert_eq!(endpoint.password.as_deref(), Some("secret"));
+        assert_eq!(endpoint.target, tcp("music", 6601));
+
+        let endpoint = Endpoint::parse("secret@").unwrap();
+        assert_eq!(endpoint.password.as_deref(), Some("secret"));
+        assert_eq!(endpoint.target, Target::default());
+
+        let endpoint = Endpoint::parse("secret@@mpd").unwrap();
+        assert_eq!(endpoint.target, Target::Unix("@mpd".into()));
+
+        // the user belongs to ssh, not to MPD
+        let endpoint = Endpoint::parse("ssh://me@music").unwrap();
+        assert_eq!(endpoint.password, None);
+
+        let endpoint = Endpoint::parse("~/.mpd/socket").unwrap();
+        let home = dirs::home_dir().unwrap();
+        assert_eq!(endpoint.target, Target::Unix(home.join(".mpd/socket")));
+
+        assert!(Endpoint::parse("").is_none());
+        assert!(format!("{:?}", Endpoint::parse("secret@music").unwrap()).contains("***"));
+    }
+}
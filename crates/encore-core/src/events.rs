@@ -0,0 +1,150 @@
+use std::{
+    path::Path,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use encore_mpd::{PlayerState, Status, Track};
+use eyre::Result;
+use serde::Serialize;
+use tokio::{
+    fs::{File, OpenOptions},
+    io::{AsyncWrite, AsyncWriteExt},
+};
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event {
+    Play {
+        pos: usize,
+        file: Option<String>,
+    },
+    Pause {
+        pos: usize,
+    },
+    Stop,
+    Advance {
+        from: usize,
+        to: usize,
+        file: Option<String>,
+    },
+    Skip {
+        from: usize,
+        to: usize,
+        file: Option<String>,
+    },
+    Seek {
+        pos: usize,
+        from: u16,
+        to: u16,
+    },
+    QueueEdit {
+        len: usize,
+    },
+}
+
+#[derive(Serialize)]
+struct Record<'a> {
+    ts: u64,
+    #[serde(flatten)]
+    event: &'a Event,
+}
+
+pub struct EventLog<W> {
+    out: W,
+}
+
+impl EventLog<File> {
+    pub async fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let out = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .await?;
+        Ok(Self::new(out))
+    }
+}
+
+impl<W: AsyncWrite + Unpin> EventLog<W> {
+    pub fn new(out: W) -> Self {
+        Self { out }
+    }
+
+    pub async fn emit(&mut self, event: &Event) -> Result<()> {
+        self.out.write_all(&line(event)?).await?;
+        self.out.flush().await?;
+        Ok(())
+    }
+}
+
+// an event as it is logged, stamped and newline terminated
+pub fn line(event: &Event) -> Result<Vec<u8>> {
+    let ts = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+    let mut line = serde_json::to_vec(&Record { ts, event })?;
+    line.push(b'\n');
+    Ok(line)
+}
+
+// `since` is the wall time between the two statuses, used to tell seeks
+// from normal progress and skips from tracks that played to the end.
+// Queue edits are not visible in the status and are emitted by the caller
+// on playlist idle events.
+pub fn diff(old: &Status, new: &Status, queue: &[Track], since: Duration) -> Vec<Event> {
+    let file = |pos: usize| queue.get(pos).map(|track| track.file.clone());
+    let mut events = Vec::new();
+
+    let (old_song, new_song) = match (&old.song, &new.song) {
+        (_, None) => {
+            if old.state != PlayerState::Stop && new.state == PlayerState::Stop {
+                events.push(Event::Stop);
+            }
+            return events;
+        }
+        (None, Some(new_song)) => {
+            if new.state == PlayerState::Play {
+                events.push(Event::Play {
+                    pos: new_song.pos,
+                    file: file(new_song.pos),
+                });
+            }
+            return events;
+        }
+        (Some(old_song), Some(new_song)) => (old_song, new_song),
+    };
+
+    let expected = old_song.elapsed as u64
+        + if old.state == PlayerState::Play {
+            since.as_secs()
+        } else {
+            0
+        };
+
+    if old_song.pos != new_song.pos {
+        let finished = queue
+            .get(old_song.pos)
+            .is_none_or(|track| expected + 2 >= track.time as u64);
+        let (from, to, file) = (old_song.pos, new_song.pos, file(new_song.pos));
+        events.push(if finished {
+            Event::Advance { from, to, file }
+        } else {
+            Event::Skip { from, to, file }
+        });
+    } else if (new_song.elapsed as u64).abs_diff(expected) > 2 {
+        events.push(Event::Seek {
+            pos: new_song.pos,
+            from: old_song.elapsed,
+            to: new_song.elapsed,
+        });
+    }
+
+    match (&old.state, &new.state) {
+        (PlayerState::Play, PlayerState::Pause) => events.push(Event::Pause { pos: new_song.pos }),
+        (PlayerState::Pause | PlayerState::Stop, PlayerState::Play) => events.push(Event::Play {
+            pos: new_song.pos,
+            file: file(new_song.pos),
+        }),
+        (_, PlayerState::Stop) if old.state != PlayerState::Stop => events.push(Event::Stop),
+        _ => {}
+    }
+
+    events
+}
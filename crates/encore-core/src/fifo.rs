@@ -0,0 +1,166 @@
+use std::{
+    collections::VecDeque,
+    fs::File,
+    io::Read,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    thread,
+    time::Duration,
+};
+
+use serde::{Deserialize, Deserializer};
+
+// about a tenth of a second at 44.1kHz, more than any pane is wide
+const KEEP: usize = 4096;
+const REOPEN: Duration = Duration::from_millis(500);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Bits {
+    Eight,
+    Sixteen,
+    // in a 32 bit container, as MPD writes it
+    TwentyFour,
+    ThirtyTwo,
+    Float,
+}
+
+// `rate:bits:channels` like the `format` of MPD's fifo output
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Format {
+    pub rate: u32,
+    pub bits: Bits,
+    pub channels: u8,
+}
+
+impl Default for Format {
+    fn default() -> Self {
+        Self {
+            rate: 44100,
+            bits: Bits::Sixteen,
+            channels: 2,
+        }
+    }
+}
+
+impl Format {
+    pub fn parse(s: &str) -> Option<Self> {
+        let mut parts = s.trim().split(':');
+        let rate = parts.next()?.parse().ok().filter(|rate| *rate > 0)?;
+        let bits = match parts.next()? {
+            "8" => Bits::Eight,
+            "16" => Bits::Sixteen,
+            "24" => Bits::TwentyFour,
+            "32" => Bits::ThirtyTwo,
+            "f" => Bits::Float,
+            _ => return None,
+        };
+        let channels = parts.next()?.parse().ok().filter(|n| *n > 0)?;
+        if parts.next().is_some() {
+            return None;
+        }
+
+        Some(Self {
+            rate,
+            bits,
+            channels,
+        })
+    }
+
+    fn sample_len(self) -> usize {
+        match self.bits {
+            Bits::Eight => 1,
+            Bits::Sixteen => 2,
+            Bits::TwentyFour | Bits::ThirtyTwo | Bits::Float => 4,
+        }
+    }
+
+    // one sample in native byte order, scaled to -1..1
+    fn sample(self, bytes: &[u8]) -> f32 {
+        match self.bits {
+            Bits::Eight => bytes[0] as i8 as f32 / 128.0,
+            Bits::Sixteen => i16::from_ne_bytes([bytes[0], bytes[1]]) as f32 / 32768.0,
+            Bits::TwentyFour => {
+                let sample = i32::from_ne_bytes(bytes.try_into().unwrap());
+                sample as f32 / 8388608.0
+            }
+            Bits::ThirtyTwo => i32::from_ne_bytes(bytes.try_into().unwrap()) as f32 / 2147483648.0,
+            Bits::Float => f32::from_ne_bytes(bytes.try_into().unwrap()),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Format {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        Self::parse(&s).ok_or_else(|| {
+            serde::de::Error::custom(format!("invalid format {s:?}, expected e.g. 44100:16:2"))
+        })
+    }
+}
+
+// the latest samples MPD wrote to its fifo output, mixed down to mono; a
+// thread of its own waits on the pipe, which blocks until MPD opens it
+pub struct Fifo {
+    pub format: Format,
+    samples: Arc<Mutex<VecDeque<f32>>>,
+}
+
+impl Fifo {
+    pub fn open(path: PathBuf, format: Format) -> Self {
+        let samples = Arc::new(Mutex::new(VecDeque::with_capacity(KEEP)));
+        let shared = Arc::downgrade(&samples);
+        thread::spawn(move || {
+            let frame = format.sample_len() * format.channels as usize;
+            let mut buf = vec![0; frame * 1024];
+            // the reader stops with the last handle to the samples
+            while let Some(samples) = shared.upgrade() {
+                let Ok(mut file) = File::open(&path) else {
+                    drop(samples);
+                    thread::sleep(REOPEN);
+                    continue;
+                };
+
+                let mut filled = 0;
+                loop {
+                    let n = match file.read(&mut buf[filled..]) {
+                        Ok(0) | Err(_) => break,
+                        Ok(n) => n,
+                    };
+                    filled += n;
+
+                    let whole = filled / frame * frame;
+                    let mixed = buf[..whole].chunks_exact(frame).map(|frame| {
+                        let channels = frame.chunks_exact(format.sample_len());
+                        let sum: f32 = channels.map(|bytes| format.sample(bytes)).sum();
+                        sum / format.channels as f32
+                    });
+                    {
+                        let mut samples = samples.lock().unwrap();
+                        samples.extend(mixed);
+                        let excess = samples.len().saturating_sub(KEEP);
+                        samples.drain(..excess);
+                    }
+                    buf.copy_within(whole..filled, 0);
+                    filled -= whole;
+
+                    if Arc::strong_count(&samples) == 1 {
+                        return;
+                    }
+                }
+
+                // the writer went away, wait for it to come back
+                drop(samples);
+                thread::sleep(REOPEN);
+            }
+        });
+
+        Self { format, samples }
+    }
+
+    // the last `n` samples, oldest first; fewer until that many came in
+    pub fn latest(&self, n: usize) -> Vec<f32> {
+        let samples = self.samples.lock().unwrap();
+        let skip = samples.len().saturating_sub(n);
+        samples.iter().skip(skip).copied().collect()
+    }
+}
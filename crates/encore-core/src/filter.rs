@@ -0,0 +1,102 @@
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Chip {
+    Contains { tag: String, value: String },
+    Equals { tag: String, value: String },
+    Text(String),
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Query {
+    pub chips: Vec<Chip>,
+}
+
+impl Chip {
+    // `artist:foo` matches a substring, `artist=foo` the exact tag value and
+    // anything else is searched for in every tag
+    pub fn parse(input: &str) -> Option<Self> {
+        let input = input.trim();
+        if input.is_empty() {
+            return None;
+        }
+
+        let split = input.find([':', '=']).filter(|&i| {
+            i > 0
+                && input[..i]
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_')
+        });
+
+        Some(match split {
+            Some(i) => {
+                let tag = input[..i].to_lowercase();
+                let value = input[i + 1..].trim().into();
+                if input.as_bytes()[i] == b'=' {
+                    Chip::Equals { tag, value }
+                } else {
+                    Chip::Contains { tag, value }
+                }
+            }
+            None => Chip::Text(input.into()),
+        })
+    }
+
+    pub fn label(&self) -> String {
+        match self {
+            Chip::Contains { tag, value } => format!("{tag}:{value}"),
+            Chip::Equals { tag, value } => format!("{tag}={value}"),
+            Chip::Text(value) => value.clone(),
+        }
+    }
+
+    fn expression(&self) -> String {
+        match self {
+            Chip::Contains { tag, value } => format!("({tag} contains {})", quote(value)),
+            Chip::Equals { tag, value } => format!("({tag} == {})", quote(value)),
+            Chip::Text(value) => format!("(any contains {})", quote(value)),
+        }
+    }
+}
+
+impl Query {
+    pub fn add(&mut self, input: &str) -> bool {
+        match Chip::parse(input) {
+            Some(chip) if !self.chips.contains(&chip) => {
+                self.chips.push(chip);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn remove(&mut self, i: usize) -> Option<Chip> {
+        (i < self.chips.len()).then(|| self.chips.remove(i))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.chips.is_empty()
+    }
+
+    pub fn compile(&self) -> Option<String> {
+        match self.chips.as_slice() {
+            [] => None,
+            [chip] => Some(chip.expression()),
+            chips => {
+                let expressions: Vec<_> = chips.iter().map(Chip::expression).collect();
+                Some(format!("({})", expressions.join(" AND ")))
+            }
+        }
+    }
+}
+
+fn quote(value: &str) -> String {
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('\'');
+    for c in value.chars() {
+        if matches!(c, '\'' | '"' | '\\') {
+            quoted.push('\\');
+        }
+        quoted.push(c);
+    }
+    quoted.push('\'');
+    quoted
+}
@@ -0,0 +1,78 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use eyre::Result;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+use encore_mpd::Client;
+
+const STICKER: &str = "listen_later";
+
+#[derive(Default)]
+pub struct Inbox {
+    pub songs: Vec<String>,
+    pub selected: usize,
+}
+
+impl Inbox {
+    pub async fn refresh<R, W>(&mut self, client: &mut Client<R, W>) -> Result<()>
+    where
+        R: AsyncReadExt + Unpin,
+        W: AsyncWriteExt + Unpin,
+    {
+        self.songs = flagged(client).await?;
+        self.selected = self.selected.min(self.songs.len().saturating_sub(1));
+        Ok(())
+    }
+
+    pub async fn enqueue_selected<R, W>(&mut self, client: &mut Client<R, W>) -> Result<()>
+    where
+        R: AsyncReadExt + Unpin,
+        W: AsyncWriteExt + Unpin,
+    {
+        if self.selected >= self.songs.len() {
+            return Ok(());
+        }
+
+        enqueue(client, &self.songs[self.selected]).await?;
+        self.songs.remove(self.selected);
+        self.selected = self.selected.min(self.songs.len().saturating_sub(1));
+        Ok(())
+    }
+}
+
+// the flagged songs, the longest waiting first
+pub async fn flagged<R, W>(client: &mut Client<R, W>) -> Result<Vec<String>>
+where
+    R: AsyncReadExt + Unpin,
+    W: AsyncWriteExt + Unpin,
+{
+    let mut flagged = client.sticker_find("", STICKER).await?;
+    flagged.sort_by_key(|(_, since)| since.parse::<u64>().unwrap_or(0));
+    Ok(flagged.into_iter().map(|(file, _)| file).collect())
+}
+
+// to the end of the queue, and off the list
+pub async fn enqueue<R, W>(client: &mut Client<R, W>, uri: &str) -> Result<()>
+where
+    R: AsyncReadExt + Unpin,
+    W: AsyncWriteExt + Unpin,
+{
+    client.add(uri).await?;
+    client.sticker_delete(uri, Some(STICKER)).await?;
+    Ok(())
+}
+
+pub async fn toggle<R, W>(client: &mut Client<R, W>, uri: &str) -> Result<bool>
+where
+    R: AsyncReadExt + Unpin,
+    W: AsyncWriteExt + Unpin,
+{
+    if client.sticker_get(uri, STICKER).await?.is_some() {
+        client.sticker_delete(uri, Some(STICKER)).await?;
+        Ok(false)
+    } else {
+        let since = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        client.sticker_set(uri, STICKER, &since.to_string()).await?;
+        Ok(true)
+    }
+}
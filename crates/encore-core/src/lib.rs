@@ -0,0 +1,27 @@
+pub mod art;
+pub mod attribution;
+pub mod cache;
+pub mod config;
+pub mod connection;
+pub mod events;
+pub mod fifo;
+pub mod filter;
+pub mod inbox;
+pub mod lyrics;
+#[cfg(feature = "notifications")]
+pub mod notification;
+pub mod perf;
+#[cfg(all(unix, feature = "dbus"))]
+pub mod power;
+#[cfg(feature = "http-remote")]
+pub mod remote_control;
+pub mod session;
+pub mod spectrum;
+mod ssh;
+pub mod tags;
+#[cfg(feature = "tls")]
+mod tls;
+pub mod transition;
+pub mod trash;
+#[cfg(feature = "watch")]
+pub mod watch;
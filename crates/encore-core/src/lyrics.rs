@@ -0,0 +1,179 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Line {
+    // when it is sung, none for plain lyrics
+    pub at: Option<Duration>,
+    pub text: String,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Lyrics {
+    pub lines: Vec<Line>,
+}
+
+impl Lyrics {
+    // LRC when any line has a time tag, plain text otherwise
+    pub fn parse(text: &str) -> Self {
+        let mut offset = 0i64;
+        let mut timed = Vec::new();
+        let mut plain = Vec::new();
+
+        for line in text.lines() {
+            let line = line.trim_end();
+            let mut rest = line;
+            let mut times = Vec::new();
+            while let Some((tag, after)) = rest.strip_prefix('[').and_then(|r| r.split_once(']')) {
+                match time(tag) {
+                    Some(at) => times.push(at),
+                    // `[Chorus]` in plain lyrics is part of the text
+                    None if !tag.contains(':') => break,
+                    None => {
+                        if let Some(ms) = tag.strip_prefix("offset:") {
+                            offset = ms.trim().parse().unwrap_or(0);
+                        }
+                    }
+                }
+                rest = after;
+            }
+
+            // `[ar:...]` and the like only describe the file
+            if rest.len() != line.len() && times.is_empty() {
+                continue;
+            }
+
+            let text = words(rest.trim());
+            plain.push(text.clone());
+            for at in times {
+                timed.push((at, text.clone()));
+            }
+        }
+
+        if timed.is_empty() {
+            // leading and trailing blank lines are only layout
+            let start = plain.iter().position(|line| !line.is_empty());
+            let end = plain.iter().rposition(|line| !line.is_empty());
+            let plain = match (start, end) {
+                (Some(start), Some(end)) => plain.drain(start..=end).collect(),
+                _ => Vec::new(),
+            };
+            let lines = plain.into_iter().map(|text| Line { at: None, text });
+            return Self {
+                lines: lines.collect(),
+            };
+        }
+
+        // a positive offset shows the lines that much earlier
+        let shift = |at: Duration| {
+            let ms = at.as_millis() as i64 - offset;
+            Duration::from_millis(ms.max(0) as u64)
+        };
+        timed.sort_by_key(|(at, _)| *at);
+        let lines = timed.into_iter().map(|(at, text)| Line {
+            at: Some(shift(at)),
+            text,
+        });
+        Self {
+            lines: lines.collect(),
+        }
+    }
+
+    pub fn is_synced(&self) -> bool {
+        self.lines.first().is_some_and(|line| line.at.is_some())
+    }
+
+    // the last line that started by `elapsed`
+    pub fn current(&self, elapsed: Duration) -> Option<usize> {
+        if !self.is_synced() {
+            return None;
+        }
+
+        let started = self
+            .lines
+            .partition_point(|line| line.at.is_some_and(|at| at <= elapsed));
+        started.checked_sub(1)
+    }
+}
+
+// `mm:ss`, `mm:ss.xx` or `mm:ss:xx`
+fn time(tag: &str) -> Option<Duration> {
+    let (minutes, seconds) = tag.split_once(':')?;
+    let minutes: u64 = minutes.trim().parse().ok()?;
+    let seconds = seconds.trim().replacen(':', ".", 1);
+    let seconds: f64 = seconds.parse().ok()?;
+    if !(0.0..60.0).contains(&seconds) {
+        return None;
+    }
+
+    Some(Duration::from_secs(minutes * 60) + Duration::from_secs_f64(seconds))
+}
+
+// enhanced LRC times single words with `<mm:ss.xx>`, the line is enough here
+fn words(text: &str) -> String {
+    let mut out = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find('<') {
+        let Some(end) = rest[start..].find('>') else {
+            break;
+        };
+        if time(&rest[start + 1..start + end]).is_none() {
+            out.push_str(&rest[..start + end + 1]);
+        } else {
+            out.push_str(&rest[..start]);
+        }
+        rest = &rest[start + end + 1..];
+    }
+    out.push_str(rest);
+    out.trim().to_string()
+}
+
+// where lyrics for a song may be: next to the track in the music directory,
+// then in the lyrics directory by "artist - title" or by the track's name;
+// synced files first, plain text after
+pub fn candidates(
+    uri: &str,
+    artist: Option<&str>,
+    title: Option<&str>,
+    music_dir: Option<&Path>,
+    lyrics_dir: Option<&Path>,
+) -> Vec<PathBuf> {
+    let track = Path::new(uri);
+    let mut stems = Vec::new();
+    if let Some(dir) = music_dir {
+        stems.push(dir.join(track.with_extension("")));
+    }
+    if let Some(dir) = lyrics_dir {
+        if let (Some(artist), Some(title)) = (artist, title) {
+            // a slash in a tag would otherwise point into a subdirectory
+            let name = format!("{artist} - {title}").replace('/', "_");
+            stems.push(dir.join(name));
+        }
+        if let Some(name) = track.file_stem() {
+            stems.push(dir.join(name));
+        }
+    }
+
+    ["lrc", "txt"]
+        .iter()
+        .flat_map(|ext| {
+            stems.iter().map(move |stem| {
+                let mut path = stem.clone().into_os_string();
+                path.push(".");
+                path.push(ext);
+                PathBuf::from(path)
+            })
+        })
+        .collect()
+}
+
+// the first candidate that can be read
+pub fn load(candidates: &[PathBuf]) -> Option<(PathBuf, Lyrics)> {
+    candidates.iter().find_map(|path| {
+        let text = fs::read_to_string(path).ok()?;
+        Some((path.clone(), Lyrics::parse(&text)))
+    })
+}
@@ -0,0 +1,85 @@
+use std::time::Duration;
+
+use eyre::Result;
+use notify_rust::Notification;
+#[cfg(all(unix, not(target_os = "macos")))]
+use notify_rust::{Hint, Image};
+use tokio::{sync::mpsc::UnboundedReceiver, time::sleep};
+
+#[derive(Debug)]
+pub struct Note {
+    pub summary: String,
+    // plain text, escaped for the servers that read markup
+    pub body: String,
+    pub picture: Option<Picture>,
+}
+
+// 8 bit RGBA, row after row
+#[derive(Debug)]
+pub struct Picture {
+    pub width: u32,
+    pub height: u32,
+    pub rgba: Vec<u8>,
+}
+
+// shows notes as they come, but no more often than `every`; of those that
+// came in between only the newest is shown, and it replaces the last one
+pub async fn serve(mut notes: UnboundedReceiver<Note>, every: Duration) {
+    let mut shown = 0;
+    while let Some(mut note) = notes.recv().await {
+        while let Ok(newer) = notes.try_recv() {
+            note = newer;
+        }
+
+        // without a notification server there is nobody to tell, the next
+        // note tries again
+        if let Ok(id) = show(shown, note).await {
+            shown = id;
+        }
+        sleep(every).await;
+    }
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+async fn show(replaces: u32, note: Note) -> Result<u32> {
+    let mut notification = Notification::new();
+    notification
+        .appname("encore")
+        .icon("audio-x-generic")
+        .summary(&note.summary)
+        .body(&escape(&note.body))
+        .hint(Hint::Category("x-gnome.music".into()))
+        .id(replaces);
+    if let Some(Picture {
+        width,
+        height,
+        rgba,
+    }) = note.picture
+    {
+        let image = Image::from_rgba(width as i32, height as i32, rgba)?;
+        notification.image_data(image);
+    }
+
+    let handle = notification.show_async().await?;
+    Ok(handle.id())
+}
+
+// macOS and Windows show notes in a blocking call and replace none, nor
+// take a picture
+#[cfg(not(all(unix, not(target_os = "macos"))))]
+async fn show(_: u32, note: Note) -> Result<u32> {
+    let mut notification = Notification::new();
+    notification
+        .appname("encore")
+        .summary(&note.summary)
+        .body(&note.body);
+    tokio::task::spawn_blocking(move || notification.show().map(drop)).await??;
+    Ok(0)
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
@@ -0,0 +1,46 @@
+use std::time::Duration;
+
+use tokio::time::Instant;
+
+pub struct Perf {
+    pub frame_time: Duration,
+    pub events_per_sec: u32,
+    pub mpd_latency: Option<Duration>,
+    pub pending: usize,
+    events: u32,
+    window: Instant,
+}
+
+impl Default for Perf {
+    fn default() -> Self {
+        Self {
+            frame_time: Duration::ZERO,
+            events_per_sec: 0,
+            mpd_latency: None,
+            pending: 0,
+            events: 0,
+            window: Instant::now(),
+        }
+    }
+}
+
+impl Perf {
+    pub fn record_frame(&mut self, elapsed: Duration) {
+        self.frame_time = elapsed;
+    }
+
+    pub fn record_event(&mut self) {
+        let now = Instant::now();
+        if now.duration_since(self.window) >= Duration::from_secs(1) {
+            self.events_per_sec = self.events;
+            self.events = 0;
+            self.window = now;
+        }
+        self.events += 1;
+    }
+
+    pub fn record_mpd(&mut self, elapsed: Duration, pending: usize) {
+        self.mpd_latency = Some(elapsed);
+        self.pending = pending;
+    }
+}
@@ -0,0 +1,173 @@
+use encore_mpd::{Client, PlayerState};
+use eyre::Result;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio_stream::StreamExt;
+use zbus::{proxy, zvariant::OwnedFd, Connection};
+
+#[proxy(
+    interface = "org.freedesktop.login1.Manager",
+    default_service = "org.freedesktop.login1",
+    default_path = "/org/freedesktop/login1"
+)]
+trait Login1Manager {
+    fn inhibit(&self, what: &str, who: &str, why: &str, mode: &str) -> zbus::Result<OwnedFd>;
+
+    #[zbus(signal)]
+    fn prepare_for_sleep(&self, start: bool) -> zbus::Result<()>;
+}
+
+#[proxy(
+    interface = "org.freedesktop.ScreenSaver",
+    default_service = "org.freedesktop.ScreenSaver",
+    default_path = "/org/freedesktop/ScreenSaver"
+)]
+trait ScreenSaver {
+    fn inhibit(&self, application_name: &str, reason_for_inhibit: &str) -> zbus::Result<u32>;
+    fn un_inhibit(&self, cookie: u32) -> zbus::Result<()>;
+}
+
+enum Inhibit {
+    ScreenSaver(ScreenSaverProxy<'static>, u32),
+    Logind { _fd: OwnedFd },
+}
+
+#[derive(Default)]
+pub struct IdleInhibitor {
+    inhibit: Option<Inhibit>,
+}
+
+pub struct SuspendGuard {
+    manager: Login1ManagerProxy<'static>,
+    signals: PrepareForSleepStream,
+    lock: Option<OwnedFd>,
+    pause_on_suspend: bool,
+    resume_on_wake: bool,
+    paused: bool,
+}
+
+impl SuspendGuard {
+    pub async fn new(pause_on_suspend: bool, resume_on_wake: bool) -> Result<Self> {
+        let conn = Connection::system().await?;
+        let manager = Login1ManagerProxy::new(&conn).await?;
+        let signals = manager.receive_prepare_for_sleep().await?;
+
+        Ok(Self {
+            manager,
+            signals,
+            lock: None,
+            pause_on_suspend,
+            resume_on_wake,
+            paused: false,
+        })
+    }
+
+    // suspend only waits for us while there is something to pause
+    pub async fn update(&mut self, state: &PlayerState) -> Result<()> {
+        match state {
+            PlayerState::Play => self.lock().await,
+            _ => {
+                self.lock = None;
+                Ok(())
+            }
+        }
+    }
+
+    // true right before the system suspends, false once it has woken up
+    pub async fn next(&mut self) -> Option<bool> {
+        let signal = self.signals.next().await?;
+        signal.args().ok().map(|args| args.start)
+    }
+
+    // without a connection there is nothing to pause, but the lock still
+    // has to go
+    pub async fn handle<R, W>(
+        &mut self,
+        sleeping: bool,
+        client: Option<&mut Client<R, W>>,
+    ) -> Result<()>
+    where
+        R: AsyncReadExt + Unpin,
+        W: AsyncWriteExt + Unpin,
+    {
+        let Some(client) = client else {
+            self.lock = None;
+            return Ok(());
+        };
+
+        if sleeping {
+            let result = self.pause(client).await;
+            // logind waits for the delay lock to be released before suspending
+            self.lock = None;
+            result
+        } else {
+            if std::mem::take(&mut self.paused) && self.resume_on_wake {
+                client.command(b"pause 0").await?;
+            }
+            Ok(())
+        }
+    }
+
+    async fn pause<R, W>(&mut self, client: &mut Client<R, W>) -> Result<()>
+    where
+        R: AsyncReadExt + Unpin,
+        W: AsyncWriteExt + Unpin,
+    {
+        if self.pause_on_suspend && client.status().await?.state == PlayerState::Play {
+            client.command(b"pause 1").await?;
+            self.paused = true;
+        }
+
+        Ok(())
+    }
+
+    async fn lock(&mut self) -> Result<()> {
+        if self.pause_on_suspend && self.lock.is_none() {
+            let why = "Pause playback before suspend";
+            self.lock = Some(
+                self.manager
+                    .inhibit("sleep", "encore", why, "delay")
+                    .await?,
+            );
+        }
+
+        Ok(())
+    }
+}
+
+impl IdleInhibitor {
+    pub fn is_active(&self) -> bool {
+        self.inhibit.is_some()
+    }
+
+    pub async fn update(&mut self, state: &PlayerState) -> Result<()> {
+        let playing = *state == PlayerState::Play;
+        if playing && self.inhibit.is_none() {
+            self.inhibit = Some(Self::acquire().await?);
+        } else if !playing {
+            // dropping a logind lock closes its fd, which releases it
+            if let Some(Inhibit::ScreenSaver(proxy, cookie)) = self.inhibit.take() {
+                proxy.un_inhibit(cookie).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn acquire() -> Result<Inhibit> {
+        let why = "Playing music";
+        if let Ok(conn) = Connection::session().await {
+            if let Ok(proxy) = ScreenSaverProxy::new(&conn).await {
+                if let Ok(cookie) = proxy.inhibit("encore", why).await {
+                    return Ok(Inhibit::ScreenSaver(proxy, cookie));
+                }
+            }
+        }
+
+        let conn = Connection::system().await?;
+        let manager = Login1ManagerProxy::new(&conn).await?;
+        let fd = manager
+            .inhibit("idle:sleep", "encore", why, "block")
+            .await?;
+        Ok(Inhibit::Logind { _fd: fd })
+    }
+}
@@ -0,0 +1,264 @@
+use std::{net::SocketAddr, time::Duration};
+
+use encore_mpd::PlayerState;
+use eyre::{Result, WrapErr};
+use serde::Serialize;
+use tokio::{
+    io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+    net::{TcpListener, TcpStream},
+    sync::{mpsc, oneshot},
+    time::{sleep, timeout},
+};
+
+use crate::connection::Connection;
+
+const PAGE: &str = r#"<!doctype html>
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>encore</title>
+<style>body{font-family:sans-serif;text-align:center}button{font-size:2em;margin:.2em}</style>
+<h2 id="title">-</h2><p id="artist"></p>
+<button onclick="send('previous')">⏮</button><button onclick="send('toggle')">⏯</button><button onclick="send('next')">⏭</button>
+<script>
+const headers = { 'X-Encore-Token': decodeURIComponent(location.hash.slice(1)) };
+async function refresh() {
+  const res = await fetch('/api/now-playing', { headers });
+  if (!res.ok) return;
+  const np = await res.json();
+  document.getElementById('title').textContent = np.title || np.file || '-';
+  document.getElementById('artist').textContent = [np.artist, np.album].filter(Boolean).join(' — ');
+}
+async function send(cmd) { await fetch('/api/' + cmd, { method: 'POST', headers }); refresh(); }
+refresh(); setInterval(refresh, 5000);
+</script>
+"#;
+
+// the request line and headers together, and how long they may take
+const MAX_HEAD: u64 = 8 * 1024;
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+// a header a page on another site cannot send without asking first
+const TOKEN: &str = "x-encore-token";
+
+#[derive(Clone, Copy, Debug)]
+pub enum Command {
+    NowPlaying,
+    Play,
+    Pause,
+    Toggle,
+    Next,
+    Previous,
+}
+
+pub struct Request {
+    pub command: Command,
+    pub reply: oneshot::Sender<Result<Option<NowPlaying>>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct NowPlaying {
+    pub state: &'static str,
+    pub elapsed: Option<u16>,
+    pub duration: Option<u16>,
+    pub file: Option<String>,
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    pub album: Option<String>,
+}
+
+pub async fn execute(client: &mut Connection, command: Command) -> Result<Option<NowPlaying>> {
+    let cmd: &[u8] = match command {
+        Command::NowPlaying => {
+            let status = client.status().await?;
+            let song = client.current_song().await?;
+            return Ok(Some(NowPlaying {
+                state: match status.state {
+                    PlayerState::Play => "play",
+                    PlayerState::Pause => "pause",
+                    PlayerState::Stop => "stop",
+                },
+                elapsed: status.song.map(|song| song.elapsed),
+                duration: song.as_ref().and_then(|song| song.duration),
+                file: song.as_ref().map(|song| song.uri.clone()),
+                title: song.as_ref().and_then(|song| song.title.clone()),
+                artist: song.as_ref().and_then(|song| song.artist.clone()),
+                album: song.and_then(|song| song.album),
+            }));
+        }
+        Command::Play => b"play",
+        Command::Pause => b"pause 1",
+        Command::Toggle => b"pause",
+        Command::Next => b"next",
+        Command::Previous => b"previous",
+    };
+
+    client.command(cmd).await?;
+    Ok(None)
+}
+
+pub async fn bind(addr: SocketAddr) -> Result<TcpListener> {
+    let listener = TcpListener::bind(addr).await;
+    listener.wrap_err_with(|| format!("Not serving the remote control on {addr}"))
+}
+
+pub async fn serve(listener: TcpListener, token: String, tx: mpsc::Sender<Request>) {
+    loop {
+        // out of descriptors, say, which a little wait beats spinning on
+        let Ok((stream, _)) = listener.accept().await else {
+            sleep(Duration::from_millis(100)).await;
+            continue;
+        };
+        let (token, tx) = (token.clone(), tx.clone());
+        tokio::spawn(async move {
+            let _ = handle(stream, &token, tx).await;
+        });
+    }
+}
+
+struct Head {
+    method: String,
+    path: String,
+    token: Option<String>,
+}
+
+// None once the head runs past the limit or the connection ends early
+async fn read_head(r: impl AsyncBufRead + Unpin) -> Result<Option<Head>> {
+    let mut r = r.take(MAX_HEAD);
+    let mut lines = Vec::new();
+    loop {
+        let mut line = String::new();
+        r.read_line(&mut line).await?;
+        if !line.ends_with('\n') {
+            return Ok(None);
+        }
+        if line.trim().is_empty() {
+            break;
+        }
+        lines.push(line);
+    }
+
+    let mut lines = lines.iter();
+    let mut parts = lines
+        .next()
+        .map_or("", |line| line.as_str())
+        .split_whitespace();
+    let (method, path) = (parts.next().unwrap_or(""), parts.next().unwrap_or(""));
+    let token = lines
+        .filter_map(|line| line.split_once(':'))
+        .find(|(name, _)| name.trim().eq_ignore_ascii_case(TOKEN))
+        .map(|(_, value)| value.trim().to_string());
+    Ok(Some(Head {
+        method: method.into(),
+        path: path.into(),
+        token,
+    }))
+}
+
+// as long for any wrong token of the same length
+fn matches(token: &str, expected: &str) -> bool {
+    let diff = token
+        .bytes()
+        .zip(expected.bytes())
+        .fold(0, |diff, (a, b)| diff | (a ^ b));
+    token.len() == expected.len() && diff == 0
+}
+
+async fn handle(stream: TcpStream, expected: &str, tx: mpsc::Sender<Request>) -> Result<()> {
+    let (r, mut w) = stream.into_split();
+    let head = match timeout(READ_TIMEOUT, read_head(BufReader::new(r))).await {
+        Ok(head) => head?,
+        Err(_) => return respond(&mut w, "408 Request Timeout", "text/plain", "too slow").await,
+    };
+    let Some(head) = head else {
+        let status = "431 Request Header Fields Too Large";
+        return respond(&mut w, status, "text/plain", "too large").await;
+    };
+
+    // the page itself tells nothing, it takes the token from its url
+    let command = match (head.method.as_str(), head.path.as_str()) {
+        ("GET", "/") => return respond(&mut w, "200 OK", "text/html; charset=utf-8", PAGE).await,
+        _ if !head.token.is_some_and(|token| matches(&token, expected)) => {
+            return respond(&mut w, "401 Unauthorized", "text/plain", "wrong token").await
+        }
+        ("GET", "/api/now-playing") => Command::NowPlaying,
+        ("POST", "/api/play") => Command::Play,
+        ("POST", "/api/pause") => Command::Pause,
+        ("POST", "/api/toggle") => Command::Toggle,
+        ("POST", "/api/next") => Command::Next,
+        ("POST", "/api/previous") => Command::Previous,
+        _ => return respond(&mut w, "404 Not Found", "text/plain", "not found").await,
+    };
+
+    let (reply, rx) = oneshot::channel();
+    tx.send(Request { command, reply }).await?;
+    match rx.await {
+        Ok(Ok(Some(now_playing))) => {
+            let body = serde_json::to_string(&now_playing)?;
+            respond(&mut w, "200 OK", "application/json", &body).await
+        }
+        Ok(Ok(None)) => respond(&mut w, "204 No Content", "text/plain", "").await,
+        Ok(Err(e)) => respond(&mut w, "502 Bad Gateway", "text/plain", &format!("{e:#}")).await,
+        Err(_) => {
+            respond(
+                &mut w,
+                "503 Service Unavailable",
+                "text/plain",
+                "not connected",
+            )
+            .await
+        }
+    }
+}
+
+async fn respond(
+    w: &mut (impl AsyncWriteExt + Unpin),
+    status: &str,
+    kind: &str,
+    body: &str,
+) -> Result<()> {
+    let head = format!(
+        "HTTP/1.1 {status}\r\nContent-Type: {kind}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        body.len()
+    );
+    w.write_all(head.as_bytes()).await?;
+    w.write_all(body.as_bytes()).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // what a raw request gets back, up to the status code
+    async fn status(addr: SocketAddr, request: &[u8]) -> String {
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        let _ = stream.write_all(request).await;
+        let mut reply = String::new();
+        let _ = stream.read_to_string(&mut reply).await;
+        reply.split_whitespace().nth(1).unwrap_or("").into()
+    }
+
+    #[tokio::test]
+    async fn guards_the_api() {
+        let listener = bind("127.0.0.1:0".parse().unwrap()).await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (tx, mut rx) = mpsc::channel(8);
+        tokio::spawn(serve(listener, "secret".into(), tx));
+        tokio::spawn(async move {
+            while let Some(request) = rx.recv().await {
+                let _ = request.reply.send(Ok(None));
+            }
+        });
+
+        assert_eq!(status(addr, b"GET / HTTP/1.1\r\n\r\n").await, "200");
+        let next = b"POST /api/next HTTP/1.1\r\nX-Encore-Token: secret\r\n\r\n";
+        assert_eq!(status(addr, next).await, "204");
+        let wrong = b"POST /api/next HTTP/1.1\r\nX-Encore-Token: secreT\r\n\r\n";
+        assert_eq!(status(addr, wrong).await, "401");
+        let none = b"POST /api/next HTTP/1.1\r\n\r\n";
+        assert_eq!(status(addr, none).await, "401");
+
+        // headers without end are cut off
+        let mut huge = b"GET / HTTP/1.1\r\n".to_vec();
+        huge.extend(b"X-Padding: 0123456789abcdef\r\n".repeat(1024));
+        assert_eq!(status(addr, &huge).await, "431");
+    }
+}
@@ -0,0 +1,91 @@
+use std::collections::HashSet;
+
+use encore_mpd::{Feature, ProtocolVersion, Status, Subsystem};
+use eyre::Result;
+
+use crate::connection::{self, Connection, Endpoint};
+
+// MPD answers nothing else while a connection sits in `idle`, so one
+// connection stays parked there to report changes and the other is always
+// free for commands
+pub struct Session {
+    idle: Connection,
+    command: Connection,
+}
+
+impl Session {
+    pub async fn connect(endpoint: &Endpoint) -> Result<Self> {
+        let command = connection::connect(endpoint).await?;
+        let idle = connection::connect(endpoint).await?;
+        Self::new(idle, command).await
+    }
+
+    pub async fn new(mut idle: Connection, command: Connection) -> Result<Self> {
+        idle.send_idle(&[]).await?;
+        Ok(Self { idle, command })
+    }
+
+    pub fn version(&self) -> ProtocolVersion {
+        self.command.version()
+    }
+
+    pub fn supports(&self, feature: Feature) -> bool {
+        self.command.supports(feature)
+    }
+
+    pub fn command(&mut self) -> &mut Connection {
+        &mut self.command
+    }
+
+    pub fn is_idling(&self) -> bool {
+        self.idle.is_idling()
+    }
+
+    // safe to cancel, the idle stays pending until it is read
+    pub async fn wait_idle(&mut self) -> Result<HashSet<Subsystem>> {
+        self.idle.wait_idle().await
+    }
+
+    pub async fn resume_idle(&mut self) -> Result<()> {
+        self.idle.send_idle(&[]).await
+    }
+
+    // both connections move, so changes are reported for the partition that
+    // commands go to
+    pub async fn switch_partition(&mut self, name: &str) -> Result<()> {
+        self.command.switch_partition(name).await?;
+        self.idle.noidle().await?;
+        self.idle.switch_partition(name).await?;
+        self.idle.send_idle(&[]).await
+    }
+
+    // a round trip on both connections, returning whatever changed meanwhile
+    pub async fn ping(&mut self) -> Result<HashSet<Subsystem>> {
+        self.command.ping().await?;
+        let changed = self.idle.noidle().await?;
+        self.idle.send_idle(&[]).await?;
+        Ok(changed)
+    }
+}
+
+// the MPD instance cached state was read from; a different protocol version
+// or a queue version going backwards after a reconnect means the server was
+// restarted, so positions and ids from before may point at other songs
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Instance {
+    version: Option<ProtocolVersion>,
+    queue_version: u32,
+}
+
+impl Instance {
+    // records the latest status and tells whether the server was replaced
+    pub fn observe(&mut self, version: ProtocolVersion, status: &Status) -> bool {
+        let restarted = self
+            .version
+            .is_some_and(|v| v != version || status.queue_version < self.queue_version);
+
+        self.version = Some(version);
+        self.queue_version = status.queue_version;
+        restarted
+    }
+}
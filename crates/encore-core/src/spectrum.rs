@@ -0,0 +1,100 @@
+use std::f32::consts::PI;
+
+// a power of two, about 46ms at 44.1kHz
+const SIZE: usize = 2048;
+const LOW: f32 = 40.0;
+const HIGH: f32 = 16000.0;
+// decibels below full scale where a bar is empty
+const FLOOR: f32 = 60.0;
+
+// bar heights from 0 to 1 over bands spaced evenly in pitch; a bar jumps up
+// with the music and falls back slowly by the smoothing factor per update
+pub struct Spectrum {
+    pub bars: Vec<f32>,
+    smoothing: f32,
+}
+
+impl Spectrum {
+    pub fn new(bars: usize, smoothing: f32) -> Self {
+        Self {
+            bars: vec![0.0; bars],
+            smoothing: smoothing.clamp(0.0, 0.99),
+        }
+    }
+
+    pub fn update(&mut self, samples: &[f32], rate: u32) {
+        let fresh = bands(samples, rate, self.bars.len());
+        for (bar, fresh) in self.bars.iter_mut().zip(fresh) {
+            *bar = fresh.max(*bar * self.smoothing);
+        }
+    }
+}
+
+// the loudest bin in each band, from the last `SIZE` samples
+fn bands(samples: &[f32], rate: u32, count: usize) -> Vec<f32> {
+    let mut re = vec![0.0; SIZE];
+    let mut im = vec![0.0; SIZE];
+    let tail = &samples[samples.len().saturating_sub(SIZE)..];
+    // a hann window keeps the edges of the slice from smearing every band
+    for (i, sample) in tail.iter().enumerate() {
+        let window = 0.5 - 0.5 * (2.0 * PI * i as f32 / (SIZE - 1) as f32).cos();
+        re[i] = sample * window;
+    }
+    fft(&mut re, &mut im);
+
+    let hz = rate as f32 / SIZE as f32;
+    let high = HIGH.min(rate as f32 / 2.0);
+    let ratio = (high / LOW).powf(1.0 / count.max(1) as f32);
+    (0..count)
+        .map(|band| {
+            let from = LOW * ratio.powi(band as i32) / hz;
+            let to = LOW * ratio.powi(band as i32 + 1) / hz;
+            // the low bands are narrower than a bin, they share one
+            let from = (from as usize).clamp(1, SIZE / 2 - 1);
+            let to = (to as usize).clamp(from + 1, SIZE / 2);
+            let peak = (from..to)
+                .map(|bin| (re[bin] * re[bin] + im[bin] * im[bin]).sqrt())
+                .fold(0.0, f32::max);
+
+            // a full scale sine peaks at a quarter of the size once windowed
+            let db = 20.0 * (peak / (SIZE as f32 / 4.0)).max(f32::MIN_POSITIVE).log10();
+            ((db + FLOOR) / FLOOR).clamp(0.0, 1.0)
+        })
+        .collect()
+}
+
+// in place, iterative radix 2; the length has to be a power of two
+fn fft(re: &mut [f32], im: &mut [f32]) {
+    let n = re.len();
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            re.swap(i, j);
+            im.swap(i, j);
+        }
+    }
+
+    let mut len = 2;
+    while len <= n {
+        let angle = -2.0 * PI / len as f32;
+        for start in (0..n).step_by(len) {
+            for k in 0..len / 2 {
+                let (sin, cos) = (angle * k as f32).sin_cos();
+                let (a, b) = (start + k, start + k + len / 2);
+                let t_re = re[b] * cos - im[b] * sin;
+                let t_im = re[b] * sin + im[b] * cos;
+                re[b] = re[a] - t_re;
+                im[b] = im[a] - t_im;
+                re[a] += t_re;
+                im[a] += t_im;
+            }
+        }
+        len <<= 1;
+    }
+}
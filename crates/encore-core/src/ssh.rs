@@ -0,0 +1,73 @@
+use std::{
+    pin::Pin,
+    process::Stdio,
+    task::{Context, Poll},
+};
+
+use encore_mpd::Client;
+use eyre::{bail, Context as _, Result};
+use tokio::{
+    io::{self, AsyncRead, AsyncReadExt, ReadBuf},
+    process::{Child, ChildStdout, Command},
+};
+
+use crate::connection::Connection;
+
+// the read half of `ssh -W`, which forwards its stdio to a port on the remote
+// side; ssh is killed once this is dropped
+struct Tunnel {
+    stdout: ChildStdout,
+    _child: Child,
+}
+
+impl AsyncRead for Tunnel {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.stdout).poll_read(cx, buf)
+    }
+}
+
+// reaches an MPD that only listens on the remote host's loopback; ssh cannot
+// ask for a password behind the TUI, so this relies on keys or an agent
+pub async fn connect(destination: &str, port: u16) -> Result<Connection> {
+    let forward = format!("localhost:{port}");
+    let mut child = Command::new("ssh")
+        .args(["-o", "BatchMode=yes", "-W", &forward, "--", destination])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .context("Failed to run ssh")?;
+
+    let (Some(w), Some(stdout), Some(mut stderr)) =
+        (child.stdin.take(), child.stdout.take(), child.stderr.take())
+    else {
+        bail!("ssh did not provide stdio pipes");
+    };
+
+    let r = Tunnel {
+        stdout,
+        _child: child,
+    };
+
+    match Client::init_client(Box::new(r) as _, Box::new(w) as _).await {
+        Ok(client) => {
+            // keep ssh from blocking on or dying from a full stderr pipe
+            tokio::spawn(async move { io::copy(&mut stderr, &mut io::sink()).await });
+            Ok(client)
+        }
+        Err(e) => {
+            // ssh has been killed along with the tunnel, so this reaches EOF
+            let mut message = String::new();
+            let _ = stderr.read_to_string(&mut message).await;
+            match message.lines().rev().find(|line| !line.trim().is_empty()) {
+                Some(line) => bail!("ssh: {}", line.trim()),
+                None => Err(e),
+            }
+        }
+    }
+}
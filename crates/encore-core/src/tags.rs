@@ -0,0 +1,160 @@
+use std::{fs, path::Path};
+
+use eyre::{bail, eyre, Result, WrapErr};
+
+const MAGIC: &[u8] = b"fLaC";
+const VORBIS_COMMENT: u8 = 4;
+const LAST: u8 = 0x80;
+// block lengths are 24 bits
+const MOST: usize = (1 << 24) - 1;
+
+// only FLAC files are written, their vorbis comments are plain enough to
+// edit by hand
+pub fn is_editable(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("flac"))
+}
+
+// sets `name` to `value` in the file's vorbis comments, an empty value takes
+// the tag off; every other comment and block stays as it was
+pub fn write(path: &Path, name: &str, value: &str) -> Result<()> {
+    if !is_editable(path) {
+        bail!("Only FLAC files can be tagged");
+    }
+
+    let data = fs::read(path).wrap_err_with(|| format!("Failed to read {}", path.display()))?;
+    let data =
+        retag(&data, name, value).wrap_err_with(|| format!("Failed to tag {}", path.display()))?;
+
+    // a rename leaves nothing half written behind
+    let tmp = path.with_extension("flac.tmp");
+    fs::write(&tmp, data)
+        .and_then(|_| fs::rename(&tmp, path))
+        .wrap_err_with(|| format!("Failed to write {}", path.display()))
+}
+
+fn retag(data: &[u8], name: &str, value: &str) -> Result<Vec<u8>> {
+    if !data.starts_with(MAGIC) {
+        bail!("Not a FLAC file");
+    }
+
+    let mut blocks = Vec::new();
+    let mut at = MAGIC.len();
+    loop {
+        let header = data
+            .get(at..at + 4)
+            .ok_or_else(|| eyre!("Truncated metadata"))?;
+        let kind = header[0] & !LAST;
+        let len = u32::from_be_bytes([0, header[1], header[2], header[3]]) as usize;
+        let body = data
+            .get(at + 4..at + 4 + len)
+            .ok_or_else(|| eyre!("Truncated metadata"))?;
+        blocks.push((kind, body.to_vec()));
+        at += 4 + len;
+        if header[0] & LAST != 0 {
+            break;
+        }
+    }
+
+    let mut comments = match blocks.iter().position(|(kind, _)| *kind == VORBIS_COMMENT) {
+        Some(i) => parse(&blocks[i].1).ok_or_else(|| eyre!("Malformed vorbis comments"))?,
+        None => {
+            // right after the stream info, which always comes first
+            blocks.insert(1.min(blocks.len()), (VORBIS_COMMENT, Vec::new()));
+            Comments {
+                vendor: b"encore".to_vec(),
+                list: Vec::new(),
+            }
+        }
+    };
+    comments.set(name, value);
+    let block = comments.encode();
+    if block.len() > MOST {
+        bail!("Tags too large");
+    }
+    if let Some((_, body)) = blocks.iter_mut().find(|(kind, _)| *kind == VORBIS_COMMENT) {
+        *body = block;
+    }
+
+    let mut out = MAGIC.to_vec();
+    let count = blocks.len();
+    for (i, (kind, body)) in blocks.into_iter().enumerate() {
+        let flag = if i + 1 == count { LAST } else { 0 };
+        out.push(kind | flag);
+        out.extend_from_slice(&(body.len() as u32).to_be_bytes()[1..]);
+        out.extend_from_slice(&body);
+    }
+    out.extend_from_slice(&data[at..]);
+    Ok(out)
+}
+
+struct Comments {
+    vendor: Vec<u8>,
+    // "NAME=value", kept as bytes so nothing unreadable gets lost
+    list: Vec<Vec<u8>>,
+}
+
+impl Comments {
+    // names are case insensitive, the new value goes where the first old one was
+    fn set(&mut self, name: &str, value: &str) {
+        let matches = |comment: &Vec<u8>| {
+            comment.len() > name.len()
+                && comment[name.len()] == b'='
+                && comment[..name.len()].eq_ignore_ascii_case(name.as_bytes())
+        };
+        let first = self.list.iter().position(matches);
+        self.list.retain(|comment| !matches(comment));
+        if !value.is_empty() {
+            let comment = format!("{}={value}", name.to_uppercase()).into_bytes();
+            let at = first.unwrap_or(self.list.len()).min(self.list.len());
+            self.list.insert(at, comment);
+        }
+    }
+
+    fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        push_field(&mut out, &self.vendor);
+        out.extend_from_slice(&(self.list.len() as u32).to_le_bytes());
+        for comment in &self.list {
+            push_field(&mut out, comment);
+        }
+        out
+    }
+}
+
+// a length, then that many bytes
+fn push_field(out: &mut Vec<u8>, bytes: &[u8]) {
+    out.extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+    out.extend_from_slice(bytes);
+}
+
+fn parse(body: &[u8]) -> Option<Comments> {
+    let mut reader = Reader { body, at: 0 };
+    let vendor = reader.field()?;
+    let count = reader.u32()?;
+    let list = (0..count).map(|_| reader.field()).collect::<Option<_>>()?;
+    Some(Comments { vendor, list })
+}
+
+struct Reader<'a> {
+    body: &'a [u8],
+    at: usize,
+}
+
+impl Reader<'_> {
+    fn take(&mut self, len: usize) -> Option<&[u8]> {
+        let bytes = self.body.get(self.at..self.at.checked_add(len)?)?;
+        self.at += len;
+        Some(bytes)
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        Some(u32::from_le_bytes(self.take(4)?.try_into().ok()?))
+    }
+
+    fn field(&mut self) -> Option<Vec<u8>> {
+        let len = self.u32()? as usize;
+        self.take(len).map(<[u8]>::to_vec)
+    }
+}
@@ -0,0 +1,34 @@
+use std::sync::Arc;
+
+use encore_mpd::Client;
+use eyre::{Context, Result};
+use tokio::io::split;
+use tokio_rustls::{
+    rustls::{crypto::ring, pki_types::ServerName, ClientConfig, RootCertStore},
+    TlsConnector,
+};
+
+use crate::connection::{connect_tcp, Connection};
+
+// MPD has no TLS of its own, this is for one exposed through a terminating
+// proxy like stunnel or haproxy; certificates are checked against the
+// bundled Mozilla roots
+pub async fn connect(host: &str, port: u16) -> Result<Connection> {
+    let roots = RootCertStore {
+        roots: webpki_roots::TLS_SERVER_ROOTS.to_vec(),
+    };
+    let config = ClientConfig::builder_with_provider(Arc::new(ring::default_provider()))
+        .with_safe_default_protocol_versions()?
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+
+    let name = ServerName::try_from(host.to_owned()).context("Invalid TLS server name")?;
+    let stream = connect_tcp(host, port).await?;
+    let stream = TlsConnector::from(Arc::new(config))
+        .connect(name, stream)
+        .await
+        .context("TLS handshake failed")?;
+
+    let (r, w) = split(stream);
+    Client::init_client(Box::new(r) as _, Box::new(w) as _).await
+}
@@ -0,0 +1,45 @@
+use encore_mpd::{ConsumeMode, Status, Track};
+
+#[derive(Debug, Eq, PartialEq)]
+pub enum Transition {
+    Gapless,
+    Crossfade(u32),
+    Gap,
+}
+
+pub fn upcoming(status: &Status, queue: &[Track]) -> Option<Transition> {
+    let current = queue.get(status.song.as_ref()?.pos)?;
+    let next = queue.get(status.next?)?;
+
+    Some(if status.xfade > 0 {
+        Transition::Crossfade(status.xfade)
+    } else if same_album(current, next) {
+        Transition::Gapless
+    } else {
+        Transition::Gap
+    })
+}
+
+// random picks the next track from anywhere in the queue and consume drops
+// each track once played, so the rest of the current album will not play
+// in order
+pub fn breaks_album(status: &Status, queue: &[Track]) -> bool {
+    if !status.random || status.consume == ConsumeMode::Off {
+        return false;
+    }
+
+    let Some(pos) = status.song.as_ref().map(|song| song.pos) else {
+        return false;
+    };
+
+    queue.get(pos).is_some_and(|current| {
+        queue
+            .iter()
+            .enumerate()
+            .any(|(i, track)| i != pos && same_album(current, track))
+    })
+}
+
+fn same_album(a: &Track, b: &Track) -> bool {
+    a.album.is_some() && a.album == b.album && a.artist == b.artist
+}
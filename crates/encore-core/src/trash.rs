@@ -0,0 +1,130 @@
+use std::{
+    error::Error,
+    fmt,
+    path::PathBuf,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use encore_mpd::{Client, CommandList};
+use eyre::Result;
+use tokio::{
+    fs,
+    io::{AsyncReadExt, AsyncWriteExt},
+};
+
+pub struct Trash {
+    dir: PathBuf,
+}
+
+#[derive(Clone, Debug)]
+pub struct Trashed {
+    pub name: String,
+    pub deleted: SystemTime,
+    path: PathBuf,
+}
+
+// the trash itself failing, the server and the connection to it are fine
+#[derive(Debug)]
+pub struct TrashError(pub String);
+
+impl fmt::Display for TrashError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl Error for TrashError {}
+
+impl Trash {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    // under the user's data directory, deleted playlists outlive the server
+    pub fn open() -> Option<Self> {
+        Some(Self::new(dirs::data_dir()?.join("encore").join("trash")))
+    }
+
+    pub async fn delete_playlist<R, W>(&self, client: &mut Client<R, W>, name: &str) -> Result<()>
+    where
+        R: AsyncReadExt + Unpin,
+        W: AsyncWriteExt + Unpin,
+    {
+        let mut contents = String::new();
+        for track in client.playlist(name).await? {
+            contents.push_str(&track.file);
+            contents.push('\n');
+        }
+
+        let deleted = SystemTime::now().duration_since(UNIX_EPOCH);
+        let deleted = deleted.unwrap_or_default().as_secs();
+        let path = self.dir.join(format!("{deleted}_{name}.m3u"));
+        let kept = async {
+            fs::create_dir_all(&self.dir).await?;
+            fs::write(&path, contents).await
+        };
+        kept.await
+            .map_err(|e| TrashError(format!("Failed to keep {name} in the trash: {e}")))?;
+
+        client.delete_playlist(name).await
+    }
+
+    pub async fn list(&self) -> Result<Vec<Trashed>> {
+        let mut trashed = Vec::new();
+        let Ok(mut entries) = fs::read_dir(&self.dir).await else {
+            return Ok(trashed);
+        };
+
+        let failed = |e| TrashError(format!("Failed to read the trash: {e}"));
+        while let Some(entry) = entries.next_entry().await.map_err(failed)? {
+            let path = entry.path();
+            let Some(stem) = path.file_stem().and_then(|stem| stem.to_str()) else {
+                continue;
+            };
+            let Some((deleted, name)) = stem.split_once('_') else {
+                continue;
+            };
+            let Ok(deleted) = deleted.parse() else {
+                continue;
+            };
+
+            trashed.push(Trashed {
+                name: name.into(),
+                deleted: UNIX_EPOCH + Duration::from_secs(deleted),
+                path,
+            });
+        }
+
+        trashed.sort_by_key(|trashed| std::cmp::Reverse(trashed.deleted));
+        Ok(trashed)
+    }
+
+    pub async fn restore<R, W>(&self, client: &mut Client<R, W>, trashed: &Trashed) -> Result<()>
+    where
+        R: AsyncReadExt + Unpin,
+        W: AsyncWriteExt + Unpin,
+    {
+        let name = &trashed.name;
+        if client.playlists().await?.contains(name) {
+            return Err(TrashError(format!("A playlist named {name} is back already")).into());
+        }
+
+        let contents = fs::read_to_string(&trashed.path)
+            .await
+            .map_err(|e| TrashError(format!("Failed to read {name} from the trash: {e}")))?;
+        let mut list = CommandList::new();
+        for uri in contents.lines().filter(|line| !line.is_empty()) {
+            list.push(b"playlistadd", &[&trashed.name, uri])?;
+        }
+        // MPD only creates a playlist by adding to it, so an empty one stays in the trash
+        if list.is_empty() {
+            return Err(TrashError(format!("{name} is empty, MPD can't restore it")).into());
+        }
+
+        client.run(&list).await?;
+        fs::remove_file(&trashed.path)
+            .await
+            .map_err(|e| TrashError(format!("Failed to empty {name} from the trash: {e}")))?;
+        Ok(())
+    }
+}
@@ -0,0 +1,97 @@
+use std::path::{self, Path, PathBuf};
+
+use eyre::Result;
+use notify::{
+    event::ModifyKind, recommended_watcher, Event, EventKind, RecommendedWatcher, RecursiveMode,
+    Watcher,
+};
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Change {
+    Lyrics(PathBuf),
+    Art(PathBuf),
+    Config(PathBuf),
+}
+
+pub struct DirWatcher {
+    _watcher: RecommendedWatcher,
+    rx: UnboundedReceiver<Change>,
+}
+
+impl DirWatcher {
+    // lyrics turn up next to the tracks as well, so under either directory;
+    // the config file is watched through its directory, since editors save
+    // by replacing it
+    pub fn new(lyrics: Option<&Path>, art: Option<&Path>, config: Option<&Path>) -> Result<Self> {
+        let (tx, rx) = unbounded_channel();
+        let config = config.map(path::absolute).transpose()?;
+        let file = config.clone();
+        let roots = (lyrics.map(Path::to_path_buf), art.map(Path::to_path_buf));
+
+        let mut watcher = recommended_watcher(move |event: notify::Result<Event>| {
+            let Ok(event) = event else {
+                return;
+            };
+
+            if !matches!(
+                event.kind,
+                EventKind::Create(_) | EventKind::Modify(ModifyKind::Data(_) | ModifyKind::Name(_))
+            ) {
+                return;
+            }
+
+            for path in event.paths {
+                let within =
+                    |dir: &Option<PathBuf>| dir.as_ref().is_some_and(|d| path.starts_with(d));
+                let change = match &roots {
+                    _ if file.as_ref() == Some(&path) => Change::Config(path),
+                    (lyrics, art) if (within(lyrics) || within(art)) && is_lyrics(&path) => {
+                        Change::Lyrics(path)
+                    }
+                    (_, art) if within(art) && is_art(&path) => Change::Art(path),
+                    _ => continue,
+                };
+
+                if tx.send(change).is_err() {
+                    return;
+                }
+            }
+        })?;
+
+        for dir in [lyrics, art].into_iter().flatten() {
+            watcher.watch(dir, RecursiveMode::Recursive)?;
+        }
+        // a config written later is picked up too, as long as its directory is there
+        if let Some(dir) = config
+            .as_deref()
+            .and_then(Path::parent)
+            .filter(|d| d.is_dir())
+        {
+            watcher.watch(dir, RecursiveMode::NonRecursive)?;
+        }
+
+        Ok(Self {
+            _watcher: watcher,
+            rx,
+        })
+    }
+
+    pub async fn recv(&mut self) -> Option<Change> {
+        self.rx.recv().await
+    }
+}
+
+fn is_lyrics(path: &Path) -> bool {
+    has_extension(path, &["lrc", "txt"])
+}
+
+fn is_art(path: &Path) -> bool {
+    has_extension(path, &["jpg", "jpeg", "png", "webp", "gif", "bmp"])
+}
+
+fn has_extension(path: &Path, extensions: &[&str]) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| extensions.iter().any(|e| ext.eq_ignore_ascii_case(e)))
+}
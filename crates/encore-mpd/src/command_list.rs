@@ -0,0 +1,80 @@
+use expand::expand;
+use eyre::{Context, Result};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt};
+
+use crate::{encode, next_line, Client, MpdError};
+
+#[derive(Default)]
+pub struct CommandList {
+    buf: Vec<u8>,
+    len: usize,
+}
+
+impl CommandList {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, cmd: &[u8], args: &[&str]) -> Result<&mut Self> {
+        encode(&mut self.buf, cmd, args)?;
+        self.len += 1;
+        Ok(self)
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    // the name of each command, in order
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.buf
+            .split(|&b| b == b'\n')
+            .filter_map(|line| line.split(|&b| b == b' ').next())
+            .filter_map(|name| std::str::from_utf8(name).ok())
+            .filter(|name| !name.is_empty())
+    }
+}
+
+impl<R, W> Client<R, W>
+where
+    R: AsyncReadExt + Unpin,
+    W: AsyncWriteExt + Unpin,
+{
+    pub async fn run(&mut self, list: &CommandList) -> Result<Vec<Vec<(String, String)>>> {
+        if list.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        async move {
+            let mut responses = Vec::with_capacity(list.len);
+            let mut pairs = Vec::new();
+
+            self.w.write_all(b"command_list_ok_begin\n").await?;
+            self.w.write_all(&list.buf).await?;
+            self.w.write_all(b"command_list_end\n").await?;
+            let mut lines = (&mut self.r).lines();
+
+            loop {
+                let line = next_line(&mut lines).await?;
+                match line.as_bytes() {
+                    b"OK" => break,
+                    b"list_OK" => responses.push(std::mem::take(&mut pairs)),
+                    expand!([@b"ACK ", ..]) => return Err(MpdError::parse(&line).into()),
+                    _ => {
+                        if let Some((key, value)) = line.split_once(": ") {
+                            pairs.push((key.into(), value.into()));
+                        }
+                    }
+                }
+            }
+
+            Result::<_>::Ok(responses)
+        }
+        .await
+        .context("Failed to run command list")
+    }
+}
@@ -0,0 +1,148 @@
+use std::{error::Error, fmt};
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum AckCode {
+    NotList,
+    Arg,
+    Password,
+    Permission,
+    UnknownCmd,
+    NoExist,
+    PlaylistMax,
+    System,
+    PlaylistLoad,
+    UpdateAlready,
+    PlayerSync,
+    Exist,
+    Other(u16),
+}
+
+#[derive(Debug)]
+pub struct MpdError {
+    pub code: AckCode,
+    pub index: usize,
+    pub command: String,
+    pub message: String,
+}
+
+impl From<u16> for AckCode {
+    fn from(code: u16) -> Self {
+        match code {
+            1 => AckCode::NotList,
+            2 => AckCode::Arg,
+            3 => AckCode::Password,
+            4 => AckCode::Permission,
+            5 => AckCode::UnknownCmd,
+            50 => AckCode::NoExist,
+            51 => AckCode::PlaylistMax,
+            52 => AckCode::System,
+            53 => AckCode::PlaylistLoad,
+            54 => AckCode::UpdateAlready,
+            55 => AckCode::PlayerSync,
+            56 => AckCode::Exist,
+            code => AckCode::Other(code),
+        }
+    }
+}
+
+impl From<AckCode> for u16 {
+    fn from(code: AckCode) -> Self {
+        match code {
+            AckCode::NotList => 1,
+            AckCode::Arg => 2,
+            AckCode::Password => 3,
+            AckCode::Permission => 4,
+            AckCode::UnknownCmd => 5,
+            AckCode::NoExist => 50,
+            AckCode::PlaylistMax => 51,
+            AckCode::System => 52,
+            AckCode::PlaylistLoad => 53,
+            AckCode::UpdateAlready => 54,
+            AckCode::PlayerSync => 55,
+            AckCode::Exist => 56,
+            AckCode::Other(code) => code,
+        }
+    }
+}
+
+// ACK [error@command_listNum] {current_command} message_text
+impl MpdError {
+    pub fn parse(line: &str) -> Self {
+        let line = line.strip_prefix("ACK ").unwrap_or(line);
+        let (code, index, rest) = line
+            .strip_prefix('[')
+            .and_then(|line| line.split_once(']'))
+            .and_then(|(inner, rest)| {
+                let (code, index) = inner.split_once('@')?;
+                Some((code.parse().ok()?, index.parse().ok()?, rest.trim_start()))
+            })
+            .unwrap_or((0, 0, line));
+
+        let (command, message) = rest
+            .strip_prefix('{')
+            .and_then(|rest| rest.split_once('}'))
+            .map(|(command, message)| (command, message.trim_start()))
+            .unwrap_or(("", rest));
+
+        MpdError {
+            code: code.into(),
+            index,
+            command: command.into(),
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for MpdError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.command.is_empty() {
+            write!(f, "{}", self.message)
+        } else {
+            write!(f, "{}: {}", self.command, self.message)
+        }
+    }
+}
+
+impl Error for MpdError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_a_full_ack() {
+        let e = MpdError::parse("ACK [50@2] {load} No such playlist");
+        assert_eq!(e.code, AckCode::NoExist);
+        assert_eq!(e.index, 2);
+        assert_eq!(e.command, "load");
+        assert_eq!(e.message, "No such playlist");
+        assert_eq!(e.to_string(), "load: No such playlist");
+    }
+
+    #[test]
+    fn parse_without_a_command() {
+        let e = MpdError::parse("ACK [4@0] {} you don't have permission");
+        assert_eq!(e.code, AckCode::Permission);
+        assert_eq!(e.command, "");
+        assert_eq!(e.to_string(), "you don't have permission");
+    }
+
+    #[test]
+    fn parse_unknown_codes() {
+        let e = MpdError::parse("ACK [99@0] {x} new");
+        assert_eq!(e.code, AckCode::Other(99));
+        assert_eq!(u16::from(e.code), 99);
+    }
+
+    #[test]
+    fn parse_malformed_lines() {
+        let e = MpdError::parse("ACK something broke");
+        assert_eq!(e.code, AckCode::Other(0));
+        assert_eq!(e.index, 0);
+        assert_eq!(e.message, "something broke");
+
+        let e = MpdError::parse("ACK [x@y] {status} bad");
+        assert_eq!(e.code, AckCode::Other(0));
+        assert_eq!(e.message, "[x@y] {status} bad");
+    }
+}
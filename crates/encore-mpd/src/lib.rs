@@ -0,0 +1,1687 @@
+#[cfg(unix)]
+use std::path::Path;
+use std::{collections::HashSet, time::Duration};
+
+use expand::expand;
+use eyre::{bail, Context, Result};
+#[cfg(unix)]
+use tokio::net::unix;
+use tokio::{
+    io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader, Lines},
+    net::{tcp, TcpStream, ToSocketAddrs},
+};
+
+mod command_list;
+mod error;
+mod list_all;
+#[cfg(unix)]
+mod socket;
+mod version;
+
+pub use command_list::CommandList;
+pub use error::{AckCode, MpdError};
+pub use list_all::{Entry, Listing};
+#[cfg(unix)]
+pub use socket::connect_unix;
+pub use version::{Feature, ProtocolVersion};
+
+// MPD defaults to 8KiB chunks, which takes dozens of round trips per cover
+pub const DEFAULT_BINARY_LIMIT: usize = 1024 * 1024;
+
+pub struct Client<R, W> {
+    r: BufReader<R>,
+    w: W,
+    idling: bool,
+    line: Vec<u8>,
+    changed: HashSet<Subsystem>,
+    version: ProtocolVersion,
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub enum PlayerState {
+    Play,
+    Pause,
+    Stop,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SingleMode {
+    Off,
+    On,
+    Oneshot,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ConsumeMode {
+    Off,
+    On,
+    Oneshot,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SaveMode {
+    Create,
+    Append,
+    Replace,
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Subsystem {
+    Database,
+    Update,
+    StoredPlaylist,
+    Playlist,
+    Player,
+    Mixer,
+    Output,
+    Options,
+    Partition,
+    Sticker,
+    Subscription,
+    Message,
+    Neighbor,
+    Mount,
+}
+
+#[derive(Debug)]
+pub struct Status {
+    pub repeat: bool,
+    pub random: bool,
+    pub single: SingleMode,
+    pub consume: ConsumeMode,
+    pub queue_len: usize,
+    pub queue_version: u32,
+    // None when the server has no mixer to control
+    pub volume: Option<u8>,
+    pub state: PlayerState,
+    pub song: Option<Song>,
+    pub next: Option<usize>,
+    pub xfade: u32,
+    pub partition: String,
+    // what the playing song decodes to, and its bit rate in kbps
+    pub audio: Option<AudioFormat>,
+    pub bitrate: Option<u32>,
+}
+
+#[derive(Debug)]
+pub struct Song {
+    pub pos: usize,
+    pub elapsed: u16,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Sample {
+    Bits(u8),
+    Float,
+    Dsd,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct AudioFormat {
+    // in Hz; DSD counts its one bit samples
+    pub sample_rate: u32,
+    pub sample: Sample,
+    pub channels: u8,
+}
+
+impl AudioFormat {
+    // "44100:16:2", "192000:f:2" or "dsd64:2"; formats MPD leaves open
+    // with `*` are not known yet
+    pub fn parse(format: &str) -> Option<Self> {
+        let parts: Vec<_> = format.split(':').collect();
+        match parts[..] {
+            [rate, channels] => Some(Self {
+                sample_rate: rate.strip_prefix("dsd")?.parse::<u32>().ok()? * 44100,
+                sample: Sample::Dsd,
+                channels: channels.parse().ok()?,
+            }),
+            [rate, bits, channels] => Some(Self {
+                sample_rate: rate.parse().ok()?,
+                sample: match bits {
+                    "f" => Sample::Float,
+                    "dsd" => Sample::Dsd,
+                    bits => Sample::Bits(bits.parse().ok()?),
+                },
+                channels: channels.parse().ok()?,
+            }),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Track {
+    pub file: String,
+    pub id: Option<u32>,
+    pub name: Option<String>,
+    pub artist: Option<String>,
+    pub album_artist: Option<String>,
+    pub album: Option<String>,
+    pub title: Option<String>,
+    pub track: Option<String>,
+    pub disc: Option<String>,
+    pub date: Option<String>,
+    pub genre: Option<String>,
+    pub time: u16,
+}
+
+impl Track {
+    // fills in the tag on one line of a song's response, other lines are
+    // left alone
+    fn tag(&mut self, line: &str) -> Result<()> {
+        match line.as_bytes() {
+            expand!([@b"Id: ", ..]) => self.id = Some(line[4..].parse()?),
+            expand!([@b"Name: ", ..]) => self.name = Some(line[6..].into()),
+            expand!([@b"Artist: ", ..]) => self.artist = Some(line[8..].into()),
+            expand!([@b"AlbumArtist: ", ..]) => self.album_artist = Some(line[13..].into()),
+            expand!([@b"Album: ", ..]) => self.album = Some(line[7..].into()),
+            expand!([@b"Title: ", ..]) => self.title = Some(line[7..].into()),
+            expand!([@b"Track: ", ..]) => self.track = Some(line[7..].into()),
+            expand!([@b"Disc: ", ..]) => self.disc = Some(line[6..].into()),
+            expand!([@b"Date: ", ..]) => self.date = Some(line[6..].into()),
+            expand!([@b"Genre: ", ..]) => self.genre = Some(line[7..].into()),
+            expand!([@b"Time: ", ..]) => self.time = line[6..].parse()?,
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct CurrentSong {
+    pub uri: String,
+    pub id: u32,
+    pub title: Option<String>,
+    pub name: Option<String>,
+    pub artist: Option<String>,
+    pub album_artist: Option<String>,
+    pub album: Option<String>,
+    pub track: Option<String>,
+    pub disc: Option<String>,
+    pub date: Option<String>,
+    pub genre: Option<String>,
+    pub duration: Option<u16>,
+}
+
+#[derive(Debug)]
+pub struct Output {
+    pub id: u32,
+    pub name: String,
+    pub plugin: String,
+    pub enabled: bool,
+    pub attributes: Vec<(String, String)>,
+}
+
+#[derive(Debug)]
+pub struct Decoder {
+    pub plugin: String,
+    pub suffixes: Vec<String>,
+    pub mime_types: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct Message {
+    pub channel: String,
+    pub text: String,
+}
+
+#[derive(Debug)]
+pub struct Mount {
+    pub path: String,
+    pub storage: Option<String>,
+}
+
+#[derive(Debug)]
+pub struct Neighbor {
+    pub uri: String,
+    pub name: Option<String>,
+}
+
+#[derive(Debug, Default)]
+pub struct Count {
+    pub group: Option<String>,
+    pub songs: u32,
+    pub playtime: u32,
+}
+
+impl SingleMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SingleMode::Off => "0",
+            SingleMode::On => "1",
+            SingleMode::Oneshot => "oneshot",
+        }
+    }
+}
+
+impl ConsumeMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ConsumeMode::Off => "0",
+            ConsumeMode::On => "1",
+            ConsumeMode::Oneshot => "oneshot",
+        }
+    }
+}
+
+impl SaveMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SaveMode::Create => "create",
+            SaveMode::Append => "append",
+            SaveMode::Replace => "replace",
+        }
+    }
+}
+
+impl Subsystem {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Subsystem::Database => "database",
+            Subsystem::Update => "update",
+            Subsystem::StoredPlaylist => "stored_playlist",
+            Subsystem::Playlist => "playlist",
+            Subsystem::Player => "player",
+            Subsystem::Mixer => "mixer",
+            Subsystem::Output => "output",
+            Subsystem::Options => "options",
+            Subsystem::Partition => "partition",
+            Subsystem::Sticker => "sticker",
+            Subsystem::Subscription => "subscription",
+            Subsystem::Message => "message",
+            Subsystem::Neighbor => "neighbor",
+            Subsystem::Mount => "mount",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "database" => Subsystem::Database,
+            "update" => Subsystem::Update,
+            "stored_playlist" => Subsystem::StoredPlaylist,
+            "playlist" => Subsystem::Playlist,
+            "player" => Subsystem::Player,
+            "mixer" => Subsystem::Mixer,
+            "output" => Subsystem::Output,
+            "options" => Subsystem::Options,
+            "partition" => Subsystem::Partition,
+            "sticker" => Subsystem::Sticker,
+            "subscription" => Subsystem::Subscription,
+            "message" => Subsystem::Message,
+            "neighbor" => Subsystem::Neighbor,
+            "mount" => Subsystem::Mount,
+            _ => return None,
+        })
+    }
+}
+
+impl<R, W> Client<R, W>
+where
+    R: AsyncReadExt + Unpin,
+    W: AsyncWriteExt + Unpin,
+{
+    pub async fn init_tcp_client(
+        addr: impl ToSocketAddrs,
+    ) -> Result<Client<tcp::OwnedReadHalf, tcp::OwnedWriteHalf>> {
+        let (r, w) = TcpStream::connect(addr).await?.into_split();
+        Client::init_client(r, w).await
+    }
+
+    #[cfg(unix)]
+    pub async fn init_sock_client(
+        addr: impl AsRef<Path>,
+    ) -> Result<Client<unix::OwnedReadHalf, unix::OwnedWriteHalf>> {
+        let (r, w) = connect_unix(addr).await?.into_split();
+        Client::init_client(r, w).await
+    }
+
+    pub async fn init_client(r: R, w: W) -> Result<Client<R, W>> {
+        let client = Client {
+            r: BufReader::new(r),
+            w,
+            idling: false,
+            line: Vec::new(),
+            changed: HashSet::new(),
+            version: ProtocolVersion::default(),
+        };
+
+        client.init().await
+    }
+
+    pub async fn init(mut self) -> Result<Client<R, W>> {
+        let buf = &mut [0; 7];
+        let _ = self.r.read(buf).await?;
+        if buf != b"OK MPD " {
+            bail!("server did not greet with a success");
+        }
+        let mut version = String::with_capacity(8);
+        self.r.read_line(&mut version).await?;
+        self.version = ProtocolVersion::parse(&version).unwrap_or_default();
+        Ok(self)
+    }
+
+    pub fn version(&self) -> ProtocolVersion {
+        self.version
+    }
+
+    pub fn supports(&self, feature: Feature) -> bool {
+        self.version >= feature.since()
+    }
+
+    pub async fn password(&mut self, password: &str) -> Result<()> {
+        async move {
+            self.send(b"password", &[password]).await?;
+            self.read_pairs().await?;
+            Result::<_>::Ok(())
+        }
+        .await
+        .context("Failed to authenticate")
+    }
+
+    pub async fn ping(&mut self) -> Result<()> {
+        async move {
+            self.send(b"ping", &[]).await?;
+            self.read_pairs().await?;
+            Result::<_>::Ok(())
+        }
+        .await
+        .context("Failed to ping")
+    }
+
+    pub async fn idle(&mut self, subsystems: &[Subsystem]) -> Result<HashSet<Subsystem>> {
+        self.send_idle(subsystems).await?;
+        self.wait_idle().await
+    }
+
+    pub async fn send_idle(&mut self, subsystems: &[Subsystem]) -> Result<()> {
+        let mut cmd = b"idle".to_vec();
+        for subsystem in subsystems {
+            cmd.push(b' ');
+            cmd.extend_from_slice(subsystem.as_str().as_bytes());
+        }
+        cmd.push(b'\n');
+        self.w.write_all(&cmd).await.context("Failed to idle")?;
+        self.idling = true;
+        Ok(())
+    }
+
+    // cancel safe: a partially read line and the changes seen so far are
+    // kept on the client, so dropping this future in a select! and calling
+    // noidle afterwards loses nothing
+    pub async fn wait_idle(&mut self) -> Result<HashSet<Subsystem>> {
+        async move {
+            loop {
+                if self.r.read_until(b'\n', &mut self.line).await? == 0 {
+                    bail!("connection closed");
+                }
+
+                let line = std::mem::take(&mut self.line);
+                match line.trim_ascii_end() {
+                    b"OK" => {
+                        self.idling = false;
+                        return Ok(std::mem::take(&mut self.changed));
+                    }
+                    expand!([@b"ACK ", ..]) => {
+                        self.idling = false;
+                        let line = String::from_utf8_lossy(&line);
+                        return Err(MpdError::parse(line.trim_end()).into());
+                    }
+                    expand!([@b"changed: ", ..]) => {
+                        let name = String::from_utf8_lossy(&line[9..]);
+                        if let Some(subsystem) = Subsystem::from_name(name.trim_end()) {
+                            self.changed.insert(subsystem);
+                        }
+                    }
+                    _ => continue,
+                }
+            }
+        }
+        .await
+        .context("Failed to idle")
+    }
+
+    pub fn is_idling(&self) -> bool {
+        self.idling
+    }
+
+    pub async fn noidle(&mut self) -> Result<HashSet<Subsystem>> {
+        if !self.idling {
+            return Ok(HashSet::new());
+        }
+
+        self.w
+            .write_all(b"noidle\n")
+            .await
+            .context("Failed to cancel idle")?;
+        self.wait_idle().await
+    }
+
+    pub async fn queue(&mut self, len: usize) -> Result<Vec<Track>> {
+        async move {
+            self.w.write_all(b"playlistinfo\n").await?;
+            self.read_tracks(len).await
+        }
+        .await
+        .context("Failed to query queue")
+    }
+
+    pub async fn queue_find(&mut self, filter: &str) -> Result<Vec<Track>> {
+        async move {
+            self.send(b"playlistfind", &[filter]).await?;
+            self.read_tracks(0).await
+        }
+        .await
+        .context("Failed to search queue")
+    }
+
+    pub async fn queue_search(&mut self, filter: &str) -> Result<Vec<Track>> {
+        async move {
+            self.send(b"playlistsearch", &[filter]).await?;
+            self.read_tracks(0).await
+        }
+        .await
+        .context("Failed to search queue")
+    }
+
+    pub async fn playlists(&mut self) -> Result<Vec<String>> {
+        async move {
+            self.send(b"listplaylists", &[]).await?;
+            let playlists = self
+                .read_pairs()
+                .await?
+                .into_iter()
+                .filter(|(key, _)| key == "playlist")
+                .map(|(_, name)| name)
+                .collect();
+
+            Result::<_>::Ok(playlists)
+        }
+        .await
+        .context("Failed to list playlists")
+    }
+
+    pub async fn playlist(&mut self, name: &str) -> Result<Vec<Track>> {
+        async move {
+            self.send(b"listplaylistinfo", &[name]).await?;
+            self.read_tracks(0).await
+        }
+        .await
+        .context("Failed to query playlist")
+    }
+
+    // appends the playlist to the queue
+    pub async fn load(&mut self, name: &str) -> Result<()> {
+        async move {
+            self.send(b"load", &[name]).await?;
+            self.read_pairs().await?;
+            Result::<_>::Ok(())
+        }
+        .await
+        .context("Failed to load playlist")
+    }
+
+    pub async fn playlist_add(&mut self, name: &str, uri: &str) -> Result<()> {
+        async move {
+            self.send(b"playlistadd", &[name, uri]).await?;
+            self.read_pairs().await?;
+            Result::<_>::Ok(())
+        }
+        .await
+        .context("Failed to add to playlist")
+    }
+
+    pub async fn delete_playlist(&mut self, name: &str) -> Result<()> {
+        async move {
+            self.send(b"rm", &[name]).await?;
+            self.read_pairs().await?;
+            Result::<_>::Ok(())
+        }
+        .await
+        .context("Failed to delete playlist")
+    }
+
+    pub async fn save(&mut self, name: &str, mode: SaveMode) -> Result<()> {
+        async move {
+            if self.supports(Feature::SaveMode) {
+                self.send(b"save", &[name, mode.as_str()]).await?;
+                self.read_pairs().await?;
+                return Result::<_>::Ok(());
+            }
+
+            match mode {
+                SaveMode::Create => {}
+                SaveMode::Append => {
+                    let mut list = CommandList::new();
+                    for track in self.queue(0).await? {
+                        list.push(b"playlistadd", &[name, &track.file])?;
+                    }
+                    self.run(&list).await?;
+                    return Ok(());
+                }
+                SaveMode::Replace => {
+                    self.send(b"rm", &[name]).await?;
+                    if let Err(e) = self.read_pairs().await {
+                        match e.downcast_ref::<MpdError>() {
+                            Some(MpdError {
+                                code: AckCode::NoExist,
+                                ..
+                            }) => {}
+                            _ => return Err(e),
+                        }
+                    }
+                }
+            }
+
+            self.send(b"save", &[name]).await?;
+            self.read_pairs().await?;
+            Ok(())
+        }
+        .await
+        .context("Failed to save playlist")
+    }
+
+    pub async fn status(&mut self) -> Result<Status> {
+        async move {
+            let mut repeat = None;
+            let mut random = None;
+            let mut single = None;
+            let mut consume = None;
+            let mut queue_len = None;
+            let mut queue_version = 0;
+            let mut volume = None;
+            let mut state = PlayerState::Stop;
+            let mut partition = None;
+            let mut pos = None;
+            let mut elapsed = None;
+            let mut next = None;
+            let mut xfade = 0;
+            let mut audio = None;
+            let mut bitrate = None;
+
+            self.w.write_all(b"status\n").await?;
+            let mut lines = (&mut self.r).lines();
+
+            loop {
+                let line = next_line(&mut lines).await?;
+                match line.as_bytes() {
+                    b"OK" => break,
+                    expand!([@b"ACK ", ..]) => return Err(MpdError::parse(&line).into()),
+                    b"repeat: 0" => repeat = Some(false),
+                    b"repeat: 1" => repeat = Some(true),
+                    b"random: 0" => random = Some(false),
+                    b"random: 1" => random = Some(true),
+                    b"single: 0" => single = Some(SingleMode::Off),
+                    b"single: 1" => single = Some(SingleMode::On),
+                    b"single: oneshot" => single = Some(SingleMode::Oneshot),
+                    b"consume: 0" => consume = Some(ConsumeMode::Off),
+                    b"consume: 1" => consume = Some(ConsumeMode::On),
+                    b"consume: oneshot" => consume = Some(ConsumeMode::Oneshot),
+                    expand!([@b"playlistlength: ", ..]) => queue_len = Some(line[16..].parse()?),
+                    expand!([@b"playlist: ", ..]) => queue_version = line[10..].parse()?,
+                    expand!([@b"volume: ", ..]) => volume = line[8..].parse().ok(),
+                    b"state: play" => state = PlayerState::Play,
+                    b"state: pause" => state = PlayerState::Pause,
+                    expand!([@b"partition: ", ..]) => partition = Some(line[11..].into()),
+                    expand!([@b"song: ", ..]) => pos = Some(line[6..].parse()?),
+                    expand!([@b"elapsed: ", ..]) => {
+                        elapsed = Some(line[9..].parse::<f32>()?.round() as u16)
+                    }
+                    expand!([@b"nextsong: ", ..]) => next = Some(line[10..].parse()?),
+                    expand!([@b"xfade: ", ..]) => xfade = line[7..].parse()?,
+                    expand!([@b"audio: ", ..]) => audio = AudioFormat::parse(&line[7..]),
+                    // streams without one report 0
+                    expand!([@b"bitrate: ", ..]) => {
+                        bitrate = line[9..].parse().ok().filter(|&kbps| kbps > 0)
+                    }
+                    _ => continue,
+                }
+            }
+
+            if let (Some(repeat), Some(random), Some(single), Some(consume), Some(queue_len)) =
+                (repeat, random, single, consume, queue_len)
+            {
+                Ok(Status {
+                    repeat,
+                    random,
+                    single,
+                    consume,
+                    queue_len,
+                    queue_version,
+                    volume,
+                    state,
+                    song: if let (Some(pos), Some(elapsed)) = (pos, elapsed) {
+                        Some(Song { pos, elapsed })
+                    } else {
+                        None
+                    },
+                    next,
+                    xfade,
+                    partition: partition.unwrap_or_else(|| "default".into()),
+                    audio,
+                    bitrate,
+                })
+            } else {
+                bail!("incomplete status response");
+            }
+        }
+        .await
+        .context("Failed to query status")
+    }
+
+    // when the database last changed, as a unix time, 0 if it never did
+    pub async fn db_update(&mut self) -> Result<u64> {
+        async move {
+            self.send(b"stats", &[]).await?;
+            let pairs = self.read_pairs().await?;
+            let update = pairs.into_iter().find(|(key, _)| key == "db_update");
+            Result::<_>::Ok(
+                update
+                    .and_then(|(_, value)| value.parse().ok())
+                    .unwrap_or(0),
+            )
+        }
+        .await
+        .context("Failed to read stats")
+    }
+
+    pub async fn read_comments(&mut self, uri: &str) -> Result<Vec<(String, String)>> {
+        async move {
+            self.send(b"readcomments", &[uri]).await?;
+            self.read_pairs().await
+        }
+        .await
+        .context("Failed to read comments")
+    }
+
+    pub async fn sticker_get(&mut self, uri: &str, name: &str) -> Result<Option<String>> {
+        async move {
+            let mut value = None;
+
+            self.send(b"sticker get song", &[uri, name]).await?;
+            let mut lines = (&mut self.r).lines();
+
+            loop {
+                let line = next_line(&mut lines).await?;
+                match line.as_bytes() {
+                    b"OK" => break,
+                    expand!([@b"ACK ", ..]) => {
+                        let e = MpdError::parse(&line);
+                        if e.code == AckCode::NoExist {
+                            return Result::<_>::Ok(None);
+                        }
+                        return Err(e.into());
+                    }
+                    expand!([@b"sticker: ", ..]) => {
+                        value = line[9..].split_once('=').map(|(_, v)| v.into())
+                    }
+                    _ => continue,
+                }
+            }
+
+            Ok(value)
+        }
+        .await
+        .context("Failed to get sticker")
+    }
+
+    pub async fn sticker_set(&mut self, uri: &str, name: &str, value: &str) -> Result<()> {
+        async move {
+            self.send(b"sticker set song", &[uri, name, value]).await?;
+            self.read_pairs().await?;
+            Result::<_>::Ok(())
+        }
+        .await
+        .context("Failed to set sticker")
+    }
+
+    pub async fn sticker_delete(&mut self, uri: &str, name: Option<&str>) -> Result<()> {
+        async move {
+            match name {
+                Some(name) => self.send(b"sticker delete song", &[uri, name]).await?,
+                None => self.send(b"sticker delete song", &[uri]).await?,
+            }
+            self.read_pairs().await?;
+            Result::<_>::Ok(())
+        }
+        .await
+        .context("Failed to delete sticker")
+    }
+
+    pub async fn sticker_list(&mut self, uri: &str) -> Result<Vec<(String, String)>> {
+        async move {
+            self.send(b"sticker list song", &[uri]).await?;
+            let stickers = self
+                .read_pairs()
+                .await?
+                .into_iter()
+                .filter(|(key, _)| key == "sticker")
+                .filter_map(|(_, sticker)| {
+                    sticker
+                        .split_once('=')
+                        .map(|(name, value)| (name.into(), value.into()))
+                })
+                .collect();
+
+            Result::<_>::Ok(stickers)
+        }
+        .await
+        .context("Failed to list stickers")
+    }
+
+    pub async fn sticker_find(&mut self, dir: &str, name: &str) -> Result<Vec<(String, String)>> {
+        async move {
+            let mut stickers = Vec::new();
+            let mut file = None;
+
+            self.send(b"sticker find song", &[dir, name]).await?;
+            for (key, value) in self.read_pairs().await? {
+                match key.as_str() {
+                    "file" => file = Some(value),
+                    "sticker" => {
+                        if let (Some(file), Some((_, value))) = (file.take(), value.split_once('='))
+                        {
+                            stickers.push((file, value.into()));
+                        }
+                    }
+                    _ => continue,
+                }
+            }
+
+            Result::<_>::Ok(stickers)
+        }
+        .await
+        .context("Failed to find stickers")
+    }
+
+    pub async fn fingerprint(&mut self, uri: &str) -> Result<String> {
+        async move {
+            self.send(b"getfingerprint", &[uri]).await?;
+            let pairs = self.read_pairs().await?;
+            let Some((_, chromaprint)) = pairs.into_iter().find(|(key, _)| key == "chromaprint")
+            else {
+                bail!("server returned no chromaprint");
+            };
+
+            Result::<_>::Ok(chromaprint)
+        }
+        .await
+        .context("Failed to get fingerprint")
+    }
+
+    pub async fn current_song(&mut self) -> Result<Option<CurrentSong>> {
+        async move {
+            let mut song = CurrentSong::default();
+            let mut found = false;
+
+            self.send(b"currentsong", &[]).await?;
+            for (key, value) in self.read_pairs().await? {
+                found = true;
+                match key.as_str() {
+                    "file" => song.uri = value,
+                    "Id" => song.id = value.parse()?,
+                    "Title" => song.title = Some(value),
+                    "Name" => song.name = Some(value),
+                    "Artist" => song.artist = Some(value),
+                    "AlbumArtist" => song.album_artist = Some(value),
+                    "Album" => song.album = Some(value),
+                    "Track" => song.track = Some(value),
+                    "Disc" => song.disc = Some(value),
+                    "Date" => song.date = Some(value),
+                    "Genre" => song.genre = Some(value),
+                    "duration" => song.duration = Some(value.parse::<f32>()?.round() as u16),
+                    "Time" if song.duration.is_none() => song.duration = Some(value.parse()?),
+                    _ => continue,
+                }
+            }
+
+            Result::<_>::Ok(found.then_some(song))
+        }
+        .await
+        .context("Failed to query current song")
+    }
+
+    pub async fn play(&mut self, pos: usize) -> Result<()> {
+        self.send(b"play", &[&pos.to_string()]).await?;
+        let mut lines = (&mut self.r).lines();
+
+        loop {
+            let line = next_line(&mut lines).await?;
+            match line.as_bytes() {
+                b"OK" => break,
+                expand!([@b"ACK ", ..]) => return Err(MpdError::parse(&line).into()),
+                _ => continue,
+            }
+        }
+
+        Ok(())
+    }
+
+    pub async fn set_volume(&mut self, volume: u8) -> Result<()> {
+        async move {
+            self.send(b"setvol", &[&volume.min(100).to_string()])
+                .await?;
+            self.read_pairs().await?;
+            Result::<_>::Ok(())
+        }
+        .await
+        .context("Failed to set volume")
+    }
+
+    pub async fn play_id(&mut self, id: u32) -> Result<()> {
+        async move {
+            self.send(b"playid", &[&id.to_string()]).await?;
+            self.read_pairs().await?;
+            Result::<_>::Ok(())
+        }
+        .await
+        .context("Failed to play song")
+    }
+
+    pub async fn seek_id(&mut self, id: u32, time: Duration) -> Result<()> {
+        async move {
+            let time = format!("{:.3}", time.as_secs_f64());
+            self.send(b"seekid", &[&id.to_string(), &time]).await?;
+            self.read_pairs().await?;
+            Result::<_>::Ok(())
+        }
+        .await
+        .context("Failed to seek")
+    }
+
+    pub async fn set_repeat(&mut self, repeat: bool) -> Result<()> {
+        async move {
+            self.send(b"repeat", &[if repeat { "1" } else { "0" }])
+                .await?;
+            self.read_pairs().await?;
+            Result::<_>::Ok(())
+        }
+        .await
+        .context("Failed to set repeat")
+    }
+
+    pub async fn set_random(&mut self, random: bool) -> Result<()> {
+        async move {
+            self.send(b"random", &[if random { "1" } else { "0" }])
+                .await?;
+            self.read_pairs().await?;
+            Result::<_>::Ok(())
+        }
+        .await
+        .context("Failed to set random")
+    }
+
+    pub async fn set_single(&mut self, mode: SingleMode) -> Result<()> {
+        async move {
+            self.send(b"single", &[mode.as_str()]).await?;
+            self.read_pairs().await?;
+            Result::<_>::Ok(())
+        }
+        .await
+        .context("Failed to set single mode")
+    }
+
+    pub async fn set_consume(&mut self, mode: ConsumeMode) -> Result<()> {
+        async move {
+            // plain consume would keep deleting played songs, so refuse instead
+            if mode == ConsumeMode::Oneshot && !self.supports(Feature::ConsumeOneshot) {
+                bail!(
+                    "consume oneshot requires MPD {}, server is {}",
+                    Feature::ConsumeOneshot.since(),
+                    self.version
+                );
+            }
+            self.send(b"consume", &[mode.as_str()]).await?;
+            self.read_pairs().await?;
+            Result::<_>::Ok(())
+        }
+        .await
+        .context("Failed to set consume mode")
+    }
+
+    pub async fn clear(&mut self) -> Result<()> {
+        async move {
+            self.send(b"clear", &[]).await?;
+            self.read_pairs().await?;
+            Result::<_>::Ok(())
+        }
+        .await
+        .context("Failed to clear queue")
+    }
+
+    pub async fn add(&mut self, uri: &str) -> Result<()> {
+        async move {
+            self.send(b"add", &[uri]).await?;
+            self.read_pairs().await?;
+            Result::<_>::Ok(())
+        }
+        .await
+        .context("Failed to add to queue")
+    }
+
+    pub async fn add_id(&mut self, uri: &str) -> Result<u32> {
+        async move {
+            self.send(b"addid", &[uri]).await?;
+            let pairs = self.read_pairs().await?;
+            let Some((_, id)) = pairs.iter().find(|(key, _)| key == "Id") else {
+                bail!("missing Id in addid response");
+            };
+            Result::<_>::Ok(id.parse()?)
+        }
+        .await
+        .context("Failed to add to queue")
+    }
+
+    // distinct values of `tag`, optionally among songs matching `filter`
+    pub async fn list(&mut self, tag: &str, filter: Option<&str>) -> Result<Vec<String>> {
+        async move {
+            match filter {
+                Some(filter) => self.send(b"list", &[tag, filter]).await?,
+                None => self.send(b"list", &[tag]).await?,
+            }
+
+            let values = self
+                .read_pairs()
+                .await?
+                .into_iter()
+                .filter(|(key, _)| key.eq_ignore_ascii_case(tag))
+                .map(|(_, value)| value)
+                .collect();
+            Result::<_>::Ok(values)
+        }
+        .await
+        .context("Failed to list tags")
+    }
+
+    pub async fn find(&mut self, filter: &str) -> Result<Vec<Track>> {
+        async move {
+            self.send(b"find", &[filter]).await?;
+            self.read_tracks(0).await
+        }
+        .await
+        .context("Failed to search database")
+    }
+
+    // like find, but ignoring case
+    pub async fn search(&mut self, filter: &str) -> Result<Vec<Track>> {
+        async move {
+            self.send(b"search", &[filter]).await?;
+            self.read_tracks(0).await
+        }
+        .await
+        .context("Failed to search database")
+    }
+
+    pub async fn search_add(&mut self, filter: &str) -> Result<()> {
+        async move {
+            self.send(b"searchadd", &[filter]).await?;
+            self.read_pairs().await?;
+            Result::<_>::Ok(())
+        }
+        .await
+        .context("Failed to add to queue")
+    }
+
+    pub async fn find_add(&mut self, filter: &str) -> Result<()> {
+        async move {
+            self.send(b"findadd", &[filter]).await?;
+            self.read_pairs().await?;
+            Result::<_>::Ok(())
+        }
+        .await
+        .context("Failed to add to queue")
+    }
+
+    pub async fn add_tag_id(&mut self, id: u32, tag: &str, value: &str) -> Result<()> {
+        async move {
+            self.send(b"addtagid", &[&id.to_string(), tag, value])
+                .await?;
+            self.read_pairs().await?;
+            Result::<_>::Ok(())
+        }
+        .await
+        .context("Failed to add tag")
+    }
+
+    pub async fn clear_tag_id(&mut self, id: u32, tag: Option<&str>) -> Result<()> {
+        async move {
+            match tag {
+                Some(tag) => self.send(b"cleartagid", &[&id.to_string(), tag]).await?,
+                None => self.send(b"cleartagid", &[&id.to_string()]).await?,
+            }
+            self.read_pairs().await?;
+            Result::<_>::Ok(())
+        }
+        .await
+        .context("Failed to clear tags")
+    }
+
+    pub async fn outputs(&mut self) -> Result<Vec<Output>> {
+        async move {
+            let mut outputs = Vec::new();
+
+            self.send(b"outputs", &[]).await?;
+            for (key, value) in self.read_pairs().await? {
+                match (key.as_str(), outputs.last_mut()) {
+                    ("outputid", _) => outputs.push(Output {
+                        id: value.parse()?,
+                        name: String::new(),
+                        plugin: String::new(),
+                        enabled: false,
+                        attributes: Vec::new(),
+                    }),
+                    ("outputname", Some(output)) => output.name = value,
+                    ("plugin", Some(output)) => output.plugin = value,
+                    ("outputenabled", Some(output)) => output.enabled = value == "1",
+                    ("attribute", Some(output)) => {
+                        if let Some((name, value)) = value.split_once('=') {
+                            output.attributes.push((name.into(), value.into()));
+                        }
+                    }
+                    _ => continue,
+                }
+            }
+
+            Result::<_>::Ok(outputs)
+        }
+        .await
+        .context("Failed to query outputs")
+    }
+
+    pub async fn enable_output(&mut self, id: u32) -> Result<()> {
+        self.output_command(b"enableoutput", id)
+            .await
+            .context("Failed to enable output")
+    }
+
+    pub async fn disable_output(&mut self, id: u32) -> Result<()> {
+        self.output_command(b"disableoutput", id)
+            .await
+            .context("Failed to disable output")
+    }
+
+    pub async fn toggle_output(&mut self, id: u32) -> Result<()> {
+        self.output_command(b"toggleoutput", id)
+            .await
+            .context("Failed to toggle output")
+    }
+
+    pub async fn output_set(&mut self, id: u32, name: &str, value: &str) -> Result<()> {
+        async move {
+            self.send(b"outputset", &[&id.to_string(), name, value])
+                .await?;
+            self.read_pairs().await?;
+            Result::<_>::Ok(())
+        }
+        .await
+        .context("Failed to set output attribute")
+    }
+
+    pub async fn partitions(&mut self) -> Result<Vec<String>> {
+        async move {
+            self.send(b"listpartitions", &[]).await?;
+            let partitions = self
+                .read_pairs()
+                .await?
+                .into_iter()
+                .filter(|(key, _)| key == "partition")
+                .map(|(_, name)| name)
+                .collect();
+
+            Result::<_>::Ok(partitions)
+        }
+        .await
+        .context("Failed to list partitions")
+    }
+
+    pub async fn new_partition(&mut self, name: &str) -> Result<()> {
+        self.partition_command(b"newpartition", name)
+            .await
+            .context("Failed to create partition")
+    }
+
+    pub async fn switch_partition(&mut self, name: &str) -> Result<()> {
+        self.partition_command(b"partition", name)
+            .await
+            .context("Failed to switch partition")
+    }
+
+    pub async fn delete_partition(&mut self, name: &str) -> Result<()> {
+        self.partition_command(b"delpartition", name)
+            .await
+            .context("Failed to delete partition")
+    }
+
+    // songs matching `filter` exactly and how long they play, per value of
+    // `group` if given
+    pub async fn count(&mut self, filter: &str, group: Option<&str>) -> Result<Vec<Count>> {
+        self.counts(b"count", filter, group)
+            .await
+            .context("Failed to count songs")
+    }
+
+    pub async fn search_count(&mut self, filter: &str, group: Option<&str>) -> Result<Vec<Count>> {
+        self.counts(b"searchcount", filter, group)
+            .await
+            .context("Failed to count search results")
+    }
+
+    async fn counts(
+        &mut self,
+        cmd: &[u8],
+        filter: &str,
+        group: Option<&str>,
+    ) -> Result<Vec<Count>> {
+        match group {
+            Some(group) => self.send(cmd, &[filter, "group", group]).await?,
+            None => self.send(cmd, &[filter]).await?,
+        }
+
+        let mut counts = Vec::<Count>::new();
+        for (key, value) in self.read_pairs().await? {
+            match (key.as_str(), counts.last_mut()) {
+                ("songs", Some(count)) => count.songs = value.parse()?,
+                ("playtime", Some(count)) => count.playtime = value.parse()?,
+                ("songs", None) => counts.push(Count {
+                    songs: value.parse()?,
+                    ..Count::default()
+                }),
+                ("playtime", None) => counts.push(Count {
+                    playtime: value.parse()?,
+                    ..Count::default()
+                }),
+                _ => counts.push(Count {
+                    group: Some(value),
+                    ..Count::default()
+                }),
+            }
+        }
+
+        Ok(counts)
+    }
+
+    pub async fn mounts(&mut self) -> Result<Vec<Mount>> {
+        async move {
+            let mut mounts = Vec::new();
+
+            self.send(b"listmounts", &[]).await?;
+            for (key, value) in self.read_pairs().await? {
+                match (key.as_str(), mounts.last_mut()) {
+                    ("mount", _) => mounts.push(Mount {
+                        path: value,
+                        storage: None,
+                    }),
+                    ("storage", Some(mount)) => mount.storage = Some(value),
+                    _ => continue,
+                }
+            }
+
+            Result::<_>::Ok(mounts)
+        }
+        .await
+        .context("Failed to list mounts")
+    }
+
+    pub async fn mount(&mut self, path: &str, uri: &str) -> Result<()> {
+        async move {
+            self.send(b"mount", &[path, uri]).await?;
+            self.read_pairs().await?;
+            Result::<_>::Ok(())
+        }
+        .await
+        .context("Failed to mount storage")
+    }
+
+    pub async fn unmount(&mut self, path: &str) -> Result<()> {
+        async move {
+            self.send(b"unmount", &[path]).await?;
+            self.read_pairs().await?;
+            Result::<_>::Ok(())
+        }
+        .await
+        .context("Failed to unmount storage")
+    }
+
+    pub async fn neighbors(&mut self) -> Result<Vec<Neighbor>> {
+        async move {
+            let mut neighbors = Vec::new();
+
+            self.send(b"listneighbors", &[]).await?;
+            for (key, value) in self.read_pairs().await? {
+                match (key.as_str(), neighbors.last_mut()) {
+                    ("neighbor", _) => neighbors.push(Neighbor {
+                        uri: value,
+                        name: None,
+                    }),
+                    ("name", Some(neighbor)) => neighbor.name = Some(value),
+                    _ => continue,
+                }
+            }
+
+            Result::<_>::Ok(neighbors)
+        }
+        .await
+        .context("Failed to list neighbors")
+    }
+
+    pub async fn subscribe(&mut self, channel: &str) -> Result<()> {
+        async move {
+            self.send(b"subscribe", &[channel]).await?;
+            self.read_pairs().await?;
+            Result::<_>::Ok(())
+        }
+        .await
+        .context("Failed to subscribe to channel")
+    }
+
+    pub async fn unsubscribe(&mut self, channel: &str) -> Result<()> {
+        async move {
+            self.send(b"unsubscribe", &[channel]).await?;
+            self.read_pairs().await?;
+            Result::<_>::Ok(())
+        }
+        .await
+        .context("Failed to unsubscribe from channel")
+    }
+
+    pub async fn channels(&mut self) -> Result<Vec<String>> {
+        async move {
+            self.send(b"channels", &[]).await?;
+            let channels = self
+                .read_pairs()
+                .await?
+                .into_iter()
+                .filter(|(key, _)| key == "channel")
+                .map(|(_, channel)| channel)
+                .collect();
+
+            Result::<_>::Ok(channels)
+        }
+        .await
+        .context("Failed to list channels")
+    }
+
+    pub async fn send_message(&mut self, channel: &str, text: &str) -> Result<()> {
+        async move {
+            self.send(b"sendmessage", &[channel, text]).await?;
+            self.read_pairs().await?;
+            Result::<_>::Ok(())
+        }
+        .await
+        .context("Failed to send message")
+    }
+
+    pub async fn read_messages(&mut self) -> Result<Vec<Message>> {
+        async move {
+            let mut messages = Vec::new();
+            let mut channel = None;
+
+            self.send(b"readmessages", &[]).await?;
+            for (key, value) in self.read_pairs().await? {
+                match key.as_str() {
+                    "channel" => channel = Some(value),
+                    "message" => {
+                        if let Some(channel) = channel.clone() {
+                            messages.push(Message {
+                                channel,
+                                text: value,
+                            });
+                        }
+                    }
+                    _ => continue,
+                }
+            }
+
+            Result::<_>::Ok(messages)
+        }
+        .await
+        .context("Failed to read messages")
+    }
+
+    // a cover file next to the song, none when there is no such file
+    pub async fn album_art(&mut self, uri: &str) -> Result<Option<Vec<u8>>> {
+        async move {
+            match self.read_binary(b"albumart", uri).await {
+                Err(e) => match e.downcast::<MpdError>() {
+                    Ok(e) if e.code == AckCode::NoExist => Ok(None),
+                    Ok(e) => Err(e.into()),
+                    Err(e) => Err(e),
+                },
+                data => data,
+            }
+        }
+        .await
+        .context("Failed to read album art")
+    }
+
+    // a picture embedded in the song's tags
+    pub async fn read_picture(&mut self, uri: &str) -> Result<Option<Vec<u8>>> {
+        self.read_binary(b"readpicture", uri)
+            .await
+            .context("Failed to read picture")
+    }
+
+    pub async fn binary_limit(&mut self, bytes: usize) -> Result<()> {
+        async move {
+            self.send(b"binarylimit", &[&bytes.to_string()]).await?;
+            self.read_pairs().await?;
+            Result::<_>::Ok(())
+        }
+        .await
+        .context("Failed to set binary limit")
+    }
+
+    pub async fn decoders(&mut self) -> Result<Vec<Decoder>> {
+        async move {
+            let mut decoders = Vec::new();
+
+            self.send(b"decoders", &[]).await?;
+            for (key, value) in self.read_pairs().await? {
+                match (key.as_str(), decoders.last_mut()) {
+                    ("plugin", _) => decoders.push(Decoder {
+                        plugin: value,
+                        suffixes: Vec::new(),
+                        mime_types: Vec::new(),
+                    }),
+                    ("suffix", Some(decoder)) => decoder.suffixes.push(value),
+                    ("mime_type", Some(decoder)) => decoder.mime_types.push(value),
+                    _ => continue,
+                }
+            }
+
+            Result::<_>::Ok(decoders)
+        }
+        .await
+        .context("Failed to query decoders")
+    }
+
+    pub async fn url_handlers(&mut self) -> Result<Vec<String>> {
+        async move {
+            self.send(b"urlhandlers", &[]).await?;
+            let handlers = self
+                .read_pairs()
+                .await?
+                .into_iter()
+                .filter(|(key, _)| key == "handler")
+                .map(|(_, handler)| handler)
+                .collect();
+
+            Result::<_>::Ok(handlers)
+        }
+        .await
+        .context("Failed to query url handlers")
+    }
+
+    pub async fn command(&mut self, cmd: &[u8]) -> Result<()> {
+        self.w.write_all(cmd).await?;
+        self.w.write_all(b"\n").await?;
+        let mut lines = (&mut self.r).lines();
+
+        loop {
+            let line = next_line(&mut lines).await?;
+            match line.as_bytes() {
+                b"OK" => break,
+                expand!([@b"ACK ", ..]) => return Err(MpdError::parse(&line).into()),
+                _ => continue,
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn output_command(&mut self, cmd: &[u8], id: u32) -> Result<()> {
+        self.send(cmd, &[&id.to_string()]).await?;
+        self.read_pairs().await?;
+        Ok(())
+    }
+
+    async fn partition_command(&mut self, cmd: &[u8], name: &str) -> Result<()> {
+        self.send(cmd, &[name]).await?;
+        self.read_pairs().await?;
+        Ok(())
+    }
+
+    async fn send(&mut self, cmd: &[u8], args: &[&str]) -> Result<()> {
+        let mut buf = Vec::new();
+        encode(&mut buf, cmd, args)?;
+        self.w.write_all(&buf).await?;
+        Ok(())
+    }
+
+    async fn read_tracks(&mut self, len: usize) -> Result<Vec<Track>> {
+        let mut tracks = Vec::with_capacity(len);
+        let mut track: Option<Track> = None;
+
+        let mut lines = (&mut self.r).lines();
+
+        loop {
+            let line = next_line(&mut lines).await?;
+            match line.as_bytes() {
+                b"OK" => break,
+                expand!([@b"ACK ", ..]) => return Err(MpdError::parse(&line).into()),
+                expand!([@b"file: ", ..]) => {
+                    tracks.extend(track.take());
+                    track = Some(Track {
+                        file: line[6..].into(),
+                        ..Track::default()
+                    });
+                }
+                _ => match &mut track {
+                    Some(track) => track.tag(&line)?,
+                    None => bail!("incomplete playlist response"),
+                },
+            }
+        }
+
+        tracks.extend(track);
+        Ok(tracks)
+    }
+
+    // binary responses come in chunks of at most the binary limit, each asked
+    // for at the offset reached so far
+    async fn read_binary(&mut self, cmd: &[u8], uri: &str) -> Result<Option<Vec<u8>>> {
+        let mut data = Vec::new();
+        loop {
+            self.send(cmd, &[uri, &data.len().to_string()]).await?;
+            let Some((size, chunk)) = self.read_chunk().await? else {
+                return Ok(None);
+            };
+
+            if chunk.is_empty() && data.len() < size {
+                bail!("binary response stopped at {} of {size} bytes", data.len());
+            }
+            data.extend_from_slice(&chunk);
+            if data.len() >= size {
+                return Ok(Some(data));
+            }
+        }
+    }
+
+    // the total size and the data of one chunk, none for an empty response
+    async fn read_chunk(&mut self) -> Result<Option<(usize, Vec<u8>)>> {
+        let mut size = 0;
+        let mut line = String::new();
+
+        loop {
+            line.clear();
+            if self.r.read_line(&mut line).await? == 0 {
+                bail!("connection closed");
+            }
+
+            let line = line.trim_end();
+            match line.as_bytes() {
+                b"OK" => return Ok(None),
+                expand!([@b"ACK ", ..]) => return Err(MpdError::parse(line).into()),
+                expand!([@b"size: ", ..]) => size = line[6..].parse()?,
+                expand!([@b"binary: ", ..]) => {
+                    let mut chunk = vec![0; line[8..].parse()?];
+                    self.r.read_exact(&mut chunk).await?;
+                    // a newline ends the data, then the usual OK
+                    self.r.read_u8().await?;
+                    self.read_pairs().await?;
+                    return Ok(Some((size, chunk)));
+                }
+                _ => continue,
+            }
+        }
+    }
+
+    async fn read_pairs(&mut self) -> Result<Vec<(String, String)>> {
+        let mut pairs = Vec::new();
+        let mut lines = (&mut self.r).lines();
+
+        loop {
+            let line = next_line(&mut lines).await?;
+            match line.as_bytes() {
+                b"OK" => break,
+                expand!([@b"ACK ", ..]) => return Err(MpdError::parse(&line).into()),
+                _ => {
+                    if let Some((key, value)) = line.split_once(": ") {
+                        pairs.push((key.into(), value.into()));
+                    }
+                }
+            }
+        }
+
+        Ok(pairs)
+    }
+}
+
+// a response only ends with OK or ACK, the connection going away before
+// that leaves it cut short rather than empty
+async fn next_line<R: AsyncBufRead + Unpin>(lines: &mut Lines<R>) -> Result<String> {
+    match lines.next_line().await? {
+        Some(line) => Ok(line),
+        None => bail!("connection closed"),
+    }
+}
+
+fn encode(buf: &mut Vec<u8>, cmd: &[u8], args: &[&str]) -> Result<()> {
+    if let Some(arg) = args.iter().find(|arg| arg.contains(['\n', '\r'])) {
+        bail!("argument contains a line break: {arg:?}");
+    }
+
+    buf.extend_from_slice(cmd);
+    for arg in args {
+        buf.extend_from_slice(b" \"");
+        for &byte in arg.as_bytes() {
+            if byte == b'"' || byte == b'\\' {
+                buf.push(b'\\');
+            }
+            buf.push(byte);
+        }
+        buf.push(b'"');
+    }
+    buf.push(b'\n');
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::{duplex, split, DuplexStream, ReadHalf, WriteHalf};
+
+    use super::*;
+
+    type Fake = Client<ReadHalf<DuplexStream>, WriteHalf<DuplexStream>>;
+
+    // a server that greets, answers the first command with `reply` and hangs up
+    async fn answering(reply: &'static [u8]) -> Fake {
+        let (client, mut server) = duplex(1024);
+        server.write_all(b"OK MPD 0.24.0\n").await.unwrap();
+        tokio::spawn(async move {
+            let mut command = String::new();
+            BufReader::new(&mut server)
+                .read_line(&mut command)
+                .await
+                .unwrap();
+            server.write_all(reply).await.unwrap();
+        });
+
+        let (r, w) = split(client);
+        Client::init_client(r, w).await.unwrap()
+    }
+
+    fn encoded(cmd: &[u8], args: &[&str]) -> Result<String> {
+        let mut buf = Vec::new();
+        encode(&mut buf, cmd, args)?;
+        Ok(String::from_utf8(buf).unwrap())
+    }
+
+    #[test]
+    fn encode_quotes_every_argument() {
+        assert_eq!(encoded(b"status", &[]).unwrap(), "status\n");
+        assert_eq!(
+            encoded(b"find", &["artist", "Sigur Rós"]).unwrap(),
+            "find \"artist\" \"Sigur Rós\"\n"
+        );
+        assert_eq!(encoded(b"add", &[""]).unwrap(), "add \"\"\n");
+    }
+
+    #[test]
+    fn encode_escapes_quotes_and_backslashes() {
+        assert_eq!(
+            encoded(b"find", &[r#"(artist == "AC\DC")"#]).unwrap(),
+            "find \"(artist == \\\"AC\\\\DC\\\")\"\n"
+        );
+    }
+
+    #[test]
+    fn encode_refuses_line_breaks() {
+        assert!(encoded(b"add", &["a\nclear"]).is_err());
+        assert!(encoded(b"add", &["a\r"]).is_err());
+        assert!(encoded(b"add", &["ok", "a\nb"]).is_err());
+    }
+
+    #[test]
+    fn command_list_names_its_commands() {
+        let mut list = CommandList::new();
+        list.push(b"clear", &[]).unwrap();
+        list.push(b"load", &["a \"b\""]).unwrap();
+        assert_eq!(list.names().collect::<Vec<_>>(), ["clear", "load"]);
+    }
+
+    #[test]
+    fn audio_format_parse() {
+        assert_eq!(
+            AudioFormat::parse("44100:16:2"),
+            Some(AudioFormat {
+                sample_rate: 44100,
+                sample: Sample::Bits(16),
+                channels: 2,
+            })
+        );
+        assert_eq!(
+            AudioFormat::parse("192000:f:2"),
+            Some(AudioFormat {
+                sample_rate: 192000,
+                sample: Sample::Float,
+                channels: 2,
+            })
+        );
+        assert_eq!(
+            AudioFormat::parse("dsd64:2"),
+            Some(AudioFormat {
+                sample_rate: 64 * 44100,
+                sample: Sample::Dsd,
+                channels: 2,
+            })
+        );
+        assert_eq!(AudioFormat::parse("*:*:*"), None);
+        assert_eq!(AudioFormat::parse("44100:16"), None);
+        assert_eq!(AudioFormat::parse(""), None);
+    }
+
+    #[tokio::test]
+    async fn a_response_cut_short_is_an_error() {
+        let mut client = answering(b"volume: 50\nrepeat: 0\n").await;
+        assert!(client.status().await.is_err());
+
+        let mut client = answering(b"").await;
+        assert!(client.command(b"play").await.is_err());
+
+        let mut client = answering(b"file: a.flac\n").await;
+        assert!(client.queue(1).await.is_err());
+
+        let mut client = answering(b"OK\n").await;
+        assert!(client.command(b"play").await.is_ok());
+    }
+}
@@ -0,0 +1,133 @@
+use expand::expand;
+use eyre::{bail, Context, Result};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt};
+
+use crate::{Client, MpdError, Track};
+
+#[derive(Clone, Debug)]
+pub enum Entry {
+    Directory(String),
+    File(Box<Track>),
+    Playlist(String),
+}
+
+// yields database entries as they arrive instead of collecting the whole
+// library; it has to be read to the end before the client is used again
+#[must_use]
+pub struct Listing<'a, R, W> {
+    client: &'a mut Client<R, W>,
+    pending: Option<String>,
+    done: bool,
+}
+
+impl<R, W> Client<R, W>
+where
+    R: AsyncReadExt + Unpin,
+    W: AsyncWriteExt + Unpin,
+{
+    pub async fn list_all(&mut self, path: Option<&str>) -> Result<Listing<'_, R, W>> {
+        self.listing(b"listall", path)
+            .await
+            .context("Failed to list database")
+    }
+
+    pub async fn list_all_info(&mut self, path: Option<&str>) -> Result<Listing<'_, R, W>> {
+        self.listing(b"listallinfo", path)
+            .await
+            .context("Failed to list database")
+    }
+
+    // one level of the tree only, unlike the two above
+    pub async fn lsinfo(&mut self, path: Option<&str>) -> Result<Listing<'_, R, W>> {
+        self.listing(b"lsinfo", path)
+            .await
+            .context("Failed to list directory")
+    }
+
+    async fn listing(&mut self, cmd: &[u8], path: Option<&str>) -> Result<Listing<'_, R, W>> {
+        match path {
+            Some(path) => self.send(cmd, &[path]).await?,
+            None => self.send(cmd, &[]).await?,
+        }
+
+        Ok(Listing {
+            client: self,
+            pending: None,
+            done: false,
+        })
+    }
+}
+
+impl<R, W> Listing<'_, R, W>
+where
+    R: AsyncReadExt + Unpin,
+    W: AsyncWriteExt + Unpin,
+{
+    pub async fn next(&mut self) -> Result<Option<Entry>> {
+        if self.done {
+            return Ok(None);
+        }
+
+        async move {
+            let mut entry = None;
+
+            loop {
+                let line = match self.pending.take() {
+                    Some(line) => line,
+                    None => self.read_line().await?,
+                };
+
+                match line.as_bytes() {
+                    b"OK" => {
+                        self.done = true;
+                        return Result::<_>::Ok(entry);
+                    }
+                    expand!([@b"ACK ", ..]) => {
+                        self.done = true;
+                        return Err(MpdError::parse(&line).into());
+                    }
+                    expand!([@b"file: ", ..])
+                    | expand!([@b"directory: ", ..])
+                    | expand!([@b"playlist: ", ..])
+                        if entry.is_some() =>
+                    {
+                        self.pending = Some(line);
+                        return Result::<_>::Ok(entry);
+                    }
+                    expand!([@b"file: ", ..]) => {
+                        entry = Some(Entry::File(Box::new(Track {
+                            file: line[6..].into(),
+                            ..Track::default()
+                        })))
+                    }
+                    expand!([@b"directory: ", ..]) => {
+                        entry = Some(Entry::Directory(line[11..].into()))
+                    }
+                    expand!([@b"playlist: ", ..]) => {
+                        entry = Some(Entry::Playlist(line[10..].into()))
+                    }
+                    _ => {
+                        let Some(Entry::File(track)) = &mut entry else {
+                            continue;
+                        };
+
+                        track.tag(&line)?;
+                    }
+                }
+            }
+        }
+        .await
+        .context("Failed to read database listing")
+    }
+
+    async fn read_line(&mut self) -> Result<String> {
+        let mut line = String::new();
+        if self.client.r.read_line(&mut line).await? == 0 {
+            self.done = true;
+            bail!("connection closed");
+        }
+
+        line.truncate(line.trim_end_matches('\n').len());
+        Ok(line)
+    }
+}
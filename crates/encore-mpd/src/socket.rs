@@ -0,0 +1,33 @@
+use std::{os::unix::ffi::OsStrExt, path::Path};
+
+use eyre::Result;
+use tokio::net::UnixStream;
+
+// MPD can bind to the abstract namespace on linux, written `@name` like in
+// its own config; a literal leading NUL is accepted as well
+pub async fn connect_unix(path: impl AsRef<Path>) -> Result<UnixStream> {
+    let path = path.as_ref();
+    match path.as_os_str().as_bytes() {
+        [b'@' | b'\0', name @ ..] => connect_abstract(name),
+        _ => Ok(UnixStream::connect(path).await?),
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn connect_abstract(name: &[u8]) -> Result<UnixStream> {
+    #[cfg(target_os = "android")]
+    use std::os::android::net::SocketAddrExt;
+    #[cfg(target_os = "linux")]
+    use std::os::linux::net::SocketAddrExt;
+    use std::os::unix::net::{self, SocketAddr};
+
+    // connecting to a local socket does not block, so std's connect is fine
+    let stream = net::UnixStream::connect_addr(&SocketAddr::from_abstract_name(name)?)?;
+    stream.set_nonblocking(true)?;
+    Ok(UnixStream::from_std(stream)?)
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+fn connect_abstract(_: &[u8]) -> Result<UnixStream> {
+    eyre::bail!("abstract sockets are only supported on linux")
+}
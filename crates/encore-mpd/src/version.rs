@@ -0,0 +1,64 @@
+use std::fmt;
+
+#[derive(Clone, Copy, Debug, Default, Eq, Ord, PartialEq, PartialOrd)]
+pub struct ProtocolVersion {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Feature {
+    AlbumArt,
+    Filters,
+    SingleOneshot,
+    Partitions,
+    ReadPicture,
+    Fingerprint,
+    BinaryLimit,
+    ConsumeOneshot,
+    SearchCount,
+    SaveMode,
+}
+
+impl ProtocolVersion {
+    pub const fn new(major: u32, minor: u32, patch: u32) -> Self {
+        Self {
+            major,
+            minor,
+            patch,
+        }
+    }
+
+    pub(crate) fn parse(s: &str) -> Option<Self> {
+        let mut parts = s.trim().splitn(3, '.').map(|part| part.parse().ok());
+        Some(Self::new(
+            parts.next()??,
+            parts.next()??,
+            parts.next().flatten().unwrap_or(0),
+        ))
+    }
+}
+
+impl fmt::Display for ProtocolVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+impl Feature {
+    // first protocol version that understands the command or argument
+    pub fn since(&self) -> ProtocolVersion {
+        match self {
+            Feature::AlbumArt
+            | Feature::Filters
+            | Feature::SingleOneshot
+            | Feature::Fingerprint => ProtocolVersion::new(0, 21, 0),
+            Feature::Partitions | Feature::ReadPicture => ProtocolVersion::new(0, 22, 0),
+            Feature::BinaryLimit => ProtocolVersion::new(0, 22, 4),
+            Feature::ConsumeOneshot | Feature::SearchCount | Feature::SaveMode => {
+                ProtocolVersion::new(0, 24, 0)
+            }
+        }
+    }
+}
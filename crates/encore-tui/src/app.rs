@@ -0,0 +1,2316 @@
+use std::{
+    cmp::{Ordering, Reverse},
+    collections::{hash_map::RandomState, BTreeMap, HashSet},
+    future::{pending, Future},
+    hash::{BuildHasher, Hasher},
+    io::{self, stdout},
+    path::{Path, PathBuf},
+    pin::Pin,
+    sync::Arc,
+    time::Duration,
+};
+
+use encore_core::{
+    attribution::{self, Attribution, Origin},
+    cache::Cache,
+    config::{self, Config, Timeouts},
+    connection::{Connection, Endpoint},
+    events, inbox,
+    perf::Perf,
+    session::{Instance, Session},
+    trash::{Trash, TrashError, Trashed},
+};
+use encore_mpd::{
+    CommandList, ConsumeMode, CurrentSong, Entry, Feature, MpdError, Output, PlayerState, SaveMode,
+    SingleMode, Status, Subsystem, Track, DEFAULT_BINARY_LIMIT,
+};
+use eyre::Result;
+use image::RgbaImage;
+use ratatui::{
+    backend::Backend,
+    buffer::Buffer,
+    crossterm::event::{Event, EventStream, KeyCode, KeyEventKind, KeyModifiers},
+    layout::{Constraint, Layout, Rect},
+    text::Line,
+    widgets::Clear,
+    DefaultTerminal, Frame, Terminal,
+};
+use tokio::{
+    runtime::Handle,
+    sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender},
+    task::JoinHandle,
+    time::{sleep, sleep_until, timeout, Instant},
+};
+use tokio_stream::{Stream, StreamExt};
+
+use crate::{
+    art::{self, Art, Protocol},
+    bulk::{self, Source},
+    changes::{self, Changes},
+    command::{self, Command, Seek, Setting, Sort, Volume},
+    connecting::{self, Connecting},
+    covers::Covers,
+    details::{self, Details},
+    event_log::Log,
+    files::{self, Files},
+    flags, header, help,
+    info::{Info, Stats},
+    later::{self, Later},
+    library::{self, Library, Node},
+    lyrics::{self, Lyrics},
+    menu::Menu,
+    nav::{self, Rows},
+    notification::Notifier,
+    outputs::{self, Outputs},
+    panes::Panes,
+    partitions::{self, Partitions},
+    perf,
+    playlists::{self, Item, Playlists},
+    power::Power,
+    progress,
+    prompt::{self, Confirm, Note, Prompt},
+    queue::{self, Queue},
+    ratings::{self, Ratings},
+    remote::{self, Remote},
+    schedule::{Pane, Scheduler},
+    search::{self, Focus, Search},
+    status_bar::{self, Link},
+    tabs::{self, Tab},
+    theme::Theme,
+    toast::{self, Toasts},
+    trash::{self, Bin},
+    visualizer::{self, Visualizer},
+    volume,
+    watcher::{Change, Watcher},
+};
+
+#[cfg(test)]
+mod tests;
+
+const RETRY: Duration = Duration::from_secs(2);
+const MAX_RETRY: Duration = Duration::from_secs(60);
+// goes up whenever what is cached changes shape
+const CACHED: u32 = 1;
+
+type Connector =
+    Arc<dyn Fn(Endpoint) -> Pin<Box<dyn Future<Output = Result<Session>> + Send>> + Send + Sync>;
+
+enum ConnectEvent {
+    Failed(Endpoint, String),
+    Connected(Endpoint, Box<Session>, Box<Update>),
+}
+
+// what a round trip learned about the server, each part only if it changed;
+// the current song is always read along with the status
+#[derive(Default)]
+struct Update {
+    status: Option<Status>,
+    song: Option<CurrentSong>,
+    queue: Option<Vec<Track>>,
+    database: bool,
+    playlists: bool,
+    stickers: bool,
+    outputs: bool,
+    partitions: bool,
+}
+
+// user actions that need the server
+enum Action {
+    Play(usize),
+    SetVolume(u8),
+    SetRepeat(bool),
+    SetRandom(bool),
+    SetSingle(SingleMode),
+    SetConsume(ConsumeMode),
+    Browse(Option<Node>),
+    List(Option<String>),
+    Load(String),
+    Replace(String),
+    View(Option<String>),
+    Delete(String),
+    Save(String),
+    Search(String),
+    SearchAdd(String),
+    AddPlay(String),
+    Run(CommandList),
+    // a list that adds to or takes from the queue, told by how much
+    Edit(CommandList),
+    AddTo(String, CommandList),
+    Art(String),
+    // what to check a stream URL against before asking for one
+    UrlHandlers,
+    Ratings,
+    Sort(Sort),
+    Details(String),
+    Info(Node),
+    Outputs,
+    Partitions,
+    SwitchPartition(String),
+    NewPartition(String),
+    DeletePartition(String),
+    // the playlists in the trash
+    Trash,
+    Restore(Trashed),
+    // the songs flagged to listen to later
+    Flag(String),
+    Later,
+    Enqueue(String),
+}
+
+// what an action brought back to show
+enum Reply {
+    Done,
+    Library(Vec<Node>),
+    Files(Vec<Entry>),
+    Playlists(Vec<Item>),
+    Search(Vec<Track>),
+    Art(String, Option<RgbaImage>),
+    UrlHandlers(Vec<String>),
+    Ratings(Vec<(String, String)>),
+    Details(String, Vec<(String, String)>),
+    // the filter counted, and what was found
+    Info(String, Stats),
+    Outputs(Vec<Output>),
+    Partitions(Vec<String>),
+    Trash(Vec<Trashed>),
+    // the song, and whether it is flagged now
+    Flagged(String, bool),
+    Later(Vec<String>),
+    // everything the partition switched to has
+    Switched(String, Box<Update>),
+    // the queue's length once changed
+    Queued(usize),
+}
+
+// what the last draw left on screen and where the panes that tick on their
+// own went, so a tick only draws its pane again over the rest
+#[derive(Default)]
+struct Drawn {
+    buffer: Buffer,
+    lyrics: Option<Rect>,
+    visualizer: Option<Rect>,
+}
+
+pub struct App {
+    should_quit: bool,
+    is_playing: bool,
+    status: Option<Status>,
+    status_at: Instant,
+    song: Option<CurrentSong>,
+    tab: Tab,
+    queue: Queue,
+    library: Library,
+    info: Info,
+    files: Files,
+    playlists: Playlists,
+    search: Search,
+    lyrics: Lyrics,
+    visualizer: Visualizer,
+    panes: Panes,
+    theme: Theme,
+    prompt: Option<Prompt>,
+    details: Option<Details>,
+    outputs: Option<Outputs>,
+    partitions: Option<Partitions>,
+    bin: Option<Bin>,
+    later: Option<Later>,
+    changes: Option<Changes>,
+    music_dir: Option<PathBuf>,
+    cache: Option<Cache>,
+    // where deleted playlists go
+    trash: Option<Trash>,
+    toasts: Toasts,
+    notifier: Notifier,
+    art: Art,
+    covers: Covers,
+    perf: Perf,
+    show_perf: bool,
+    show_help: bool,
+    keys: nav::Keys,
+    schedule: Scheduler,
+    // the panes due when the loop last woke for them alone
+    ticked: Vec<Pane>,
+    drawn: Drawn,
+    endpoint: Endpoint,
+    client: Option<Session>,
+    instance: Instance,
+    connecting: Connecting,
+    connect: Option<JoinHandle<()>>,
+    connector: Connector,
+    connect_tx: UnboundedSender<ConnectEvent>,
+    connect_rx: UnboundedReceiver<ConnectEvent>,
+    timeouts: Timeouts,
+    volume_step: u8,
+    hosts: BTreeMap<String, String>,
+    profiles: config::Profiles,
+    confirm: config::Confirm,
+    seek: config::Seek,
+    active: Instant,
+    remote: Remote,
+    watcher: Watcher,
+    power: Power,
+    // `[power]`, for the servers without a profile
+    power_config: config::Power,
+    log: Log,
+    // who made the changes the server reports
+    attribution: Attribution,
+}
+
+impl App {
+    pub fn new(endpoint: Endpoint, config: &Config, theme: Theme, remote: Remote) -> Self {
+        let (connect_tx, connect_rx) = unbounded_channel();
+        let power = Power::new(config.power_for(&endpoint));
+        let mut visualizer = Visualizer::new(config.visualizer.clone());
+        if config.layout.visualizer {
+            visualizer.toggle();
+        }
+        let mut toasts = Toasts::default();
+        let watcher = Watcher::start(config).unwrap_or_else(|e| {
+            toasts.error(format!("Not watching for changes: {e}"));
+            Watcher::default()
+        });
+        let log = Log::start(config.event_log.as_deref()).unwrap_or_else(|e| {
+            toasts.error(format!("Not logging events: {e}"));
+            Log::default()
+        });
+        Self {
+            should_quit: false,
+            is_playing: false,
+            status: None,
+            status_at: Instant::now(),
+            song: None,
+            tab: Tab::default(),
+            queue: Queue::new(config.queue.columns.clone()),
+            library: Library::default(),
+            info: Info::default(),
+            files: Files::default(),
+            playlists: Playlists::default(),
+            search: Search::default(),
+            lyrics: Lyrics::new(config.music_dir.clone(), config.lyrics_dir.clone()),
+            visualizer,
+            panes: Panes::new(&config.layout),
+            theme,
+            prompt: None,
+            details: None,
+            outputs: None,
+            partitions: None,
+            bin: None,
+            later: None,
+            changes: None,
+            music_dir: config.music_dir.clone(),
+            cache: Cache::open(&config.cache),
+            trash: Trash::open(),
+            toasts,
+            notifier: Notifier::start(&config.notifications),
+            art: Art::new(Protocol::pick(config.art), Box::new(stdout())),
+            covers: Covers::start(config),
+            perf: Perf::default(),
+            show_perf: false,
+            show_help: false,
+            keys: nav::Keys::default(),
+            schedule: Scheduler::default(),
+            ticked: Vec::new(),
+            drawn: Drawn::default(),
+            endpoint,
+            client: None,
+            instance: Instance::default(),
+            connecting: Connecting::default(),
+            connect: None,
+            connector: Arc::new(|endpoint| {
+                Box::pin(async move { Session::connect(&endpoint).await })
+            }),
+            connect_tx,
+            connect_rx,
+            timeouts: config.timeout,
+            volume_step: config.volume_step,
+            hosts: config.hosts.clone(),
+            profiles: config.profiles.clone(),
+            confirm: config.confirm,
+            seek: config.seek,
+            active: Instant::now(),
+            remote,
+            watcher,
+            power,
+            power_config: config.power,
+            log,
+            attribution: Attribution::default(),
+        }
+    }
+
+    pub async fn run(mut self, mut terminal: DefaultTerminal) -> Result<()> {
+        let mut events = EventStream::new();
+        if let Err(e) = self.power.start().await {
+            self.toasts.error(format!("{e:#}"));
+        }
+
+        while !self.should_quit {
+            self.step(&mut terminal, &mut events).await?;
+        }
+
+        self.art.clear()?;
+        Ok(())
+    }
+
+    // one pass of the loop: draw, then handle whichever source is ready first
+    async fn step<B, S>(&mut self, terminal: &mut Terminal<B>, events: &mut S) -> Result<()>
+    where
+        B: Backend,
+        S: Stream<Item = io::Result<Event>> + Unpin,
+    {
+        if self.client.is_none() && self.connect.is_none() {
+            self.connect = Some(spawn_connect(
+                self.endpoint.clone(),
+                self.connector.clone(),
+                self.timeouts,
+                self.connect_tx.clone(),
+            ));
+        }
+
+        let progress = self.is_playing.then_some(Duration::from_secs(1));
+        self.schedule.set(Pane::Progress, progress);
+        // synced lyrics move on within the second
+        let synced = self.is_playing && self.tab == Tab::Lyrics && self.lyrics.is_synced();
+        let lyrics = synced.then_some(Duration::from_millis(250));
+        self.schedule.set(Pane::Lyrics, lyrics);
+        let animated = self.is_playing && self.visualizer.shown;
+        let visualizer = animated.then_some(visualizer::EVERY);
+        self.schedule.set(Pane::Visualizer, visualizer);
+
+        let start = Instant::now();
+        let ticked = std::mem::take(&mut self.ticked);
+        let mut full = false;
+        let frame = terminal.draw(|f| {
+            full = !self.redraw(f, &ticked);
+            if full {
+                self.draw(f);
+            }
+        })?;
+        self.drawn.buffer.clone_from(frame.buffer);
+        if full && self.art.flush()? {
+            terminal.clear()?;
+            let frame = terminal.draw(|f| self.draw(f))?;
+            self.drawn.buffer.clone_from(frame.buffer);
+            self.art.flush()?;
+        }
+        self.perf.record_frame(start.elapsed());
+
+        tokio::select! {
+            biased;
+            Some(Ok(event)) = events.next() => {
+                if let Some(action) = self.handle_event(&event) {
+                    self.handle_action(action).await;
+                }
+            }
+            Some(event) = self.connect_rx.recv() => self.handle_connect(event).await,
+            changed = wait_idle(self.client.as_mut()) => self.handle_idle(changed).await,
+            Some(request) = self.remote.recv() => self.handle_remote(request).await,
+            Some(change) = self.watcher.recv() => self.handle_change(change),
+            Some(sleeping) = self.power.sleeping() => self.handle_sleep(sleeping).await,
+            Some((uri, cover)) = self.covers.recv() => {
+                self.notifier.cover(&uri, cover.as_ref());
+                self.art.set(&uri, cover);
+            }
+            Some(filter) = self.search.settled(), if self.client.is_some() => {
+                self.handle_action(Action::Search(filter)).await
+            }
+            Some(list) = self.queue.shifted(), if self.client.is_some() => {
+                self.handle_action(Action::Run(list)).await
+            }
+            _ = self.toasts.expired() => {}
+            _ = sleep_until(self.active + self.timeouts.keepalive), if self.client.is_some() => {
+                self.handle_keepalive().await
+            }
+            panes = self.schedule.wait() => {
+                for &pane in &panes {
+                    self.handle_tick(pane);
+                }
+                self.ticked = panes;
+            },
+        }
+
+        // one load can make way for the next, like an artist's numbers once
+        // the artists are in
+        while let Some(action) = self.wanted() {
+            self.ticked.clear();
+            self.handle_action(action).await;
+        }
+        if let Err(e) = self.power.update(self.is_playing).await {
+            self.toasts.error(format!("{e:#}"));
+        }
+
+        Ok(())
+    }
+
+    // draws only the lyrics or the visualizer when nothing but their tick
+    // woke the loop, unless something drawn on top of them or a resize asks
+    // for the whole screen
+    fn redraw(&mut self, frame: &mut Frame, ticked: &[Pane]) -> bool {
+        let covered = self.overlay() || self.show_help || self.show_perf || !self.toasts.is_empty();
+        if ticked.is_empty() || covered || frame.area() != self.drawn.buffer.area {
+            return false;
+        }
+
+        let areas: Option<Vec<_>> = ticked
+            .iter()
+            .map(|pane| match pane {
+                Pane::Progress => None,
+                Pane::Lyrics => self.drawn.lyrics,
+                Pane::Visualizer => self.drawn.visualizer,
+            })
+            .collect();
+        let Some(areas) = areas else {
+            return false;
+        };
+
+        let theme = &self.theme;
+        let buffer = frame.buffer_mut();
+        buffer.content.clone_from(&self.drawn.buffer.content);
+        for (pane, area) in ticked.iter().zip(areas) {
+            frame.render_widget(Clear, area);
+            match pane {
+                Pane::Lyrics => {
+                    let elapsed = self.elapsed();
+                    lyrics::draw(&mut self.lyrics, elapsed, theme, frame, area)
+                }
+                _ => visualizer::draw(&mut self.visualizer, self.is_playing, theme, frame, area),
+            }
+        }
+        true
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let theme = &self.theme;
+        self.art.area = Rect::default();
+        self.drawn.lyrics = None;
+        self.drawn.visualizer = None;
+
+        // once connected, losing the server keeps the last known view around
+        // and only the status bar reports the reconnect
+        match self.link() {
+            None => connecting::draw(&self.connecting, &self.endpoint, theme, frame),
+            Some(link) => {
+                let [top, _, gauge, _, tab_bar, main, bar] = Layout::vertical([
+                    Constraint::Length(header::HEIGHT),
+                    Constraint::Length(1),
+                    Constraint::Length(1),
+                    Constraint::Length(1),
+                    Constraint::Length(1),
+                    Constraint::Fill(1),
+                    Constraint::Length(1),
+                ])
+                .areas(frame.area());
+                let [top, modes] =
+                    Layout::horizontal([Constraint::Fill(1), Constraint::Length(flags::WIDTH)])
+                        .spacing(2)
+                        .areas(top);
+                let status = self.status.as_ref();
+                header::draw(self.song.as_ref(), status, theme, frame, top);
+                if let Some(status) = &self.status {
+                    flags::draw(status, &self.queue.tracks, theme, frame, modes);
+                }
+
+                let current = self.status.as_ref().and_then(|status| status.song.as_ref());
+                if current.is_some() {
+                    progress::draw(self.elapsed(), self.duration(), theme, frame, gauge);
+                }
+
+                let following = self.tab == Tab::Queue && self.queue.follow;
+                let [tab_bar, mode] = Layout::horizontal([
+                    Constraint::Fill(1),
+                    Constraint::Length(if following { 9 } else { 0 }),
+                ])
+                .spacing(1)
+                .areas(tab_bar);
+                tabs::draw(self.tab, theme, frame, tab_bar);
+                if following {
+                    frame.render_widget(Line::styled("following", theme.muted), mode);
+                }
+                // the cover takes a pane once there is one, and hides while
+                // an overlay would end up under it
+                let (mut main, pane) = self.panes.split_art(main, self.art.has_cover());
+                if !self.show_help && !self.show_perf && !self.overlay() {
+                    self.art.draw(frame, pane);
+                }
+                if self.visualizer.shown {
+                    let (rest, pane) = self.panes.split_visualizer(main);
+                    visualizer::draw(&mut self.visualizer, self.is_playing, theme, frame, pane);
+                    self.drawn.visualizer = Some(pane);
+                    main = rest;
+                }
+                self.info.fit(main);
+
+                match self.tab {
+                    Tab::Queue => {
+                        let current = current.map(|song| song.pos);
+                        let elapsed = self.elapsed();
+                        queue::draw(&mut self.queue, current, elapsed, theme, frame, main)
+                    }
+                    Tab::Library => {
+                        library::draw(&mut self.library, &mut self.info, theme, frame, main)
+                    }
+                    Tab::Files => files::draw(&mut self.files, theme, frame, main),
+                    Tab::Playlists => playlists::draw(&mut self.playlists, theme, frame, main),
+                    Tab::Search => search::draw(&mut self.search, theme, frame, main),
+                    Tab::Lyrics => {
+                        let elapsed = self.elapsed();
+                        lyrics::draw(&mut self.lyrics, elapsed, theme, frame, main);
+                        self.drawn.lyrics = Some(main);
+                    }
+                }
+
+                // the mixer can only be shown and changed while connected
+                let mixer = match link {
+                    Link::Connected => volume::WIDTH,
+                    _ => 0,
+                };
+                let [bar, mixer] =
+                    Layout::horizontal([Constraint::Fill(1), Constraint::Length(mixer)])
+                        .spacing(2)
+                        .areas(bar);
+                match &self.prompt {
+                    Some(prompt) => prompt::draw(prompt, theme, frame, bar),
+                    None => {
+                        let connecting = &self.connecting;
+                        let connected = status_bar::Connected {
+                            partition: self.status.as_ref().map(|s| s.partition.as_str()),
+                            awake: self.power.is_awake(),
+                        };
+                        let endpoint = &self.endpoint;
+                        status_bar::draw(link, connecting, endpoint, connected, theme, frame, bar)
+                    }
+                }
+                if link == Link::Connected {
+                    let volume = self.status.as_ref().and_then(|status| status.volume);
+                    volume::draw(volume, theme, frame, mixer);
+                }
+            }
+        }
+
+        if let Some(details) = &mut self.details {
+            details::draw(details, theme, frame);
+        }
+
+        if let Some(outputs) = &mut self.outputs {
+            outputs::draw(outputs, theme, frame);
+        }
+
+        if let Some(partitions) = &mut self.partitions {
+            let current = self.status.as_ref().map_or("", |status| &status.partition);
+            partitions::draw(partitions, current, theme, frame);
+        }
+
+        if let Some(bin) = &mut self.bin {
+            trash::draw(bin, theme, frame);
+        }
+
+        if let Some(later) = &mut self.later {
+            later::draw(later, theme, frame);
+        }
+
+        if let Some(changes) = &mut self.changes {
+            changes::draw(changes, &self.attribution.history, theme, frame);
+        }
+
+        toast::draw(&self.toasts, theme, frame);
+
+        if self.show_perf {
+            perf::draw(&self.perf, theme, frame);
+        }
+
+        if self.show_help {
+            help::draw(self.tab, theme, frame);
+        }
+    }
+
+    // a popup that the cover and the ticking panes would end up under
+    fn overlay(&self) -> bool {
+        self.details.is_some()
+            || self.outputs.is_some()
+            || self.partitions.is_some()
+            || self.bin.is_some()
+            || self.later.is_some()
+            || self.changes.is_some()
+    }
+
+    // the status only has whole seconds as of the last refresh, so playback
+    // is extrapolated from there until the next one
+    fn elapsed(&self) -> Duration {
+        let Some(song) = self.status.as_ref().and_then(|status| status.song.as_ref()) else {
+            return Duration::ZERO;
+        };
+
+        let elapsed = Duration::from_secs(song.elapsed.into());
+        if self.is_playing {
+            elapsed + self.status_at.elapsed()
+        } else {
+            elapsed
+        }
+    }
+
+    fn playing(&self) -> Option<usize> {
+        Some(self.status.as_ref()?.song.as_ref()?.pos)
+    }
+
+    fn duration(&self) -> Option<u16> {
+        let pos = self.status.as_ref()?.song.as_ref()?.pos;
+        let song = self.song.as_ref().and_then(|song| song.duration);
+        song.or_else(|| self.queue.tracks.get(pos).map(|track| track.time))
+    }
+
+    fn link(&self) -> Option<Link> {
+        if self.client.is_some() {
+            return Some(Link::Connected);
+        }
+
+        match &self.connecting {
+            Connecting { input: Some(_), .. }
+            | Connecting {
+                reconnecting: false,
+                ..
+            } => None,
+            Connecting { attempts: 0, .. } => Some(Link::Reconnecting),
+            _ => Some(Link::Offline),
+        }
+    }
+
+    fn handle_event(&mut self, event: &Event) -> Option<Action> {
+        self.perf.record_event();
+
+        let Event::Key(key) = event else {
+            return None;
+        };
+
+        if key.kind != KeyEventKind::Press {
+            return None;
+        }
+
+        if let Some(input) = &mut self.connecting.input {
+            match key.code {
+                KeyCode::Char(c) => input.push(c),
+                KeyCode::Backspace => {
+                    input.pop();
+                }
+                KeyCode::Enter => {
+                    if let Some(endpoint) = Endpoint::parse(input) {
+                        self.retarget(endpoint);
+                    }
+                }
+                KeyCode::Esc => self.connecting.input = None,
+                _ => {}
+            }
+            return None;
+        }
+
+        if self.show_help {
+            match key.code {
+                KeyCode::Esc | KeyCode::Char('?') => self.show_help = false,
+                KeyCode::Tab => self.tab = self.tab.cycle(true),
+                KeyCode::BackTab => self.tab = self.tab.cycle(false),
+                _ => {}
+            }
+            return None;
+        }
+
+        if self.details.is_some() {
+            return self.handle_details_key(key.code);
+        }
+
+        if self.outputs.is_some() {
+            return self.handle_outputs_key(key.code);
+        }
+
+        if self.bin.is_some() {
+            return self.handle_trash_key(key.code);
+        }
+
+        if self.later.is_some() {
+            return self.handle_later_key(key.code);
+        }
+
+        if self.changes.is_some() {
+            self.handle_changes_key(key.code);
+            return None;
+        }
+
+        if self.prompt.is_some() {
+            return self.handle_prompt_key(key.code);
+        }
+
+        // after the prompt, which asks before a partition is deleted
+        if self.partitions.is_some() {
+            return self.handle_partitions_key(key.code);
+        }
+
+        // an input takes every key but the ones to leave the tab
+        let typing = match self.tab {
+            Tab::Queue => self
+                .queue
+                .filter
+                .as_ref()
+                .is_some_and(|filter| filter.typing),
+            Tab::Search => self.search.focus == Focus::Input,
+            _ => false,
+        };
+        if typing && !matches!(key.code, KeyCode::Tab | KeyCode::BackTab) {
+            match self.tab {
+                Tab::Queue => self.handle_filter_input(key.code),
+                _ => self.handle_search_input(key.code),
+            }
+            return None;
+        }
+
+        // Esc drops the marks, then the queue filter, before it quits
+        if key.code == KeyCode::Esc {
+            let marks = self.list().map(|list| list.marks());
+            if let Some(marks) = marks.filter(|marks| !marks.is_empty()) {
+                marks.clear();
+                return None;
+            }
+            if self.tab == Tab::Queue && self.queue.filter.is_some() {
+                self.queue.unfilter();
+                return None;
+            }
+        }
+
+        if let Some(list) = self.list() {
+            if nav::mark(list, key.code) {
+                return None;
+            }
+        }
+
+        if let Some(motion) = self.keys.motion(key) {
+            match self.tab {
+                Tab::Lyrics => self.lyrics.scroll(motion),
+                _ => {
+                    if let Some(list) = self.list() {
+                        nav::go(list, motion);
+                    }
+                }
+            }
+            return None;
+        }
+
+        if self.keys.is_pending() {
+            return None;
+        }
+
+        let shift = key.modifiers.contains(KeyModifiers::SHIFT);
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => self.should_quit = true,
+            KeyCode::Char('e') if self.client.is_none() => {
+                self.connecting.input = Some(self.endpoint.to_string());
+            }
+            KeyCode::F(12) => self.show_perf = !self.show_perf,
+            KeyCode::F(7) if self.client.is_some() => return Some(Action::Partitions),
+            KeyCode::F(8) if self.client.is_some() => return Some(Action::Outputs),
+            KeyCode::Char('?') => self.show_help = true,
+            KeyCode::Char(':') => self.prompt = Some(Prompt::Command(String::new(), None)),
+            KeyCode::Char('*') if self.client.is_some() => self.prompt = self.rating(),
+            KeyCode::Char('i') if self.client.is_some() => {
+                return self.under_cursor().map(Action::Details)
+            }
+            KeyCode::Char('w') if self.client.is_some() => {
+                return self.under_cursor().map(Action::Flag)
+            }
+            KeyCode::Char('W') if self.client.is_some() => return Some(Action::Later),
+            KeyCode::Char('v') => self.visualizer.toggle(),
+            KeyCode::Char('b') if self.visualizer.shown => self.visualizer.switch(),
+            KeyCode::Char('c') => self.panes.art = !self.panes.art,
+            KeyCode::Char('<') => self.panes.resize_art(false),
+            KeyCode::Char('>') => self.panes.resize_art(true),
+            KeyCode::Char('[') if self.visualizer.shown => self.panes.resize_visualizer(false),
+            KeyCode::Char(']') if self.visualizer.shown => self.panes.resize_visualizer(true),
+            KeyCode::Char('|') => self.panes.flip(),
+            KeyCode::Char('o') => {
+                if let Some(pos) = self.playing() {
+                    if self.queue.row(pos).is_none() {
+                        self.queue.unfilter();
+                    }
+                    self.tab = Tab::Queue;
+                    self.queue.reveal(pos);
+                }
+            }
+            KeyCode::Tab => self.tab = self.tab.cycle(true),
+            KeyCode::BackTab => self.tab = self.tab.cycle(false),
+            KeyCode::Char(c @ '1'..='9') => {
+                if let Some(tab) = Tab::from_key(c) {
+                    self.tab = tab;
+                }
+            }
+            KeyCode::Char('+') if self.client.is_some() => return self.change_volume(true),
+            KeyCode::Char('-') if self.client.is_some() => return self.change_volume(false),
+            KeyCode::Char('r' | 'z' | 'y' | 'R') if self.client.is_some() => {
+                return self.toggle_flag(key.code)
+            }
+            // the tabs that browse with the arrows only seek with shift
+            KeyCode::Left | KeyCode::Right
+                if shift || !matches!(self.tab, Tab::Library | Tab::Files | Tab::Playlists) =>
+            {
+                let step = match shift {
+                    true => self.seek.long_step,
+                    false => self.seek.step,
+                };
+                return self.seek(match key.code {
+                    KeyCode::Right => Seek::Forward(step),
+                    _ => Seek::Back(step),
+                });
+            }
+            code => match self.tab {
+                Tab::Queue => return self.handle_queue_key(code),
+                Tab::Library => return self.handle_library_key(code),
+                Tab::Files => return self.handle_files_key(code),
+                Tab::Playlists => return self.handle_playlists_key(code),
+                Tab::Search => return self.handle_search_key(code),
+                Tab::Lyrics => {}
+            },
+        }
+
+        None
+    }
+
+    // the list the open tab shows
+    fn list(&mut self) -> Option<&mut dyn Rows> {
+        Some(match self.tab {
+            Tab::Queue => &mut self.queue,
+            Tab::Library => self.library.current()?,
+            Tab::Files => self.files.current()?,
+            Tab::Playlists => self.playlists.current()?,
+            Tab::Search => &mut self.search.results,
+            Tab::Lyrics => return None,
+        })
+    }
+
+    fn handle_queue_key(&mut self, code: KeyCode) -> Option<Action> {
+        match code {
+            KeyCode::Char('f') => {
+                self.queue.follow = !self.queue.follow;
+                if let Some(pos) = self.playing().filter(|_| self.queue.follow) {
+                    self.queue.reveal(pos);
+                }
+            }
+            KeyCode::Char('/') => {
+                self.queue
+                    .filter
+                    .get_or_insert_with(Default::default)
+                    .typing = true;
+            }
+            KeyCode::Char('n') => self.queue.next_match(true),
+            KeyCode::Char('N') => self.queue.next_match(false),
+            // the queue stays readable while offline, but not editable
+            _ if self.client.is_none() => {}
+            KeyCode::Enter => return self.queue.selected().map(Action::Play),
+            // plain arrows are motions, so these only come with ctrl
+            KeyCode::Char('K') | KeyCode::Up => self.queue.shift(true),
+            KeyCode::Char('J') | KeyCode::Down => self.queue.shift(false),
+            KeyCode::Char('d') | KeyCode::Delete => {
+                let ids = self.take_marked().iter().filter_map(|t| t.id).collect();
+                return self.ask(Confirm::Remove(ids));
+            }
+            // the marked tracks go right above the cursor
+            KeyCode::Char('m') => {
+                let cursor = self.queue.selected()?;
+                let marked = self.queue.take_marked();
+                let tracks = &self.queue.tracks;
+                let ids: Vec<_> = tracks.iter().filter_map(|track| track.id).collect();
+                let before = (cursor..tracks.len())
+                    .find(|pos| !marked.contains(pos))
+                    .and_then(|pos| tracks[pos].id);
+                let marked: Vec<_> = marked.iter().filter_map(|&pos| tracks[pos].id).collect();
+                return bulk::gather(&ids, &marked, before).map(Action::Run);
+            }
+            KeyCode::Char('p') => {
+                let sources = self.take_marked().into_iter().map(Source::from).collect();
+                self.prompt = Prompt::add_to(sources);
+            }
+            KeyCode::Char('u') => return Some(Action::UrlHandlers),
+            KeyCode::Char('s') => self.prompt = Some(Prompt::Sort),
+            _ => {}
+        }
+
+        None
+    }
+
+    fn take_marked(&mut self) -> Vec<&Track> {
+        let marked = self.queue.take_marked();
+        let tracks = &self.queue.tracks;
+        marked
+            .into_iter()
+            .filter_map(|pos| tracks.get(pos))
+            .collect()
+    }
+
+    fn handle_library_key(&mut self, code: KeyCode) -> Option<Action> {
+        let node = self.library.selected().cloned();
+        match code {
+            KeyCode::Left | KeyCode::Backspace => self.library.close(),
+            KeyCode::Char('~') => self.library.close_all(),
+            _ if self.client.is_none() => {}
+            KeyCode::Enter | KeyCode::Right => {
+                return node.map(|node| match node {
+                    Node::Track(track) => Action::AddPlay(track.file),
+                    node => Action::Browse(Some(node)),
+                })
+            }
+            KeyCode::Char('a') => {
+                return bulk::enqueue(&marked(self.library.current())).map(Action::Edit)
+            }
+            KeyCode::Char('p') => self.prompt = Prompt::add_to(marked(self.library.current())),
+            _ => {}
+        }
+
+        None
+    }
+
+    fn handle_files_key(&mut self, code: KeyCode) -> Option<Action> {
+        let entry = self.files.selected().cloned();
+        match code {
+            KeyCode::Left | KeyCode::Backspace => self.files.close(),
+            KeyCode::Char('~') => self.files.close_all(),
+            _ if self.client.is_none() => {}
+            KeyCode::Enter | KeyCode::Right => {
+                return entry.map(|entry| match entry {
+                    Entry::Directory(path) => Action::List(Some(path)),
+                    Entry::File(track) => Action::AddPlay(track.file),
+                    Entry::Playlist(path) => Action::Load(path),
+                })
+            }
+            // a directory is added with everything below it
+            KeyCode::Char('a') => {
+                return bulk::enqueue(&marked(self.files.current())).map(Action::Edit)
+            }
+            KeyCode::Char('p') => self.prompt = Prompt::add_to(marked(self.files.current())),
+            _ => {}
+        }
+
+        None
+    }
+
+    fn handle_playlists_key(&mut self, code: KeyCode) -> Option<Action> {
+        let name = playlists::name(&self.playlists).map(String::from);
+        let item = self.playlists.selected().cloned();
+        match code {
+            KeyCode::Left | KeyCode::Backspace => self.playlists.close(),
+            KeyCode::Char('~') => self.playlists.close_all(),
+            _ if self.client.is_none() => {}
+            KeyCode::Enter | KeyCode::Right => {
+                return item.map(|item| match item {
+                    Item::Playlist(name) => Action::View(Some(name)),
+                    Item::Track(track) => Action::AddPlay(track.file),
+                })
+            }
+            KeyCode::Char('a') => {
+                return bulk::enqueue(&marked(self.playlists.current())).map(Action::Edit)
+            }
+            KeyCode::Char('l') => return name.map(Action::Replace),
+            KeyCode::Char('d') if self.playlists.levels.len() == 1 => {
+                return name.and_then(|name| self.ask(Confirm::Delete(name)))
+            }
+            KeyCode::Char('s') => self.prompt = Some(Prompt::Save(String::new())),
+            KeyCode::Char('T') => return Some(Action::Trash),
+            _ => {}
+        }
+
+        None
+    }
+
+    fn handle_filter_input(&mut self, code: KeyCode) {
+        let Some(filter) = &mut self.queue.filter else {
+            return;
+        };
+
+        match code {
+            KeyCode::Char(c) => filter.input.push(c),
+            KeyCode::Backspace if !filter.input.is_empty() => {
+                filter.input.pop();
+            }
+            // backing out of an empty filter closes it
+            KeyCode::Backspace | KeyCode::Esc => {
+                self.queue.unfilter();
+                return;
+            }
+            KeyCode::Enter | KeyCode::Down => {
+                filter.typing = false;
+                return;
+            }
+            _ => return,
+        }
+        self.queue.refilter();
+    }
+
+    fn handle_search_input(&mut self, code: KeyCode) {
+        let search = &mut self.search;
+        match code {
+            KeyCode::Char(c) => {
+                search.input.push(c);
+                search.changed();
+            }
+            KeyCode::Backspace => match search.input.pop() {
+                Some(_) => search.changed(),
+                None => search.uncommit(),
+            },
+            KeyCode::Enter if !search.input.is_empty() => {
+                search.commit();
+            }
+            KeyCode::Enter | KeyCode::Esc | KeyCode::Down => search.focus = Focus::Results,
+            _ => {}
+        }
+    }
+
+    fn handle_search_key(&mut self, code: KeyCode) -> Option<Action> {
+        let track = self.search.results.selected();
+        match code {
+            KeyCode::Char('/') => self.search.focus = Focus::Input,
+            _ if self.client.is_none() => {}
+            KeyCode::Enter => return track.map(|track| Action::AddPlay(track.file.clone())),
+            KeyCode::Char('a') => {
+                return bulk::enqueue(&marked(Some(&mut self.search.results))).map(Action::Edit)
+            }
+            KeyCode::Char('p') => {
+                self.prompt = Prompt::add_to(marked(Some(&mut self.search.results)))
+            }
+            // everything that matched, not just what is on screen
+            KeyCode::Char('A') if track.is_some() => {
+                return self.search.filter().map(Action::SearchAdd)
+            }
+            _ => {}
+        }
+
+        None
+    }
+
+    fn handle_prompt_key(&mut self, code: KeyCode) -> Option<Action> {
+        let mut prompt = self.prompt.take()?;
+        let typed = match (prompt.input(), code) {
+            (Some(input), KeyCode::Char(c)) => {
+                input.push(c);
+                true
+            }
+            (Some(input), KeyCode::Backspace) => {
+                input.pop();
+                true
+            }
+            _ => false,
+        };
+        if typed {
+            if let Prompt::Stream(_, _, note) | Prompt::Command(_, note) = &mut prompt {
+                *note = None;
+            }
+            self.prompt = Some(prompt);
+            return None;
+        }
+
+        match (prompt, code) {
+            (Prompt::Save(name), KeyCode::Enter) if !name.is_empty() => {
+                return Some(Action::Save(name))
+            }
+            (Prompt::AddTo(name, sources), KeyCode::Enter) if !name.is_empty() => {
+                return bulk::save_to(&name, &sources).map(|list| Action::AddTo(name, list))
+            }
+            (Prompt::Confirm(confirm), KeyCode::Char('y')) => return confirmed(confirm),
+            (Prompt::Rate(_, uris), KeyCode::Char(c @ '0'..='5')) => {
+                let rating = c as u8 - b'0';
+                return ratings::rate(&uris, (rating > 0).then_some(rating)).map(Action::Run);
+            }
+            (Prompt::Sort, KeyCode::Char(c)) if Sort::by_key(c).is_some() => {
+                return Sort::by_key(c).map(Action::Sort)
+            }
+            (Prompt::Stream(url, handlers, _), KeyCode::Enter) if !url.trim().is_empty() => {
+                match prompt::stream(&url, &handlers) {
+                    Ok(url) => return bulk::enqueue(&[Source::Uri(url)]).map(Action::Edit),
+                    Err(e) => {
+                        self.prompt = Some(Prompt::Stream(url, handlers, Some(Note::Error(e))))
+                    }
+                }
+            }
+            (Prompt::Command(mut input, _), KeyCode::Tab) => {
+                let hosts: Vec<_> = self.hosts.keys().map(String::as_str).collect();
+                command::complete(&mut input, &hosts);
+                let choices = command::candidates(&input, &hosts);
+                let choices = choices.into_iter().map(String::from).collect::<Vec<_>>();
+                let note = (choices.len() > 1).then_some(Note::Choices(choices));
+                self.prompt = Some(Prompt::Command(input, note));
+            }
+            (Prompt::Command(input, _), KeyCode::Enter) if !input.trim().is_empty() => {
+                match Command::parse(&input).and_then(|command| self.run_command(command)) {
+                    Ok(action) => return action,
+                    Err(e) => self.prompt = Some(Prompt::Command(input, Some(Note::Error(e)))),
+                }
+            }
+            (_, KeyCode::Esc | KeyCode::Char('n')) => {}
+            (prompt, _) => self.prompt = Some(prompt),
+        }
+
+        None
+    }
+
+    fn handle_details_key(&mut self, code: KeyCode) -> Option<Action> {
+        let details = self.details.as_mut()?;
+        if let Some(input) = details.input() {
+            match code {
+                KeyCode::Char(c) => input.push(c),
+                KeyCode::Backspace => {
+                    input.pop();
+                }
+                KeyCode::Esc => details.cancel(),
+                KeyCode::Enter => match details.save() {
+                    Ok(Some((name, update))) => {
+                        self.toasts.info(format!("Saved {name}"));
+                        return Some(Action::Run(update));
+                    }
+                    Ok(None) => {}
+                    Err(e) => self.toasts.error(format!("{e:#}")),
+                },
+                _ => {}
+            }
+            return None;
+        }
+
+        match code {
+            KeyCode::Esc | KeyCode::Char('i' | 'q') => self.details = None,
+            KeyCode::Char('j') | KeyCode::Down => details.step(true),
+            KeyCode::Char('k') | KeyCode::Up => details.step(false),
+            KeyCode::Enter => details.edit(),
+            _ => {}
+        }
+
+        None
+    }
+
+    fn handle_outputs_key(&mut self, code: KeyCode) -> Option<Action> {
+        let outputs = self.outputs.as_mut()?;
+        if let Some(input) = outputs.input() {
+            match code {
+                KeyCode::Char(c) => input.push(c),
+                KeyCode::Backspace => {
+                    input.pop();
+                }
+                KeyCode::Esc => outputs.cancel(),
+                KeyCode::Enter => {
+                    let (name, list) = outputs.save()?;
+                    self.toasts.info(format!("Set {name}"));
+                    return Some(Action::Run(list));
+                }
+                _ => {}
+            }
+            return None;
+        }
+
+        match code {
+            KeyCode::Esc | KeyCode::F(8) | KeyCode::Char('q') => self.outputs = None,
+            KeyCode::Char('j') | KeyCode::Down => outputs.step(true),
+            KeyCode::Char('k') | KeyCode::Up => outputs.step(false),
+            KeyCode::Enter => return outputs.enter().map(Action::Run),
+            _ => {}
+        }
+
+        None
+    }
+
+    fn handle_trash_key(&mut self, code: KeyCode) -> Option<Action> {
+        let bin = self.bin.as_mut()?;
+        match code {
+            KeyCode::Esc | KeyCode::Char('q' | 'T') => self.bin = None,
+            KeyCode::Char('j') | KeyCode::Down => bin.step(true),
+            KeyCode::Char('k') | KeyCode::Up => bin.step(false),
+            KeyCode::Enter => return bin.selected().cloned().map(Action::Restore),
+            _ => {}
+        }
+
+        None
+    }
+
+    fn handle_later_key(&mut self, code: KeyCode) -> Option<Action> {
+        let later = self.later.as_mut()?;
+        match code {
+            KeyCode::Esc | KeyCode::Char('q' | 'W') => self.later = None,
+            KeyCode::Char('j') | KeyCode::Down => later.step(true),
+            KeyCode::Char('k') | KeyCode::Up => later.step(false),
+            KeyCode::Enter => return later.selected().cloned().map(Action::Enqueue),
+            KeyCode::Char('d') => return later.selected().cloned().map(Action::Flag),
+            _ => {}
+        }
+
+        None
+    }
+
+    fn handle_changes_key(&mut self, code: KeyCode) {
+        let Some(changes) = &mut self.changes else {
+            return;
+        };
+        let len = changes::shown(&self.attribution.history).count();
+        match code {
+            KeyCode::Esc | KeyCode::Char('q') => self.changes = None,
+            KeyCode::Char('j') | KeyCode::Down => changes.step(true, len),
+            KeyCode::Char('k') | KeyCode::Up => changes.step(false, len),
+            _ => {}
+        }
+    }
+
+    fn handle_partitions_key(&mut self, code: KeyCode) -> Option<Action> {
+        let partitions = self.partitions.as_mut()?;
+        if let Some(input) = partitions.input() {
+            match code {
+                KeyCode::Char(c) => input.push(c),
+                KeyCode::Backspace => {
+                    input.pop();
+                }
+                KeyCode::Esc => partitions.cancel(),
+                KeyCode::Enter => return partitions.named().map(Action::NewPartition),
+                _ => {}
+            }
+            return None;
+        }
+
+        let name = partitions.selected().cloned();
+        match code {
+            KeyCode::Esc | KeyCode::F(7) | KeyCode::Char('q') => self.partitions = None,
+            KeyCode::Char('j') | KeyCode::Down => partitions.step(true),
+            KeyCode::Char('k') | KeyCode::Up => partitions.step(false),
+            KeyCode::Char('n') => partitions.name(),
+            KeyCode::Enter => return name.map(Action::SwitchPartition),
+            KeyCode::Char('d') => {
+                return name.and_then(|name| self.ask(Confirm::DeletePartition(name)))
+            }
+            _ => {}
+        }
+
+        None
+    }
+
+    // what a command line asks of the server, or why it cannot be done
+    fn run_command(&mut self, command: Command) -> Result<Option<Action>, String> {
+        let list = |cmd: &[u8], args: &[&str]| {
+            let mut list = CommandList::new();
+            list.push(cmd, args).ok()?;
+            Some(list)
+        };
+
+        let action = match command {
+            Command::Connect(name) => {
+                let host = self.hosts.get(&name).unwrap_or(&name);
+                let endpoint = Endpoint::parse(host).ok_or(format!("Invalid host `{host}`"))?;
+                self.retarget(endpoint);
+                return Ok(None);
+            }
+            Command::Help => {
+                self.show_help = true;
+                return Ok(None);
+            }
+            Command::Quit => {
+                self.should_quit = true;
+                return Ok(None);
+            }
+            Command::Clear => self.ask(Confirm::Clear),
+            Command::Next => list(b"next", &[]).map(Action::Run),
+            Command::Pause => list(b"pause", &[]).map(Action::Run),
+            Command::Play => list(b"play", &[]).map(Action::Run),
+            Command::Previous => list(b"previous", &[]).map(Action::Run),
+            Command::Stop => list(b"stop", &[]).map(Action::Run),
+            Command::Save(name) => Some(Action::Save(name)),
+            Command::Trash => Some(Action::Trash),
+            Command::History => {
+                self.changes = Some(Changes::default());
+                return Ok(None);
+            }
+            Command::Seek(seek) => self.seek(seek),
+            Command::Sort(by) => Some(Action::Sort(by)),
+            Command::Set(setting) => Some(match setting {
+                Setting::Repeat(on) => Action::SetRepeat(on),
+                Setting::Random(on) => Action::SetRandom(on),
+                Setting::Single(mode) => Action::SetSingle(mode),
+                Setting::Consume(mode) => Action::SetConsume(mode),
+            }),
+            Command::Volume(volume) => {
+                let current = self.status.as_ref().and_then(|status| status.volume);
+                Some(Action::SetVolume(match (volume, current) {
+                    (Volume::To(volume), _) => volume,
+                    (Volume::Up(step), Some(volume)) => volume.saturating_add(step).min(100),
+                    (Volume::Down(step), Some(volume)) => volume.saturating_sub(step),
+                    _ => return Err("The volume is not known".into()),
+                }))
+            }
+        };
+
+        match self.client {
+            Some(_) => Ok(action),
+            None => Err("Not connected".into()),
+        }
+    }
+
+    // the marked tracks in the queue, or else the playing song
+    fn rating(&mut self) -> Option<Prompt> {
+        let uris: Vec<_> = match self.tab {
+            Tab::Queue => self.take_marked().iter().map(|t| t.file.clone()).collect(),
+            _ => self.song.iter().map(|song| song.uri.clone()).collect(),
+        };
+        let what = match &uris[..] {
+            [] => return None,
+            [uri] => {
+                let track = self.queue.tracks.iter().find(|track| track.file == *uri);
+                let file = uri.rsplit('/').next().unwrap_or(uri);
+                format!("\"{}\"", track.map_or(file, queue::title))
+            }
+            uris => queue::tracks(uris.len()),
+        };
+        Some(Prompt::Rate(what, uris))
+    }
+
+    // the song under the cursor, or else the playing one
+    fn under_cursor(&self) -> Option<String> {
+        let queue = &self.queue;
+        let selected = match self.tab {
+            Tab::Queue => queue.selected().and_then(|pos| queue.tracks.get(pos)),
+            Tab::Search => self.search.results.selected(),
+            _ => None,
+        };
+        match selected {
+            Some(track) => Some(track.file.clone()),
+            None => Some(self.song.as_ref()?.uri.clone()),
+        }
+    }
+
+    // the progress moves right away instead of with the next status
+    fn seek(&mut self, seek: Seek) -> Option<Action> {
+        self.client.as_ref()?;
+        let elapsed = self.elapsed();
+        let duration = self.duration().map(|secs| Duration::from_secs(secs.into()));
+        let (to, time) = match seek {
+            Seek::To(time) => (time, time.as_secs_f64().to_string()),
+            Seek::Forward(time) => (elapsed + time, format!("+{}", time.as_secs_f64())),
+            Seek::Back(time) => (
+                elapsed.saturating_sub(time),
+                format!("-{}", time.as_secs_f64()),
+            ),
+        };
+
+        if let Some(song) = self.status.as_mut().and_then(|status| status.song.as_mut()) {
+            let to = duration.map_or(to, |duration| to.min(duration));
+            song.elapsed = to.as_secs().try_into().unwrap_or(u16::MAX);
+            self.status_at = Instant::now();
+        }
+
+        let mut list = CommandList::new();
+        list.push(b"seekcur", &[&time]).ok()?;
+        Some(Action::Run(list))
+    }
+
+    // the config says which of these ask first
+    fn ask(&mut self, confirm: Confirm) -> Option<Action> {
+        let ask = match &confirm {
+            Confirm::Clear => self.confirm.clear,
+            Confirm::Delete(_) => self.confirm.delete_playlist,
+            // its queue goes with it
+            Confirm::DeletePartition(_) => true,
+            Confirm::Remove(ids) => self.confirm.remove > 0 && ids.len() >= self.confirm.remove,
+        };
+        if !ask {
+            return confirmed(confirm);
+        }
+
+        self.prompt = Some(Prompt::Confirm(confirm));
+        None
+    }
+
+    // lists whatever the current tab shows but has not loaded yet
+    fn wanted(&mut self) -> Option<Action> {
+        self.client.as_ref()?;
+        if let Some(uri) = self.art.wanted() {
+            return Some(Action::Art(uri));
+        }
+        if self.outputs.as_mut().is_some_and(Outputs::wanted) {
+            return Some(Action::Outputs);
+        }
+        if self.partitions.as_mut().is_some_and(Partitions::wanted) {
+            return Some(Action::Partitions);
+        }
+        if self.bin.as_mut().is_some_and(Bin::wanted) {
+            return Some(Action::Trash);
+        }
+        if self.later.as_mut().is_some_and(Later::wanted) {
+            return Some(Action::Later);
+        }
+        if self.queue.wants_ratings() {
+            return Some(Action::Ratings);
+        }
+
+        match self.tab {
+            Tab::Library if self.library.wants_top() => Some(Action::Browse(None)),
+            Tab::Files if self.files.wants_top() => Some(Action::List(None)),
+            Tab::Playlists if self.playlists.wants_top() => Some(Action::View(None)),
+            Tab::Library => {
+                let node = self.library.selected()?;
+                self.info.wanted(node.filter())?;
+                Some(Action::Info(node.clone()))
+            }
+            _ => None,
+        }
+    }
+
+    fn change_volume(&self, up: bool) -> Option<Action> {
+        let volume = self.status.as_ref()?.volume?;
+        let volume = match up {
+            true => volume.saturating_add(self.volume_step).min(100),
+            false => volume.saturating_sub(self.volume_step),
+        };
+        Some(Action::SetVolume(volume))
+    }
+
+    // single and consume also cycle through oneshot where the server has it
+    fn toggle_flag(&self, key: KeyCode) -> Option<Action> {
+        let status = self.status.as_ref()?;
+        let supports = |feature| self.client.as_ref().is_some_and(|c| c.supports(feature));
+
+        Some(match key {
+            KeyCode::Char('r') => Action::SetRepeat(!status.repeat),
+            KeyCode::Char('z') => Action::SetRandom(!status.random),
+            KeyCode::Char('y') => Action::SetSingle(match status.single {
+                SingleMode::Off => SingleMode::On,
+                SingleMode::On if supports(Feature::SingleOneshot) => SingleMode::Oneshot,
+                _ => SingleMode::Off,
+            }),
+            KeyCode::Char('R') => Action::SetConsume(match status.consume {
+                ConsumeMode::Off => ConsumeMode::On,
+                ConsumeMode::On if supports(Feature::ConsumeOneshot) => ConsumeMode::Oneshot,
+                _ => ConsumeMode::Off,
+            }),
+            _ => return None,
+        })
+    }
+
+    async fn handle_action(&mut self, action: Action) {
+        let Some(session) = &mut self.client else {
+            return;
+        };
+        self.attribution.issued(&issued(&action));
+
+        // what to tell once it went through; covers are best effort, a
+        // missing one is no news
+        let done = match &action {
+            Action::Replace(name) => Some(format!("Replaced the queue with {name}")),
+            Action::Delete(name) if self.trash.is_some() => {
+                Some(format!("Moved {name} to the trash, T brings it back"))
+            }
+            Action::Delete(name) => Some(format!("Deleted {name}")),
+            Action::Restore(trashed) => Some(format!("Restored {}", trashed.name)),
+            Action::Save(name) => Some(format!("Saved the queue as {name}")),
+            Action::AddTo(name, _) => Some(format!("Added to {name}")),
+            Action::Enqueue(uri) => {
+                Some(format!("Queued {}", uri.rsplit('/').next().unwrap_or(uri)))
+            }
+            _ => None,
+        };
+        let quiet = matches!(action, Action::Art(_) | Action::Ratings);
+        let before = self.status.as_ref().map(|status| status.queue_len);
+
+        let cache = self.cache.as_ref().map(|cache| (cache, &self.endpoint));
+        let trash = self.trash.as_ref();
+        let start = Instant::now();
+        let result = timed(self.timeouts.command, async move {
+            let client = session.command();
+            match action {
+                Action::Play(pos) => client.play(pos).await?,
+                Action::SetVolume(volume) => client.set_volume(volume).await?,
+                Action::SetRepeat(repeat) => client.set_repeat(repeat).await?,
+                Action::SetRandom(random) => client.set_random(random).await?,
+                Action::SetSingle(mode) => client.set_single(mode).await?,
+                Action::SetConsume(mode) => client.set_consume(mode).await?,
+                Action::Browse(node) => {
+                    return browse(client, node, cache).await.map(Reply::Library)
+                }
+                Action::List(path) => return list(client, path).await.map(Reply::Files),
+                Action::Load(name) => {
+                    client.load(&name).await?;
+                    return queued(client).await;
+                }
+                // in one list so other clients never see the queue empty
+                Action::Replace(name) => {
+                    let mut list = CommandList::new();
+                    list.push(b"clear", &[])?.push(b"load", &[&name])?;
+                    client.run(&list).await?;
+                }
+                Action::View(name) => return view(client, name).await.map(Reply::Playlists),
+                Action::Delete(name) => match trash {
+                    Some(trash) => trash.delete_playlist(client, &name).await?,
+                    None => client.delete_playlist(&name).await?,
+                },
+                Action::Trash => {
+                    let list = match trash {
+                        Some(trash) => trash.list().await?,
+                        None => Vec::new(),
+                    };
+                    return Ok(Reply::Trash(list));
+                }
+                Action::Restore(trashed) => {
+                    if let Some(trash) = trash {
+                        trash.restore(client, &trashed).await?;
+                    }
+                }
+                Action::Flag(uri) => {
+                    let flagged = inbox::toggle(client, &uri).await?;
+                    return Ok(Reply::Flagged(uri, flagged));
+                }
+                Action::Later => return Ok(Reply::Later(inbox::flagged(client).await?)),
+                Action::Enqueue(uri) => inbox::enqueue(client, &uri).await?,
+                Action::Save(name) => client.save(&name, SaveMode::Create).await?,
+                Action::Search(filter) => return client.search(&filter).await.map(Reply::Search),
+                Action::SearchAdd(filter) => {
+                    client.search_add(&filter).await?;
+                    return queued(client).await;
+                }
+                Action::Run(list) | Action::AddTo(_, list) => {
+                    client.run(&list).await?;
+                }
+                Action::Edit(list) => {
+                    client.run(&list).await?;
+                    return queued(client).await;
+                }
+                Action::AddPlay(uri) => {
+                    let id = client.add_id(&uri).await?;
+                    client.play_id(id).await?;
+                }
+                Action::Art(uri) => {
+                    let cover = cover(client, &uri).await?;
+                    return Ok(Reply::Art(uri, cover));
+                }
+                Action::UrlHandlers => return client.url_handlers().await.map(Reply::UrlHandlers),
+                Action::Sort(by) => sort(client, by).await?,
+                Action::Ratings => {
+                    let stickers = client.sticker_find("", ratings::STICKER).await?;
+                    return Ok(Reply::Ratings(stickers));
+                }
+                Action::Info(node) => {
+                    let (filter, stats) = info(client, node).await?;
+                    return Ok(Reply::Info(filter, stats));
+                }
+                Action::Outputs => return client.outputs().await.map(Reply::Outputs),
+                Action::Partitions => return client.partitions().await.map(Reply::Partitions),
+                Action::SwitchPartition(name) => {
+                    session.switch_partition(&name).await?;
+                    let update = sync(session.command()).await?;
+                    return Ok(Reply::Switched(name, Box::new(update)));
+                }
+                Action::NewPartition(name) => {
+                    client.new_partition(&name).await?;
+                    return client.partitions().await.map(Reply::Partitions);
+                }
+                Action::DeletePartition(name) => {
+                    client.delete_partition(&name).await?;
+                    return client.partitions().await.map(Reply::Partitions);
+                }
+                Action::Details(uri) => {
+                    let comments = client.read_comments(&uri).await?;
+                    return Ok(Reply::Details(uri, comments));
+                }
+            }
+            Ok(Reply::Done)
+        })
+        .await;
+        self.perf.record_mpd(start.elapsed(), tasks());
+
+        match result {
+            Ok(Reply::Done) => {
+                if let Some(done) = done {
+                    self.toasts.info(done);
+                }
+            }
+            Ok(Reply::Queued(after)) => {
+                let before = before.unwrap_or(after);
+                let change = match after.cmp(&before) {
+                    Ordering::Greater => format!("Added {}", queue::tracks(after - before)),
+                    Ordering::Less => format!("Removed {}", queue::tracks(before - after)),
+                    Ordering::Equal => return,
+                };
+                self.toasts.info(change);
+            }
+            Ok(Reply::Library(nodes)) => self.library.open(nodes),
+            Ok(Reply::Files(entries)) => self.files.open(entries),
+            Ok(Reply::Playlists(items)) => self.playlists.open(items),
+            Ok(Reply::Search(tracks)) => self.search.results = Menu::new(tracks),
+            Ok(Reply::Ratings(stickers)) => self.queue.set_ratings(stickers),
+            Ok(Reply::Info(filter, stats)) => self.info.set(filter, stats),
+            Ok(Reply::Outputs(list)) => match &mut self.outputs {
+                Some(outputs) => outputs.set(list),
+                None => self.outputs = Some(Outputs::new(list)),
+            },
+            Ok(Reply::Partitions(list)) => match &mut self.partitions {
+                Some(partitions) => partitions.set(list),
+                None => {
+                    let current = self.status.as_ref().map_or("", |status| &status.partition);
+                    self.partitions = Some(Partitions::new(list, current));
+                }
+            },
+            Ok(Reply::Trash(list)) => match &mut self.bin {
+                Some(bin) => bin.set(list),
+                None => self.bin = Some(Bin::new(list)),
+            },
+            Ok(Reply::Flagged(uri, flagged)) => {
+                let file = uri.rsplit('/').next().unwrap_or(&uri);
+                let track = self.queue.tracks.iter().find(|track| track.file == uri);
+                let title = track.map_or(file, queue::title);
+                let done = match flagged {
+                    true => format!("Flagged \"{title}\" to listen later"),
+                    false => format!("Unflagged \"{title}\""),
+                };
+                self.toasts.info(done);
+            }
+            Ok(Reply::Later(songs)) => match &mut self.later {
+                Some(later) => later.set(songs),
+                None => self.later = Some(Later::new(songs)),
+            },
+            // a queue version from another partition is no sign of a restart
+            Ok(Reply::Switched(name, update)) => {
+                self.instance = Instance::default();
+                self.queue.state.select(None);
+                self.apply(*update);
+                self.partitions = None;
+                self.toasts.info(format!("Switched to {name}"));
+            }
+            Ok(Reply::Details(uri, comments)) => {
+                let music_dir = self.music_dir.as_deref();
+                self.details = Some(Details::new(uri, comments, music_dir));
+            }
+            Ok(Reply::UrlHandlers(handlers)) => {
+                self.prompt = Some(Prompt::Stream(String::new(), handlers, None))
+            }
+            Ok(Reply::Art(uri, None)) if self.remote_cover(&uri) => {}
+            Ok(Reply::Art(uri, cover)) => {
+                self.notifier.cover(&uri, cover.as_ref());
+                self.art.set(&uri, cover);
+            }
+            // as does the trash on disk failing
+            Err(e) if e.is::<TrashError>() => self.toasts.error(e.to_string()),
+            // the server refusing a command leaves the connection usable
+            Err(e) => match e.downcast_ref::<MpdError>() {
+                Some(_) if quiet => self.notifier.flush(),
+                Some(ack) => {
+                    let error = match ack.command.as_str() {
+                        "" => ack.message.clone(),
+                        command => format!("{command}: {}", ack.message),
+                    };
+                    self.toasts.error(error);
+                }
+                None => self.lost(e),
+            },
+        }
+    }
+
+    async fn handle_connect(&mut self, event: ConnectEvent) {
+        let (ConnectEvent::Failed(endpoint, _) | ConnectEvent::Connected(endpoint, ..)) = &event;
+        if *endpoint != self.endpoint {
+            return;
+        }
+
+        match event {
+            ConnectEvent::Failed(_, error) => {
+                self.connecting.attempts += 1;
+                self.connecting.error = Some(error);
+            }
+            ConnectEvent::Connected(_, client, update) => {
+                if self.connecting.reconnecting {
+                    let endpoint = &self.endpoint;
+                    self.toasts.info(format!("Reconnected to {endpoint}"));
+                }
+                self.connect = None;
+                self.connecting = Connecting::default();
+                self.active = Instant::now();
+                self.client = Some(*client);
+                self.apply(*update);
+            }
+        }
+    }
+
+    async fn handle_idle(&mut self, changed: Result<HashSet<Subsystem>>) {
+        if let Ok(changed) = &changed {
+            self.attribute(changed);
+        }
+        let Some(client) = &mut self.client else {
+            return;
+        };
+
+        // the status does not tell the queue changed
+        let edited = changed
+            .as_ref()
+            .is_ok_and(|changed| changed.contains(&Subsystem::Playlist));
+        let result = match changed {
+            Ok(changed) => {
+                let perf = &mut self.perf;
+                timed(self.timeouts.command, async move {
+                    let status = refresh(client.command(), &changed, perf).await?;
+                    client.resume_idle().await?;
+                    Ok(status)
+                })
+                .await
+            }
+            Err(e) => Err(e),
+        };
+
+        self.handle_refresh(result);
+        if edited && self.client.is_some() {
+            let len = self.queue.tracks.len();
+            self.log.emit([events::Event::QueueEdit { len }]);
+        }
+    }
+
+    async fn handle_remote(&mut self, request: remote::Request) {
+        let Some(client) = &mut self.client else {
+            let _ = remote::handle(request, None).await;
+            return;
+        };
+
+        // whatever the command changes is reported by the idle connection
+        let result = timed(self.timeouts.command, async move {
+            remote::handle(request, Some(client.command())).await?;
+            Ok(Update::default())
+        })
+        .await;
+
+        self.handle_refresh(result);
+    }
+
+    // the server is told before the system goes down, and again once it is
+    // back; the suspend waits for the first
+    async fn handle_sleep(&mut self, sleeping: bool) {
+        let Some(client) = &mut self.client else {
+            let _ = self.power.handle(sleeping, None).await;
+            return;
+        };
+
+        let power = &mut self.power;
+        let result = timed(self.timeouts.command, async move {
+            power.handle(sleeping, Some(client.command())).await?;
+            Ok(Update::default())
+        })
+        .await;
+
+        self.handle_refresh(result);
+    }
+
+    // changes nobody here asked for are told, and by whom
+    fn attribute(&mut self, changed: &HashSet<Subsystem>) {
+        for change in self.attribution.classify(changed) {
+            let name = changes::name(change.subsystem);
+            if let Some(name) = name.filter(|_| change.origin == Origin::External) {
+                let by = changes::by(change.origin);
+                self.toasts.info(format!("{name} changed by {by}"));
+            }
+        }
+    }
+
+    // files edited on disk take effect without a restart
+    fn handle_change(&mut self, change: Change) {
+        match change {
+            Change::Lyrics => self.lyrics.reload(self.song.as_ref()),
+            // a cover put next to the playing song
+            Change::Art(path) => {
+                let song = self.song.as_ref().zip(self.music_dir.as_ref());
+                let song = song.map(|(song, dir)| dir.join(&song.uri));
+                if song.as_deref().and_then(Path::parent) == path.parent() {
+                    self.art.refetch();
+                }
+            }
+            Change::Config(path) => {
+                let config = Config::load(Some(path));
+                match config.and_then(|config| Ok((Theme::load(&config)?, config))) {
+                    Ok((theme, config)) => {
+                        self.reconfigure(&config, theme);
+                        self.toasts.info("Reloaded the config");
+                    }
+                    Err(e) => self.toasts.error(format!("{e:#}")),
+                }
+            }
+        }
+    }
+
+    // the settings that are read as they are used; the rest wait for a restart
+    fn reconfigure(&mut self, config: &Config, theme: Theme) {
+        self.theme = theme;
+        self.lyrics = Lyrics::new(config.music_dir.clone(), config.lyrics_dir.clone());
+        self.lyrics.set_song(self.song.as_ref());
+        self.music_dir = config.music_dir.clone();
+        self.timeouts = config.timeout;
+        self.volume_step = config.volume_step;
+        self.hosts = config.hosts.clone();
+        self.profiles = config.profiles.clone();
+        self.power_config = config.power;
+        self.power.configure(config.power_for(&self.endpoint));
+        self.confirm = config.confirm;
+        self.seek = config.seek;
+    }
+
+    // an idle connection is invisible to NAT tables and a dead peer is only
+    // noticed on the next write, so poke the server every so often
+    async fn handle_keepalive(&mut self) {
+        let Some(client) = &mut self.client else {
+            return;
+        };
+
+        let perf = &mut self.perf;
+        let result = timed(self.timeouts.command, async move {
+            let changed = client.ping().await?;
+            refresh(client.command(), &changed, perf).await
+        })
+        .await;
+
+        self.active = Instant::now();
+        self.handle_refresh(result);
+    }
+
+    fn handle_refresh(&mut self, result: Result<Update>) {
+        match result {
+            Ok(update) => self.apply(update),
+            Err(e) => self.lost(e),
+        }
+    }
+
+    fn apply(&mut self, update: Update) {
+        let playing = self.playing();
+        if let Some(status) = update.status {
+            // a fresh connection has nothing to compare with
+            let id = |song: &Option<CurrentSong>| song.as_ref().map(|song| song.id);
+            let changed = self.status.is_some() && id(&self.song) != id(&update.song);
+            self.song = update.song;
+            if let Some(song) = self.song.as_ref().filter(|_| changed) {
+                if status.state == PlayerState::Play {
+                    self.notifier.song(song, self.art.protocol.is_some());
+                }
+            }
+            let uri = self.song.as_ref().map(|song| song.uri.as_str());
+            self.art.set_song(uri);
+            self.lyrics.set_song(self.song.as_ref());
+            if let Some(old) = &self.status {
+                let tracks = update.queue.as_deref().unwrap_or(&self.queue.tracks);
+                let since = self.status_at.elapsed();
+                self.log.emit(events::diff(old, &status, tracks, since));
+            }
+            self.apply_status(status);
+        }
+
+        if let Some(queue) = update.queue {
+            self.queue.set(queue);
+        }
+
+        // only a change of song moves the cursor, browsing in between is
+        // left alone
+        let now = self.playing();
+        if let Some(pos) = now.filter(|_| self.queue.follow && now != playing) {
+            self.queue.reveal(pos);
+        }
+
+        if update.database {
+            self.library.clear();
+            self.info.clear();
+            self.files.clear();
+            self.search.changed();
+        }
+
+        if update.playlists {
+            self.playlists.clear();
+            if let Some(bin) = &mut self.bin {
+                bin.changed();
+            }
+        }
+
+        if update.stickers {
+            self.queue.ratings.clear();
+            if let Some(later) = &mut self.later {
+                later.changed();
+            }
+        }
+
+        if let Some(outputs) = self.outputs.as_mut().filter(|_| update.outputs) {
+            outputs.changed();
+        }
+
+        if let Some(partitions) = self.partitions.as_mut().filter(|_| update.partitions) {
+            partitions.changed();
+        }
+    }
+
+    fn apply_status(&mut self, status: Status) {
+        if let Some(client) = &self.client {
+            if self.instance.observe(client.version(), &status) {
+                self.resync();
+            }
+        }
+
+        self.is_playing = status.state == PlayerState::Play;
+        self.status = Some(status);
+        self.status_at = Instant::now();
+    }
+
+    // anything cached from the previous server instance is void; the update
+    // that revealed the restart carries a fresh queue, so only the cursor
+    // from before has to go
+    fn resync(&mut self) {
+        self.queue.state.select(None);
+        self.library.clear();
+        self.info.clear();
+        self.files.clear();
+        self.playlists.clear();
+        self.queue.ratings.clear();
+    }
+
+    // looks elsewhere for a cover the server has none of, when the song is
+    // still playing and has the tags to look it up by
+    fn remote_cover(&self, uri: &str) -> bool {
+        let song = self.song.as_ref().filter(|song| song.uri == uri);
+        song.is_some_and(|song| self.covers.fetch(song))
+    }
+
+    fn lost(&mut self, error: eyre::Report) {
+        self.toasts.error(format!("Lost the connection: {error}"));
+        self.notifier.flush();
+        self.client = None;
+        self.is_playing = false;
+        self.connecting = Connecting {
+            reconnecting: true,
+            error: Some(format!("{error:#}")),
+            ..Connecting::default()
+        };
+    }
+
+    // panes derive what they show at draw time, a tick only has to wake the
+    // loop for the redraw; the spectrum is the one that keeps state between
+    fn handle_tick(&mut self, pane: Pane) {
+        match pane {
+            Pane::Progress | Pane::Lyrics => {}
+            Pane::Visualizer => self.visualizer.tick(),
+        }
+    }
+
+    fn retarget(&mut self, endpoint: Endpoint) {
+        if let Some(connect) = self.connect.take() {
+            connect.abort();
+        }
+
+        let power = self.profiles.power(&self.hosts, &endpoint);
+        self.power.configure(power.unwrap_or(self.power_config));
+        self.endpoint = endpoint;
+        self.client = None;
+        self.connecting = Connecting::default();
+    }
+}
+
+fn confirmed(confirm: Confirm) -> Option<Action> {
+    match confirm {
+        Confirm::Clear => {
+            let mut list = CommandList::new();
+            list.push(b"clear", &[]).ok()?;
+            Some(Action::Edit(list))
+        }
+        Confirm::Delete(name) => Some(Action::Delete(name)),
+        Confirm::DeletePartition(name) => Some(Action::DeletePartition(name)),
+        Confirm::Remove(ids) => bulk::delete(&ids).map(Action::Edit),
+    }
+}
+
+// the marked rows of a menu, or the one under the cursor
+fn marked<T>(menu: Option<&mut Menu<T>>) -> Vec<Source>
+where
+    for<'a> Source: From<&'a T>,
+{
+    let items = menu.map(Menu::take_marked).unwrap_or_default();
+    items.into_iter().map(Source::from).collect()
+}
+
+fn spawn_connect(
+    endpoint: Endpoint,
+    connector: Connector,
+    timeouts: Timeouts,
+    tx: UnboundedSender<ConnectEvent>,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        for attempt in 0.. {
+            let connected = async {
+                let mut client = timed(timeouts.connect, connector(endpoint.clone())).await?;
+                let update = timed(timeouts.command, sync(client.command())).await?;
+                Result::<_>::Ok((client, update))
+            };
+
+            let event = match connected.await {
+                Ok((client, update)) => {
+                    ConnectEvent::Connected(endpoint.clone(), Box::new(client), Box::new(update))
+                }
+                Err(e) => ConnectEvent::Failed(endpoint.clone(), format!("{e:#}")),
+            };
+
+            let connected = matches!(event, ConnectEvent::Connected(..));
+            if tx.send(event).is_err() || connected {
+                return;
+            }
+
+            sleep(backoff(attempt)).await;
+        }
+    })
+}
+
+// doubles the delay up to a minute and adds up to a quarter on top, so
+// clients that lost the same server do not all come back in lockstep
+fn backoff(attempt: u32) -> Duration {
+    let delay = RETRY.saturating_mul(1 << attempt.min(5)).min(MAX_RETRY);
+    let jitter = RandomState::new().build_hasher().finish() % 1024;
+    delay + delay / 4 * jitter as u32 / 1024
+}
+
+// a server that stops answering would otherwise freeze the loop; the
+// connection is in an unknown state afterwards, so callers drop it
+async fn timed<T>(limit: Duration, request: impl Future<Output = Result<T>>) -> Result<T> {
+    match timeout(limit, request).await {
+        Ok(result) => result,
+        Err(_) => Err(eyre::eyre!("no response within {limit:?}")),
+    }
+}
+
+// the tasks still at work beside the loop: connecting, covers, remotes
+fn tasks() -> usize {
+    Handle::current().metrics().num_alive_tasks()
+}
+
+async fn wait_idle(client: Option<&mut Session>) -> Result<HashSet<Subsystem>> {
+    match client {
+        Some(client) if client.is_idling() => client.wait_idle().await,
+        _ => pending().await,
+    }
+}
+
+// everything a fresh connection needs before the first draw
+async fn sync(client: &mut Connection) -> Result<Update> {
+    let status = client.status().await?;
+    let song = client.current_song().await?;
+    let queue = client.queue(status.queue_len).await?;
+    Ok(Update {
+        status: Some(status),
+        song,
+        queue: Some(queue),
+        database: false,
+        playlists: false,
+        stickers: false,
+        outputs: false,
+        partitions: false,
+    })
+}
+
+async fn refresh(
+    client: &mut Connection,
+    changed: &HashSet<Subsystem>,
+    perf: &mut Perf,
+) -> Result<Update> {
+    let mut update = Update::default();
+
+    // keeps the queue version current so a restart is caught on reconnect
+    let watched = [
+        Subsystem::Player,
+        Subsystem::Mixer,
+        Subsystem::Options,
+        Subsystem::Playlist,
+    ];
+    if watched.iter().any(|subsystem| changed.contains(subsystem)) {
+        let start = Instant::now();
+        update.status = Some(client.status().await?);
+        update.song = client.current_song().await?;
+        perf.record_mpd(start.elapsed(), tasks());
+    }
+
+    if changed.contains(&Subsystem::Playlist) {
+        let len = update.status.as_ref().map_or(0, |status| status.queue_len);
+        update.queue = Some(client.queue(len).await?);
+    }
+
+    update.database = changed.contains(&Subsystem::Database);
+    update.playlists = changed.contains(&Subsystem::StoredPlaylist);
+    update.stickers = changed.contains(&Subsystem::Sticker);
+    update.outputs = changed.contains(&Subsystem::Output);
+    update.partitions = changed.contains(&Subsystem::Partition);
+    Ok(update)
+}
+
+// the picture in the song's tags, or else the cover file next to it
+async fn cover(client: &mut Connection, uri: &str) -> Result<Option<RgbaImage>> {
+    // covers are big, fewer and larger chunks save round trips
+    if client.supports(Feature::BinaryLimit) {
+        client.binary_limit(DEFAULT_BINARY_LIMIT).await?;
+    }
+
+    let mut data = None;
+    if client.supports(Feature::ReadPicture) {
+        data = client.read_picture(uri).await?;
+    }
+    if data.is_none() && client.supports(Feature::AlbumArt) {
+        data = client.album_art(uri).await?;
+    }
+
+    let Some(data) = data else {
+        return Ok(None);
+    };
+    Ok(tokio::task::spawn_blocking(move || art::decode(&data)).await?)
+}
+
+// what the server will report as changed once the action went through
+fn issued(action: &Action) -> Vec<Subsystem> {
+    match action {
+        Action::Play(_) => vec![Subsystem::Player],
+        Action::SetVolume(_) => vec![Subsystem::Mixer],
+        Action::SetRepeat(_) | Action::SetRandom(_) => vec![Subsystem::Options],
+        Action::SetSingle(_) | Action::SetConsume(_) => vec![Subsystem::Options],
+        Action::Load(_) | Action::SearchAdd(_) | Action::Sort(_) => vec![Subsystem::Playlist],
+        Action::Replace(_) | Action::AddPlay(_) => vec![Subsystem::Playlist, Subsystem::Player],
+        Action::Delete(_) | Action::Save(_) | Action::Restore(_) => {
+            vec![Subsystem::StoredPlaylist]
+        }
+        Action::AddTo(..) => vec![Subsystem::StoredPlaylist],
+        Action::Run(list) | Action::Edit(list) => list
+            .names()
+            .flat_map(attribution::touched)
+            .copied()
+            .collect(),
+        Action::NewPartition(_) | Action::DeletePartition(_) => vec![Subsystem::Partition],
+        Action::Flag(_) => vec![Subsystem::Sticker],
+        Action::Enqueue(_) => vec![Subsystem::Playlist, Subsystem::Sticker],
+        _ => Vec::new(),
+    }
+}
+
+// the queue and what it is sorted by as they are now, rather than as last
+// seen; moving tracks leaves the playing one playing
+async fn sort(client: &mut Connection, by: Sort) -> Result<()> {
+    let len = client.status().await?.queue_len;
+    let tracks = client.queue(len).await?;
+    let ids: Vec<_> = tracks.iter().filter_map(|track| track.id).collect();
+    let mut sorted: Vec<_> = tracks.iter().collect();
+    match by {
+        Sort::Rating => {
+            let mut ratings = Ratings::default();
+            ratings.set(client.sticker_find("", ratings::STICKER).await?);
+            sorted.sort_by_key(|track| Reverse(ratings.get(&track.file)));
+        }
+        Sort::Random => shuffle(&mut sorted),
+        by => sorted.sort_by(|a, b| order(a, b, by)),
+    }
+
+    let sorted: Vec<_> = sorted.iter().filter_map(|track| track.id).collect();
+    if let Some(list) = bulk::reorder(&ids, &sorted) {
+        client.run(&list).await?;
+    }
+    Ok(())
+}
+
+fn order(a: &Track, b: &Track, by: Sort) -> Ordering {
+    let album = |t: &Track| (text(&t.album), number(&t.disc), number(&t.track));
+    let track = |t: &Track| (number(&t.disc), number(&t.track));
+    match by {
+        Sort::Artist => (text(&a.artist), album(a)).cmp(&(text(&b.artist), album(b))),
+        Sort::Album => album(a).cmp(&album(b)),
+        Sort::Track => track(a).cmp(&track(b)),
+        Sort::Title => text(&a.title).cmp(&text(&b.title)),
+        Sort::Date => (text(&a.date), album(a)).cmp(&(text(&b.date), album(b))),
+        Sort::Random | Sort::Rating => Ordering::Equal,
+    }
+}
+
+// a missing tag sorts after every other
+fn text(tag: &Option<String>) -> (bool, Option<String>) {
+    (tag.is_none(), tag.as_deref().map(str::to_lowercase))
+}
+
+// track and disc numbers may come as `3/12`, and 10 goes after 9
+fn number(tag: &Option<String>) -> (bool, Option<u32>) {
+    let first = tag.as_deref().and_then(|tag| tag.split('/').next());
+    let number = first.and_then(|number| number.trim().parse().ok());
+    (number.is_none(), number)
+}
+
+fn shuffle<T>(items: &mut [T]) {
+    for i in (1..items.len()).rev() {
+        let j = RandomState::new().build_hasher().finish() as usize % (i + 1);
+        items.swap(i, j);
+    }
+}
+
+async fn queued(client: &mut Connection) -> Result<Reply> {
+    Ok(Reply::Queued(client.status().await?.queue_len))
+}
+
+// the level below `node`, or the artists for none
+async fn browse(
+    client: &mut Connection,
+    node: Option<Node>,
+    cache: Option<(&Cache, &Endpoint)>,
+) -> Result<Vec<Node>> {
+    let filter = node.as_ref().and_then(Node::filter);
+    Ok(match node {
+        None => {
+            let artists = artists(client, cache).await?;
+            artists.into_iter().map(Node::Artist).collect()
+        }
+        Some(Node::Artist(artist)) => {
+            let albums = client.list("album", filter.as_deref()).await?;
+            let album = |album| Node::Album {
+                artist: artist.clone(),
+                album,
+            };
+            albums.into_iter().map(album).collect()
+        }
+        Some(Node::Album { .. }) => {
+            let tracks = client.find(filter.as_deref().unwrap_or_default()).await?;
+            tracks.into_iter().map(Box::new).map(Node::Track).collect()
+        }
+        Some(Node::Track(_)) => Vec::new(),
+    })
+}
+
+// all of them is the one list that takes long on a big library, so it is kept
+// for each server until its database changes
+async fn artists(
+    client: &mut Connection,
+    cache: Option<(&Cache, &Endpoint)>,
+) -> Result<Vec<String>> {
+    let Some((cache, endpoint)) = cache else {
+        return client.list("artist", None).await;
+    };
+
+    let server = endpoint.to_string();
+    let server = server.replace(|c: char| !c.is_alphanumeric(), "_");
+    let name = format!("artists-{server}");
+    let db_update = client.db_update().await?;
+    if let Some(artists) = cache.load_list(&name, CACHED, db_update) {
+        return Ok(artists);
+    }
+
+    let artists = client.list("artist", None).await?;
+    // one that cannot be written only costs the next start its time
+    let _ = cache.store_list(&name, CACHED, db_update, &artists);
+    Ok(artists)
+}
+
+// an artist's songs are counted by album, an album's all together
+async fn info(client: &mut Connection, node: Node) -> Result<(String, Stats)> {
+    let filter = node.filter().unwrap_or_default();
+    let group = matches!(node, Node::Artist(_)).then_some("album");
+    let counts = client.count(&filter, group).await?;
+    let dates = client.list("date", Some(&filter)).await?;
+    let genres = client.list("genre", Some(&filter)).await?;
+    Ok((filter, Stats::new(counts, dates, genres)))
+}
+
+async fn list(client: &mut Connection, path: Option<String>) -> Result<Vec<Entry>> {
+    let mut listing = client.lsinfo(path.as_deref()).await?;
+    let mut entries = Vec::new();
+    while let Some(entry) = listing.next().await? {
+        entries.push(entry);
+    }
+    Ok(entries)
+}
+
+// the tracks of a playlist, or the saved playlists for none
+async fn view(client: &mut Connection, name: Option<String>) -> Result<Vec<Item>> {
+    Ok(match name {
+        Some(name) => {
+            let tracks = client.playlist(&name).await?;
+            tracks.into_iter().map(Box::new).map(Item::Track).collect()
+        }
+        None => {
+            let names = client.playlists().await?;
+            names.into_iter().map(Item::Playlist).collect()
+        }
+    })
+}
@@ -0,0 +1,3216 @@
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    io,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use encore_core::{
+    cache::Cache,
+    config::{self, Config, Width},
+    connection::{Connection, Endpoint, Target},
+    session::Session,
+    trash::Trash,
+};
+use encore_mpd::{Client, ConsumeMode, SingleMode};
+use eyre::{eyre, Result};
+use ratatui::{
+    backend::TestBackend,
+    buffer::Cell,
+    crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers},
+    style::{Color, Modifier, Style, Stylize},
+    Terminal,
+};
+use tokio::{
+    io::{duplex, split, AsyncBufReadExt, AsyncWriteExt, BufReader, DuplexStream},
+    sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender},
+    task::yield_now,
+    time::{sleep, timeout, Instant},
+};
+use tokio_stream::wrappers::UnboundedReceiverStream;
+
+use super::{App, ConnectEvent};
+use crate::{
+    art::{Art, Protocol},
+    event_log,
+    lyrics::Lyrics,
+    remote::Remote,
+    schedule::Pane,
+    tabs::Tab,
+    theme::Theme,
+    visualizer::Visualizer,
+    watcher::Watcher,
+};
+
+const OPTIONS: [&str; 4] = ["repeat", "random", "single", "consume"];
+
+enum Attempt {
+    Refuse,
+    Serve(&'static str),
+    Mute,
+}
+
+enum Signal {
+    Changed(&'static str),
+    Hangup,
+}
+
+type Log = Arc<Mutex<Vec<String>>>;
+
+// artist, album and file of a song in the database
+type Song = (&'static str, &'static str, &'static str);
+
+type Output = (&'static str, bool, Vec<(&'static str, String)>);
+
+// scripted session attempts against an in-memory MPD that logs every command
+// it receives per connection, answers `status` with the current player state
+// and holds `idle` until the test signals a change
+#[derive(Clone, Default)]
+struct FakeMpd {
+    attempts: Arc<Mutex<VecDeque<Attempt>>>,
+    targets: Arc<Mutex<Vec<Endpoint>>>,
+    commands: Log,
+    idles: Log,
+    state: Arc<Mutex<&'static str>>,
+    queue: Arc<Mutex<Vec<&'static str>>>,
+    song: Arc<Mutex<Option<usize>>>,
+    volume: Arc<Mutex<Option<u8>>>,
+    options: Arc<Mutex<HashMap<String, String>>>,
+    library: Arc<Mutex<Vec<Song>>>,
+    playlists: Arc<Mutex<BTreeMap<String, Vec<&'static str>>>>,
+    ids: Arc<Mutex<Vec<&'static str>>>,
+    covers: Arc<Mutex<HashMap<&'static str, Vec<u8>>>>,
+    // by file and sticker name
+    stickers: Arc<Mutex<BTreeMap<(String, String), String>>>,
+    // what `readcomments` finds in any file
+    comments: Arc<Mutex<Vec<(&'static str, &'static str)>>>,
+    // name, whether enabled, and attributes
+    outputs: Arc<Mutex<Vec<Output>>>,
+    // the partition in use, empty for the default one, and the queues of
+    // the others
+    partition: Arc<Mutex<String>>,
+    // the format and bit rate `status` reports while a song is current
+    audio: Arc<Mutex<Option<(&'static str, u32)>>>,
+    partitions: Arc<Mutex<BTreeMap<String, Vec<&'static str>>>>,
+    // when `stats` says the database last changed
+    db_update: Arc<Mutex<u64>>,
+    signals: Arc<Mutex<Vec<UnboundedSender<Signal>>>>,
+}
+
+impl FakeMpd {
+    fn script(attempts: impl IntoIterator<Item = Attempt>) -> Self {
+        Self {
+            attempts: Arc::new(Mutex::new(attempts.into_iter().collect())),
+            ..Self::default()
+        }
+    }
+
+    async fn connect(self, endpoint: Endpoint) -> Result<Session> {
+        self.targets.lock().unwrap().push(endpoint.clone());
+        let attempt = self.attempts.lock().unwrap().pop_front();
+        let state = match attempt {
+            Some(Attempt::Serve(state)) => state,
+            Some(Attempt::Mute) => "mute",
+            _ => return Err(eyre!("connection refused")),
+        };
+
+        *self.state.lock().unwrap() = state;
+        self.signals.lock().unwrap().clear();
+        let command = self.open(&endpoint, self.commands.clone()).await?;
+        let idle = self.open(&endpoint, self.idles.clone()).await?;
+        Session::new(idle, command).await
+    }
+
+    async fn open(&self, endpoint: &Endpoint, log: Log) -> Result<Connection> {
+        let (tx, rx) = unbounded_channel();
+        self.signals.lock().unwrap().push(tx);
+
+        let (client, server) = duplex(4096);
+        tokio::spawn(self.clone().serve(server, rx, log));
+
+        let (r, w) = split(client);
+        let mut client = Client::init_client(Box::new(r) as _, Box::new(w) as _).await?;
+        if let Some(password) = &endpoint.password {
+            client.password(password).await?;
+        }
+
+        Ok(client)
+    }
+
+    async fn serve(
+        self,
+        server: DuplexStream,
+        mut signals: UnboundedReceiver<Signal>,
+        log: Log,
+    ) -> io::Result<()> {
+        let (r, mut w) = split(server);
+        let mut lines = BufReader::new(r).lines();
+        let mut idling = false;
+        let mut batch: Option<Vec<String>> = None;
+        w.write_all(b"OK MPD 0.24.0\n").await?;
+
+        loop {
+            let line = tokio::select! {
+                biased;
+                line = lines.next_line() => line?,
+                Some(signal) = signals.recv() => match signal {
+                    Signal::Changed(subsystem) if idling => {
+                        idling = false;
+                        w.write_all(format!("changed: {subsystem}\nOK\n").as_bytes()).await?;
+                        continue;
+                    }
+                    Signal::Changed(_) => continue,
+                    Signal::Hangup => return Ok(()),
+                },
+            };
+
+            let Some(line) = line else {
+                return Ok(());
+            };
+
+            // a command list is answered in one go once it ends
+            if let Some(batch) = &mut batch {
+                if line != "command_list_end" {
+                    log.lock().unwrap().push(line.clone());
+                    batch.push(line);
+                    continue;
+                }
+            }
+
+            // pictures are the one reply that is not text
+            if let Some(args) = line.strip_prefix("readpicture ") {
+                let reply = self.picture(args);
+                log.lock().unwrap().push(line);
+                w.write_all(&reply).await?;
+                continue;
+            }
+
+            let reply = match line.as_str() {
+                _ if *self.state.lock().unwrap() == "mute" => String::new(),
+                "idle" => {
+                    idling = true;
+                    String::new()
+                }
+                "noidle" => {
+                    idling = false;
+                    "OK\n".into()
+                }
+                "command_list_ok_begin" => {
+                    batch = Some(Vec::new());
+                    String::new()
+                }
+                // the list stops at the first command the server refuses
+                "command_list_end" => {
+                    let mut replies = String::new();
+                    for line in batch.take().unwrap_or_default() {
+                        let reply = self.answer(&line);
+                        match reply.strip_suffix("OK\n") {
+                            Some(reply) => replies.push_str(&format!("{reply}list_OK\n")),
+                            None => {
+                                replies.push_str(&reply);
+                                break;
+                            }
+                        }
+                    }
+                    if !replies.contains("ACK ") {
+                        replies.push_str("OK\n");
+                    }
+                    replies
+                }
+                _ => self.answer(&line),
+            };
+            log.lock().unwrap().push(line);
+            w.write_all(reply.as_bytes()).await?;
+        }
+    }
+
+    fn answer(&self, line: &str) -> String {
+        match line {
+            "status" => {
+                let state = *self.state.lock().unwrap();
+                let len = self.queue.lock().unwrap().len();
+                let song = match *self.song.lock().unwrap() {
+                    Some(pos) if pos + 1 < len => {
+                        format!("song: {pos}\nelapsed: 0.000\nnextsong: {}\n", pos + 1)
+                    }
+                    Some(pos) => format!("song: {pos}\nelapsed: 0.000\n"),
+                    None => String::new(),
+                };
+                let audio = match (&song[..], *self.audio.lock().unwrap()) {
+                    ("", _) | (_, None) => String::new(),
+                    (_, Some((format, kbps))) => format!("audio: {format}\nbitrate: {kbps}\n"),
+                };
+                let volume = self.volume.lock().unwrap().map_or(-1, i16::from);
+                let options = self.options.lock().unwrap();
+                let options: String = OPTIONS
+                    .iter()
+                    .map(|name| {
+                        let value = options.get(*name).map_or("0", String::as_str);
+                        format!("{name}: {value}\n")
+                    })
+                    .collect();
+                let partition = self.partition();
+                format!(
+                    "volume: {volume}\n{options}playlistlength: {len}\nstate: {state}\n{song}\
+                     {audio}partition: {partition}\nOK\n"
+                )
+            }
+            "currentsong" => match *self.song.lock().unwrap() {
+                Some(pos) => {
+                    let file = self.queue.lock().unwrap()[pos];
+                    let library = self.library.lock().unwrap();
+                    let tags = match library.iter().find(|song| song.2 == file) {
+                        Some((artist, album, _)) => format!("Artist: {artist}\nAlbum: {album}\n"),
+                        None => String::new(),
+                    };
+                    format!("file: {file}\nId: {}\n{tags}OK\n", self.id(file))
+                }
+                None => "OK\n".into(),
+            },
+            "playlistinfo" => {
+                let queue = self.queue.lock().unwrap();
+                let library = self.library.lock().unwrap();
+                let tracks: String = queue
+                    .iter()
+                    .enumerate()
+                    .map(|(pos, file)| {
+                        let (id, len) = (self.id(file), queue.len());
+                        // songs from the library come with its tags
+                        let tags = match library.iter().find(|song| song.2 == *file) {
+                            Some((artist, album, _)) => {
+                                format!("Artist: {artist}\nAlbum: {album}\n")
+                            }
+                            None => String::new(),
+                        };
+                        format!(
+                            "file: {file}\nId: {id}\n{tags}Track: {}/{len}\nTime: 200\n",
+                            pos + 1
+                        )
+                    })
+                    .collect();
+                format!("{tracks}OK\n")
+            }
+            _ if OPTIONS
+                .iter()
+                .any(|name| line.starts_with(&format!("{name} "))) =>
+            {
+                let (name, value) = line.split_once(' ').unwrap();
+                let value = value.trim_matches('"').to_string();
+                self.options.lock().unwrap().insert(name.into(), value);
+                self.signal(|| Signal::Changed("options"));
+                "OK\n".into()
+            }
+            _ if line.starts_with("setvol ") => {
+                *self.volume.lock().unwrap() = line[7..].trim_matches('"').parse().ok();
+                self.signal(|| Signal::Changed("mixer"));
+                "OK\n".into()
+            }
+            "stats" => {
+                let db_update = self.db_update.lock().unwrap();
+                format!("artists: 2\ndb_update: {db_update}\nOK\n")
+            }
+            _ if line.starts_with("list ") => {
+                let tag = match line[5..].split(' ').next() {
+                    Some("\"album\"") => "Album",
+                    Some("\"date\"") => "Date",
+                    Some("\"genre\"") => "Genre",
+                    _ => "Artist",
+                };
+                let filter = quoted(line);
+                // every song is rock, the first album came out before the rest
+                let value = |(artist, album, _): Song| match tag {
+                    "Album" => album,
+                    "Date" if album == "First" => "2001",
+                    "Date" => "2004-05-01",
+                    "Genre" => "Rock",
+                    _ => artist,
+                };
+                let mut values: Vec<_> = self.songs(&filter).into_iter().map(value).collect();
+                values.dedup();
+                let values: String = values
+                    .iter()
+                    .map(|value| format!("{tag}: {value}\n"))
+                    .collect();
+                format!("{values}OK\n")
+            }
+            _ if line.starts_with("count ") => {
+                let songs = self.songs(&quoted(line));
+                let count = |n: usize| format!("songs: {n}\nplaytime: {}\n", n * 200);
+                let counts: String = match line.ends_with("\"group\" \"album\"") {
+                    true => {
+                        let mut albums: Vec<_> = songs.iter().map(|song| song.1).collect();
+                        albums.dedup();
+                        let album = |album| {
+                            let n = songs.iter().filter(|song| song.1 == album).count();
+                            format!("Album: {album}\n{}", count(n))
+                        };
+                        albums.into_iter().map(album).collect()
+                    }
+                    false => count(songs.len()),
+                };
+                format!("{counts}OK\n")
+            }
+            _ if line.starts_with("find ") => {
+                let tracks: String = self
+                    .songs(&quoted(line))
+                    .iter()
+                    .map(|(_, _, file)| format!("file: {file}\nTime: 200\n"))
+                    .collect();
+                format!("{tracks}OK\n")
+            }
+            _ if line.starts_with("search ") => {
+                let tracks: String = self
+                    .matches(&quoted(line))
+                    .iter()
+                    .map(|(artist, _, file)| format!("file: {file}\nArtist: {artist}\nTime: 200\n"))
+                    .collect();
+                format!("{tracks}OK\n")
+            }
+            _ if line.starts_with("searchadd ") => {
+                let songs = self.matches(&quoted(line));
+                let files = songs.iter().map(|(_, _, file)| *file);
+                self.queue.lock().unwrap().extend(files);
+                self.signal(|| Signal::Changed("playlist"));
+                "OK\n".into()
+            }
+            _ if line.starts_with("findadd ") => {
+                let songs = self.songs(&quoted(line));
+                let files = songs.iter().map(|(_, _, file)| *file);
+                self.queue.lock().unwrap().extend(files);
+                self.signal(|| Signal::Changed("playlist"));
+                "OK\n".into()
+            }
+            _ if line == "lsinfo" || line.starts_with("lsinfo ") => {
+                let dir = line[6..].trim().trim_matches('"');
+                let mut entries: Vec<String> = Vec::new();
+                for (_, _, file) in self.library.lock().unwrap().iter() {
+                    let Some(rest) = file.strip_prefix(dir) else {
+                        continue;
+                    };
+                    let rest = rest.trim_start_matches('/');
+                    let entry = match rest.split_once('/') {
+                        Some((child, _)) => {
+                            let child = [dir, child].join("/");
+                            format!("directory: {}\n", child.trim_start_matches('/'))
+                        }
+                        None => format!("file: {file}\nTime: 200\n"),
+                    };
+                    if !entries.contains(&entry) {
+                        entries.push(entry);
+                    }
+                }
+                format!("{}OK\n", entries.concat())
+            }
+            _ if line.starts_with("sticker set song ") => {
+                let args: Vec<_> = line.split('"').skip(1).step_by(2).collect();
+                let stickers = &mut self.stickers.lock().unwrap();
+                stickers.insert((args[0].into(), args[1].into()), args[2].into());
+                self.signal(|| Signal::Changed("sticker"));
+                "OK\n".into()
+            }
+            _ if line.starts_with("sticker delete song ") => {
+                let args: Vec<_> = line.split('"').skip(1).step_by(2).collect();
+                let key = (args[0].into(), args[1].into());
+                self.stickers.lock().unwrap().remove(&key);
+                self.signal(|| Signal::Changed("sticker"));
+                "OK\n".into()
+            }
+            _ if line.starts_with("sticker get song ") => {
+                let args: Vec<_> = line.split('"').skip(1).step_by(2).collect();
+                let key = (args[0].into(), args[1].into());
+                match self.stickers.lock().unwrap().get(&key) {
+                    Some(value) => format!("sticker: {}={value}\nOK\n", args[1]),
+                    None => "ACK [50@0] {sticker} no such sticker\n".into(),
+                }
+            }
+            _ if line.starts_with("sticker find song ") => {
+                let args: Vec<_> = line.split('"').skip(1).step_by(2).collect();
+                let stickers = self.stickers.lock().unwrap();
+                let found: String = stickers
+                    .iter()
+                    .filter(|((_, name), _)| name == args[1])
+                    .map(|((file, name), value)| format!("file: {file}\nsticker: {name}={value}\n"))
+                    .collect();
+                format!("{found}OK\n")
+            }
+            "urlhandlers" => "handler: file://\nhandler: http://\nOK\n".into(),
+            _ if line.starts_with("readcomments ") => {
+                let comments = self.comments.lock().unwrap();
+                let found: String = comments
+                    .iter()
+                    .map(|(name, value)| format!("{name}: {value}\n"))
+                    .collect();
+                format!("{found}OK\n")
+            }
+            "outputs" => {
+                let outputs = self.outputs.lock().unwrap();
+                let outputs: String = outputs
+                    .iter()
+                    .enumerate()
+                    .map(|(id, (name, enabled, attributes))| {
+                        let enabled = u8::from(*enabled);
+                        let attributes: String = attributes
+                            .iter()
+                            .map(|(name, value)| format!("attribute: {name}={value}\n"))
+                            .collect();
+                        format!(
+                            "outputid: {id}\noutputname: {name}\nplugin: alsa\n\
+                             outputenabled: {enabled}\n{attributes}"
+                        )
+                    })
+                    .collect();
+                format!("{outputs}OK\n")
+            }
+            _ if line.starts_with("toggleoutput ") => {
+                let id: usize = line[13..].trim_matches('"').parse().unwrap();
+                let enabled = &mut self.outputs.lock().unwrap()[id].1;
+                *enabled = !*enabled;
+                self.signal(|| Signal::Changed("output"));
+                "OK\n".into()
+            }
+            _ if line.starts_with("outputset ") => {
+                let args: Vec<_> = line[10..]
+                    .split(' ')
+                    .map(|arg| arg.trim_matches('"'))
+                    .collect();
+                let id: usize = args[0].parse().unwrap();
+                let mut outputs = self.outputs.lock().unwrap();
+                for (name, value) in &mut outputs[id].2 {
+                    if *name == args[1] {
+                        *value = args[2].into();
+                    }
+                }
+                self.signal(|| Signal::Changed("output"));
+                "OK\n".into()
+            }
+            "listpartitions" => {
+                let mut names = vec![self.partition()];
+                names.extend(self.partitions.lock().unwrap().keys().cloned());
+                names.sort();
+                let names: String = names
+                    .iter()
+                    .map(|name| format!("partition: {name}\n"))
+                    .collect();
+                format!("{names}OK\n")
+            }
+            // both connections switch, the second one finds it done
+            _ if line.starts_with("partition ") => {
+                let name = line[10..].trim_matches('"');
+                if name == self.partition() {
+                    return "OK\n".into();
+                }
+                let Some(queue) = self.partitions.lock().unwrap().remove(name) else {
+                    return "ACK [50@0] {partition} partition does not exist\n".into();
+                };
+                let old = std::mem::replace(&mut *self.queue.lock().unwrap(), queue);
+                let mut partitions = self.partitions.lock().unwrap();
+                partitions.insert(self.partition(), old);
+                *self.partition.lock().unwrap() = name.into();
+                *self.song.lock().unwrap() = None;
+                "OK\n".into()
+            }
+            _ if line.starts_with("newpartition ") => {
+                let name = line[13..].trim_matches('"');
+                self.partitions
+                    .lock()
+                    .unwrap()
+                    .insert(name.into(), Vec::new());
+                self.signal(|| Signal::Changed("partition"));
+                "OK\n".into()
+            }
+            _ if line.starts_with("delpartition ") => {
+                let name = line[13..].trim_matches('"');
+                self.partitions.lock().unwrap().remove(name);
+                self.signal(|| Signal::Changed("partition"));
+                "OK\n".into()
+            }
+            _ if line.starts_with("update ") => "updating_db: 1\nOK\n".into(),
+            _ if line.starts_with("add \"http") => {
+                let url = line[4..].trim_matches('"').to_string();
+                self.queue.lock().unwrap().push(url.leak());
+                self.signal(|| Signal::Changed("playlist"));
+                "OK\n".into()
+            }
+            _ if line.starts_with("add ") => {
+                let dir = line[4..].trim_matches('"');
+                let songs = self.library.lock().unwrap().clone();
+                let files = songs.iter().map(|(_, _, file)| *file);
+                let files = files.filter(|file| file.starts_with(dir));
+                self.queue.lock().unwrap().extend(files);
+                self.signal(|| Signal::Changed("playlist"));
+                "OK\n".into()
+            }
+            "listplaylists" => {
+                let playlists = self.playlists.lock().unwrap();
+                let names: String = playlists
+                    .keys()
+                    .map(|name| format!("playlist: {name}\nLast-Modified: 2024-01-01T00:00:00Z\n"))
+                    .collect();
+                format!("{names}OK\n")
+            }
+            _ if line.starts_with("listplaylistinfo ") => {
+                let playlists = self.playlists.lock().unwrap();
+                let files = &playlists[line[17..].trim_matches('"')];
+                let tracks: String = files
+                    .iter()
+                    .map(|file| format!("file: {file}\nTime: 200\n"))
+                    .collect();
+                format!("{tracks}OK\n")
+            }
+            _ if line.starts_with("load ") => {
+                let files = self.playlists.lock().unwrap()[line[5..].trim_matches('"')].clone();
+                self.queue.lock().unwrap().extend(files);
+                self.signal(|| Signal::Changed("playlist"));
+                "OK\n".into()
+            }
+            _ if line.starts_with("seekcur ") && self.song.lock().unwrap().is_none() => {
+                "ACK [2@0] {seekcur} Not playing\n".into()
+            }
+            "clear" => {
+                self.queue.lock().unwrap().clear();
+                *self.song.lock().unwrap() = None;
+                self.signal(|| Signal::Changed("playlist"));
+                "OK\n".into()
+            }
+            _ if line.starts_with("rm ") => {
+                self.playlists
+                    .lock()
+                    .unwrap()
+                    .remove(line[3..].trim_matches('"'));
+                self.signal(|| Signal::Changed("stored_playlist"));
+                "OK\n".into()
+            }
+            _ if line.starts_with("save ") => {
+                let (name, _) = line[6..].split_once('"').unwrap();
+                let queue = self.queue.lock().unwrap().clone();
+                self.playlists.lock().unwrap().insert(name.into(), queue);
+                self.signal(|| Signal::Changed("stored_playlist"));
+                "OK\n".into()
+            }
+            _ if line.starts_with("deleteid ") => {
+                let pos = self.position(&line[9..]).unwrap();
+                self.queue.lock().unwrap().remove(pos);
+                self.signal(|| Signal::Changed("playlist"));
+                "OK\n".into()
+            }
+            _ if line.starts_with("moveid ") => {
+                let (id, to) = line[7..].split_once(' ').unwrap();
+                let pos = self.position(id).unwrap();
+                let mut queue = self.queue.lock().unwrap();
+                let file = queue.remove(pos);
+                queue.insert(to.trim_matches('"').parse().unwrap(), file);
+                self.signal(|| Signal::Changed("playlist"));
+                "OK\n".into()
+            }
+            _ if line.starts_with("playlistadd ") => {
+                let args = line[13..].split("\" \"").collect::<Vec<_>>();
+                let name = args[0].to_string();
+                let file = self
+                    .library
+                    .lock()
+                    .unwrap()
+                    .iter()
+                    .map(|song| song.2)
+                    .chain(self.queue.lock().unwrap().iter().copied())
+                    .find(|file| *file == args[1].trim_end_matches('"'))
+                    .unwrap();
+                self.playlists
+                    .lock()
+                    .unwrap()
+                    .entry(name)
+                    .or_default()
+                    .push(file);
+                self.signal(|| Signal::Changed("stored_playlist"));
+                "OK\n".into()
+            }
+            _ if line.starts_with("searchaddpl ") => {
+                let (name, _) = line[13..].split_once('"').unwrap();
+                let songs = self.matches(&quoted(line));
+                let files = songs.iter().map(|(_, _, file)| *file);
+                self.playlists
+                    .lock()
+                    .unwrap()
+                    .entry(name.into())
+                    .or_default()
+                    .extend(files);
+                self.signal(|| Signal::Changed("stored_playlist"));
+                "OK\n".into()
+            }
+            _ if line.starts_with("addid ") => {
+                let file = self
+                    .library
+                    .lock()
+                    .unwrap()
+                    .iter()
+                    .find(|(_, _, file)| line.contains(file))
+                    .unwrap()
+                    .2;
+                self.queue.lock().unwrap().push(file);
+                self.signal(|| Signal::Changed("playlist"));
+                format!("Id: {}\nOK\n", self.id(file))
+            }
+            _ if line.starts_with("playid ") => {
+                *self.song.lock().unwrap() = self.position(&line[7..]);
+                *self.state.lock().unwrap() = "play";
+                self.signal(|| Signal::Changed("player"));
+                "OK\n".into()
+            }
+            _ if line.starts_with("play ") => {
+                *self.song.lock().unwrap() = line[5..].trim_matches('"').parse().ok();
+                *self.state.lock().unwrap() = "play";
+                self.signal(|| Signal::Changed("player"));
+                "OK\n".into()
+            }
+            _ if line.starts_with("albumart ") => "ACK [50@0] {albumart} No file exists\n".into(),
+            _ => "OK\n".into(),
+        }
+    }
+
+    // ids stay with a file for as long as the fake lives
+    // covers go out in small chunks so it takes a few requests
+    fn picture(&self, args: &str) -> Vec<u8> {
+        let (uri, offset) = args.split_once(' ').unwrap();
+        let offset: usize = offset.trim_matches('"').parse().unwrap();
+        let covers = self.covers.lock().unwrap();
+        let Some(cover) = covers.get(uri.trim_matches('"')) else {
+            return b"OK\n".to_vec();
+        };
+
+        let chunk = &cover[offset..cover.len().min(offset + 100)];
+        let mut reply = format!("size: {}\ntype: image/png\n", cover.len());
+        reply += &format!("binary: {}\n", chunk.len());
+        let mut reply = reply.into_bytes();
+        reply.extend_from_slice(chunk);
+        reply.extend_from_slice(b"\nOK\n");
+        reply
+    }
+
+    fn id(&self, file: &'static str) -> usize {
+        let mut ids = self.ids.lock().unwrap();
+        match ids.iter().position(|id| *id == file) {
+            Some(id) => id,
+            None => {
+                ids.push(file);
+                ids.len() - 1
+            }
+        }
+    }
+
+    // where the track with a quoted id sits in the queue
+    fn position(&self, id: &str) -> Option<usize> {
+        let id: usize = id.trim_matches('"').parse().ok()?;
+        let file = *self.ids.lock().unwrap().get(id)?;
+        self.queue
+            .lock()
+            .unwrap()
+            .iter()
+            .position(|other| *other == file)
+    }
+
+    // songs whose tags start with the filter's values, in artist, album order
+    fn songs(&self, filter: &[&str]) -> Vec<Song> {
+        let library = self.library.lock().unwrap();
+        let songs = library.iter().filter(|(artist, album, _)| {
+            filter
+                .iter()
+                .zip([artist, album])
+                .all(|(value, tag)| value == tag)
+        });
+        songs.copied().collect()
+    }
+
+    // songs with every value somewhere in their tags, ignoring case
+    fn matches(&self, values: &[&str]) -> Vec<Song> {
+        let library = self.library.lock().unwrap();
+        let songs = library.iter().filter(|(artist, album, file)| {
+            let tags = [artist, album, file].map(|tag| tag.to_lowercase());
+            let found = |value: &&str| tags.iter().any(|tag| tag.contains(&value.to_lowercase()));
+            values.iter().all(found)
+        });
+        songs.copied().collect()
+    }
+
+    fn partition(&self) -> String {
+        match self.partition.lock().unwrap().as_str() {
+            "" => "default".into(),
+            name => name.into(),
+        }
+    }
+
+    fn signal(&self, signal: impl Fn() -> Signal) {
+        for tx in &*self.signals.lock().unwrap() {
+            let _ = tx.send(signal());
+        }
+    }
+
+    fn set_state(&self, state: &'static str) {
+        *self.state.lock().unwrap() = state;
+        self.signal(|| Signal::Changed("player"));
+    }
+
+    fn set_queue(&self, files: &[&'static str]) {
+        *self.queue.lock().unwrap() = files.to_vec();
+        self.signal(|| Signal::Changed("playlist"));
+    }
+
+    fn targets(&self) -> Vec<Endpoint> {
+        self.targets.lock().unwrap().clone()
+    }
+
+    // lets the server tasks catch up on what the app has written so far
+    async fn commands(&self) -> Vec<String> {
+        settle().await;
+        self.commands.lock().unwrap().clone()
+    }
+
+    async fn idles(&self) -> Vec<String> {
+        settle().await;
+        self.idles.lock().unwrap().clone()
+    }
+}
+
+struct Sim {
+    app: App,
+    terminal: Terminal<TestBackend>,
+    keys: UnboundedSender<io::Result<Event>>,
+    events: UnboundedReceiverStream<io::Result<Event>>,
+}
+
+impl Sim {
+    fn new(endpoint: Endpoint, mpd: &FakeMpd) -> Self {
+        let mut app = App::new(
+            endpoint,
+            &Config::default(),
+            Theme::default(),
+            Remote::default(),
+        );
+        let mpd = mpd.clone();
+        app.connector = Arc::new(move |endpoint| Box::pin(mpd.clone().connect(endpoint)));
+        app.art = Art::new(None, Box::new(io::sink()));
+        app.cache = None;
+        app.trash = None;
+        app.watcher = Watcher::default();
+
+        let (keys, rx) = unbounded_channel();
+        Self {
+            app,
+            terminal: Terminal::new(TestBackend::new(60, 10)).unwrap(),
+            keys,
+            events: UnboundedReceiverStream::new(rx),
+        }
+    }
+
+    async fn step(&mut self) {
+        self.app
+            .step(&mut self.terminal, &mut self.events)
+            .await
+            .unwrap();
+    }
+
+    async fn press(&mut self, code: KeyCode) {
+        self.keys
+            .send(Ok(Event::Key(KeyEvent::from(code))))
+            .unwrap();
+        self.step().await;
+    }
+
+    async fn typing(&mut self, text: &str) {
+        for c in text.chars() {
+            self.press(KeyCode::Char(c)).await;
+        }
+    }
+
+    fn screen(&mut self) -> String {
+        self.terminal.draw(|f| self.app.draw(f)).unwrap();
+        let buffer = self.terminal.backend().buffer();
+        buffer.content().iter().map(|cell| cell.symbol()).collect()
+    }
+}
+
+// the values of a filter expression, in the order they appear
+fn quoted(line: &str) -> Vec<&str> {
+    line.split('\'').skip(1).step_by(2).collect()
+}
+
+async fn settle() {
+    for _ in 0..8 {
+        yield_now().await;
+    }
+}
+
+fn target(host: &str) -> Endpoint {
+    Endpoint {
+        target: Target::Tcp {
+            host: host.into(),
+            port: 6600,
+        },
+        password: None,
+    }
+}
+
+#[tokio::test(start_paused = true)]
+async fn connects_and_reads_status() {
+    let mpd = FakeMpd::script([Attempt::Serve("play")]);
+    let mut sim = Sim::new(target("mpd"), &mpd);
+
+    sim.step().await;
+
+    assert!(sim.app.client.is_some());
+    assert!(sim.app.connect.is_none());
+    assert!(sim.app.is_playing);
+    assert_eq!(mpd.targets(), [target("mpd")]);
+    assert_eq!(
+        mpd.commands().await,
+        ["status", "currentsong", "playlistinfo"]
+    );
+    assert_eq!(mpd.idles().await, ["idle"]);
+}
+
+#[tokio::test(start_paused = true)]
+async fn retries_refused_connections() {
+    let mpd = FakeMpd::script([Attempt::Refuse, Attempt::Refuse, Attempt::Serve("stop")]);
+    let mut sim = Sim::new(target("mpd"), &mpd);
+    let start = Instant::now();
+
+    sim.step().await;
+    assert_eq!(sim.app.connecting.attempts, 1);
+    assert_eq!(
+        sim.app.connecting.error.as_deref(),
+        Some("connection refused")
+    );
+    assert_eq!(start.elapsed(), Duration::ZERO);
+
+    sim.step().await;
+    assert_eq!(sim.app.connecting.attempts, 2);
+    let first = start.elapsed();
+    assert!(first >= super::RETRY && first <= super::RETRY * 5 / 4);
+    assert!(sim.screen().contains("attempt 2: connection refused"));
+
+    sim.step().await;
+    assert!(sim.app.client.is_some());
+    assert!(!sim.app.is_playing);
+    assert_eq!(sim.app.connecting.attempts, 0);
+    let second = start.elapsed() - first;
+    assert!(second >= super::RETRY * 2 && second <= super::RETRY * 5 / 2);
+    assert_eq!(mpd.targets().len(), 3);
+}
+
+#[tokio::test(start_paused = true)]
+async fn edits_target_on_connecting_screen() {
+    let mpd = FakeMpd::script([Attempt::Refuse, Attempt::Serve("pause")]);
+    let mut sim = Sim::new(target("old"), &mpd);
+
+    sim.step().await;
+    sim.press(KeyCode::Char('e')).await;
+    assert_eq!(sim.app.connecting.input.as_deref(), Some("old:6600"));
+
+    for _ in 0.."old:6600".len() {
+        sim.press(KeyCode::Backspace).await;
+    }
+    sim.typing("new:6601").await;
+    assert!(sim.screen().contains("target: new:6601"));
+
+    sim.press(KeyCode::Enter).await;
+    assert!(sim.app.connect.is_none());
+    assert_eq!(sim.app.connecting.attempts, 0);
+
+    sim.step().await;
+    let new = Endpoint {
+        target: Target::Tcp {
+            host: "new".into(),
+            port: 6601,
+        },
+        password: None,
+    };
+    assert_eq!(sim.app.endpoint, new);
+    assert!(sim.app.client.is_some());
+    assert_eq!(mpd.targets(), [target("old"), new]);
+}
+
+#[tokio::test(start_paused = true)]
+async fn authenticates_with_endpoint_password() {
+    let mpd = FakeMpd::script([Attempt::Refuse, Attempt::Serve("stop")]);
+    let mut sim = Sim::new(Endpoint::parse("secret@mpd").unwrap(), &mpd);
+
+    sim.step().await;
+    let screen = sim.screen();
+    assert!(screen.contains("Connecting to mpd:6600"));
+    assert!(!screen.contains("secret"));
+
+    sim.step().await;
+    assert!(sim.app.client.is_some());
+    assert_eq!(
+        mpd.commands().await,
+        [
+            "password \"secret\"",
+            "status",
+            "currentsong",
+            "playlistinfo"
+        ]
+    );
+    assert_eq!(mpd.idles().await, ["password \"secret\"", "idle"]);
+}
+
+#[tokio::test(start_paused = true)]
+async fn refreshes_status_when_the_player_changes() {
+    let mpd = FakeMpd::script([Attempt::Serve("stop")]);
+    let mut sim = Sim::new(target("mpd"), &mpd);
+
+    sim.step().await;
+    assert!(!sim.app.is_playing);
+
+    mpd.set_state("play");
+    sim.step().await;
+    assert!(sim.app.is_playing);
+    assert_eq!(
+        mpd.commands().await,
+        [
+            "status",
+            "currentsong",
+            "playlistinfo",
+            "status",
+            "currentsong"
+        ]
+    );
+    assert_eq!(mpd.idles().await, ["idle", "idle"]);
+}
+
+#[tokio::test(start_paused = true)]
+async fn reconnects_after_the_connection_drops() {
+    let mpd = FakeMpd::script([
+        Attempt::Serve("play"),
+        Attempt::Refuse,
+        Attempt::Serve("pause"),
+    ]);
+    let mut sim = Sim::new(target("mpd"), &mpd);
+
+    sim.step().await;
+    assert!(sim.app.is_playing);
+
+    mpd.signal(|| Signal::Hangup);
+    sim.step().await;
+    assert!(sim.app.client.is_none());
+    assert!(sim.app.connecting.reconnecting);
+    let screen = sim.screen();
+    assert!(screen.contains("Reconnecting to mpd:6600"));
+    assert!(screen.contains("connection closed"));
+
+    let start = Instant::now();
+    sim.step().await;
+    assert_eq!(sim.app.connecting.attempts, 1);
+    assert_eq!(start.elapsed(), Duration::ZERO);
+    let screen = sim.screen();
+    assert!(screen.contains("Offline, retrying mpd:6600"));
+    assert!(screen.contains("connection refused"));
+
+    sim.step().await;
+    assert!(start.elapsed() >= super::RETRY);
+    assert!(sim.app.client.is_some());
+    assert!(!sim.app.is_playing);
+    assert!(!sim.app.connecting.reconnecting);
+    assert!(!sim.screen().contains("Offline"));
+    assert_eq!(
+        mpd.commands().await,
+        [
+            "status",
+            "currentsong",
+            "playlistinfo",
+            "status",
+            "currentsong",
+            "playlistinfo"
+        ]
+    );
+    assert_eq!(mpd.idles().await, ["idle", "idle"]);
+}
+
+#[tokio::test(start_paused = true)]
+async fn times_out_an_unresponsive_server() {
+    let mpd = FakeMpd::script([Attempt::Mute, Attempt::Serve("play")]);
+    let mut sim = Sim::new(target("mpd"), &mpd);
+    let start = Instant::now();
+
+    sim.step().await;
+    let command = Config::default().timeout.command;
+    assert_eq!(start.elapsed(), command);
+    assert_eq!(
+        sim.app.connecting.error,
+        Some(format!("no response within {command:?}"))
+    );
+
+    sim.step().await;
+    assert!(sim.app.client.is_some());
+}
+
+#[tokio::test(start_paused = true)]
+async fn pings_a_quiet_connection() {
+    let mpd = FakeMpd::script([Attempt::Serve("stop")]);
+    let mut sim = Sim::new(target("mpd"), &mpd);
+    let keepalive = Config::default().timeout.keepalive;
+
+    sim.step().await;
+    let start = Instant::now();
+    sim.step().await;
+    assert_eq!(start.elapsed(), keepalive);
+    assert_eq!(
+        mpd.commands().await,
+        ["status", "currentsong", "playlistinfo", "ping"]
+    );
+    assert_eq!(mpd.idles().await, ["idle", "noidle", "idle"]);
+
+    *mpd.state.lock().unwrap() = "mute";
+    sim.step().await;
+    assert!(sim.app.client.is_none());
+    assert!(sim.app.connecting.reconnecting);
+}
+
+#[tokio::test(start_paused = true)]
+async fn ignores_results_for_a_stale_target() {
+    let mpd = FakeMpd::script([]);
+    let mut sim = Sim::new(target("current"), &mpd);
+
+    sim.app
+        .connect_tx
+        .send(ConnectEvent::Failed(target("stale"), "gone".into()))
+        .unwrap();
+    sim.step().await;
+
+    assert_eq!(sim.app.connecting.attempts, 0);
+    assert!(sim.app.connecting.error.is_none());
+}
+
+#[tokio::test(start_paused = true)]
+async fn ticks_progress_only_while_playing() {
+    let mpd = FakeMpd::script([Attempt::Serve("play")]);
+    let mut sim = Sim::new(target("mpd"), &mpd);
+
+    sim.step().await;
+    let start = Instant::now();
+    sim.step().await;
+    assert_eq!(start.elapsed(), Duration::from_secs(1));
+
+    sim.app.is_playing = false;
+    sim.keys.send(Ok(Event::FocusGained)).unwrap();
+    sim.step().await;
+    assert!(timeout(Duration::from_secs(5), sim.step()).await.is_err());
+}
+
+#[tokio::test(start_paused = true)]
+async fn toggles_perf_overlay_and_quits() {
+    let mpd = FakeMpd::script([Attempt::Serve("stop")]);
+    let mut sim = Sim::new(target("mpd"), &mpd);
+
+    sim.step().await;
+    sim.press(KeyCode::F(12)).await;
+    assert!(sim.app.show_perf);
+
+    sim.press(KeyCode::Char('q')).await;
+    assert!(sim.app.should_quit);
+    assert_eq!(
+        mpd.commands().await,
+        ["status", "currentsong", "playlistinfo"]
+    );
+    assert_eq!(mpd.idles().await, ["idle"]);
+}
+
+#[tokio::test(start_paused = true)]
+async fn logs_playback_events() {
+    let path = std::env::temp_dir().join(format!("encore-events-{}.jsonl", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let mpd = FakeMpd::script([Attempt::Serve("stop")]);
+    mpd.set_queue(&["one.flac"]);
+    let mut sim = Sim::new(target("mpd"), &mpd);
+    sim.app.log = event_log::Log::start(Some(&path)).unwrap();
+
+    sim.step().await;
+    sim.press(KeyCode::Enter).await;
+    sim.step().await;
+    mpd.set_queue(&["one.flac", "two.flac"]);
+    sim.step().await;
+
+    // written by a task of its own
+    let mut lines = Vec::new();
+    for _ in 0..100 {
+        let text = std::fs::read_to_string(&path).unwrap_or_default();
+        lines = text.lines().map(String::from).collect();
+        if lines.len() >= 2 {
+            break;
+        }
+        std::thread::sleep(Duration::from_millis(10));
+        yield_now().await;
+    }
+    let events: Vec<serde_json::Value> = lines
+        .iter()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(events.len(), 2);
+    assert_eq!(events[0]["event"], "play");
+    assert_eq!(events[0]["file"], "one.flac");
+    assert_eq!(events[1]["event"], "queue_edit");
+    assert_eq!(events[1]["len"], 2);
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[cfg(unix)]
+#[tokio::test(start_paused = true)]
+async fn sends_playback_events_to_subscribers() {
+    let path = std::env::temp_dir().join(format!("encore-events-{}.sock", std::process::id()));
+    let mpd = FakeMpd::script([Attempt::Serve("stop")]);
+    mpd.set_queue(&["one.flac"]);
+    let mut sim = Sim::new(target("mpd"), &mpd);
+    let socket = std::path::PathBuf::from(format!("unix:{}", path.display()));
+    sim.app.log = event_log::Log::start(Some(&socket)).unwrap();
+    let stream = tokio::net::UnixStream::connect(&path).await.unwrap();
+    let mut lines = BufReader::new(stream).lines();
+    settle().await;
+
+    sim.step().await;
+    sim.press(KeyCode::Enter).await;
+    sim.step().await;
+    let line = lines.next_line().await.unwrap().unwrap();
+    let event: serde_json::Value = serde_json::from_str(&line).unwrap();
+    assert_eq!(event["event"], "play");
+    assert_eq!(event["file"], "one.flac");
+
+    // a socket left behind is taken over, a file is not
+    drop(sim);
+    event_log::Log::start(Some(&socket)).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    std::fs::write(&path, "").unwrap();
+    assert!(event_log::Log::start(Some(&socket)).is_err());
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[tokio::test(start_paused = true)]
+async fn tells_who_changed_what() {
+    let mpd = FakeMpd::script([Attempt::Serve("stop")]);
+    let mut sim = Sim::new(target("mpd"), &mpd);
+    sim.terminal = Terminal::new(TestBackend::new(80, 16)).unwrap();
+    sim.step().await;
+
+    // what we asked for is no news
+    sim.press(KeyCode::Char('r')).await;
+    sim.step().await;
+    assert!(!sim.screen().contains("changed by"));
+
+    mpd.set_queue(&["one.flac"]);
+    sim.step().await;
+    assert!(sim.screen().contains("The queue changed by another client"));
+
+    sim.typing(":history").await;
+    sim.press(KeyCode::Enter).await;
+    let screen = sim.screen();
+    assert!(screen.contains("The queue  by another client"));
+    assert!(screen.contains("The playback options  by encore"));
+    sim.press(KeyCode::Esc).await;
+    assert!(sim.app.changes.is_none());
+}
+
+#[tokio::test(start_paused = true)]
+async fn times_commands_in_the_perf_overlay() {
+    let mpd = FakeMpd::script([Attempt::Serve("stop")]);
+    let mut sim = Sim::new(target("mpd"), &mpd);
+
+    sim.step().await;
+    sim.press(KeyCode::F(12)).await;
+    assert!(sim.screen().contains("mpd rtt -"));
+
+    // the fake server runs as a task of its own
+    sim.press(KeyCode::Char('r')).await;
+    assert!(sim.app.perf.mpd_latency.is_some());
+    assert!(sim.app.perf.pending > 0);
+    assert!(!sim.screen().contains("mpd rtt -"));
+
+    // the rate is counted on the same clock as everything else
+    assert_eq!(sim.app.perf.events_per_sec, 0);
+    sleep(Duration::from_secs(1)).await;
+    sim.press(KeyCode::Char('r')).await;
+    assert!(sim.app.perf.events_per_sec > 0);
+}
+
+#[tokio::test(start_paused = true)]
+async fn lists_and_follows_the_queue() {
+    let mpd = FakeMpd::script([Attempt::Serve("stop")]);
+    mpd.set_queue(&["a/one.flac", "a/two.flac"]);
+    let mut sim = Sim::new(target("mpd"), &mpd);
+
+    assert!(sim.screen().contains("Connecting"));
+    sim.step().await;
+    let screen = sim.screen();
+    assert!(screen.contains("one.flac"));
+    assert!(screen.contains("two.flac"));
+    assert!(screen.contains("3:20"));
+
+    mpd.set_queue(&["a/three.flac"]);
+    sim.step().await;
+    assert!(sim.screen().contains("three.flac"));
+    assert_eq!(sim.app.queue.selected(), Some(0));
+    assert_eq!(mpd.idles().await, ["idle", "idle"]);
+
+    mpd.set_queue(&[]);
+    sim.step().await;
+    assert!(sim.screen().contains("The queue is empty"));
+    assert_eq!(sim.app.queue.selected(), None);
+}
+
+#[tokio::test(start_paused = true)]
+async fn plays_the_selected_track() {
+    let mpd = FakeMpd::script([Attempt::Serve("stop")]);
+    mpd.set_queue(&["one.flac", "two.flac", "three.flac"]);
+    let mut sim = Sim::new(target("mpd"), &mpd);
+
+    sim.step().await;
+    assert!(sim.screen().contains("Not playing"));
+    sim.press(KeyCode::End).await;
+    sim.press(KeyCode::Up).await;
+    assert_eq!(sim.app.queue.selected(), Some(1));
+
+    sim.press(KeyCode::Enter).await;
+    assert_eq!(
+        mpd.commands().await,
+        ["status", "currentsong", "playlistinfo", "play \"1\""]
+    );
+
+    sim.step().await;
+    assert!(sim.app.is_playing);
+    let song = sim
+        .app
+        .status
+        .as_ref()
+        .and_then(|status| status.song.as_ref());
+    assert_eq!(song.map(|song| song.pos), Some(1));
+    assert_eq!(
+        sim.app.song.as_ref().map(|song| song.uri.as_str()),
+        Some("two.flac")
+    );
+    assert!(!sim.screen().contains("Not playing"));
+}
+
+#[tokio::test(start_paused = true)]
+async fn keeps_the_queue_read_only_while_offline() {
+    let mpd = FakeMpd::script([Attempt::Serve("stop")]);
+    mpd.set_queue(&["one.flac", "two.flac"]);
+    let mut sim = Sim::new(target("mpd"), &mpd);
+
+    sim.step().await;
+    mpd.signal(|| Signal::Hangup);
+    sim.step().await;
+    assert!(sim.app.client.is_none());
+
+    sim.press(KeyCode::Down).await;
+    sim.press(KeyCode::Enter).await;
+    assert_eq!(sim.app.queue.selected(), Some(1));
+    assert!(sim.screen().contains("two.flac"));
+    assert_eq!(
+        mpd.commands().await,
+        ["status", "currentsong", "playlistinfo"]
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn extrapolates_progress_between_refreshes() {
+    let mpd = FakeMpd::script([Attempt::Serve("stop")]);
+    mpd.set_queue(&["one.flac"]);
+    let mut sim = Sim::new(target("mpd"), &mpd);
+
+    sim.step().await;
+    sim.press(KeyCode::Enter).await;
+    sim.step().await;
+    assert!(sim.screen().contains("0:00 / 3:20"));
+
+    sim.step().await;
+    sim.step().await;
+    assert!(sim.screen().contains("0:02 / 3:20"));
+
+    sim.app.is_playing = false;
+    sim.app.status_at = Instant::now();
+    assert!(sim.screen().contains("0:00 / 3:20"));
+}
+
+#[tokio::test(start_paused = true)]
+async fn steps_the_volume() {
+    let mpd = FakeMpd::script([Attempt::Serve("stop")]);
+    *mpd.volume.lock().unwrap() = Some(98);
+    let mut sim = Sim::new(target("mpd"), &mpd);
+
+    sim.step().await;
+    assert!(sim.screen().contains("vol  98%"));
+
+    sim.press(KeyCode::Char('+')).await;
+    sim.step().await;
+    assert!(sim.screen().contains("vol 100%"));
+
+    sim.press(KeyCode::Char('-')).await;
+    sim.step().await;
+    assert!(sim.screen().contains("vol  95%"));
+    assert_eq!(
+        mpd.commands().await[3..],
+        [
+            "setvol \"100\"",
+            "status",
+            "currentsong",
+            "setvol \"95\"",
+            "status",
+            "currentsong"
+        ]
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn greys_out_the_volume_without_a_mixer() {
+    let mpd = FakeMpd::script([Attempt::Serve("stop")]);
+    let mut sim = Sim::new(target("mpd"), &mpd);
+
+    sim.step().await;
+    assert!(sim.screen().contains("vol --"));
+
+    sim.press(KeyCode::Char('+')).await;
+    assert_eq!(
+        mpd.commands().await,
+        ["status", "currentsong", "playlistinfo"]
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn toggles_playback_flags() {
+    let mpd = FakeMpd::script([Attempt::Serve("stop")]);
+    let mut sim = Sim::new(target("mpd"), &mpd);
+
+    sim.step().await;
+    assert!(sim.screen().contains("repeat random single consume"));
+
+    for key in ['r', 'z', 'y', 'R'] {
+        sim.press(KeyCode::Char(key)).await;
+        sim.step().await;
+    }
+    let status = sim.app.status.as_ref().unwrap();
+    assert!(status.repeat && status.random);
+    assert_eq!(status.single, SingleMode::On);
+    assert_eq!(status.consume, ConsumeMode::On);
+
+    sim.press(KeyCode::Char('y')).await;
+    sim.step().await;
+    assert!(sim.screen().contains("single¹"));
+
+    sim.press(KeyCode::Char('y')).await;
+    sim.step().await;
+    assert_eq!(sim.app.status.as_ref().unwrap().single, SingleMode::Off);
+    let commands = mpd.commands().await;
+    assert_eq!(
+        commands[commands.len() - 6..],
+        [
+            "single \"oneshot\"",
+            "status",
+            "currentsong",
+            "single \"0\"",
+            "status",
+            "currentsong"
+        ]
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn tells_how_the_next_song_follows() {
+    let mpd = FakeMpd::script([Attempt::Serve("stop")]);
+    *mpd.library.lock().unwrap() = vec![("Alpha", "First", "a.flac"), ("Alpha", "First", "b.flac")];
+    mpd.set_queue(&["a.flac", "b.flac", "c.flac"]);
+    let mut sim = Sim::new(target("mpd"), &mpd);
+    sim.terminal = Terminal::new(TestBackend::new(80, 10)).unwrap();
+
+    sim.step().await;
+    sim.press(KeyCode::Enter).await;
+    sim.step().await;
+    assert!(sim.screen().contains("next gapless"));
+
+    // the next song is off the album
+    sim.press(KeyCode::Down).await;
+    sim.press(KeyCode::Enter).await;
+    sim.step().await;
+    assert!(sim.screen().contains("next after a gap"));
+
+    // random and consume would play the album out of order
+    sim.press(KeyCode::Up).await;
+    sim.press(KeyCode::Enter).await;
+    sim.step().await;
+    for key in ['z', 'R'] {
+        sim.press(KeyCode::Char(key)).await;
+        sim.step().await;
+    }
+    assert!(sim.screen().contains("album out of order"));
+}
+
+#[tokio::test(start_paused = true)]
+async fn switches_tabs() {
+    let mpd = FakeMpd::script([Attempt::Serve("stop")]);
+    mpd.set_queue(&["one.flac", "two.flac"]);
+    let mut sim = Sim::new(target("mpd"), &mpd);
+
+    sim.step().await;
+    assert!(sim.screen().contains("1 Queue  2 Library"));
+
+    sim.press(KeyCode::Char('4')).await;
+    assert_eq!(sim.app.tab, Tab::Playlists);
+    assert!(!sim.screen().contains("one.flac"));
+
+    // keys for the queue do nothing while it is hidden
+    sim.press(KeyCode::Down).await;
+    sim.press(KeyCode::Tab).await;
+    sim.press(KeyCode::Tab).await;
+    sim.press(KeyCode::Tab).await;
+    assert_eq!(sim.app.tab, Tab::Queue);
+    assert_eq!(sim.app.queue.selected(), Some(0));
+
+    sim.press(KeyCode::BackTab).await;
+    assert_eq!(sim.app.tab, Tab::Lyrics);
+    sim.press(KeyCode::Char('9')).await;
+    assert_eq!(sim.app.tab, Tab::Lyrics);
+}
+
+#[tokio::test(start_paused = true)]
+async fn keeps_the_artists_until_the_database_changes() {
+    let dir = std::env::temp_dir().join(format!("encore-artists-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let mpd = FakeMpd::script([
+        Attempt::Serve("stop"),
+        Attempt::Serve("stop"),
+        Attempt::Serve("stop"),
+    ]);
+    *mpd.library.lock().unwrap() = vec![("Alpha", "First", "a/1.flac")];
+    *mpd.db_update.lock().unwrap() = 1700;
+    let start = |artist: &str| {
+        let mut sim = Sim::new(target("mpd"), &mpd);
+        sim.app.cache = Some(Cache::new(&dir, true));
+        let artist = artist.to_string();
+        async move {
+            sim.step().await;
+            sim.press(KeyCode::Char('2')).await;
+            assert!(sim.screen().contains(&artist));
+        }
+    };
+    let listed = || async {
+        let commands = mpd.commands().await;
+        commands.iter().filter(|c| *c == "list \"artist\"").count()
+    };
+
+    // the second start reads them from disk
+    start("Alpha").await;
+    start("Alpha").await;
+    assert_eq!(listed().await, 1);
+
+    // until an update makes them stale
+    *mpd.library.lock().unwrap() = vec![("Beta", "Only", "b/1.flac")];
+    *mpd.db_update.lock().unwrap() = 1800;
+    start("Beta").await;
+    assert_eq!(listed().await, 2);
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[tokio::test(start_paused = true)]
+async fn browses_the_library() {
+    let mpd = FakeMpd::script([Attempt::Serve("stop")]);
+    *mpd.library.lock().unwrap() = vec![
+        ("Alpha", "First", "a/1.flac"),
+        ("Alpha", "First", "a/2.flac"),
+        ("Alpha", "Second", "a/3.flac"),
+        ("Beta", "Only", "b/1.flac"),
+    ];
+    let mut sim = Sim::new(target("mpd"), &mpd);
+
+    sim.step().await;
+    assert!(!mpd.commands().await.iter().any(|c| c.starts_with("list")));
+
+    // artists are listed when the tab is first shown, the rest on the way down
+    sim.press(KeyCode::Char('2')).await;
+    let screen = sim.screen();
+    assert!(screen.contains("Alpha") && screen.contains("Beta"));
+
+    sim.press(KeyCode::Enter).await;
+    let screen = sim.screen();
+    assert!(screen.contains("First") && screen.contains("Second"));
+    assert!(!screen.contains("Beta"));
+
+    sim.press(KeyCode::Enter).await;
+    assert!(sim.screen().contains("1.flac  3:20"));
+    assert_eq!(
+        mpd.commands().await[3..],
+        [
+            "list \"artist\"",
+            "list \"album\" \"(artist == 'Alpha')\"",
+            "find \"((artist == 'Alpha') AND (album == 'First'))\""
+        ]
+    );
+
+    // enter on a track adds and plays it
+    sim.press(KeyCode::Down).await;
+    sim.press(KeyCode::Enter).await;
+    sim.step().await;
+    sim.step().await;
+    assert!(sim.app.is_playing);
+    assert_eq!(sim.app.queue.tracks.len(), 1);
+    assert_eq!(sim.app.song.as_ref().unwrap().uri, "a/2.flac");
+
+    // a whole album goes to the queue in one go, and left goes back up
+    sim.press(KeyCode::Left).await;
+    sim.press(KeyCode::Down).await;
+    sim.press(KeyCode::Char('a')).await;
+    sim.step().await;
+    let queue: Vec<_> = sim.app.queue.tracks.iter().map(|t| &t.file).collect();
+    assert_eq!(queue, ["a/2.flac", "a/3.flac"]);
+
+    sim.press(KeyCode::Left).await;
+    sim.press(KeyCode::Left).await;
+    assert!(sim.screen().contains("Beta"));
+}
+
+#[tokio::test(start_paused = true)]
+async fn counts_what_the_library_has() {
+    let mpd = FakeMpd::script([Attempt::Serve("stop")]);
+    *mpd.library.lock().unwrap() = vec![
+        ("Alpha", "First", "a/1.flac"),
+        ("Alpha", "First", "a/2.flac"),
+        ("Alpha", "Second", "a/3.flac"),
+        ("Beta", "Only", "b/1.flac"),
+    ];
+    let mut sim = Sim::new(target("mpd"), &mpd);
+    sim.terminal = Terminal::new(TestBackend::new(120, 20)).unwrap();
+
+    // the artist under the cursor is counted as soon as the artists are in
+    sim.step().await;
+    sim.press(KeyCode::Char('2')).await;
+    let screen = sim.screen();
+    assert!(screen.contains("Albums  2"));
+    assert!(screen.contains("Tracks  3") && screen.contains("Length  10m"));
+    assert!(screen.contains("Years   2001–2004") && screen.contains("Genres  Rock"));
+    assert_eq!(
+        mpd.commands().await[4..],
+        [
+            "count \"(artist == 'Alpha')\" \"group\" \"album\"",
+            "list \"date\" \"(artist == 'Alpha')\"",
+            "list \"genre\" \"(artist == 'Alpha')\""
+        ]
+    );
+
+    // an album is counted on its own
+    sim.press(KeyCode::Enter).await;
+    sim.press(KeyCode::Down).await;
+    let screen = sim.screen();
+    assert!(screen.contains(" Second ") && !screen.contains("Albums"));
+    assert!(screen.contains("Tracks  1") && screen.contains("Years   2004"));
+
+    // tracks have no numbers of their own
+    sim.press(KeyCode::Enter).await;
+    assert!(!sim.screen().contains("Tracks"));
+}
+
+#[tokio::test(start_paused = true)]
+async fn browses_the_music_directory() {
+    let mpd = FakeMpd::script([Attempt::Serve("stop")]);
+    *mpd.library.lock().unwrap() = vec![
+        ("Alpha", "First", "alpha/first/1.flac"),
+        ("Alpha", "First", "alpha/first/2.flac"),
+        ("Beta", "Only", "beta/1.flac"),
+    ];
+    let mut sim = Sim::new(target("mpd"), &mpd);
+
+    sim.step().await;
+    sim.press(KeyCode::Char('3')).await;
+    assert_eq!(sim.app.tab, Tab::Files);
+    let screen = sim.screen();
+    assert!(screen.contains("alpha/") && screen.contains("beta/"));
+
+    sim.press(KeyCode::Enter).await;
+    sim.press(KeyCode::Enter).await;
+    assert!(sim.screen().contains("2.flac  3:20"));
+    assert_eq!(
+        mpd.commands().await[3..],
+        ["lsinfo", "lsinfo \"alpha\"", "lsinfo \"alpha/first\""]
+    );
+
+    // the way down is shown above the list, and ~ goes back up all of it
+    assert!(sim.screen().contains("Files ▸ alpha ▸ first"));
+    sim.press(KeyCode::Char('~')).await;
+    let screen = sim.screen();
+    assert!(screen.contains("alpha/") && !screen.contains("▸"));
+    sim.press(KeyCode::Enter).await;
+    sim.press(KeyCode::Enter).await;
+
+    // adding a directory takes everything below it
+    sim.press(KeyCode::Left).await;
+    sim.press(KeyCode::Left).await;
+    sim.press(KeyCode::Char('a')).await;
+    sim.step().await;
+    let queue: Vec<_> = sim.app.queue.tracks.iter().map(|t| &t.file).collect();
+    assert_eq!(queue, ["alpha/first/1.flac", "alpha/first/2.flac"]);
+}
+
+#[tokio::test(start_paused = true)]
+async fn manages_stored_playlists() {
+    let mpd = FakeMpd::script([Attempt::Serve("stop")]);
+    mpd.set_queue(&["one.flac"]);
+    *mpd.playlists.lock().unwrap() = BTreeMap::from([
+        ("chill".into(), vec!["a.flac", "b.flac"]),
+        ("loud".into(), vec!["c.flac"]),
+    ]);
+    let mut sim = Sim::new(target("mpd"), &mpd);
+
+    sim.step().await;
+    sim.press(KeyCode::Char('4')).await;
+    let screen = sim.screen();
+    assert!(screen.contains("chill") && screen.contains("loud"));
+
+    sim.press(KeyCode::Enter).await;
+    assert!(sim.screen().contains("b.flac  3:20"));
+
+    // loading appends, replacing clears the queue first
+    sim.press(KeyCode::Char('l')).await;
+    sim.step().await;
+    let queue: Vec<_> = sim.app.queue.tracks.iter().map(|t| &t.file).collect();
+    assert_eq!(queue, ["a.flac", "b.flac"]);
+    let commands = mpd.commands().await;
+    let at = commands.iter().position(|c| c == "clear").unwrap();
+    assert_eq!(
+        commands[at - 1..at + 3],
+        [
+            "command_list_ok_begin",
+            "clear",
+            "load \"chill\"",
+            "command_list_end"
+        ]
+    );
+
+    sim.press(KeyCode::Left).await;
+    sim.press(KeyCode::Down).await;
+    sim.press(KeyCode::Char('a')).await;
+    sim.step().await;
+    assert_eq!(sim.app.queue.tracks.len(), 3);
+
+    // deleting asks first
+    sim.press(KeyCode::Char('d')).await;
+    assert!(sim.screen().contains("Delete playlist \"loud\"? y/n"));
+    sim.press(KeyCode::Char('n')).await;
+    assert!(sim.app.prompt.is_none());
+    sim.press(KeyCode::Char('d')).await;
+    sim.press(KeyCode::Char('y')).await;
+    sim.step().await;
+    let screen = sim.screen();
+    assert!(screen.contains("Deleted loud") && screen.matches("loud").count() == 1);
+
+    // and saving asks for a name, typed keys do not leak into the app
+    sim.press(KeyCode::Char('s')).await;
+    sim.typing("mix q").await;
+    assert!(sim.screen().contains("Save queue as: mix q"));
+    sim.press(KeyCode::Enter).await;
+    sim.step().await;
+    assert!(!sim.app.should_quit);
+    assert!(sim.screen().contains("mix q"));
+    assert_eq!(mpd.playlists.lock().unwrap()["mix q"].len(), 3);
+}
+
+#[tokio::test(start_paused = true)]
+async fn searches_as_you_type() {
+    let mpd = FakeMpd::script([Attempt::Serve("stop")]);
+    *mpd.library.lock().unwrap() = vec![
+        ("Alpha", "First", "a/1.flac"),
+        ("Alpha", "Second", "a/2.flac"),
+        ("Beta", "First", "b/1.flac"),
+    ];
+    let mut sim = Sim::new(target("mpd"), &mpd);
+
+    sim.step().await;
+    sim.press(KeyCode::Char('5')).await;
+    assert_eq!(sim.app.tab, Tab::Search);
+
+    // keys that would otherwise quit or switch tabs are typed instead
+    sim.typing("first q").await;
+    sim.press(KeyCode::Backspace).await;
+    sim.press(KeyCode::Backspace).await;
+    assert!(!sim.app.should_quit);
+    assert!(sim.screen().contains("Searching…"));
+
+    // only the pause after the last key reaches the server
+    let start = Instant::now();
+    sim.step().await;
+    assert_eq!(start.elapsed(), super::search::DEBOUNCE);
+    assert!(sim.screen().contains("1.flac  Alpha  3:20"));
+    let results = &sim.app.search.results.items;
+    let results: Vec<_> = results.iter().map(|t| &t.file).collect();
+    assert_eq!(results, ["a/1.flac", "b/1.flac"]);
+    let searches: Vec<_> = mpd
+        .commands()
+        .await
+        .into_iter()
+        .filter(|c| c.starts_with("search"))
+        .collect();
+    assert_eq!(searches, ["search \"(any contains 'first')\""]);
+
+    // a chip narrows it down to a tag
+    sim.press(KeyCode::Enter).await;
+    sim.typing("artist:beta").await;
+    sim.step().await;
+    assert!(sim.screen().contains(" first  artist:beta"));
+    assert_eq!(sim.app.search.results.items.len(), 1);
+
+    sim.press(KeyCode::Enter).await;
+    sim.press(KeyCode::Enter).await;
+    assert_eq!(sim.app.search.focus, super::Focus::Results);
+    sim.press(KeyCode::Char('A')).await;
+    sim.step().await;
+    let queue: Vec<_> = sim.app.queue.tracks.iter().map(|t| &t.file).collect();
+    assert_eq!(queue, ["b/1.flac"]);
+
+    // taking the chips back widens it again
+    sim.press(KeyCode::Char('/')).await;
+    sim.press(KeyCode::Backspace).await;
+    assert_eq!(sim.app.search.input, "artist:beta");
+    for _ in 0.."artist:beta".len() {
+        sim.press(KeyCode::Backspace).await;
+    }
+    sim.step().await;
+    assert_eq!(sim.app.search.results.items.len(), 2);
+}
+
+#[tokio::test(start_paused = true)]
+async fn shows_help_for_the_open_tab() {
+    let mpd = FakeMpd::script([Attempt::Serve("stop")]);
+    let mut sim = Sim::new(target("mpd"), &mpd);
+
+    sim.step().await;
+    sim.press(KeyCode::Char('?')).await;
+    let screen = sim.screen();
+    assert!(screen.contains("Keys") && screen.contains("Enter  play"));
+
+    // the overlay keeps the keys to itself, except to flip through tabs
+    sim.press(KeyCode::Char('q')).await;
+    sim.press(KeyCode::Tab).await;
+    assert!(!sim.app.should_quit);
+    assert_eq!(sim.app.tab, Tab::Library);
+    let screen = sim.screen();
+    assert!(screen.contains("open, or play a track"));
+    // the arrows browse here, seeking takes shift
+    assert!(screen.contains("S-← S-→  seek back, forward"));
+    assert!(!screen.contains("← →  seek back, forward"));
+
+    sim.press(KeyCode::Esc).await;
+    assert!(!sim.app.should_quit);
+    assert!(!sim.screen().contains("Keys"));
+}
+
+#[tokio::test(start_paused = true)]
+async fn restores_deleted_playlists_from_the_trash() {
+    let dir = std::env::temp_dir().join(format!("encore-trash-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let mpd = FakeMpd::script([Attempt::Serve("stop")]);
+    *mpd.library.lock().unwrap() = vec![
+        ("Alpha", "First", "a/1.flac"),
+        ("Alpha", "First", "a/2.flac"),
+    ];
+    *mpd.playlists.lock().unwrap() =
+        BTreeMap::from([("chill".into(), vec!["a/1.flac", "a/2.flac"])]);
+    let mut sim = Sim::new(target("mpd"), &mpd);
+    sim.terminal = Terminal::new(TestBackend::new(80, 16)).unwrap();
+    sim.app.trash = Some(Trash::new(&dir));
+
+    sim.step().await;
+    sim.press(KeyCode::Char('4')).await;
+    sim.press(KeyCode::Char('d')).await;
+    sim.press(KeyCode::Char('y')).await;
+    sim.step().await;
+    assert!(sim.screen().contains("Moved chill to the trash"));
+    assert!(mpd.playlists.lock().unwrap().is_empty());
+
+    sim.press(KeyCode::Char('T')).await;
+    let screen = sim.screen();
+    assert!(screen.contains(" Trash ") && screen.contains("chill  just now"));
+
+    // a playlist that took the name meanwhile is not overwritten
+    mpd.playlists.lock().unwrap().insert("chill".into(), vec![]);
+    sim.press(KeyCode::Enter).await;
+    let screen = sim.screen();
+    assert!(screen.contains("A playlist named chill is back already"));
+    assert!(sim.app.client.is_some());
+
+    mpd.playlists.lock().unwrap().clear();
+    sim.press(KeyCode::Enter).await;
+    sim.step().await;
+    let playlists = mpd.playlists.lock().unwrap().clone();
+    assert_eq!(playlists["chill"], ["a/1.flac", "a/2.flac"]);
+    let screen = sim.screen();
+    assert!(screen.contains("Restored chill") && screen.contains("No playlist was deleted"));
+
+    sim.press(KeyCode::Esc).await;
+    assert!(sim.app.bin.is_none());
+    sim.press(KeyCode::Char(':')).await;
+    sim.typing("trash").await;
+    sim.press(KeyCode::Enter).await;
+    assert!(sim.app.bin.is_some());
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[tokio::test(start_paused = true)]
+async fn keeps_empty_playlists_in_the_trash() {
+    let dir = std::env::temp_dir().join(format!("encore-trash-empty-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let mpd = FakeMpd::script([Attempt::Serve("stop")]);
+    *mpd.playlists.lock().unwrap() = BTreeMap::from([("blank".into(), vec![])]);
+    let mut sim = Sim::new(target("mpd"), &mpd);
+    sim.terminal = Terminal::new(TestBackend::new(80, 16)).unwrap();
+    sim.app.trash = Some(Trash::new(&dir));
+
+    sim.step().await;
+    sim.press(KeyCode::Char('4')).await;
+    sim.press(KeyCode::Char('d')).await;
+    sim.press(KeyCode::Char('y')).await;
+    sim.step().await;
+    assert!(mpd.playlists.lock().unwrap().is_empty());
+
+    sim.press(KeyCode::Char('T')).await;
+    sim.press(KeyCode::Enter).await;
+    sim.step().await;
+    let screen = sim.screen();
+    assert!(screen.contains("blank is empty, MPD can't restore it"));
+    assert!(screen.contains("blank  just now"));
+    assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[tokio::test(start_paused = true)]
+async fn runs_typed_commands() {
+    let mpd = FakeMpd::script([Attempt::Serve("play"), Attempt::Serve("stop")]);
+    mpd.set_queue(&["one.flac"]);
+    *mpd.song.lock().unwrap() = Some(0);
+    let mut sim = Sim::new(target("mpd"), &mpd);
+    sim.app.hosts = [("home".into(), "home:6601".into())].into();
+    sim.step().await;
+
+    // Tab goes as far as the commands agree and lists what is left
+    sim.press(KeyCode::Char(':')).await;
+    sim.typing("se").await;
+    sim.press(KeyCode::Tab).await;
+    assert!(sim.screen().contains(":se█  seek  set"));
+    sim.typing("t").await;
+    sim.press(KeyCode::Tab).await;
+    sim.typing("ra").await;
+    sim.press(KeyCode::Tab).await;
+    sim.typing("on").await;
+    assert!(sim.screen().contains(":set random on█"));
+    sim.press(KeyCode::Enter).await;
+    assert!(sim.app.prompt.is_none());
+
+    sim.press(KeyCode::Char(':')).await;
+    sim.typing("seek 1:23").await;
+    sim.press(KeyCode::Enter).await;
+    let commands = mpd.commands().await;
+    assert!(commands.contains(&"random \"1\"".into()));
+    assert!(commands.contains(&"seekcur \"83\"".into()));
+
+    // a mistake stays on the line until it is fixed or given up
+    sim.press(KeyCode::Char(':')).await;
+    sim.typing("seek soon").await;
+    sim.press(KeyCode::Enter).await;
+    assert!(sim.screen().contains("Invalid time `soon`"));
+    for _ in 0.."seek soon".len() {
+        sim.press(KeyCode::Backspace).await;
+    }
+    sim.typing("bogus").await;
+    sim.press(KeyCode::Enter).await;
+    assert!(sim.screen().contains("Unknown command `bogus`"));
+    sim.press(KeyCode::Esc).await;
+    assert!(sim.app.prompt.is_none() && !sim.app.should_quit);
+
+    // hosts go by the names they were given
+    sim.press(KeyCode::Char(':')).await;
+    sim.typing("connect h").await;
+    sim.press(KeyCode::Tab).await;
+    sim.press(KeyCode::Enter).await;
+    sim.step().await;
+    assert_eq!(mpd.targets()[1], Endpoint::parse("home:6601").unwrap());
+}
+
+#[tokio::test(start_paused = true)]
+async fn tells_what_happened_in_toasts() {
+    let mpd = FakeMpd::script([Attempt::Serve("stop"), Attempt::Serve("stop")]);
+    *mpd.library.lock().unwrap() = vec![
+        ("Alpha", "First", "alpha/1.flac"),
+        ("Alpha", "First", "alpha/2.flac"),
+    ];
+    let mut sim = Sim::new(target("mpd"), &mpd);
+    sim.step().await;
+
+    // the queue grew by what the directory held
+    sim.press(KeyCode::Char('3')).await;
+    sim.step().await;
+    sim.press(KeyCode::Char('a')).await;
+    sim.step().await;
+    assert!(sim.screen().contains(" Added 2 tracks "));
+
+    // the server's refusal stacks below in the error style
+    sim.press(KeyCode::Char(':')).await;
+    sim.typing("seek 0:10").await;
+    sim.press(KeyCode::Enter).await;
+    let screen = sim.screen();
+    assert!(screen.contains(" Added 2 tracks ") && screen.contains(" seekcur: Not playing "));
+    let buffer = sim.terminal.backend().buffer();
+    let at = screen[..screen.find("seekcur").unwrap()].chars().count();
+    assert_eq!(buffer.content()[at].fg, Color::Red);
+
+    // each goes once its time is up, errors last longer
+    let start = Instant::now();
+    while sim.screen().contains("Added") {
+        sim.step().await;
+    }
+    assert_eq!(start.elapsed(), Duration::from_secs(3));
+    assert!(sim.screen().contains("seekcur"));
+    while sim.screen().contains("seekcur") {
+        sim.step().await;
+    }
+    assert_eq!(start.elapsed(), Duration::from_secs(6));
+
+    mpd.signal(|| Signal::Hangup);
+    sim.step().await;
+    assert!(sim.screen().contains(" Lost the connection"));
+    sim.step().await;
+    assert!(sim.screen().contains(" Reconnected to mpd:6600 "));
+}
+
+#[tokio::test(start_paused = true)]
+async fn follows_the_playing_song() {
+    let mpd = FakeMpd::script([Attempt::Serve("play")]);
+    let files: Vec<&'static str> = (0..20).map(|i| &*format!("{i:02}.flac").leak()).collect();
+    mpd.set_queue(&files);
+    *mpd.song.lock().unwrap() = Some(15);
+    let mut sim = Sim::new(target("mpd"), &mpd);
+
+    sim.step().await;
+    assert_eq!(sim.app.queue.selected(), Some(15));
+    let screen = sim.screen();
+    assert!(screen.contains("following"));
+    assert!(screen.contains("14.flac") && screen.contains("16.flac"));
+
+    // browsing is left alone until the song changes
+    sim.press(KeyCode::Home).await;
+    assert!(sim.screen().contains("00.flac"));
+    let advance = |pos| {
+        *mpd.song.lock().unwrap() = Some(pos);
+        mpd.set_state("play");
+    };
+    advance(16);
+    sim.step().await;
+    assert_eq!(sim.app.queue.selected(), Some(16));
+    assert!(sim.screen().contains("17.flac"));
+
+    sim.press(KeyCode::Char('f')).await;
+    sim.press(KeyCode::Home).await;
+    advance(17);
+    sim.step().await;
+    assert_eq!(sim.app.queue.selected(), Some(0));
+    assert!(!sim.screen().contains("following"));
+
+    // o finds it from anywhere, even another tab
+    sim.press(KeyCode::Char('3')).await;
+    sim.press(KeyCode::Char('o')).await;
+    assert_eq!(sim.app.tab, Tab::Queue);
+    assert_eq!(sim.app.queue.selected(), Some(17));
+    assert!(sim.screen().contains("18.flac"));
+}
+
+#[tokio::test(start_paused = true)]
+async fn moves_with_vi_keys() {
+    let mpd = FakeMpd::script([Attempt::Serve("stop")]);
+    let files: Vec<&'static str> = (0..20).map(|i| &*format!("{i:02}.flac").leak()).collect();
+    mpd.set_queue(&files);
+    let mut sim = Sim::new(target("mpd"), &mpd);
+    let ctrl = |c| Event::Key(KeyEvent::new(KeyCode::Char(c), KeyModifiers::CONTROL));
+
+    sim.step().await;
+    sim.typing("jjj").await;
+    sim.press(KeyCode::Char('k')).await;
+    assert_eq!(sim.app.queue.selected(), Some(2));
+
+    sim.press(KeyCode::Char('G')).await;
+    assert_eq!(sim.app.queue.selected(), Some(19));
+    sim.screen();
+
+    // the screen motions go by the three rows left for the queue
+    sim.press(KeyCode::Char('H')).await;
+    assert_eq!(sim.app.queue.selected(), Some(17));
+    sim.press(KeyCode::Char('M')).await;
+    assert_eq!(sim.app.queue.selected(), Some(18));
+
+    // a lone g waits for the second one and gives up on anything else
+    sim.typing("gj").await;
+    assert_eq!(sim.app.queue.selected(), Some(19));
+    sim.typing("gg").await;
+    assert_eq!(sim.app.queue.selected(), Some(0));
+    sim.screen();
+    sim.press(KeyCode::Char('L')).await;
+    assert_eq!(sim.app.queue.selected(), Some(2));
+
+    sim.keys.send(Ok(ctrl('d'))).unwrap();
+    sim.step().await;
+    assert_eq!(sim.app.queue.selected(), Some(3));
+    sim.press(KeyCode::PageDown).await;
+    assert_eq!(sim.app.queue.selected(), Some(6));
+    sim.keys.send(Ok(ctrl('u'))).unwrap();
+    sim.step().await;
+    assert_eq!(sim.app.queue.selected(), Some(5));
+}
+
+#[tokio::test(start_paused = true)]
+async fn edits_marked_tracks_in_bulk() {
+    let mpd = FakeMpd::script([Attempt::Serve("stop")]);
+    mpd.set_queue(&["0.flac", "1.flac", "2.flac", "3.flac", "4.flac", "5.flac"]);
+    let mut sim = Sim::new(target("mpd"), &mpd);
+    let queue = |sim: &Sim| -> Vec<String> {
+        let tracks = sim.app.queue.tracks.iter();
+        tracks
+            .map(|track| track.file.replace(".flac", ""))
+            .collect()
+    };
+
+    // Space marks and moves on, V marks a range
+    sim.step().await;
+    sim.typing(" jVjjV").await;
+    assert_eq!(sim.app.queue.selected(), Some(4));
+    sim.press(KeyCode::Char('p')).await;
+    sim.typing("mix").await;
+    assert!(sim.screen().contains("Add 4 to playlist: mix"));
+    sim.press(KeyCode::Enter).await;
+    sim.step().await;
+    assert_eq!(
+        mpd.playlists.lock().unwrap()["mix"],
+        ["0.flac", "2.flac", "3.flac", "4.flac"]
+    );
+    let commands = mpd.commands().await;
+    assert_eq!(commands[3], "command_list_ok_begin");
+    assert_eq!(commands[4], "playlistadd \"mix\" \"0.flac\"");
+
+    // the marks were used up, Esc drops new ones instead of quitting
+    assert!(sim.app.queue.marks.is_empty());
+    sim.typing("  ").await;
+    sim.press(KeyCode::Esc).await;
+    assert!(!sim.app.should_quit);
+    assert!(sim.app.queue.marks.is_empty());
+
+    // moving gathers the marked tracks right above the cursor
+    sim.typing("G kkkk ").await;
+    sim.press(KeyCode::Char('m')).await;
+    sim.step().await;
+    assert_eq!(queue(&sim), ["0", "1", "5", "2", "3", "4"]);
+
+    sim.typing("ggVj").await;
+    sim.press(KeyCode::Char('d')).await;
+    sim.step().await;
+    assert_eq!(queue(&sim), ["5", "2", "3", "4"]);
+
+    // and the browsing tabs add whatever is marked
+    *mpd.library.lock().unwrap() = vec![
+        ("Alpha", "First", "a/1.flac"),
+        ("Beta", "First", "b/1.flac"),
+        ("Gamma", "First", "c/1.flac"),
+    ];
+    sim.press(KeyCode::Char('2')).await;
+    sim.typing(" j ").await;
+    sim.press(KeyCode::Char('a')).await;
+    sim.step().await;
+    assert_eq!(queue(&sim), ["5", "2", "3", "4", "a/1", "c/1"]);
+}
+
+#[tokio::test(start_paused = true)]
+async fn asks_before_destroying() {
+    let mpd = FakeMpd::script([Attempt::Serve("stop")]);
+    mpd.set_queue(&["0.flac", "1.flac", "2.flac", "3.flac", "4.flac"]);
+    let mut sim = Sim::new(target("mpd"), &mpd);
+    sim.app.confirm.remove = 3;
+    sim.step().await;
+
+    // n or Esc leave the queue as it was
+    sim.press(KeyCode::Char(':')).await;
+    sim.typing("clear").await;
+    sim.press(KeyCode::Enter).await;
+    assert!(sim.screen().contains("Clear the queue? y/n"));
+    sim.press(KeyCode::Char('n')).await;
+    assert!(sim.app.prompt.is_none());
+
+    // a few tracks go right away, more ask first
+    sim.typing("Vj").await;
+    sim.press(KeyCode::Char('d')).await;
+    sim.step().await;
+    assert_eq!(sim.app.queue.tracks.len(), 3);
+    sim.typing("ggVjj").await;
+    sim.press(KeyCode::Char('d')).await;
+    assert!(sim.screen().contains("Remove 3 tracks from the queue? y/n"));
+    sim.press(KeyCode::Esc).await;
+    assert!(!sim.app.should_quit);
+    assert!(!mpd.commands().await.contains(&"clear".into()));
+
+    sim.typing("ggVjj").await;
+    sim.press(KeyCode::Char('d')).await;
+    sim.press(KeyCode::Char('y')).await;
+    sim.step().await;
+    assert!(sim.app.queue.tracks.is_empty());
+
+    // or nothing does, as configured
+    mpd.set_queue(&["0.flac"]);
+    sim.step().await;
+    sim.app.confirm.clear = false;
+    sim.press(KeyCode::Char(':')).await;
+    sim.typing("clear").await;
+    sim.press(KeyCode::Enter).await;
+    sim.step().await;
+    assert!(sim.app.prompt.is_none());
+    assert!(mpd.commands().await.contains(&"clear".into()));
+}
+
+#[tokio::test(start_paused = true)]
+async fn adds_a_stream() {
+    let mpd = FakeMpd::script([Attempt::Serve("stop")]);
+    let mut sim = Sim::new(target("mpd"), &mpd);
+    sim.terminal = Terminal::new(TestBackend::new(120, 20)).unwrap();
+    sim.step().await;
+
+    // only what the server says it plays
+    sim.press(KeyCode::Char('u')).await;
+    sim.typing("radio.example").await;
+    sim.press(KeyCode::Enter).await;
+    assert!(sim.screen().contains("Expected an http:// or https:// URL"));
+    for _ in 0.."radio.example".len() {
+        sim.press(KeyCode::Backspace).await;
+    }
+    sim.typing("https://radio.example/live").await;
+    assert!(!sim.screen().contains("Expected"));
+    sim.press(KeyCode::Enter).await;
+    let screen = sim.screen();
+    assert!(screen.contains("The server does not play https:// streams"));
+
+    sim.press(KeyCode::Esc).await;
+    sim.press(KeyCode::Char('u')).await;
+    sim.typing("http://radio.example/live").await;
+    let screen = sim.screen();
+    assert!(screen.contains("Add stream: http://radio.example/live█"));
+    sim.press(KeyCode::Enter).await;
+    sim.step().await;
+    assert!(sim.app.prompt.is_none());
+    assert_eq!(sim.app.queue.tracks[0].file, "http://radio.example/live");
+    assert!(sim.screen().contains("Added 1 track"));
+}
+
+#[tokio::test(start_paused = true)]
+async fn rates_songs_with_stickers() {
+    let mpd = FakeMpd::script([Attempt::Serve("play")]);
+    mpd.set_queue(&["a.flac", "b.flac", "c.flac"]);
+    *mpd.song.lock().unwrap() = Some(0);
+    let mut sim = Sim::new(target("mpd"), &mpd);
+    let rating = |width| config::Column {
+        tag: config::Tag::Rating,
+        width: Width::Cells(width),
+        align: config::Align::Left,
+    };
+    sim.app.queue.columns.push(rating(5));
+    sim.step().await;
+
+    // the one under the cursor, or the marked ones
+    sim.press(KeyCode::Char('*')).await;
+    assert!(sim.screen().contains("Rate \"a.flac\": 1-5, 0 clears"));
+    sim.press(KeyCode::Char('4')).await;
+    sim.step().await;
+    sim.step().await;
+    assert!(sim.screen().contains("★★★★☆"));
+    sim.typing("j  ").await;
+    sim.press(KeyCode::Char('*')).await;
+    assert!(sim.screen().contains("Rate 2 tracks"));
+    sim.press(KeyCode::Char('2')).await;
+    sim.step().await;
+    sim.typing("G*5").await;
+    sim.step().await;
+    sim.step().await;
+    assert_eq!(sim.screen().matches("★★☆☆☆").count(), 1);
+
+    // `*N` keeps the tracks rated N or more
+    sim.typing("/*4").await;
+    let screen = sim.screen();
+    assert!(screen.contains("2 of 3") && !screen.contains("b.flac"));
+    sim.press(KeyCode::Esc).await;
+
+    // elsewhere it is the playing song, 0 takes the rating off
+    sim.press(KeyCode::Char('2')).await;
+    sim.press(KeyCode::Char('*')).await;
+    sim.press(KeyCode::Char('0')).await;
+    sim.step().await;
+    let stickers = mpd.stickers.lock().unwrap().clone();
+    assert_eq!(
+        stickers
+            .into_iter()
+            .map(|((file, _), rating)| (file, rating))
+            .collect::<Vec<_>>(),
+        [("b.flac".into(), "2".into()), ("c.flac".into(), "5".into())]
+    );
+
+    // best first, the unrated last, with moves rather than a new queue
+    sim.press(KeyCode::Char(':')).await;
+    sim.typing("sort rating").await;
+    sim.press(KeyCode::Enter).await;
+    sim.step().await;
+    let tracks = sim.app.queue.tracks.iter();
+    let queue: Vec<_> = tracks.map(|track| track.file.as_str()).collect();
+    assert_eq!(queue, ["c.flac", "b.flac", "a.flac"]);
+    assert!(!mpd.commands().await.contains(&"clear".into()));
+}
+
+#[tokio::test(start_paused = true)]
+async fn flags_songs_to_listen_later() {
+    let mpd = FakeMpd::script([Attempt::Serve("play")]);
+    *mpd.library.lock().unwrap() = vec![("Alpha", "First", "a.flac")];
+    mpd.set_queue(&["a.flac", "b.flac"]);
+    *mpd.song.lock().unwrap() = Some(0);
+    let mut sim = Sim::new(target("mpd"), &mpd);
+    sim.step().await;
+    let later = |mpd: &FakeMpd| {
+        let stickers = mpd.stickers.lock().unwrap();
+        let flagged = stickers.keys().filter(|(_, name)| name == "listen_later");
+        flagged.map(|(file, _)| file.clone()).collect::<Vec<_>>()
+    };
+
+    // the song under the cursor, a second press takes the flag off
+    sim.press(KeyCode::Char('w')).await;
+    sim.step().await;
+    assert!(sim.screen().contains("Flagged \"a.flac\" to listen later"));
+    sim.typing("jw").await;
+    sim.step().await;
+    assert_eq!(later(&mpd), ["a.flac", "b.flac"]);
+    sim.press(KeyCode::Char('w')).await;
+    sim.step().await;
+    assert!(sim.screen().contains("Unflagged \"b.flac\""));
+    assert_eq!(later(&mpd), ["a.flac"]);
+
+    // Enter queues the song and takes it off the list
+    sim.press(KeyCode::Char('W')).await;
+    assert!(sim.screen().contains(" Listen later "));
+    sim.press(KeyCode::Enter).await;
+    sim.step().await;
+    sim.step().await;
+    assert_eq!(*mpd.queue.lock().unwrap(), ["a.flac", "b.flac", "a.flac"]);
+    assert!(later(&mpd).is_empty());
+
+    sim.press(KeyCode::Esc).await;
+    assert!(!sim.screen().contains(" Listen later "));
+    assert!(!sim.app.should_quit);
+    sim.press(KeyCode::Char('W')).await;
+    assert!(sim.screen().contains("Nothing is flagged"));
+}
+
+#[tokio::test(start_paused = true)]
+async fn edits_tags_of_local_files() {
+    let dir = std::env::temp_dir().join(format!("encore-tags-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    // stream info, then the comments as the last block, then the audio
+    let comments = [&[1, 0, 0, 0][..], b"x", &[1, 0, 0, 0], &[9, 0, 0, 0]].concat();
+    let comments = [comments, b"TITLE=Old".to_vec()].concat();
+    let mut flac = [&b"fLaC"[..], &[0, 0, 0, 34], &[0; 34]].concat();
+    flac.extend([0x84, 0, 0, comments.len() as u8]);
+    flac.extend(comments);
+    flac.extend(b"AUDIO");
+    std::fs::write(dir.join("a.flac"), flac).unwrap();
+
+    let mpd = FakeMpd::script([Attempt::Serve("stop")]);
+    mpd.set_queue(&["a.flac", "b.mp3"]);
+    *mpd.comments.lock().unwrap() = vec![("TITLE", "Old")];
+    let mut sim = Sim::new(target("mpd"), &mpd);
+    sim.terminal = Terminal::new(TestBackend::new(120, 20)).unwrap();
+    sim.app.music_dir = Some(dir.clone());
+    sim.step().await;
+
+    // every tag, and the common ones even when the file has none
+    sim.press(KeyCode::Char('i')).await;
+    let screen = sim.screen();
+    assert!(screen.contains("TITLE") && screen.contains("Old") && screen.contains("GENRE"));
+    assert!(screen.contains("Enter edits"));
+
+    sim.press(KeyCode::Enter).await;
+    for _ in 0..3 {
+        sim.press(KeyCode::Backspace).await;
+    }
+    sim.typing("New").await;
+    sim.press(KeyCode::Enter).await;
+    sim.typing("j").await;
+    sim.press(KeyCode::Enter).await;
+    sim.typing("Someone").await;
+    sim.press(KeyCode::Enter).await;
+    assert!(sim.screen().contains("Saved ARTIST"));
+
+    // the other blocks and the audio stay, the server reads the file again
+    let flac = std::fs::read(dir.join("a.flac")).unwrap();
+    let text = String::from_utf8_lossy(&flac);
+    assert!(text.contains("TITLE=New") && text.contains("ARTIST=Someone"));
+    assert!(!text.contains("Old") && flac.ends_with(b"AUDIO"));
+    let commands = mpd.commands().await;
+    assert!(commands.contains(&"update \"a.flac\"".into()));
+
+    // only FLAC files are written
+    sim.press(KeyCode::Esc).await;
+    sim.typing("ji").await;
+    sim.step().await;
+    assert!(sim.screen().contains("only FLAC files can be edited"));
+    sim.press(KeyCode::Enter).await;
+    assert!(!sim.screen().contains("█"));
+}
+
+#[tokio::test(start_paused = true)]
+async fn switches_and_sets_up_outputs() {
+    let mpd = FakeMpd::script([Attempt::Serve("stop")]);
+    *mpd.outputs.lock().unwrap() = vec![
+        ("Speakers", true, vec![("dop", "0".into())]),
+        ("Headphones", false, Vec::new()),
+    ];
+    let mut sim = Sim::new(target("mpd"), &mpd);
+    sim.terminal = Terminal::new(TestBackend::new(120, 20)).unwrap();
+
+    sim.step().await;
+    sim.press(KeyCode::F(8)).await;
+    let screen = sim.screen();
+    assert!(screen.contains("[x] Speakers") && screen.contains("[ ] Headphones"));
+    assert!(screen.contains("dop 0"));
+
+    // the list is read again once the server tells of the switch
+    sim.press(KeyCode::Down).await;
+    sim.press(KeyCode::Down).await;
+    sim.press(KeyCode::Enter).await;
+    sim.step().await;
+    assert!(sim.screen().contains("[x] Headphones"));
+
+    // attributes are edited in place
+    sim.press(KeyCode::Up).await;
+    sim.press(KeyCode::Enter).await;
+    sim.press(KeyCode::Backspace).await;
+    sim.typing("1").await;
+    sim.press(KeyCode::Enter).await;
+    assert!(sim.screen().contains("Set dop"));
+    sim.step().await;
+    assert!(sim.screen().contains("dop 1"));
+    let commands = mpd.commands().await;
+    assert!(commands.contains(&"toggleoutput \"1\"".into()));
+    assert!(commands.contains(&"outputset \"0\" \"dop\" \"1\"".into()));
+
+    sim.press(KeyCode::Esc).await;
+    assert!(!sim.screen().contains("Speakers"));
+    assert!(!sim.app.should_quit);
+}
+
+#[tokio::test(start_paused = true)]
+async fn switches_partitions() {
+    let mpd = FakeMpd::script([Attempt::Serve("stop")]);
+    mpd.set_queue(&["one.flac"]);
+    let mut sim = Sim::new(target("mpd"), &mpd);
+    sim.terminal = Terminal::new(TestBackend::new(120, 20)).unwrap();
+
+    sim.step().await;
+    assert!(sim.screen().contains("partition default"));
+    sim.press(KeyCode::F(7)).await;
+    assert!(sim.screen().contains("● default"));
+
+    sim.press(KeyCode::Char('n')).await;
+    sim.typing("party").await;
+    sim.press(KeyCode::Enter).await;
+    assert!(sim.screen().contains("  party"));
+
+    // both connections move, and the queue is the other partition's
+    sim.press(KeyCode::Down).await;
+    sim.press(KeyCode::Enter).await;
+    let screen = sim.screen();
+    assert!(screen.contains("partition party") && screen.contains("Switched to party"));
+    assert!(sim.app.queue.tracks.is_empty());
+    assert!(mpd.idles().await.contains(&"partition \"party\"".into()));
+
+    sim.press(KeyCode::F(7)).await;
+    sim.press(KeyCode::Up).await;
+    sim.press(KeyCode::Enter).await;
+    assert_eq!(sim.app.queue.tracks.len(), 1);
+
+    // deleting one asks first
+    sim.press(KeyCode::F(7)).await;
+    sim.press(KeyCode::Down).await;
+    sim.press(KeyCode::Char('d')).await;
+    assert!(sim.screen().contains("Delete partition \"party\"? y/n"));
+    sim.press(KeyCode::Char('y')).await;
+    let screen = sim.screen();
+    assert!(screen.contains("● default") && !screen.contains("  party"));
+}
+
+#[tokio::test(start_paused = true)]
+async fn shows_the_audio_format() {
+    let mpd = FakeMpd::script([Attempt::Serve("play")]);
+    mpd.set_queue(&["a.flac", "b.dsf"]);
+    *mpd.song.lock().unwrap() = Some(0);
+    *mpd.audio.lock().unwrap() = Some(("44100:16:2", 987));
+    let mut sim = Sim::new(target("mpd"), &mpd);
+    sim.terminal = Terminal::new(TestBackend::new(120, 20)).unwrap();
+
+    sim.step().await;
+    assert!(sim.screen().contains("44.1kHz/16bit/2ch • 987kbps FLAC"));
+
+    // a bit rate of 0 is none known
+    *mpd.song.lock().unwrap() = Some(1);
+    *mpd.audio.lock().unwrap() = Some(("dsd64:2", 0));
+    mpd.set_state("play");
+    sim.step().await;
+    let screen = sim.screen();
+    assert!(screen.contains("DSD64/2ch • DSF") && !screen.contains("kbps"));
+}
+
+#[tokio::test(start_paused = true)]
+async fn sorts_the_queue_by_tag() {
+    let mpd = FakeMpd::script([Attempt::Serve("play")]);
+    *mpd.library.lock().unwrap() = vec![
+        ("Beta", "Two", "b/1.flac"),
+        ("alpha", "One", "a/2.flac"),
+        ("Alpha", "One", "a/1.flac"),
+        ("Gamma", "Alpha", "c/1.flac"),
+    ];
+    mpd.set_queue(&["b/1.flac", "a/2.flac", "a/1.flac", "c/1.flac"]);
+    let mut sim = Sim::new(target("mpd"), &mpd);
+    sim.terminal = Terminal::new(TestBackend::new(120, 20)).unwrap();
+    sim.step().await;
+    let queue = |sim: &Sim| -> Vec<String> {
+        let tracks = sim.app.queue.tracks.iter();
+        tracks
+            .map(|track| track.file.replace(".flac", ""))
+            .collect()
+    };
+
+    // ties keep their order, case does not count
+    sim.press(KeyCode::Char('s')).await;
+    let screen = sim.screen();
+    assert!(screen.contains("Sort the queue by: a artist  l album"));
+    sim.press(KeyCode::Char('a')).await;
+    sim.step().await;
+    assert_eq!(queue(&sim), ["a/2", "a/1", "b/1", "c/1"]);
+    sim.typing("sl").await;
+    sim.step().await;
+    assert_eq!(queue(&sim), ["c/1", "a/2", "a/1", "b/1"]);
+
+    // shuffled with moves, so the playing song plays on
+    sim.press(KeyCode::Char(':')).await;
+    sim.typing("sort random").await;
+    sim.press(KeyCode::Enter).await;
+    sim.step().await;
+    let mut shuffled = queue(&sim);
+    shuffled.sort();
+    assert_eq!(shuffled, ["a/1", "a/2", "b/1", "c/1"]);
+    let mut commands = mpd.commands().await.into_iter();
+    assert!(!commands.any(|c| c == "clear" || c.starts_with("add")));
+}
+
+#[tokio::test(start_paused = true)]
+async fn seeks_with_the_arrows() {
+    let mpd = FakeMpd::script([Attempt::Serve("pause")]);
+    mpd.set_queue(&["one.flac", "two.flac"]);
+    *mpd.song.lock().unwrap() = Some(0);
+    let mut sim = Sim::new(target("mpd"), &mpd);
+    sim.step().await;
+    let shift = |code| Event::Key(KeyEvent::new(code, KeyModifiers::SHIFT));
+
+    // the progress moves before the server says so
+    sim.press(KeyCode::Right).await;
+    assert!(sim.screen().contains("0:05 / 3:20"));
+    sim.keys.send(Ok(shift(KeyCode::Right))).unwrap();
+    sim.step().await;
+    sim.press(KeyCode::Left).await;
+    assert!(sim.screen().contains("1:00 / 3:20"));
+    sim.keys.send(Ok(shift(KeyCode::Left))).unwrap();
+    sim.step().await;
+    sim.keys.send(Ok(shift(KeyCode::Left))).unwrap();
+    sim.step().await;
+    assert!(sim.screen().contains("0:00 / 3:20"));
+
+    // plain arrows browse the library, shift still seeks
+    sim.press(KeyCode::Char('2')).await;
+    sim.press(KeyCode::Right).await;
+    sim.keys.send(Ok(shift(KeyCode::Right))).unwrap();
+    sim.step().await;
+    let commands = mpd.commands().await.into_iter();
+    let seeks: Vec<_> = commands.filter(|c| c.starts_with("seekcur")).collect();
+    assert_eq!(
+        seeks,
+        [
+            "seekcur \"+5\"",
+            "seekcur \"+60\"",
+            "seekcur \"-5\"",
+            "seekcur \"-60\"",
+            "seekcur \"-60\"",
+            "seekcur \"+60\"",
+        ]
+    );
+
+    // a step need not be whole seconds
+    sim.app.seek.long_step = Duration::from_millis(2500);
+    sim.keys.send(Ok(shift(KeyCode::Left))).unwrap();
+    sim.step().await;
+    let commands = mpd.commands().await;
+    assert!(commands.contains(&"seekcur \"-2.5\"".into()));
+}
+
+#[tokio::test(start_paused = true)]
+async fn totals_the_queue() {
+    let mpd = FakeMpd::script([Attempt::Serve("pause")]);
+    let files: Vec<&'static str> = (0..20).map(|i| &*format!("{i:02}.flac").leak()).collect();
+    mpd.set_queue(&files);
+    *mpd.song.lock().unwrap() = Some(2);
+    let mut sim = Sim::new(target("mpd"), &mpd);
+    sim.terminal = Terminal::new(TestBackend::new(120, 20)).unwrap();
+    sim.step().await;
+    let screen = sim.screen();
+    assert!(screen.contains("20 tracks — 1h 06m total, 1h 00m remaining"));
+
+    // from where playback is, and along with the queue
+    sim.press(KeyCode::Right).await;
+    assert!(sim.screen().contains("1h 06m total, 59m remaining"));
+    mpd.set_queue(&files[..4]);
+    sim.step().await;
+    assert!(sim.screen().contains("4 tracks — 13m total, 6m remaining"));
+}
+
+#[tokio::test(start_paused = true)]
+async fn moves_tracks_in_batches() {
+    let mpd = FakeMpd::script([Attempt::Serve("stop")]);
+    mpd.set_queue(&["0.flac", "1.flac", "2.flac", "3.flac"]);
+    let mut sim = Sim::new(target("mpd"), &mpd);
+    let queue = |sim: &Sim| -> Vec<String> {
+        let tracks = sim.app.queue.tracks.iter();
+        tracks
+            .map(|track| track.file.replace(".flac", ""))
+            .collect()
+    };
+    let moves = |commands: Vec<String>| -> Vec<String> {
+        let commands = commands.into_iter();
+        commands.filter(|c| c.starts_with("moveid")).collect()
+    };
+
+    // the track moves along with the cursor right away
+    sim.step().await;
+    sim.typing("JJJK").await;
+    assert_eq!(queue(&sim), ["1", "2", "0", "3"]);
+    assert_eq!(sim.app.queue.selected(), Some(2));
+    assert!(moves(mpd.commands().await).is_empty());
+
+    // but the server only hears where it ended up
+    let start = Instant::now();
+    sim.step().await;
+    assert_eq!(start.elapsed(), super::queue::BATCH);
+    assert_eq!(moves(mpd.commands().await), ["moveid \"0\" \"2\""]);
+    sim.step().await;
+    assert_eq!(
+        *mpd.queue.lock().unwrap(),
+        ["1.flac", "2.flac", "0.flac", "3.flac"]
+    );
+    assert_eq!(sim.app.queue.selected(), Some(2));
+
+    // moving another track keeps both moves, in order
+    let ctrl = |code| Event::Key(KeyEvent::new(code, KeyModifiers::CONTROL));
+    sim.typing("ggJk").await;
+    sim.keys.send(Ok(ctrl(KeyCode::Down))).unwrap();
+    sim.step().await;
+    assert_eq!(queue(&sim), ["1", "2", "0", "3"]);
+    sim.step().await;
+    sim.step().await;
+    assert_eq!(
+        moves(mpd.commands().await)[1..],
+        ["moveid \"1\" \"1\"", "moveid \"2\" \"1\""]
+    );
+    assert_eq!(
+        *mpd.queue.lock().unwrap(),
+        ["1.flac", "2.flac", "0.flac", "3.flac"]
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn filters_the_queue() {
+    let mpd = FakeMpd::script([Attempt::Serve("stop")]);
+    mpd.set_queue(&[
+        "radiohead-creep.flac",
+        "ratatat-loud.flac",
+        "radiohead-nude.flac",
+        "beck-loser.flac",
+    ]);
+    let mut sim = Sim::new(target("mpd"), &mpd);
+
+    // the letters only have to come in order
+    sim.step().await;
+    sim.typing("/rdhdn").await;
+    assert_eq!(sim.app.queue.selected(), Some(2));
+    let screen = sim.screen();
+    assert!(screen.contains("/ rdhdn█  1 of 4"));
+    assert!(!screen.contains("radiohead-creep"));
+
+    sim.press(KeyCode::Backspace).await;
+    sim.press(KeyCode::Enter).await;
+    let screen = sim.screen();
+    assert!(screen.contains("radiohead-creep") && !screen.contains("ratatat"));
+    assert_eq!(sim.app.queue.selected(), Some(0));
+
+    // n and N go round the matches, whatever the queue has in between
+    sim.press(KeyCode::Char('n')).await;
+    assert_eq!(sim.app.queue.selected(), Some(2));
+    sim.press(KeyCode::Char('n')).await;
+    assert_eq!(sim.app.queue.selected(), Some(0));
+    sim.press(KeyCode::Char('N')).await;
+    assert_eq!(sim.app.queue.selected(), Some(2));
+
+    // Esc brings the rest back and leaves the cursor where it was
+    sim.press(KeyCode::Esc).await;
+    assert!(!sim.app.should_quit);
+    assert!(sim.screen().contains("ratatat"));
+    assert_eq!(sim.app.queue.selected(), Some(2));
+}
+
+#[tokio::test(start_paused = true)]
+async fn highlights_what_matched() {
+    let mpd = FakeMpd::script([Attempt::Serve("stop")]);
+    *mpd.library.lock().unwrap() = vec![("Beta", "First", "b/1.flac")];
+    mpd.set_queue(&["radiohead-creep.flac", "beck-loser.flac"]);
+    let mut sim = Sim::new(target("mpd"), &mpd);
+    let matched = |sim: &mut Sim, text: &str| -> Vec<bool> {
+        let screen = sim.screen();
+        let at = screen[..screen.rfind(text).unwrap()].chars().count();
+        let cells = &sim.terminal.backend().buffer().content()[at..at + text.len()];
+        let underlined = |cell: &Cell| cell.modifier.contains(Modifier::UNDERLINED);
+        cells.iter().map(underlined).collect()
+    };
+
+    // the letters the filter found, wherever they are
+    sim.step().await;
+    sim.typing("/crp").await;
+    let hits = matched(&mut sim, "creep");
+    assert_eq!(hits, [true, true, false, false, true]);
+
+    // the terms searched for, in the tags they were searched in
+    sim.press(KeyCode::Esc).await;
+    sim.press(KeyCode::Char('5')).await;
+    sim.typing("et").await;
+    sim.step().await;
+    assert_eq!(matched(&mut sim, "Beta"), [false, true, true, false]);
+    assert_eq!(matched(&mut sim, "1.flac"), [false; 6]);
+}
+
+// playing a.flac, which has a red cover, with b.flac next without one
+fn with_cover() -> FakeMpd {
+    let mpd = FakeMpd::script([Attempt::Serve("play")]);
+    mpd.set_queue(&["a.flac", "b.flac"]);
+    *mpd.song.lock().unwrap() = Some(0);
+    let mut png = io::Cursor::new(Vec::new());
+    let cover = image::RgbaImage::from_pixel(40, 20, image::Rgba([200, 0, 0, 255]));
+    cover.write_to(&mut png, image::ImageFormat::Png).unwrap();
+    mpd.covers
+        .lock()
+        .unwrap()
+        .insert("a.flac", png.into_inner());
+    mpd
+}
+
+#[derive(Clone, Default)]
+struct Screen(Arc<Mutex<Vec<u8>>>);
+
+impl io::Write for Screen {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[tokio::test(start_paused = true)]
+async fn shows_the_cover() {
+    let mpd = with_cover();
+    let mut sim = Sim::new(target("mpd"), &mpd);
+    let screen = Screen::default();
+    sim.app.art = Art::new(Some(Protocol::Kitty), Box::new(screen.clone()));
+    let sent = || String::from_utf8_lossy(&screen.0.lock().unwrap()).into_owned();
+
+    // read in chunks, then placed over a pane right of the queue
+    sim.step().await;
+    sim.step().await;
+    let pictures: Vec<_> = mpd
+        .commands()
+        .await
+        .into_iter()
+        .filter(|c| c.starts_with("readpicture"))
+        .collect();
+    assert!(pictures.len() > 1);
+    assert_eq!(pictures[1], "readpicture \"a.flac\" \"100\"");
+    assert!(sim.app.art.has_cover());
+    sim.step().await;
+    assert_eq!(sim.app.art.area.width, 6);
+    assert!(sent().contains("\x1b_Ga=T,f=32,s=48,v=24,i=1"));
+
+    // a song without one falls back to albumart and takes the picture down
+    *mpd.song.lock().unwrap() = Some(1);
+    mpd.set_state("play");
+    sim.step().await;
+    sim.step().await;
+    assert!(!sim.app.art.has_cover());
+    assert!(mpd
+        .commands()
+        .await
+        .contains(&"albumart \"b.flac\" \"0\"".into()));
+    sim.step().await;
+    assert!(sent().ends_with("\x1b_Ga=d,d=I,i=1,q=2\x1b\\"));
+}
+
+#[tokio::test(start_paused = true)]
+async fn shows_the_cover_without_kitty() {
+    for protocol in [Protocol::Sixel, Protocol::Iterm2, Protocol::Blocks] {
+        let mpd = with_cover();
+        let mut sim = Sim::new(target("mpd"), &mpd);
+        let screen = Screen::default();
+        sim.app.art = Art::new(Some(protocol), Box::new(screen.clone()));
+        let sent = || String::from_utf8_lossy(&screen.0.lock().unwrap()).into_owned();
+
+        sim.step().await;
+        sim.step().await;
+        sim.step().await;
+        match protocol {
+            Protocol::Sixel => assert!(sent().contains("\x1bPq\"1;1;48;24#0;2;0;0;0")),
+            Protocol::Iterm2 => assert!(sent().contains("\x1b]1337;File=inline=1;")),
+            // nothing goes around the frame, the cover is in it
+            _ => {
+                assert!(sent().is_empty());
+                let buffer = sim.terminal.backend().buffer().clone();
+                let cell = &buffer[(55, 7)];
+                assert_eq!(cell.symbol(), "▀");
+                assert_eq!(cell.fg, Color::Rgb(200, 0, 0));
+            }
+        }
+
+        // the screen is drawn afresh to take it down again
+        *mpd.song.lock().unwrap() = Some(1);
+        mpd.set_state("play");
+        sim.step().await;
+        sim.step().await;
+        sim.step().await;
+        assert!(!sim.app.art.has_cover());
+        assert!(!sim.screen().contains('▀'));
+    }
+}
+
+#[cfg(feature = "remote-art")]
+#[tokio::test(start_paused = true)]
+async fn falls_back_to_remote_covers() {
+    use tokio::net::TcpListener;
+
+    use crate::covers::Covers;
+
+    let mpd = with_cover();
+    *mpd.library.lock().unwrap() = vec![("Beta", "Only One", "b.flac")];
+    *mpd.song.lock().unwrap() = Some(1);
+
+    // answers a single request with a green cover
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let server = tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        let (read, mut write) = split(stream);
+        let mut lines = BufReader::new(read).lines();
+        let request = lines.next_line().await.unwrap().unwrap();
+        // the headers end with an empty line
+        while let Some(line) = lines.next_line().await.unwrap() {
+            if line.is_empty() {
+                break;
+            }
+        }
+
+        let mut png = io::Cursor::new(Vec::new());
+        let cover = image::RgbaImage::from_pixel(40, 20, image::Rgba([0, 200, 0, 255]));
+        cover.write_to(&mut png, image::ImageFormat::Png).unwrap();
+        let body = png.into_inner();
+        let head = format!("HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n", body.len());
+        write.write_all(head.as_bytes()).await.unwrap();
+        write.write_all(&body).await.unwrap();
+        request
+    });
+
+    let mut sim = Sim::new(target("mpd"), &mpd);
+    let mut config = Config::default();
+    config.cache.enabled = false;
+    config.remote_art = Some(config::ArtSource::Template {
+        url: format!("http://127.0.0.1:{port}/{{artist}}/{{album}}.png"),
+    });
+    sim.app.covers = Covers::start(&config);
+    sim.app.art = Art::new(Some(Protocol::Blocks), Box::new(io::sink()));
+
+    // the server has none, so it is downloaded
+    for _ in 0..20 {
+        if sim.app.art.has_cover() {
+            break;
+        }
+        sim.step().await;
+    }
+    assert!(sim.app.art.has_cover());
+    assert!(mpd
+        .commands()
+        .await
+        .contains(&"albumart \"b.flac\" \"0\"".into()));
+    assert_eq!(server.await.unwrap(), "GET /Beta/Only%20One.png HTTP/1.1");
+}
+
+#[cfg(feature = "notifications")]
+#[tokio::test(start_paused = true)]
+async fn notifies_when_the_song_changes() {
+    let mpd = with_cover();
+    let mut sim = Sim::new(target("mpd"), &mpd);
+    sim.app.art = Art::new(Some(Protocol::Blocks), Box::new(io::sink()));
+    let (tx, mut notes) = unbounded_channel();
+    sim.app.notifier = crate::notification::Notifier::sending(tx, true);
+
+    // what was playing before connecting is no news
+    sim.step().await;
+    sim.step().await;
+    assert!(notes.try_recv().is_err());
+
+    // the note waits for the cover, or for there being none
+    *mpd.song.lock().unwrap() = Some(1);
+    mpd.set_state("play");
+    sim.step().await;
+    let note = notes.try_recv().unwrap();
+    assert_eq!(note.summary, "b.flac");
+    assert!(note.picture.is_none());
+
+    *mpd.song.lock().unwrap() = Some(0);
+    mpd.set_state("play");
+    sim.step().await;
+    let note = notes.try_recv().unwrap();
+    assert_eq!(note.summary, "a.flac");
+    let picture = note.picture.unwrap();
+    assert_eq!((picture.width, picture.height), (128, 64));
+    assert_eq!(&picture.rgba[..4], [200, 0, 0, 255]);
+
+    // nor is a refresh of the same song, or one that does not play
+    mpd.set_state("play");
+    sim.step().await;
+    *mpd.song.lock().unwrap() = Some(1);
+    mpd.set_state("pause");
+    sim.step().await;
+    sim.step().await;
+    assert!(notes.try_recv().is_err());
+}
+
+#[tokio::test(start_paused = true)]
+async fn resizes_and_moves_the_cover() {
+    let mpd = with_cover();
+    let mut sim = Sim::new(target("mpd"), &mpd);
+    sim.terminal = Terminal::new(TestBackend::new(60, 20)).unwrap();
+    sim.app.art = Art::new(Some(Protocol::Blocks), Box::new(io::sink()));
+    for _ in 0..3 {
+        sim.step().await;
+    }
+    let area = |sim: &mut Sim| {
+        sim.screen();
+        sim.app.art.area
+    };
+    assert_eq!(area(&mut sim).width, 20);
+
+    // resizing starts from the share it was sized to fit
+    sim.press(KeyCode::Char('>')).await;
+    assert_eq!(area(&mut sim).width, 23);
+    sim.press(KeyCode::Char('<')).await;
+    sim.press(KeyCode::Char('<')).await;
+    assert_eq!(area(&mut sim).width, 17);
+
+    // above the queue it takes the same share of the height
+    sim.press(KeyCode::Char('|')).await;
+    let pane = area(&mut sim);
+    assert_eq!((pane.width, pane.height), (60, 4));
+    assert!(sim.screen().contains("a.flac"));
+
+    sim.press(KeyCode::Char('c')).await;
+    assert!(area(&mut sim).is_empty());
+    assert!(!sim.screen().contains('▀'));
+    sim.press(KeyCode::Char('c')).await;
+    assert!(sim.screen().contains('▀'));
+}
+
+#[tokio::test(start_paused = true)]
+async fn follows_synced_lyrics() {
+    let dir = std::env::temp_dir().join(format!("encore-lyrics-{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("album")).unwrap();
+    let lrc = "[ar:Someone]\n[offset:-1000]\n[00:01.00]first line\n[00:03.50]second line\n";
+    std::fs::write(dir.join("album/one.lrc"), lrc).unwrap();
+    std::fs::write(dir.join("two.txt"), "\n[Chorus]\nplain words\n").unwrap();
+
+    let mpd = FakeMpd::script([Attempt::Serve("stop")]);
+    mpd.set_queue(&["album/one.flac", "two.flac", "three.flac"]);
+    let mut sim = Sim::new(target("mpd"), &mpd);
+    sim.app.lyrics = Lyrics::new(Some(dir.clone()), None);
+
+    sim.step().await;
+    sim.press(KeyCode::Char('6')).await;
+    assert!(sim.screen().contains("Nothing is playing"));
+
+    // the offset holds every line back a second
+    sim.press(KeyCode::Char('1')).await;
+    sim.press(KeyCode::Enter).await;
+    sim.step().await;
+    sim.press(KeyCode::Char('6')).await;
+    let screen = sim.screen();
+    assert!(screen.contains("first line") && screen.contains("second line"));
+    let current = |sim: &mut Sim, text: &str| {
+        let screen = sim.screen();
+        let buffer = sim.terminal.backend().buffer();
+        let at = screen[..screen.find(text).unwrap()].chars().count();
+        buffer.content()[at].fg == Color::Yellow
+    };
+    assert!(!current(&mut sim, "first line"));
+
+    while sim.app.elapsed() < Duration::from_millis(2250) {
+        sim.step().await;
+    }
+    assert!(current(&mut sim, "first line"));
+    while sim.app.elapsed() < Duration::from_millis(4750) {
+        sim.step().await;
+    }
+    assert!(current(&mut sim, "second line") && !current(&mut sim, "first line"));
+
+    // plain text next to nothing in the lyrics directory is found by name
+    sim.app.lyrics = Lyrics::new(None, Some(dir.clone()));
+    sim.press(KeyCode::Char('1')).await;
+    sim.press(KeyCode::Down).await;
+    sim.press(KeyCode::Enter).await;
+    sim.step().await;
+    sim.press(KeyCode::Char('6')).await;
+    let screen = sim.screen();
+    assert!(screen.contains("[Chorus]") && screen.contains("plain words"));
+
+    sim.press(KeyCode::Char('1')).await;
+    sim.press(KeyCode::Down).await;
+    sim.press(KeyCode::Enter).await;
+    sim.step().await;
+    sim.press(KeyCode::Char('6')).await;
+    assert!(sim.screen().contains("No lyrics for this song"));
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[cfg(feature = "watch")]
+#[tokio::test(start_paused = true)]
+async fn picks_up_files_changed_on_disk() {
+    let dir = std::env::temp_dir().join(format!("encore-watch-{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("music")).unwrap();
+    let path = dir.join("config.toml");
+    let music = dir.join("music").display().to_string();
+    std::fs::write(&path, format!("music_dir = {music:?}\n")).unwrap();
+    let config = Config::load(Some(path.clone())).unwrap();
+
+    let mpd = FakeMpd::script([Attempt::Serve("stop")]);
+    mpd.set_queue(&["one.flac"]);
+    let mut sim = Sim::new(target("mpd"), &mpd);
+    sim.app.lyrics = Lyrics::new(config.music_dir.clone(), None);
+    sim.app.watcher = Watcher::start(&config).unwrap();
+    sim.step().await;
+    sim.press(KeyCode::Enter).await;
+    sim.step().await;
+    sim.press(KeyCode::Char('6')).await;
+    assert!(sim.screen().contains("No lyrics for this song"));
+
+    // the watcher runs on a thread of its own
+    std::fs::write(dir.join("music/one.txt"), "words at last\n").unwrap();
+    for _ in 0..100 {
+        if sim.screen().contains("words at last") {
+            break;
+        }
+        std::thread::sleep(Duration::from_millis(10));
+        sim.step().await;
+    }
+    assert!(sim.screen().contains("words at last"));
+
+    std::fs::write(&path, format!("music_dir = {music:?}\nvolume_step = 20\n")).unwrap();
+    for _ in 0..100 {
+        if sim.app.volume_step == 20 {
+            break;
+        }
+        std::thread::sleep(Duration::from_millis(10));
+        sim.step().await;
+    }
+    assert_eq!(sim.app.volume_step, 20);
+    assert!(sim.screen().contains("Reloaded the config"));
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[tokio::test(start_paused = true)]
+async fn draws_the_fifo_output() {
+    // a plain file stands in for the pipe, it is read again once used up
+    let path = std::env::temp_dir().join(format!("encore-fifo-{}", std::process::id()));
+    let sine = (0..4096).flat_map(|i| {
+        let sample = ((i as f32 / 20.0).sin() * 16000.0) as i16;
+        [sample, sample].map(i16::to_ne_bytes).concat()
+    });
+    std::fs::write(&path, sine.collect::<Vec<_>>()).unwrap();
+
+    let mpd = FakeMpd::script([Attempt::Serve("stop")]);
+    mpd.set_queue(&["one.flac"]);
+    let mut sim = Sim::new(target("mpd"), &mpd);
+    sim.terminal = Terminal::new(TestBackend::new(60, 16)).unwrap();
+
+    sim.step().await;
+    sim.press(KeyCode::Char('v')).await;
+    assert!(sim.screen().contains("Set visualizer.fifo"));
+    sim.press(KeyCode::Char('v')).await;
+
+    sim.app.visualizer = Visualizer::new(config::Visualizer {
+        fifo: Some(path.clone()),
+        ..Default::default()
+    });
+    sim.press(KeyCode::Char('v')).await;
+    let braille = |screen: String| {
+        screen
+            .chars()
+            .any(|c| ('\u{2801}'..='\u{28ff}').contains(&c))
+    };
+    assert!(!braille(sim.screen()), "nothing moves while stopped");
+
+    sim.press(KeyCode::Enter).await;
+    sim.step().await;
+    for _ in 0..200 {
+        if braille(sim.screen()) {
+            break;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(10));
+        sim.step().await;
+    }
+    let screen = sim.screen();
+    assert!(braille(screen.clone()) && screen.contains("level"));
+
+    // the spectrum moves on with the ticks, one tall bar for the one tone
+    sim.press(KeyCode::Char('b')).await;
+    for _ in 0..3 {
+        sim.step().await;
+    }
+    let screen = sim.screen();
+    assert!(!braille(screen.clone()) && screen.contains('█'));
+    assert!(screen.matches('█').count() < 60);
+
+    sim.press(KeyCode::Char('v')).await;
+    assert!(!sim.screen().contains("level"));
+    std::fs::remove_file(path).unwrap();
+}
+
+#[tokio::test(start_paused = true)]
+async fn a_tick_only_draws_its_pane() {
+    let mpd = FakeMpd::script([Attempt::Serve("play")]);
+    mpd.set_queue(&["one.flac"]);
+    *mpd.song.lock().unwrap() = Some(0);
+    let mut sim = Sim::new(target("mpd"), &mpd);
+    sim.terminal = Terminal::new(TestBackend::new(60, 16)).unwrap();
+
+    sim.step().await;
+    sim.press(KeyCode::Char('v')).await;
+    while sim.app.ticked != [Pane::Visualizer] {
+        sim.step().await;
+    }
+
+    // what is not the visualizer stays as it was last drawn
+    sim.app.queue.follow = false;
+    sim.step().await;
+    let drawn = |sim: &Sim| {
+        let buffer = sim.terminal.backend().buffer();
+        let symbols = buffer.content().iter().map(|cell| cell.symbol());
+        symbols.collect::<String>()
+    };
+    assert!(drawn(&sim).contains("following"));
+    assert!(drawn(&sim).contains("Set visualizer.fifo"));
+
+    // anything else draws it all
+    sim.press(KeyCode::Char('o')).await;
+    sim.step().await;
+    assert!(!drawn(&sim).contains("following"));
+}
+
+#[tokio::test(start_paused = true)]
+async fn draws_with_the_configured_theme() {
+    let dir = std::env::temp_dir().join(format!("encore-theme-{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("themes")).unwrap();
+    let path = dir.join("config.toml");
+    let load = |text: &str| {
+        std::fs::write(&path, text).unwrap();
+        Theme::load(&Config::load(Some(path.clone())).unwrap())
+    };
+
+    // a built-in theme with one element restyled from scratch
+    let theme = load(
+        "[theme]\nname = \"nord\"\ncurrent = { fg = \"#ff0000\", modifiers = [\"italic\"] }\n",
+    );
+    let theme = theme.unwrap();
+    assert_eq!(
+        theme.current,
+        Style::new().fg(Color::Rgb(255, 0, 0)).italic()
+    );
+    assert_eq!(theme.marked, Style::new().fg(Color::Rgb(0xb4, 0x8e, 0xad)));
+
+    // or a file in the themes directory next to the config
+    std::fs::write(dir.join("themes/mine.toml"), "marked = { bg = \"blue\" }\n").unwrap();
+    let theme = load("[theme]\nname = \"mine\"\n").unwrap();
+    assert_eq!(theme.marked, Style::new().bg(Color::Blue));
+    assert_eq!(theme.current, Theme::default().current);
+
+    assert!(load("[theme]\nname = \"missing\"\n").is_err());
+    assert!(load("[theme]\nplaying = { fg = \"red\" }\n").is_err());
+    assert!(load("[theme]\ncurrent = { fg = \"reddish\" }\n").is_err());
+    assert!(load("[theme]\ncurrent = { modifiers = [\"loud\"] }\n").is_err());
+    std::fs::remove_dir_all(dir).unwrap();
+
+    let mpd = FakeMpd::script([Attempt::Serve("stop")]);
+    mpd.set_queue(&["one.flac", "two.flac"]);
+    let mut sim = Sim::new(target("mpd"), &mpd);
+    sim.app.theme = Theme {
+        current: Style::new().fg(Color::Red),
+        selected: Style::new().bg(Color::Blue),
+        ..Theme::default()
+    };
+
+    sim.step().await;
+    sim.press(KeyCode::Char('j')).await;
+    sim.press(KeyCode::Enter).await;
+    sim.step().await;
+    sim.press(KeyCode::Char('k')).await;
+    let screen = sim.screen();
+    let buffer = sim.terminal.backend().buffer();
+    let at = |text| screen[..screen.rfind(text).unwrap()].chars().count();
+    assert_eq!(buffer.content()[at("one.flac")].bg, Color::Blue);
+    assert_eq!(buffer.content()[at("two.flac")].fg, Color::Red);
+}
+
+#[tokio::test(start_paused = true)]
+async fn lays_out_the_configured_columns() {
+    let path = std::env::temp_dir().join(format!("encore-columns-{}.toml", std::process::id()));
+    let load = |text: &str| {
+        std::fs::write(&path, text).unwrap();
+        Config::load(Some(path.clone()))
+    };
+
+    let config = load(
+        "[[queue.columns]]\ntag = \"track\"\nwidth = 3\nalign = \"right\"\n\
+         [[queue.columns]]\ntag = \"title\"\nwidth = \"50%\"\n\
+         [[queue.columns]]\ntag = \"duration\"\nwidth = \"1fr\"\nalign = \"center\"\n",
+    );
+    let columns = config.unwrap().queue.columns;
+    assert_eq!(
+        columns
+            .iter()
+            .map(|column| column.width)
+            .collect::<Vec<_>>(),
+        [Width::Cells(3), Width::Percent(50), Width::Fill(1)]
+    );
+    assert!(load("[[queue.columns]]\ntag = \"title\"\nwidth = \"wide\"\n").is_err());
+    assert!(load("[[queue.columns]]\ntag = \"mood\"\n").is_err());
+    std::fs::remove_file(&path).unwrap();
+
+    let mpd = FakeMpd::script([Attempt::Serve("stop")]);
+    mpd.set_queue(&["one.flac", "two.flac"]);
+    let mut sim = Sim::new(target("mpd"), &mpd);
+    sim.app.queue.columns = columns;
+    sim.step().await;
+
+    // 60 cells less two gaps of two: 3 for the number, 30 for the title and
+    // the 23 left over for the duration
+    let screen = sim.screen();
+    let row = format!("{:>3}  {:<30}  {:^23}", 2, "two.flac", "3:20");
+    assert!(screen.contains(&row), "{screen}");
+}
@@ -0,0 +1,392 @@
+use std::{
+    collections::BTreeMap,
+    io::{self, Cursor, Write},
+    sync::Arc,
+};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use encore_core::config::Graphics;
+use image::{
+    imageops::{self, FilterType},
+    ImageFormat, Rgba, RgbaImage,
+};
+use ratatui::{crossterm::terminal, layout::Rect, style::Color, Frame};
+
+use crate::ueberzug::Ueberzug;
+
+// kitty keeps images by id, the cover is the only one
+const ID: u32 = 1;
+// the most payload one escape sequence may carry
+const CHUNK: usize = 4096;
+// what a cell usually measures when the terminal does not say
+const CELL: (u32, u32) = (8, 16);
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Protocol {
+    Kitty,
+    Sixel,
+    Iterm2,
+    // a separate program that puts a window over the terminal
+    Ueberzug,
+    // two pixels per cell out of half blocks, which any terminal can show
+    Blocks,
+}
+
+impl Protocol {
+    pub fn pick(graphics: Graphics) -> Option<Self> {
+        match graphics {
+            Graphics::Auto => Some(Self::detect()),
+            Graphics::Kitty => Some(Protocol::Kitty),
+            Graphics::Sixel => Some(Protocol::Sixel),
+            Graphics::Iterm2 => Some(Protocol::Iterm2),
+            Graphics::Ueberzug => Some(Protocol::Ueberzug),
+            Graphics::Blocks => Some(Protocol::Blocks),
+            Graphics::Off => None,
+        }
+    }
+
+    // going by the environment, querying the terminal would race the key
+    // events for its answer
+    fn detect() -> Self {
+        let var = |name| std::env::var(name).unwrap_or_default();
+        let set = |name| std::env::var_os(name).is_some();
+        let term = var("TERM");
+        let program = var("TERM_PROGRAM");
+
+        if set("KITTY_WINDOW_ID")
+            || term.contains("kitty")
+            || matches!(program.as_str(), "WezTerm" | "ghostty")
+        {
+            Protocol::Kitty
+        } else if matches!(program.as_str(), "iTerm.app" | "mintty")
+            || var("LC_TERMINAL") == "iTerm2"
+        {
+            Protocol::Iterm2
+        } else if ["foot", "mlterm", "yaft", "contour"]
+            .iter()
+            .any(|name| term.starts_with(name))
+            || term.contains("sixel")
+            || set("KONSOLE_VERSION")
+            || set("WT_SESSION")
+        {
+            Protocol::Sixel
+        } else if Ueberzug::found() {
+            Protocol::Ueberzug
+        } else {
+            Protocol::Blocks
+        }
+    }
+}
+
+// the cover of the playing song; blocks are drawn with the frame, the other
+// protocols go straight to the terminal over the pane the frame left empty
+pub struct Art {
+    pub protocol: Option<Protocol>,
+    pub area: Rect,
+    screen: Rect,
+    song: Option<String>,
+    requested: bool,
+    cover: Option<Arc<RgbaImage>>,
+    shown: Option<(Rect, Rect, Arc<RgbaImage>)>,
+    // the cover scaled for the blocks, kept until the pane changes
+    scaled: Option<(Rect, Arc<RgbaImage>, RgbaImage, Rect)>,
+    ueberzug: Option<Ueberzug>,
+    out: Box<dyn Write + Send>,
+}
+
+impl Art {
+    pub fn new(protocol: Option<Protocol>, out: Box<dyn Write + Send>) -> Self {
+        Self {
+            protocol,
+            area: Rect::default(),
+            screen: Rect::default(),
+            song: None,
+            requested: false,
+            cover: None,
+            shown: None,
+            scaled: None,
+            ueberzug: None,
+            out,
+        }
+    }
+
+    pub fn set_song(&mut self, uri: Option<&str>) {
+        if self.song.as_deref() != uri {
+            self.song = uri.map(Into::into);
+            self.requested = false;
+            self.cover = None;
+        }
+    }
+
+    // the song to fetch a cover for, once per song
+    pub fn wanted(&mut self) -> Option<String> {
+        if self.protocol.is_none() || self.requested {
+            return None;
+        }
+
+        self.requested = true;
+        self.song.clone()
+    }
+
+    // fetched again, as the one on disk changed
+    pub fn refetch(&mut self) {
+        self.requested = false;
+    }
+
+    pub fn set(&mut self, uri: &str, cover: Option<RgbaImage>) {
+        if self.song.as_deref() == Some(uri) {
+            self.cover = cover.map(Arc::new);
+        }
+    }
+
+    pub fn has_cover(&self) -> bool {
+        self.cover.is_some()
+    }
+
+    // leaves `area` to the cover for this frame
+    pub fn draw(&mut self, frame: &mut Frame, area: Rect) {
+        self.area = area;
+        self.screen = frame.area();
+        if self.protocol != Some(Protocol::Blocks) {
+            return;
+        }
+        let Some(cover) = &self.cover else {
+            return;
+        };
+
+        let fresh = match &self.scaled {
+            Some((scaled, old, ..)) => *scaled == area && Arc::ptr_eq(cover, old),
+            None => false,
+        };
+        if !fresh {
+            let (image, at) = fit(cover, area, (1, 2));
+            self.scaled = Some((area, cover.clone(), image, at));
+        }
+
+        let Some((_, _, image, at)) = &self.scaled else {
+            return;
+        };
+        let buffer = frame.buffer_mut();
+        for row in 0..at.height {
+            for column in 0..at.width {
+                let top = image.get_pixel_checked(column.into(), (row * 2).into());
+                let bottom = image.get_pixel_checked(column.into(), (row * 2 + 1).into());
+                buffer[(at.x + column, at.y + row)]
+                    .set_symbol("▀")
+                    .set_fg(color(top))
+                    .set_bg(color(bottom));
+            }
+        }
+    }
+
+    // brings the terminal in line with the last frame, only sending the
+    // picture again when it or its pane changed; true when the whole screen
+    // has to be drawn again to take an old picture down
+    pub fn flush(&mut self) -> io::Result<bool> {
+        let Some(protocol) = self.protocol.filter(|p| *p != Protocol::Blocks) else {
+            return Ok(false);
+        };
+
+        let wanted = match &self.cover {
+            Some(cover) if !self.area.is_empty() => Some((self.screen, self.area, cover.clone())),
+            _ => None,
+        };
+        let same = match (&wanted, &self.shown) {
+            (Some((screen, area, cover)), Some((old_screen, old_area, old))) => {
+                screen == old_screen && area == old_area && Arc::ptr_eq(cover, old)
+            }
+            (None, None) => true,
+            _ => false,
+        };
+        if same {
+            return Ok(false);
+        }
+
+        if protocol == Protocol::Ueberzug {
+            return Ok(self.overlay(wanted).is_err());
+        }
+
+        if self.shown.take().is_some() {
+            match protocol {
+                Protocol::Kitty => write!(self.out, "\x1b_Ga=d,d=I,i={ID},q=2\x1b\\")?,
+                // pictures in cells only go away once drawn over
+                _ => return Ok(true),
+            }
+        }
+        if let Some((screen, area, cover)) = wanted {
+            let (image, at) = fit(&cover, area, cell());
+            write!(self.out, "\x1b[{};{}H", at.y + 1, at.x + 1)?;
+            match protocol {
+                Protocol::Kitty => kitty(&image, &mut self.out)?,
+                Protocol::Sixel => sixel(&image, &mut self.out)?,
+                _ => iterm2(&image, &mut self.out)?,
+            }
+            self.shown = Some((screen, area, cover));
+        }
+        self.out.flush()?;
+        Ok(false)
+    }
+
+    // ueberzugpp failing to start or going away leaves the blocks, which
+    // take a fresh frame to show up
+    fn overlay(&mut self, wanted: Option<(Rect, Rect, Arc<RgbaImage>)>) -> io::Result<()> {
+        let result = (|| {
+            let ueberzug = match &mut self.ueberzug {
+                Some(ueberzug) => ueberzug,
+                None => self.ueberzug.insert(Ueberzug::spawn()?),
+            };
+            match &wanted {
+                Some((_, area, cover)) => {
+                    ueberzug.show(cover, place(cover.dimensions(), *area, cell()))
+                }
+                None => ueberzug.hide(),
+            }
+        })();
+
+        match result {
+            Ok(()) => self.shown = wanted,
+            Err(_) => {
+                self.ueberzug = None;
+                self.protocol = Some(Protocol::Blocks);
+            }
+        }
+        result
+    }
+
+    pub fn clear(&mut self) -> io::Result<()> {
+        // dropping ueberzugpp takes its window down
+        self.ueberzug = None;
+        if self.shown.take().is_some() && self.protocol == Some(Protocol::Kitty) {
+            write!(self.out, "\x1b_Ga=d,d=A,q=2\x1b\\")?;
+        }
+        self.out.flush()
+    }
+}
+
+pub fn decode(data: &[u8]) -> Option<RgbaImage> {
+    image::load_from_memory(data)
+        .ok()
+        .map(|image| image.into_rgba8())
+}
+
+// the size of a cell in pixels
+fn cell() -> (u32, u32) {
+    match terminal::window_size() {
+        Ok(size) if size.width > 0 && size.columns > 0 && size.rows > 0 => (
+            (size.width / size.columns).into(),
+            (size.height / size.rows).into(),
+        ),
+        _ => CELL,
+    }
+}
+
+// the cover scaled to fit the pane and the cells it ends up on
+fn fit(cover: &RgbaImage, area: Rect, cell: (u32, u32)) -> (RgbaImage, Rect) {
+    let (width, height) = scaled(cover.dimensions(), area, cell);
+    let image = imageops::resize(cover, width, height, FilterType::Triangle);
+    (image, place(cover.dimensions(), area, cell))
+}
+
+// the cells a picture of `size` pixels covers once scaled to the pane,
+// centred on it
+fn place(size: (u32, u32), area: Rect, cell: (u32, u32)) -> Rect {
+    let (width, height) = scaled(size, area, cell);
+    let columns = (width.div_ceil(cell.0) as u16).min(area.width);
+    let rows = (height.div_ceil(cell.1) as u16).min(area.height);
+    let x = area.x + (area.width - columns) / 2;
+    let y = area.y + (area.height - rows) / 2;
+    Rect::new(x, y, columns, rows)
+}
+
+fn scaled((width, height): (u32, u32), area: Rect, cell: (u32, u32)) -> (u32, u32) {
+    let room = (area.width as u32 * cell.0, area.height as u32 * cell.1);
+    let scale = f64::min(room.0 as f64 / width as f64, room.1 as f64 / height as f64);
+    let width = ((width as f64 * scale) as u32).max(1);
+    let height = ((height as f64 * scale) as u32).max(1);
+    (width, height)
+}
+
+fn color(pixel: Option<&Rgba<u8>>) -> Color {
+    match pixel {
+        Some(&Rgba([r, g, b, a])) if a >= 128 => Color::Rgb(r, g, b),
+        _ => Color::Reset,
+    }
+}
+
+fn kitty(image: &RgbaImage, out: &mut impl Write) -> io::Result<()> {
+    let (width, height) = image.dimensions();
+    let data = STANDARD.encode(image.as_raw());
+    let chunks: Vec<_> = data.as_bytes().chunks(CHUNK).collect();
+    for (i, chunk) in chunks.iter().enumerate() {
+        let more = u8::from(i + 1 < chunks.len());
+        match i {
+            0 => write!(
+                out,
+                "\x1b_Ga=T,f=32,s={width},v={height},i={ID},q=2,C=1,m={more};"
+            )?,
+            _ => write!(out, "\x1b_Gm={more};")?,
+        }
+        out.write_all(chunk)?;
+        out.write_all(b"\x1b\\")?;
+    }
+    Ok(())
+}
+
+fn iterm2(image: &RgbaImage, out: &mut impl Write) -> io::Result<()> {
+    let (width, height) = image.dimensions();
+    let mut png = Cursor::new(Vec::new());
+    image
+        .write_to(&mut png, ImageFormat::Png)
+        .map_err(io::Error::other)?;
+    let png = png.into_inner();
+    write!(
+        out,
+        "\x1b]1337;File=inline=1;size={};width={width}px;height={height}px:{}\x07",
+        png.len(),
+        STANDARD.encode(&png)
+    )
+}
+
+// six rows of pixels at a time, one pass over them per colour; a 6x6x6
+// colour cube is plenty for a thumbnail and needs no quantizer
+fn sixel(image: &RgbaImage, out: &mut impl Write) -> io::Result<()> {
+    let (width, height) = image.dimensions();
+    let level = |c: u8| (c as usize * 5 + 127) / 255;
+
+    write!(out, "\x1bPq\"1;1;{width};{height}")?;
+    for i in 0..216 {
+        let (r, g, b) = (i / 36 * 20, i / 6 % 6 * 20, i % 6 * 20);
+        write!(out, "#{i};2;{r};{g};{b}")?;
+    }
+
+    for band in (0..height).step_by(6) {
+        let mut colors: BTreeMap<usize, Vec<u8>> = BTreeMap::new();
+        for y in band..(band + 6).min(height) {
+            for x in 0..width {
+                let Rgba([r, g, b, a]) = *image.get_pixel(x, y);
+                if a < 128 {
+                    continue;
+                }
+                let color = level(r) * 36 + level(g) * 6 + level(b);
+                let row = colors
+                    .entry(color)
+                    .or_insert_with(|| vec![0; width as usize]);
+                row[x as usize] |= 1 << (y - band);
+            }
+        }
+
+        for (color, row) in colors {
+            write!(out, "#{color}")?;
+            for run in row.chunk_by(|a, b| a == b) {
+                let c = (63 + run[0]) as char;
+                match run.len() {
+                    n if n > 3 => write!(out, "!{n}{c}")?,
+                    n => write!(out, "{}", c.to_string().repeat(n))?,
+                }
+            }
+            out.write_all(b"$")?;
+        }
+        out.write_all(b"-")?;
+    }
+    out.write_all(b"\x1b\\")
+}
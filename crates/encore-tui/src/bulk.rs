@@ -0,0 +1,126 @@
+use encore_mpd::{CommandList, Entry, Track};
+
+use crate::{library::Node, playlists::Item};
+
+// something that goes into the queue or a playlist with a single command
+#[derive(Clone, Debug)]
+pub enum Source {
+    Uri(String),
+    Filter(String),
+    Playlist(String),
+}
+
+impl From<&Node> for Source {
+    fn from(node: &Node) -> Self {
+        match node {
+            Node::Track(track) => Source::Uri(track.file.clone()),
+            node => Source::Filter(node.filter().unwrap_or_default()),
+        }
+    }
+}
+
+impl From<&Entry> for Source {
+    fn from(entry: &Entry) -> Self {
+        match entry {
+            Entry::Directory(path) => Source::Uri(path.clone()),
+            Entry::File(track) => Source::Uri(track.file.clone()),
+            Entry::Playlist(path) => Source::Playlist(path.clone()),
+        }
+    }
+}
+
+impl From<&Item> for Source {
+    fn from(item: &Item) -> Self {
+        match item {
+            Item::Playlist(name) => Source::Playlist(name.clone()),
+            Item::Track(track) => Source::Uri(track.file.clone()),
+        }
+    }
+}
+
+impl From<&Track> for Source {
+    fn from(track: &Track) -> Self {
+        Source::Uri(track.file.clone())
+    }
+}
+
+pub fn enqueue(sources: &[Source]) -> Option<CommandList> {
+    let mut list = CommandList::new();
+    for source in sources {
+        match source {
+            Source::Uri(uri) => list.push(b"add", &[uri]),
+            Source::Filter(filter) => list.push(b"findadd", &[filter]),
+            Source::Playlist(name) => list.push(b"load", &[name]),
+        }
+        .ok()?;
+    }
+    (!list.is_empty()).then_some(list)
+}
+
+// playlists do not nest, so those are left out
+pub fn save_to(playlist: &str, sources: &[Source]) -> Option<CommandList> {
+    let mut list = CommandList::new();
+    for source in sources {
+        match source {
+            Source::Uri(uri) => list.push(b"playlistadd", &[playlist, uri]),
+            Source::Filter(filter) => list.push(b"searchaddpl", &[playlist, filter]),
+            Source::Playlist(_) => continue,
+        }
+        .ok()?;
+    }
+    (!list.is_empty()).then_some(list)
+}
+
+pub fn delete(ids: &[u32]) -> Option<CommandList> {
+    let mut list = CommandList::new();
+    for id in ids {
+        list.push(b"deleteid", &[&id.to_string()]).ok()?;
+    }
+    (!list.is_empty()).then_some(list)
+}
+
+// moves that put the queue in the wanted order of its ids, worked out on a
+// copy like the ones below
+pub fn reorder(ids: &[u32], wanted: &[u32]) -> Option<CommandList> {
+    let mut ids = ids.to_vec();
+    let mut list = CommandList::new();
+    for (to, id) in wanted.iter().enumerate() {
+        let from = ids.iter().position(|other| other == id)?;
+        if from != to {
+            ids.remove(from);
+            ids.insert(to, *id);
+            list.push(b"moveid", &[&id.to_string(), &to.to_string()])
+                .ok()?;
+        }
+    }
+    (!list.is_empty()).then_some(list)
+}
+
+// moves that gather the marked tracks, in queue order, right in front of
+// `before` or at the end for none; the positions are worked out on a copy
+// of the queue so each one is right for the server at that point
+pub fn gather(queue: &[u32], marked: &[u32], before: Option<u32>) -> Option<CommandList> {
+    let position = |order: &[u32], id| order.iter().position(|other| *other == id);
+
+    let mut order = queue.to_vec();
+    let mut list = CommandList::new();
+    let mut previous = None;
+    for &id in marked {
+        let from = position(&order, id)?;
+        order.remove(from);
+        let to = match previous {
+            Some(previous) => position(&order, previous)? + 1,
+            None => before
+                .and_then(|before| position(&order, before))
+                .unwrap_or(order.len()),
+        };
+        order.insert(to, id);
+        previous = Some(id);
+
+        if from != to {
+            list.push(b"moveid", &[&id.to_string(), &to.to_string()])
+                .ok()?;
+        }
+    }
+    (!list.is_empty()).then_some(list)
+}
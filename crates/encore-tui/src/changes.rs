@@ -0,0 +1,104 @@
+use std::collections::VecDeque;
+
+use encore_core::attribution::{Change, Origin};
+use encore_mpd::Subsystem;
+use ratatui::{
+    layout::{Constraint, Flex, Layout},
+    text::{Line, Span},
+    widgets::{Block, Clear, List, ListItem, ListState},
+    Frame,
+};
+
+use crate::{theme::Theme, trash::ago};
+
+// what the server reported as changed lately, newest first, and whether we
+// or another client changed it
+#[derive(Default)]
+pub struct Changes {
+    state: ListState,
+}
+
+impl Changes {
+    pub fn step(&mut self, down: bool, len: usize) {
+        let last = len.saturating_sub(1);
+        let at = self.state.selected().unwrap_or(0);
+        let at = if down {
+            (at + 1).min(last)
+        } else {
+            at.saturating_sub(1)
+        };
+        self.state.select(Some(at));
+    }
+}
+
+// what a toast or the list calls it, for the changes worth telling
+pub fn name(subsystem: Subsystem) -> Option<&'static str> {
+    Some(match subsystem {
+        Subsystem::Player => "Playback",
+        Subsystem::Playlist => "The queue",
+        Subsystem::Options => "The playback options",
+        Subsystem::Mixer => "The volume",
+        Subsystem::StoredPlaylist => "The playlists",
+        Subsystem::Output => "The outputs",
+        Subsystem::Partition => "The partitions",
+        Subsystem::Sticker => "The ratings",
+        _ => return None,
+    })
+}
+
+// newest first
+pub fn shown(history: &VecDeque<Change>) -> impl Iterator<Item = (&Change, &'static str)> {
+    history
+        .iter()
+        .rev()
+        .filter_map(|change| Some((change, name(change.subsystem)?)))
+}
+
+pub fn by(origin: Origin) -> &'static str {
+    match origin {
+        Origin::Local => "encore",
+        Origin::External => "another client",
+    }
+}
+
+pub fn draw(changes: &mut Changes, history: &VecDeque<Change>, theme: &Theme, frame: &mut Frame) {
+    let items: Vec<_> = shown(history)
+        .map(|(change, name)| {
+            let style = match change.origin {
+                Origin::Local => theme.muted,
+                Origin::External => theme.key,
+            };
+            ListItem::new(Line::from(vec![
+                Span::raw(format!("  {name}")),
+                Span::styled(format!("  by {}", by(change.origin)), style),
+                Span::styled(format!("  {}", ago(change.at)), theme.muted),
+            ]))
+        })
+        .collect();
+
+    let area = frame.area();
+    let height = items.len().max(1) as u16 + 2;
+    let [popup] = Layout::horizontal([Constraint::Max(56)])
+        .flex(Flex::Center)
+        .areas(area);
+    let [popup] = Layout::vertical([Constraint::Max(height)])
+        .flex(Flex::Center)
+        .areas(popup);
+
+    let block = Block::bordered()
+        .border_style(theme.border)
+        .title(" Changes ")
+        .title_bottom(" Esc closes ");
+
+    let empty = items.is_empty();
+    let list = List::new(items)
+        .block(block)
+        .highlight_style(theme.selected);
+
+    frame.render_widget(Clear, popup);
+    frame.render_stateful_widget(list, popup, &mut changes.state);
+    if empty {
+        let none = Line::styled("Nothing changed yet", theme.muted);
+        frame.render_widget(none, Block::bordered().inner(popup));
+    }
+}
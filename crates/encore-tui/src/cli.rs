@@ -0,0 +1,92 @@
+#[cfg(feature = "http-remote")]
+use std::net::SocketAddr;
+use std::{env, path::PathBuf};
+
+use clap::Parser;
+use encore_core::{
+    config::{self, Config},
+    connection::{Endpoint, Target},
+};
+use eyre::{eyre, Result};
+
+/// A terminal client for MPD
+#[derive(Parser)]
+#[command(name = "encore", version)]
+pub struct Cli {
+    /// MPD host as `[password@]host[:port]`, `ssh://[user@]host[/port]` or
+    /// `mpds://host[:port]`; overrides MPD_HOST
+    #[arg(long, value_parser = parse_endpoint)]
+    host: Option<Endpoint>,
+
+    /// MPD port; overrides MPD_PORT
+    #[arg(long, short)]
+    port: Option<u16>,
+
+    /// Path to the MPD unix socket, or `@name` for an abstract one
+    #[arg(long, short, conflicts_with_all = ["host", "port"])]
+    socket: Option<PathBuf>,
+
+    /// Config file to use instead of the default location
+    #[arg(long, short)]
+    config: Option<PathBuf>,
+
+    /// Serve the HTTP remote control on this address; overrides
+    /// `remote_control.addr`
+    #[cfg(feature = "http-remote")]
+    #[arg(long, env = "ENCORE_HTTP_ADDR")]
+    http: Option<SocketAddr>,
+}
+
+impl Cli {
+    pub fn config(&self) -> Result<Config> {
+        Config::load(self.config.clone())
+    }
+
+    // command line over MPD_HOST/MPD_PORT over the config file
+    pub fn endpoint(&self, config: &Config) -> Result<Endpoint> {
+        let fallback = match &config.host {
+            Some(host) => {
+                Endpoint::parse(host).ok_or_else(|| eyre!("invalid host `{host}` in config"))?
+            }
+            None => Endpoint::default(),
+        };
+
+        let mut endpoint = match &self.host {
+            Some(host) => host.clone(),
+            None => Endpoint::from_env(fallback),
+        };
+
+        if let Some(socket) = &self.socket {
+            endpoint.target = Target::Unix(socket.clone());
+        }
+
+        if let (Some(port), Some(p)) = (endpoint.target.port_mut(), self.port) {
+            *port = p;
+        }
+
+        Ok(endpoint)
+    }
+
+    // command line and environment over the config file
+    #[cfg(feature = "http-remote")]
+    pub fn remote_control(&self, config: &Config) -> config::RemoteControl {
+        let mut remote = config.remote_control.clone();
+        remote.addr = self.http.or(remote.addr);
+        remote.token = env::var("ENCORE_HTTP_TOKEN").ok().or(remote.token);
+        remote
+    }
+
+    // there is no flag without the feature, but an address in the
+    // environment still asks for the remote control
+    #[cfg(not(feature = "http-remote"))]
+    pub fn remote_control(&self, config: &Config) -> config::RemoteControl {
+        let mut remote = config.remote_control.clone();
+        let addr = env::var("ENCORE_HTTP_ADDR").ok();
+        remote.addr = addr.and_then(|addr| addr.parse().ok()).or(remote.addr);
+        remote
+    }
+}
+
+fn parse_endpoint(s: &str) -> Result<Endpoint, String> {
+    Endpoint::parse(s).ok_or_else(|| format!("invalid host `{s}`"))
+}
@@ -0,0 +1,253 @@
+use std::time::Duration;
+
+use encore_mpd::{ConsumeMode, SingleMode};
+
+const NAMES: [&str; 16] = [
+    "clear", "connect", "help", "history", "next", "pause", "play", "previous", "quit", "save",
+    "seek", "set", "sort", "stop", "trash", "volume",
+];
+
+const FLAGS: [&str; 4] = ["consume", "random", "repeat", "single"];
+
+const SORTS: [&str; 7] = [
+    "album", "artist", "date", "random", "rating", "title", "track",
+];
+
+// what can be typed after `:`, for everything with or without a key
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    Clear,
+    // a name from `[hosts]` or anything the command line takes
+    Connect(String),
+    Help,
+    // what changed lately, and who changed it
+    History,
+    Next,
+    Pause,
+    Play,
+    Previous,
+    Quit,
+    Save(String),
+    Seek(Seek),
+    Set(Setting),
+    Sort(Sort),
+    Stop,
+    // the deleted playlists, to bring one back
+    Trash,
+    Volume(Volume),
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Seek {
+    To(Duration),
+    Forward(Duration),
+    Back(Duration),
+}
+
+// what the queue is ordered by; songs without the tag go last and ties
+// keep their order
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Sort {
+    // then album, disc and track
+    Artist,
+    // then disc and track
+    Album,
+    // disc, then track
+    Track,
+    Title,
+    // oldest first, then album, disc and track
+    Date,
+    Random,
+    // best first
+    Rating,
+}
+
+impl Sort {
+    // in the order the sort menu shows them
+    pub const ALL: [Sort; 7] = [
+        Sort::Artist,
+        Sort::Album,
+        Sort::Track,
+        Sort::Title,
+        Sort::Date,
+        Sort::Random,
+        Sort::Rating,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Sort::Artist => "artist",
+            Sort::Album => "album",
+            Sort::Track => "track",
+            Sort::Title => "title",
+            Sort::Date => "date",
+            Sort::Random => "random",
+            Sort::Rating => "rating",
+        }
+    }
+
+    // what picks it in the sort menu
+    pub fn key(self) -> char {
+        match self {
+            Sort::Artist => 'a',
+            Sort::Album => 'l',
+            Sort::Track => 'n',
+            Sort::Title => 't',
+            Sort::Date => 'd',
+            Sort::Random => 'r',
+            Sort::Rating => '*',
+        }
+    }
+
+    pub fn by_key(key: char) -> Option<Self> {
+        Self::ALL.into_iter().find(|sort| sort.key() == key)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Setting {
+    Repeat(bool),
+    Random(bool),
+    Single(SingleMode),
+    Consume(ConsumeMode),
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Volume {
+    To(u8),
+    Up(u8),
+    Down(u8),
+}
+
+impl Command {
+    pub fn parse(line: &str) -> Result<Self, String> {
+        let line = line.trim();
+        let (name, rest) = line.split_once(' ').unwrap_or((line, ""));
+        let rest = rest.trim();
+        let none = |command| match rest {
+            "" => Ok(command),
+            _ => Err(format!("{name} takes nothing more")),
+        };
+        let some = || match rest {
+            "" => Err(format!("{name} needs an argument")),
+            rest => Ok(rest.to_string()),
+        };
+
+        match name {
+            "clear" => none(Self::Clear),
+            "connect" => some().map(Self::Connect),
+            "help" => none(Self::Help),
+            "next" => none(Self::Next),
+            "pause" => none(Self::Pause),
+            "history" => none(Self::History),
+            "play" => none(Self::Play),
+            "previous" | "prev" => none(Self::Previous),
+            "quit" | "q" => none(Self::Quit),
+            "save" => some().map(Self::Save),
+            "seek" => seek(&some()?).map(Self::Seek),
+            "set" => setting(&some()?).map(Self::Set),
+            "sort" => {
+                let by = some()?;
+                let sort = Sort::ALL.into_iter().find(|sort| sort.name() == by);
+                sort.map(Self::Sort)
+                    .ok_or_else(|| format!("Cannot sort by `{by}`"))
+            }
+            "stop" => none(Self::Stop),
+            "trash" => none(Self::Trash),
+            "volume" | "vol" => volume(&some()?).map(Self::Volume),
+            _ => Err(format!("Unknown command `{name}`")),
+        }
+    }
+}
+
+// `1:23`, `83`, or `+10` and `-0:10` from where playback is
+fn seek(s: &str) -> Result<Seek, String> {
+    let time = |s: &str| {
+        let (minutes, seconds) = s.split_once(':').unwrap_or(("0", s));
+        let minutes: u64 = minutes.parse().ok()?;
+        let seconds: u64 = seconds.parse().ok().filter(|s| *s < 60 || minutes == 0)?;
+        Some(Duration::from_secs(minutes * 60 + seconds))
+    };
+    let seek = match (s.strip_prefix('+'), s.strip_prefix('-')) {
+        (Some(s), _) => time(s).map(Seek::Forward),
+        (_, Some(s)) => time(s).map(Seek::Back),
+        _ => time(s).map(Seek::To),
+    };
+    seek.ok_or_else(|| format!("Invalid time `{s}`, expected e.g. 1:23 or +10"))
+}
+
+fn setting(s: &str) -> Result<Setting, String> {
+    let (flag, value) = s.split_once(' ').unwrap_or((s, ""));
+    let on = match value.trim() {
+        "on" | "1" => Some(true),
+        "off" | "0" => Some(false),
+        _ => None,
+    };
+    let setting = match (flag, on, value.trim()) {
+        ("repeat", Some(on), _) => Setting::Repeat(on),
+        ("random", Some(on), _) => Setting::Random(on),
+        ("single", Some(on), _) => {
+            Setting::Single(if on { SingleMode::On } else { SingleMode::Off })
+        }
+        ("single", None, "oneshot") => Setting::Single(SingleMode::Oneshot),
+        ("consume", Some(on), _) => Setting::Consume(if on {
+            ConsumeMode::On
+        } else {
+            ConsumeMode::Off
+        }),
+        ("consume", None, "oneshot") => Setting::Consume(ConsumeMode::Oneshot),
+        _ if !FLAGS.contains(&flag) => return Err(format!("Unknown option `{flag}`")),
+        _ => return Err(format!("Invalid value for {flag}, expected on or off")),
+    };
+    Ok(setting)
+}
+
+fn volume(s: &str) -> Result<Volume, String> {
+    let volume = |s: &str| s.parse().ok().filter(|volume| *volume <= 100);
+    let volume = match (s.strip_prefix('+'), s.strip_prefix('-')) {
+        (Some(s), _) => volume(s).map(Volume::Up),
+        (_, Some(s)) => volume(s).map(Volume::Down),
+        _ => volume(s).map(Volume::To),
+    };
+    volume.ok_or_else(|| format!("Invalid volume `{s}`, expected 0 to 100"))
+}
+
+// what the last word could be, going by the words before it
+pub fn candidates<'a>(line: &str, hosts: &'a [&'a str]) -> Vec<&'a str> {
+    let words: Vec<_> = line.trim_start().split(' ').collect();
+    let choices: &[&str] = match words[..] {
+        [_] => &NAMES,
+        ["set", _] => &FLAGS,
+        ["set", "repeat" | "random", _] => &["off", "on"],
+        ["set", "single" | "consume", _] => &["off", "on", "oneshot"],
+        ["sort", _] => &SORTS,
+        ["connect", _] => hosts,
+        _ => &[],
+    };
+    let last = words.last().copied().unwrap_or_default();
+    let choices = choices.iter().copied();
+    choices.filter(|choice| choice.starts_with(last)).collect()
+}
+
+// the last word as far as every candidate agrees, and a space after it
+// once only one is left
+pub fn complete(line: &mut String, hosts: &[&str]) {
+    let candidates = candidates(line, hosts);
+    let Some(first) = candidates.first() else {
+        return;
+    };
+
+    let common = candidates.iter().fold(first.len(), |len, candidate| {
+        let same = first
+            .bytes()
+            .zip(candidate.bytes())
+            .take_while(|(a, b)| a == b);
+        len.min(same.count())
+    });
+    let start = line.rfind(' ').map_or(0, |space| space + 1);
+    line.truncate(start);
+    line.push_str(&first[..common]);
+    if candidates.len() == 1 {
+        line.push(' ');
+    }
+}
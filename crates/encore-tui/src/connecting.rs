@@ -0,0 +1,49 @@
+use encore_core::connection::Endpoint;
+use ratatui::{
+    layout::{Constraint, Flex, Layout},
+    text::{Line, Text},
+    widgets::Paragraph,
+    Frame,
+};
+
+use crate::theme::Theme;
+
+#[derive(Default)]
+pub struct Connecting {
+    pub reconnecting: bool,
+    pub attempts: u32,
+    pub error: Option<String>,
+    pub input: Option<String>,
+}
+
+pub fn draw(state: &Connecting, endpoint: &Endpoint, theme: &Theme, frame: &mut Frame) {
+    let mut text = Text::default();
+    let verb = if state.reconnecting {
+        "Reconnecting"
+    } else {
+        "Connecting"
+    };
+    text.push_line(Line::styled(format!("{verb} to {endpoint}…"), theme.title));
+
+    if let Some(error) = &state.error {
+        let error = match state.attempts {
+            0 => error.clone(),
+            attempts => format!("attempt {attempts}: {error}"),
+        };
+        text.push_line(Line::styled(error, theme.error));
+    }
+
+    text.push_line("");
+    match &state.input {
+        Some(input) => {
+            text.push_line(format!("target: {input}█"));
+            text.push_line(Line::styled("enter: connect   esc: cancel", theme.muted));
+        }
+        None => text.push_line(Line::styled("e: edit target   q: quit", theme.muted)),
+    }
+
+    let [area] = Layout::vertical([Constraint::Length(text.height() as u16)])
+        .flex(Flex::Center)
+        .areas(frame.area());
+    frame.render_widget(Paragraph::new(text).centered(), area);
+}
@@ -0,0 +1,75 @@
+#[cfg(feature = "remote-art")]
+use std::sync::Arc;
+
+use encore_core::config::Config;
+#[cfg(feature = "remote-art")]
+use encore_core::{art::Fallback, cache::Cache};
+use encore_mpd::CurrentSong;
+use image::RgbaImage;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+
+#[cfg(feature = "remote-art")]
+use crate::art;
+
+// covers from elsewhere for the songs the server has none for, each sent
+// back with the song it is for
+pub struct Covers {
+    #[cfg(feature = "remote-art")]
+    fallback: Option<Arc<Fallback>>,
+    #[cfg_attr(not(feature = "remote-art"), allow(dead_code))]
+    tx: UnboundedSender<(String, Option<RgbaImage>)>,
+    rx: UnboundedReceiver<(String, Option<RgbaImage>)>,
+}
+
+impl Covers {
+    #[cfg(feature = "remote-art")]
+    pub fn start(config: &Config) -> Self {
+        let fallback = config.remote_art.clone().map(|source| {
+            let cache = Cache::open(&config.cache);
+            Arc::new(Fallback::new(source, cache))
+        });
+        let (tx, rx) = unbounded_channel();
+        Self { fallback, tx, rx }
+    }
+
+    #[cfg(not(feature = "remote-art"))]
+    pub fn start(_: &Config) -> Self {
+        let (tx, rx) = unbounded_channel();
+        Self { tx, rx }
+    }
+
+    // false when there is nowhere to look or too little to look for
+    #[cfg(feature = "remote-art")]
+    pub fn fetch(&self, song: &CurrentSong) -> bool {
+        let Some(fallback) = self.fallback.clone() else {
+            return false;
+        };
+        let artist = song.album_artist.as_ref().or(song.artist.as_ref());
+        let (Some(artist), Some(album)) = (artist.cloned(), song.album.clone()) else {
+            return false;
+        };
+
+        let (uri, tx) = (song.uri.clone(), self.tx.clone());
+        tokio::spawn(async move {
+            // a failed download is as good as no cover
+            let cover = match fallback.fetch(&artist, &album).await {
+                Ok(Some(data)) => tokio::task::spawn_blocking(move || art::decode(&data))
+                    .await
+                    .ok()
+                    .flatten(),
+                _ => None,
+            };
+            let _ = tx.send((uri, cover));
+        });
+        true
+    }
+
+    #[cfg(not(feature = "remote-art"))]
+    pub fn fetch(&self, _: &CurrentSong) -> bool {
+        false
+    }
+
+    pub async fn recv(&mut self) -> Option<(String, Option<RgbaImage>)> {
+        self.rx.recv().await
+    }
+}
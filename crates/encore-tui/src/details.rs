@@ -0,0 +1,175 @@
+use std::path::{Path, PathBuf};
+
+use encore_core::tags;
+use encore_mpd::CommandList;
+use eyre::Result;
+use ratatui::{
+    layout::{Constraint, Flex, Layout},
+    text::{Line, Span},
+    widgets::{Block, Clear, List, ListItem, ListState},
+    Frame,
+};
+
+use crate::theme::Theme;
+
+// the tags most worth fixing, offered even when the file has none of them
+const COMMON: [&str; 8] = [
+    "TITLE",
+    "ARTIST",
+    "ALBUM",
+    "ALBUMARTIST",
+    "DATE",
+    "GENRE",
+    "TRACKNUMBER",
+    "DISCNUMBER",
+];
+
+const NAME: usize = 14;
+
+// every tag of one song as the server reads them from the file, editable
+// when the file is on this machine too
+pub struct Details {
+    pub uri: String,
+    tags: Vec<(String, String)>,
+    path: Result<PathBuf, &'static str>,
+    state: ListState,
+    // the new value of the tag under the cursor
+    editing: Option<String>,
+}
+
+impl Details {
+    pub fn new(uri: String, comments: Vec<(String, String)>, music_dir: Option<&Path>) -> Self {
+        let path = match music_dir {
+            None => Err("set music_dir to edit"),
+            Some(dir) => {
+                let path = dir.join(&uri);
+                match (tags::is_editable(&path), path.is_file()) {
+                    (false, _) => Err("only FLAC files can be edited"),
+                    (true, false) => Err("not in music_dir"),
+                    (true, true) => Ok(path),
+                }
+            }
+        };
+
+        let mut tags = comments;
+        if path.is_ok() {
+            for name in COMMON {
+                if !tags.iter().any(|(tag, _)| tag.eq_ignore_ascii_case(name)) {
+                    tags.push((name.into(), String::new()));
+                }
+            }
+        }
+
+        Self {
+            uri,
+            tags,
+            path,
+            state: ListState::default().with_selected(Some(0)),
+            editing: None,
+        }
+    }
+
+    pub fn step(&mut self, down: bool) {
+        let last = self.tags.len().saturating_sub(1);
+        let at = self.state.selected().unwrap_or(0);
+        let at = if down {
+            (at + 1).min(last)
+        } else {
+            at.saturating_sub(1)
+        };
+        self.state.select(Some(at));
+    }
+
+    pub fn edit(&mut self) {
+        if self.path.is_ok() {
+            let at = self.state.selected().unwrap_or(0);
+            self.editing = self.tags.get(at).map(|(_, value)| value.clone());
+        }
+    }
+
+    pub fn input(&mut self) -> Option<&mut String> {
+        self.editing.as_mut()
+    }
+
+    pub fn cancel(&mut self) {
+        self.editing = None;
+    }
+
+    // writes the edited tag into the file, which leaves the tag's name and
+    // what tells the server to read the file again
+    pub fn save(&mut self) -> Result<Option<(String, CommandList)>> {
+        let (Some(value), Ok(path)) = (self.editing.take(), &self.path) else {
+            return Ok(None);
+        };
+        let at = self.state.selected().unwrap_or(0);
+        let Some((name, _)) = self.tags.get(at) else {
+            return Ok(None);
+        };
+
+        let name = name.clone();
+        let value = value.trim();
+        tags::write(path, &name, value)?;
+
+        // the other values of a tag with many are gone too
+        let same = |tag: &str| tag.eq_ignore_ascii_case(&name);
+        let first = self
+            .tags
+            .iter()
+            .position(|(tag, _)| same(tag))
+            .unwrap_or(at);
+        self.tags[first].1 = value.into();
+        let rest = self.tags.split_off(first + 1);
+        self.tags
+            .extend(rest.into_iter().filter(|(tag, _)| !same(tag)));
+        self.state.select(Some(first));
+
+        let mut update = CommandList::new();
+        update.push(b"update", &[&self.uri])?;
+        Ok(Some((name, update)))
+    }
+}
+
+pub fn draw(details: &mut Details, theme: &Theme, frame: &mut Frame) {
+    let area = frame.area();
+    let height = details.tags.len().max(1) as u16 + 2;
+    let [popup] = Layout::horizontal([Constraint::Max(72)])
+        .flex(Flex::Center)
+        .areas(area);
+    let [popup] = Layout::vertical([Constraint::Max(height)])
+        .flex(Flex::Center)
+        .areas(popup);
+
+    let name = details.uri.rsplit('/').next().unwrap_or(&details.uri);
+    let hint = match (&details.path, &details.editing) {
+        (_, Some(_)) => " Enter saves, Esc cancels ".to_string(),
+        (Ok(_), None) => " Enter edits, Esc closes ".to_string(),
+        (Err(why), None) => format!(" Read only, {why} "),
+    };
+    let block = Block::bordered()
+        .border_style(theme.border)
+        .title(format!(" {name} "))
+        .title_bottom(hint);
+
+    let at = details.state.selected();
+    let items: Vec<_> = details
+        .tags
+        .iter()
+        .enumerate()
+        .map(|(i, (name, value))| {
+            let value = match &details.editing {
+                Some(input) if at == Some(i) => format!("{input}█"),
+                _ => value.clone(),
+            };
+            ListItem::new(Line::from(vec![
+                Span::styled(format!("{name:<NAME$} "), theme.key),
+                Span::raw(value),
+            ]))
+        })
+        .collect();
+    let list = List::new(items)
+        .block(block)
+        .highlight_style(theme.selected);
+
+    frame.render_widget(Clear, popup);
+    frame.render_stateful_widget(list, popup, &mut details.state);
+}
@@ -0,0 +1,100 @@
+use std::{fs::OpenOptions, path::Path};
+
+use encore_core::events::{Event, EventLog};
+#[cfg(not(unix))]
+use eyre::eyre;
+use eyre::Result;
+use tokio::{
+    fs::File,
+    sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender},
+};
+
+// playback as JSON lines in a file, or to whoever is connected to a socket,
+// for whoever wants to tally it; written by a task of its own so a slow disk
+// or reader never holds up the screen
+#[derive(Default)]
+pub struct Log {
+    tx: Option<UnboundedSender<Event>>,
+}
+
+impl Log {
+    // opened right away, so a bad path is told at start; `unix:` before it
+    // serves a socket there instead
+    pub fn start(path: Option<&Path>) -> Result<Self> {
+        let Some(path) = path else {
+            return Ok(Self::default());
+        };
+
+        let (tx, rx) = unbounded_channel();
+        match path.to_str().and_then(|path| path.strip_prefix("unix:")) {
+            Some(socket) => serve(Path::new(socket), rx)?,
+            None => {
+                let file = OpenOptions::new().create(true).append(true).open(path)?;
+                tokio::spawn(write(EventLog::new(File::from_std(file)), rx));
+            }
+        }
+        Ok(Self { tx: Some(tx) })
+    }
+
+    pub fn emit(&self, events: impl IntoIterator<Item = Event>) {
+        if let Some(tx) = &self.tx {
+            for event in events {
+                let _ = tx.send(event);
+            }
+        }
+    }
+}
+
+async fn write(mut log: EventLog<File>, mut rx: UnboundedReceiver<Event>) {
+    while let Some(event) = rx.recv().await {
+        if log.emit(&event).await.is_err() {
+            break;
+        }
+    }
+}
+
+// every event goes to every subscriber connected at the time; one that hung
+// up or stopped reading is dropped
+#[cfg(unix)]
+fn serve(path: &Path, mut rx: UnboundedReceiver<Event>) -> Result<()> {
+    use std::{fs, os::unix::fs::FileTypeExt, time::Duration};
+
+    use encore_core::events;
+    use tokio::{io::AsyncWriteExt, net::UnixListener, select, time::timeout};
+
+    // a socket left by an earlier run is in the way, anything else is kept
+    if fs::symlink_metadata(path).is_ok_and(|meta| meta.file_type().is_socket()) {
+        fs::remove_file(path)?;
+    }
+    let listener = UnixListener::bind(path)?;
+
+    tokio::spawn(async move {
+        let mut subscribers = Vec::new();
+        loop {
+            select! {
+                Ok((stream, _)) = listener.accept() => subscribers.push(stream),
+                event = rx.recv() => {
+                    let Some(event) = event else { break };
+                    let Ok(line) = events::line(&event) else { continue };
+                    let mut kept = Vec::with_capacity(subscribers.len());
+                    for mut stream in subscribers {
+                        let sent = timeout(Duration::from_secs(1), stream.write_all(&line)).await;
+                        if matches!(sent, Ok(Ok(()))) {
+                            kept.push(stream);
+                        }
+                    }
+                    subscribers = kept;
+                }
+            }
+        }
+    });
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn serve(path: &Path, _: UnboundedReceiver<Event>) -> Result<()> {
+    Err(eyre!(
+        "Can't serve {}, unix sockets need a unix system",
+        path.display()
+    ))
+}
@@ -0,0 +1,43 @@
+use encore_mpd::Entry;
+use ratatui::{layout::Rect, text::Line, Frame};
+
+use crate::{
+    menu::{self, Stack},
+    queue,
+    theme::Theme,
+};
+
+// the music directory as the server lays it out, one directory per level
+pub type Files = Stack<Entry>;
+
+fn label<'a>(entry: &'a Entry, theme: &Theme) -> Line<'a> {
+    match entry {
+        Entry::Directory(path) => Line::styled(format!("{}/", name(path)), theme.directory),
+        Entry::File(track) => {
+            let time = queue::duration(track.time);
+            Line::from(format!("{}  {time}", queue::title(track)))
+        }
+        Entry::Playlist(path) => Line::styled(name(path), theme.playlist),
+    }
+}
+
+fn name(path: &str) -> &str {
+    path.rsplit('/').next().unwrap_or(path)
+}
+
+pub fn draw(files: &mut Files, theme: &Theme, frame: &mut Frame, area: Rect) {
+    let empty = match files.levels.len() {
+        0 | 1 => "The music directory is empty",
+        _ => "This directory is empty",
+    };
+
+    let crumbs = files.path().map(|entry| match entry {
+        Entry::Directory(path) | Entry::Playlist(path) => name(path).to_string(),
+        Entry::File(track) => queue::title(track).to_string(),
+    });
+    let area = menu::breadcrumbs("Files", crumbs.collect(), theme, frame, area);
+    if let Some(level) = files.current() {
+        let label = |entry| label(entry, theme);
+        menu::draw(level, label, empty, theme, frame, area);
+    }
+}
@@ -0,0 +1,64 @@
+use encore_core::transition::{self, Transition};
+use encore_mpd::{ConsumeMode, SingleMode, Status, Track};
+use ratatui::{
+    layout::{Constraint, Layout, Rect},
+    text::{Line, Span},
+    Frame,
+};
+
+use crate::theme::Theme;
+
+// "repeat random single¹ consume¹"
+pub const WIDTH: u16 = 31;
+
+// with how the next song follows on below
+pub fn draw(status: &Status, queue: &[Track], theme: &Theme, frame: &mut Frame, area: Rect) {
+    let [area, below] = Layout::vertical([Constraint::Length(1); 2]).areas(area);
+    let single = match status.single {
+        SingleMode::Off => flag("single", false, theme),
+        SingleMode::On => flag("single", true, theme),
+        SingleMode::Oneshot => oneshot("single¹", theme),
+    };
+    let consume = match status.consume {
+        ConsumeMode::Off => flag("consume", false, theme),
+        ConsumeMode::On => flag("consume", true, theme),
+        ConsumeMode::Oneshot => oneshot("consume¹", theme),
+    };
+
+    let line = Line::from(vec![
+        flag("repeat", status.repeat, theme),
+        Span::raw(" "),
+        flag("random", status.random, theme),
+        Span::raw(" "),
+        single,
+        Span::raw(" "),
+        consume,
+    ]);
+    frame.render_widget(line.right_aligned(), area);
+
+    // the rest of the album would play in any order, or not at all
+    let next = match transition::upcoming(status, queue) {
+        _ if transition::breaks_album(status, queue) => {
+            Line::styled("album out of order", theme.error)
+        }
+        Some(Transition::Gapless) => Line::styled("next gapless", theme.muted),
+        Some(Transition::Crossfade(secs)) => {
+            Line::styled(format!("next crossfades {secs}s"), theme.muted)
+        }
+        Some(Transition::Gap) => Line::styled("next after a gap", theme.muted),
+        None => return,
+    };
+    frame.render_widget(next.right_aligned(), below);
+}
+
+fn flag(name: &'static str, on: bool, theme: &Theme) -> Span<'static> {
+    match on {
+        true => Span::styled(name, theme.flag_on),
+        false => Span::styled(name, theme.flag_off),
+    }
+}
+
+// applies to the current song only, then turns itself off
+fn oneshot(name: &'static str, theme: &Theme) -> Span<'static> {
+    Span::styled(name, theme.flag_oneshot)
+}
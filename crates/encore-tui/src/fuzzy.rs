@@ -0,0 +1,57 @@
+// how well `pattern` matches `text` with its characters in order but not
+// necessarily next to each other, ignoring case and the spaces typed; none
+// when a character is missing. Runs and word starts count for more
+pub fn score(pattern: &str, text: &str) -> Option<u32> {
+    let mut pattern = pattern
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .peekable();
+
+    let mut score = 0;
+    let mut run = 0;
+    let mut previous = ' ';
+    for c in text.chars().flat_map(char::to_lowercase) {
+        let Some(&wanted) = pattern.peek() else {
+            break;
+        };
+
+        if c == wanted {
+            pattern.next();
+            run += 1;
+            score += run;
+            if !previous.is_alphanumeric() {
+                score += 2;
+            }
+        } else {
+            run = 0;
+        }
+        previous = c;
+    }
+
+    pattern.peek().is_none().then_some(score)
+}
+
+// the chars of `text` the pattern matched, taking the first fit for each
+// like `score` does
+pub fn positions(pattern: &str, text: &str) -> Option<Vec<usize>> {
+    let mut pattern = pattern
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .peekable();
+
+    let mut positions = Vec::new();
+    for (i, c) in text.chars().enumerate() {
+        for c in c.to_lowercase() {
+            if pattern.peek() == Some(&c) {
+                pattern.next();
+                if positions.last() != Some(&i) {
+                    positions.push(i);
+                }
+            }
+        }
+    }
+
+    pattern.peek().is_none().then_some(positions)
+}
@@ -0,0 +1,79 @@
+use encore_mpd::{AudioFormat, CurrentSong, Sample, Status};
+use ratatui::{
+    layout::{Constraint, Layout, Rect},
+    text::Line,
+    Frame,
+};
+
+use crate::theme::Theme;
+
+pub const HEIGHT: u16 = 2;
+
+pub fn draw(
+    song: Option<&CurrentSong>,
+    status: Option<&Status>,
+    theme: &Theme,
+    frame: &mut Frame,
+    area: Rect,
+) {
+    let Some(song) = song else {
+        frame.render_widget(Line::styled("Not playing", theme.muted), area);
+        return;
+    };
+
+    let file = song.uri.rsplit('/').next().unwrap_or(&song.uri);
+    let title = song
+        .title
+        .as_deref()
+        .or(song.name.as_deref())
+        .unwrap_or(file);
+
+    let detail = [song.artist.as_deref(), song.album.as_deref()]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>()
+        .join(" — ");
+
+    // how it sounds goes beside the artist, under the flags
+    let quality = status.map_or_else(String::new, |status| quality(status, &song.uri));
+    let width = quality.chars().count() as u16;
+    let [top, bottom] = Layout::vertical([Constraint::Length(1); 2]).areas(area);
+    let [bottom, right] = Layout::horizontal([Constraint::Fill(1), Constraint::Length(width)])
+        .spacing(if width > 0 { 2 } else { 0 })
+        .areas(bottom);
+
+    frame.render_widget(Line::styled(title, theme.title), top);
+    frame.render_widget(Line::styled(detail, theme.muted), bottom);
+    frame.render_widget(Line::styled(quality, theme.muted), right);
+}
+
+// like "44.1kHz/16bit/2ch • 987kbps FLAC", with whatever is known
+fn quality(status: &Status, uri: &str) -> String {
+    // streams have no extension worth showing
+    let codec = match uri.contains("://") {
+        true => None,
+        false => uri
+            .rsplit_once('.')
+            .map(|(_, ext)| ext.to_uppercase())
+            .filter(|ext| !ext.contains('/')),
+    };
+    let rate = status.bitrate.map(|kbps| format!("{kbps}kbps"));
+    let rate = [rate, codec].into_iter().flatten().collect::<Vec<_>>();
+
+    let parts = [status.audio.map(format), Some(rate.join(" "))];
+    let parts = parts.into_iter().flatten().filter(|part| !part.is_empty());
+    parts.collect::<Vec<_>>().join(" • ")
+}
+
+fn format(audio: AudioFormat) -> String {
+    let channels = audio.channels;
+    match audio.sample {
+        Sample::Dsd => format!("DSD{}/{channels}ch", audio.sample_rate / 44100),
+        Sample::Float => format!("{}/float/{channels}ch", khz(audio.sample_rate)),
+        Sample::Bits(bits) => format!("{}/{bits}bit/{channels}ch", khz(audio.sample_rate)),
+    }
+}
+
+fn khz(hz: u32) -> String {
+    format!("{}kHz", f64::from(hz) / 1000.0)
+}
@@ -0,0 +1,183 @@
+use ratatui::{
+    layout::{Constraint, Flex, Layout},
+    text::{Line, Span, Text},
+    widgets::{Block, Clear, Paragraph},
+    Frame,
+};
+
+use crate::{tabs::Tab, theme::Theme};
+
+const COLUMN: u16 = 38;
+
+const GLOBAL: &[(&str, &str)] = &[
+    ("q Esc", "quit"),
+    ("1-6", "switch tab"),
+    ("Tab S-Tab", "next, previous tab"),
+    ("+ -", "volume up, down"),
+    ("r z", "toggle repeat, random"),
+    ("y R", "cycle single, consume"),
+    ("e", "edit target (offline)"),
+    ("o", "jump to the playing song"),
+    (":", "run a command, Tab completes"),
+    ("*", "rate marked, or the playing song"),
+    ("i", "tags of the selected song"),
+    ("w", "flag to listen later, or unflag"),
+    ("W", "the songs to listen to later"),
+    ("F7", "partitions"),
+    ("F8", "audio outputs"),
+    ("v", "toggle the visualizer"),
+    ("b", "waveform or spectrum"),
+    ("[ ]", "shrink, grow the visualizer"),
+    ("c", "toggle the cover"),
+    ("< >", "shrink, grow the cover"),
+    ("|", "cover beside or above"),
+    ("?", "this help"),
+    ("F12", "performance overlay"),
+];
+
+const LISTS: &[(&str, &str)] = &[
+    ("↑ ↓ k j", "move"),
+    ("PgUp PgDn", "move a page"),
+    ("C-u C-d", "move half a page"),
+    ("Home End", "first, last"),
+    ("gg G", "first, last"),
+    ("H M L", "top, middle, bottom row"),
+    ("Space", "mark and move on"),
+    ("V", "mark a range"),
+    ("Esc", "drop the marks"),
+];
+
+const QUEUE: &[(&str, &str)] = &[
+    ("Enter", "play"),
+    ("d Delete", "remove marked"),
+    ("m", "move marked to cursor"),
+    ("K J C-↑ C-↓", "move the track up, down"),
+    ("p", "add marked to a playlist"),
+    ("u", "add a stream URL"),
+    ("s", "sort by a tag"),
+    ("f", "follow the playing song"),
+    ("/", "filter, fuzzily"),
+    ("n N", "next, previous match"),
+    ("Esc", "drop the filter"),
+    ("← →", "seek back, forward"),
+    ("S-← S-→", "seek a longer step"),
+];
+
+const LIBRARY: &[(&str, &str)] = &[
+    ("Enter →", "open, or play a track"),
+    ("← Backspace", "back up"),
+    ("~", "back to the top"),
+    ("a", "add marked to the queue"),
+    ("p", "add marked to a playlist"),
+    ("S-← S-→", "seek back, forward"),
+];
+
+const FILES: &[(&str, &str)] = &[
+    ("Enter →", "open, play or load"),
+    ("← Backspace", "back up"),
+    ("~", "back to the top"),
+    ("a", "add marked, recursively"),
+    ("p", "add marked to a playlist"),
+    ("S-← S-→", "seek back, forward"),
+];
+
+const PLAYLISTS: &[(&str, &str)] = &[
+    ("Enter →", "view, or play a track"),
+    ("← Backspace", "back up"),
+    ("~", "back to the top"),
+    ("a", "append marked to queue"),
+    ("l", "replace the queue"),
+    ("d", "delete, to the trash"),
+    ("T", "the trash, to restore"),
+    ("s", "save the queue"),
+    ("S-← S-→", "seek back, forward"),
+];
+
+const SEARCH: &[(&str, &str)] = &[
+    ("tag:text", "search one tag only"),
+    ("Enter", "keep as chip, or results"),
+    ("Backspace", "take the last chip back"),
+    ("Esc ↓", "go to the results"),
+    ("/", "back to typing"),
+    ("Enter", "play"),
+    ("a", "add marked to the queue"),
+    ("A", "add every match"),
+    ("p", "add marked to a playlist"),
+    ("← →", "seek back, forward"),
+    ("S-← S-→", "seek a longer step"),
+];
+
+const LYRICS: &[(&str, &str)] = &[
+    ("↑ ↓ k j", "scroll plain lyrics"),
+    ("PgUp PgDn", "scroll a page"),
+    ("← →", "seek back, forward"),
+    ("S-← S-→", "seek a longer step"),
+];
+
+// what works in the open tab first, small screens cut off the rest
+fn sections(tab: Tab) -> [(&'static str, &'static [(&'static str, &'static str)]); 3] {
+    let keys = match tab {
+        Tab::Queue => QUEUE,
+        Tab::Library => LIBRARY,
+        Tab::Files => FILES,
+        Tab::Playlists => PLAYLISTS,
+        Tab::Search => SEARCH,
+        Tab::Lyrics => LYRICS,
+    };
+    [
+        (tab.title(), keys),
+        ("Everywhere", GLOBAL),
+        ("Lists", LISTS),
+    ]
+}
+
+pub fn draw(tab: Tab, theme: &Theme, frame: &mut Frame) {
+    let area = frame.area();
+    let columns = (area.width.saturating_sub(2) / COLUMN).max(1);
+    let rows = area.height.saturating_sub(2) as usize;
+
+    // sections flow down a column and on to the next one once it is full,
+    // whatever does not fit is cut off
+    let mut texts = vec![Text::default()];
+    for (title, keys) in sections(tab) {
+        let text = texts.last_mut().unwrap();
+        let full = !text.lines.is_empty() && text.lines.len() + keys.len() + 2 > rows;
+        if full && texts.len() < columns as usize {
+            texts.push(Text::default());
+        }
+
+        let text = texts.last_mut().unwrap();
+        if !text.lines.is_empty() {
+            text.push_line("");
+        }
+        text.push_line(Line::styled(title, theme.title));
+        for (key, action) in keys {
+            text.push_line(Line::from(vec![
+                Span::styled(format!("{key:>11}  "), theme.key),
+                Span::raw(*action),
+            ]));
+        }
+    }
+
+    let height = texts.iter().map(|text| text.lines.len()).max().unwrap_or(0) as u16;
+    let width = texts.len() as u16 * COLUMN;
+    let [popup] = Layout::horizontal([Constraint::Length(width + 2)])
+        .flex(Flex::Center)
+        .areas(area);
+    let [popup] = Layout::vertical([Constraint::Length(height + 2)])
+        .flex(Flex::Center)
+        .areas(popup);
+
+    let block = Block::bordered()
+        .border_style(theme.border)
+        .title(" Keys ")
+        .title_bottom(" Esc closes, Tab flips tabs ");
+    let inner = block.inner(popup);
+    frame.render_widget(Clear, popup);
+    frame.render_widget(block, popup);
+
+    let areas = Layout::horizontal(vec![Constraint::Length(COLUMN); texts.len()]).split(inner);
+    for (text, area) in texts.into_iter().zip(areas.iter()) {
+        frame.render_widget(Paragraph::new(text), *area);
+    }
+}
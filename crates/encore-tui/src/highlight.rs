@@ -0,0 +1,48 @@
+use ratatui::{style::Style, text::Span};
+
+// `text` with the chars at `hits`, sorted, standing out
+pub fn spans<'a>(text: &'a str, hits: &[usize], style: Style, matched: Style) -> Vec<Span<'a>> {
+    let styled = |text, hit| match hit {
+        true => Span::styled(text, style.patch(matched)),
+        false => Span::styled(text, style),
+    };
+
+    let mut spans = Vec::new();
+    let (mut start, mut hit) = (0, false);
+    for (i, (at, _)) in text.char_indices().enumerate() {
+        let now = hits.binary_search(&i).is_ok();
+        if now != hit {
+            if at > start {
+                spans.push(styled(&text[start..at], hit));
+            }
+            (start, hit) = (at, now);
+        }
+    }
+    if start < text.len() {
+        spans.push(styled(&text[start..], hit));
+    }
+    spans
+}
+
+// every char of every place `needle` is in `text`, ignoring case
+pub fn find(text: &str, needle: &str) -> Vec<usize> {
+    let lower = |c: char| c.to_lowercase().next().unwrap_or(c);
+    let text: Vec<_> = text.chars().map(lower).collect();
+    let needle: Vec<_> = needle.chars().map(lower).collect();
+    if needle.is_empty() {
+        return Vec::new();
+    }
+
+    let mut hits = Vec::new();
+    let mut i = 0;
+    while i + needle.len() <= text.len() {
+        match text[i..].starts_with(&needle) {
+            true => {
+                hits.extend(i..i + needle.len());
+                i += needle.len();
+            }
+            false => i += 1,
+        }
+    }
+    hits
+}
@@ -0,0 +1,147 @@
+use encore_mpd::Count;
+use ratatui::{
+    layout::{Constraint, Layout, Rect},
+    text::{Line, Span},
+    widgets::{Block, Paragraph, Wrap},
+    Frame,
+};
+
+use crate::{queue, theme::Theme};
+
+// the panel only comes up when the list keeps enough room beside it
+const MIN_WIDTH: u16 = 80;
+const WIDTH: u16 = 32;
+const NAME: usize = 8;
+
+// what the library holds of one artist or album
+#[derive(Debug)]
+pub struct Stats {
+    albums: usize,
+    tracks: u32,
+    playtime: u64,
+    years: Vec<String>,
+    genres: Vec<String>,
+}
+
+impl Stats {
+    // `counts` are grouped by album for an artist and a single one for an
+    // album, `dates` and `genres` listed for the same songs
+    pub fn new(counts: Vec<Count>, dates: Vec<String>, genres: Vec<String>) -> Self {
+        let mut years: Vec<String> = dates
+            .iter()
+            .filter_map(|date| date.get(..4))
+            .filter(|year| year.bytes().all(|b| b.is_ascii_digit()))
+            .map(String::from)
+            .collect();
+        years.sort();
+        years.dedup();
+
+        Self {
+            albums: counts.iter().filter(|count| count.group.is_some()).count(),
+            tracks: counts.iter().map(|count| count.songs).sum(),
+            playtime: counts.iter().map(|count| u64::from(count.playtime)).sum(),
+            years,
+            genres: genres
+                .into_iter()
+                .filter(|genre| !genre.is_empty())
+                .collect(),
+        }
+    }
+}
+
+// numbers on the artist or album under the library's cursor, counted once
+// the cursor comes to rest on one
+#[derive(Default)]
+pub struct Info {
+    // the filter they were counted for
+    filter: Option<String>,
+    stats: Option<Stats>,
+    // whether the panel had room as of the last draw
+    room: bool,
+}
+
+impl Info {
+    // the filter to count, once for every selection while the panel shows
+    pub fn wanted(&mut self, filter: Option<String>) -> Option<String> {
+        if !self.room || filter.is_none() || filter == self.filter {
+            return None;
+        }
+
+        self.stats = None;
+        self.filter.clone_from(&filter);
+        filter
+    }
+
+    // late answers for a selection since left are dropped
+    pub fn set(&mut self, filter: String, stats: Stats) {
+        if self.filter.as_ref() == Some(&filter) {
+            self.stats = Some(stats);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.filter = None;
+        self.stats = None;
+    }
+
+    // the screen the library would be drawn on, measured on every tab so
+    // the first count is not held up until the library was drawn once
+    pub fn fit(&mut self, area: Rect) {
+        self.room = area.width >= MIN_WIDTH;
+    }
+
+    // the list's part of `area` and the panel's, which is empty when there
+    // is nothing to show or no room for it
+    pub fn split(&mut self, area: Rect, selected: bool) -> (Rect, Rect) {
+        self.fit(area);
+        let width = if selected && self.room { WIDTH } else { 0 };
+        let [list, panel] = Layout::horizontal([Constraint::Fill(1), Constraint::Length(width)])
+            .spacing(1)
+            .areas(area);
+        (list, panel)
+    }
+}
+
+pub fn draw(info: &Info, title: String, theme: &Theme, frame: &mut Frame, area: Rect) {
+    if area.is_empty() {
+        return;
+    }
+
+    let block = Block::bordered()
+        .border_style(theme.border)
+        .title(format!(" {title} "));
+    let Some(stats) = &info.stats else {
+        let counting = Paragraph::new(Line::styled("Counting…", theme.muted)).block(block);
+        frame.render_widget(counting, area);
+        return;
+    };
+
+    let years = match (stats.years.first(), stats.years.last()) {
+        (Some(first), Some(last)) if first != last => format!("{first}–{last}"),
+        (Some(year), _) => year.clone(),
+        _ => "unknown".into(),
+    };
+    let genres = match stats.genres.is_empty() {
+        true => "unknown".into(),
+        false => stats.genres.join(", "),
+    };
+
+    let row = |name: &str, value: String| {
+        Line::from(vec![
+            Span::styled(format!("{name:<NAME$}"), theme.key),
+            Span::raw(value),
+        ])
+    };
+    let mut rows = Vec::new();
+    if stats.albums > 0 {
+        rows.push(row("Albums", stats.albums.to_string()));
+    }
+    rows.extend([
+        row("Tracks", stats.tracks.to_string()),
+        row("Length", queue::length(stats.playtime)),
+        row("Years", years),
+        row("Genres", genres),
+    ]);
+    let rows = Paragraph::new(rows).block(block).wrap(Wrap { trim: true });
+    frame.render_widget(rows, area);
+}
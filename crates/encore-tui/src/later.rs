@@ -0,0 +1,92 @@
+use ratatui::{
+    layout::{Constraint, Flex, Layout},
+    text::Line,
+    widgets::{Block, Clear, List, ListItem, ListState},
+    Frame,
+};
+
+use crate::theme::Theme;
+
+// the songs flagged to listen to later, the longest waiting first; read
+// again whenever the server's stickers change
+pub struct Later {
+    songs: Vec<String>,
+    state: ListState,
+    // the server told of a change since the list was read
+    stale: bool,
+}
+
+impl Later {
+    pub fn new(songs: Vec<String>) -> Self {
+        Self {
+            songs,
+            state: ListState::default().with_selected(Some(0)),
+            stale: false,
+        }
+    }
+
+    pub fn set(&mut self, songs: Vec<String>) {
+        self.songs = songs;
+        self.stale = false;
+        let last = self.songs.len().saturating_sub(1);
+        let at = self.state.selected().unwrap_or(0).min(last);
+        self.state.select(Some(at));
+    }
+
+    pub fn changed(&mut self) {
+        self.stale = true;
+    }
+
+    // true once per change
+    pub fn wanted(&mut self) -> bool {
+        std::mem::take(&mut self.stale)
+    }
+
+    pub fn selected(&self) -> Option<&String> {
+        self.songs.get(self.state.selected()?)
+    }
+
+    pub fn step(&mut self, down: bool) {
+        let last = self.songs.len().saturating_sub(1);
+        let at = self.state.selected().unwrap_or(0);
+        let at = if down {
+            (at + 1).min(last)
+        } else {
+            at.saturating_sub(1)
+        };
+        self.state.select(Some(at));
+    }
+}
+
+pub fn draw(later: &mut Later, theme: &Theme, frame: &mut Frame) {
+    let area = frame.area();
+    let height = later.songs.len().max(1) as u16 + 2;
+    let [popup] = Layout::horizontal([Constraint::Max(56)])
+        .flex(Flex::Center)
+        .areas(area);
+    let [popup] = Layout::vertical([Constraint::Max(height)])
+        .flex(Flex::Center)
+        .areas(popup);
+
+    let block = Block::bordered()
+        .border_style(theme.border)
+        .title(" Listen later ")
+        .title_bottom(" Enter queues, d unflags, Esc closes ");
+
+    let items: Vec<_> = later
+        .songs
+        .iter()
+        .map(|uri| ListItem::new(format!("  {uri}")))
+        .collect();
+    let empty = items.is_empty();
+    let list = List::new(items)
+        .block(block)
+        .highlight_style(theme.selected);
+
+    frame.render_widget(Clear, popup);
+    frame.render_stateful_widget(list, popup, &mut later.state);
+    if empty {
+        let none = Line::styled("Nothing is flagged, w flags a song", theme.muted);
+        frame.render_widget(none, Block::bordered().inner(popup));
+    }
+}
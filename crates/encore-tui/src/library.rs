@@ -0,0 +1,87 @@
+use encore_core::filter::{Chip, Query};
+use encore_mpd::Track;
+use ratatui::{layout::Rect, text::Line, Frame};
+
+use crate::{
+    info::{self, Info},
+    menu::{self, Stack},
+    queue,
+    theme::Theme,
+};
+
+#[derive(Clone, Debug)]
+pub enum Node {
+    Artist(String),
+    Album { artist: String, album: String },
+    Track(Box<Track>),
+}
+
+// artists, then their albums, then the tracks on one
+pub type Library = Stack<Node>;
+
+impl Node {
+    // what to list below this node, as a filter expression
+    pub fn filter(&self) -> Option<String> {
+        let equals = |tag: &str, value: &str| Chip::Equals {
+            tag: tag.into(),
+            value: value.into(),
+        };
+
+        let chips = match self {
+            Node::Artist(artist) => vec![equals("artist", artist)],
+            Node::Album { artist, album } => {
+                vec![equals("artist", artist), equals("album", album)]
+            }
+            Node::Track(_) => return None,
+        };
+        Query { chips }.compile()
+    }
+
+    fn crumb(&self) -> String {
+        let or_unknown = |value: &str, unknown: &str| match value {
+            "" => unknown.to_string(),
+            value => value.to_string(),
+        };
+
+        match self {
+            Node::Artist(artist) => or_unknown(artist, "Unknown artist"),
+            Node::Album { album, .. } => or_unknown(album, "Unknown album"),
+            Node::Track(track) => queue::title(track).to_string(),
+        }
+    }
+
+    fn label(&self, theme: &Theme) -> Line<'_> {
+        let or_unknown = |value: &str, unknown| match value {
+            "" => Line::styled(unknown, theme.muted),
+            value => Line::from(value.to_string()),
+        };
+
+        match self {
+            Node::Artist(artist) => or_unknown(artist, "Unknown artist"),
+            Node::Album { album, .. } => or_unknown(album, "Unknown album"),
+            Node::Track(track) => {
+                let time = queue::duration(track.time);
+                Line::from(format!("{}  {time}", queue::title(track)))
+            }
+        }
+    }
+}
+
+pub fn draw(library: &mut Library, info: &mut Info, theme: &Theme, frame: &mut Frame, area: Rect) {
+    let empty = match library.levels.len() {
+        0 | 1 => "The library is empty",
+        _ => "Nothing here",
+    };
+
+    let crumbs = library.path().map(Node::crumb).collect();
+    let area = menu::breadcrumbs("Library", crumbs, theme, frame, area);
+    // artists and albums have their numbers beside the list
+    let selected = library.selected().filter(|node| node.filter().is_some());
+    let title = selected.map(Node::crumb).unwrap_or_default();
+    let (area, panel) = info.split(area, selected.is_some());
+    info::draw(info, title, theme, frame, panel);
+
+    if let Some(level) = library.current() {
+        menu::draw(level, |node| node.label(theme), empty, theme, frame, area);
+    }
+}
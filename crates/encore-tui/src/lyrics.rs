@@ -0,0 +1,122 @@
+use std::{path::PathBuf, time::Duration};
+
+use encore_core::lyrics;
+use encore_mpd::CurrentSong;
+use ratatui::{
+    layout::{Constraint, Flex, Layout, Rect},
+    text::Line,
+    widgets::Paragraph,
+    Frame,
+};
+
+use crate::{nav::Motion, theme::Theme};
+
+// lyrics of the playing song, read from files on this machine
+#[derive(Default)]
+pub struct Lyrics {
+    music_dir: Option<PathBuf>,
+    lyrics_dir: Option<PathBuf>,
+    // the song they were looked up for
+    song: Option<String>,
+    found: Option<lyrics::Lyrics>,
+    // plain lyrics do not move on their own, so they scroll by hand
+    top: usize,
+    height: usize,
+}
+
+impl Lyrics {
+    pub fn new(music_dir: Option<PathBuf>, lyrics_dir: Option<PathBuf>) -> Self {
+        Self {
+            music_dir,
+            lyrics_dir,
+            ..Self::default()
+        }
+    }
+
+    pub fn set_song(&mut self, song: Option<&CurrentSong>) {
+        if self.song.as_deref() == song.map(|song| song.uri.as_str()) {
+            return;
+        }
+
+        self.song = song.map(|song| song.uri.clone());
+        self.top = 0;
+        self.found = song.and_then(|song| {
+            let candidates = lyrics::candidates(
+                &song.uri,
+                song.artist.as_deref(),
+                song.title.as_deref(),
+                self.music_dir.as_deref(),
+                self.lyrics_dir.as_deref(),
+            );
+            lyrics::load(&candidates).map(|(_, found)| found)
+        });
+    }
+
+    // looked up again, as the files changed
+    pub fn reload(&mut self, song: Option<&CurrentSong>) {
+        self.song = None;
+        self.set_song(song);
+    }
+
+    // whether they move along with playback
+    pub fn is_synced(&self) -> bool {
+        self.found.as_ref().is_some_and(lyrics::Lyrics::is_synced)
+    }
+
+    pub fn scroll(&mut self, motion: Motion) {
+        let Some(found) = self.found.as_ref().filter(|_| !self.is_synced()) else {
+            return;
+        };
+
+        let last = found.lines.len().saturating_sub(self.height);
+        let height = self.height.max(1) as isize;
+        self.top = match motion {
+            Motion::Lines(n) => self.top.saturating_add_signed(n),
+            Motion::Pages(n) => self.top.saturating_add_signed(n * height),
+            Motion::HalfPages(n) => self.top.saturating_add_signed(n * (height / 2).max(1)),
+            Motion::Top => 0,
+            Motion::Bottom => last,
+            _ => self.top,
+        }
+        .min(last);
+    }
+}
+
+pub fn draw(lyrics: &mut Lyrics, elapsed: Duration, theme: &Theme, frame: &mut Frame, area: Rect) {
+    lyrics.height = area.height.into();
+
+    let found = lyrics
+        .found
+        .as_ref()
+        .filter(|found| !found.lines.is_empty());
+    let Some(found) = found else {
+        let empty = match (&lyrics.song, &lyrics.music_dir, &lyrics.lyrics_dir) {
+            (None, ..) => "Nothing is playing",
+            (_, None, None) => "Set music_dir or lyrics_dir to find lyrics",
+            _ => "No lyrics for this song",
+        };
+        let [area] = Layout::vertical([Constraint::Length(1)])
+            .flex(Flex::Center)
+            .areas(area);
+        frame.render_widget(Line::styled(empty, theme.muted).centered(), area);
+        return;
+    };
+
+    // the line being sung stays in the middle, what is past is dimmed
+    let current = found.current(elapsed);
+    let top = match current {
+        Some(current) => current.saturating_sub(area.height as usize / 2),
+        None if found.is_synced() => 0,
+        None => lyrics.top,
+    };
+    let lines = found.lines.iter().enumerate().skip(top);
+    let lines = lines.take(area.height.into()).map(|(row, line)| {
+        let text = Line::from(line.text.as_str()).centered();
+        match current {
+            Some(current) if row == current => text.style(theme.current),
+            Some(current) if row < current => text.style(theme.muted),
+            _ => text,
+        }
+    });
+    frame.render_widget(Paragraph::new(lines.collect::<Vec<_>>()), area);
+}
@@ -0,0 +1,68 @@
+use app::App;
+use clap::Parser;
+use cli::Cli;
+use eyre::Result;
+use remote::Remote;
+use theme::Theme;
+
+mod app;
+mod art;
+mod bulk;
+mod changes;
+mod cli;
+mod command;
+mod connecting;
+mod covers;
+mod details;
+mod event_log;
+mod files;
+mod flags;
+mod fuzzy;
+mod header;
+mod help;
+mod highlight;
+mod info;
+mod later;
+mod library;
+mod lyrics;
+mod marks;
+mod menu;
+mod nav;
+mod notification;
+mod outputs;
+mod panes;
+mod partitions;
+mod perf;
+mod playlists;
+mod power;
+mod progress;
+mod prompt;
+mod queue;
+mod ratings;
+mod remote;
+mod schedule;
+mod search;
+mod status_bar;
+mod tabs;
+mod theme;
+mod toast;
+mod trash;
+mod ueberzug;
+mod visualizer;
+mod volume;
+mod watcher;
+
+#[tokio::main(flavor = "multi_thread", worker_threads = 4)]
+async fn main() -> Result<()> {
+    color_eyre::install()?;
+    let cli = Cli::parse();
+    let config = cli.config()?;
+    let endpoint = cli.endpoint(&config)?;
+    let theme = Theme::load(&config)?;
+    let remote = Remote::start(cli.remote_control(&config)).await?;
+    let terminal = ratatui::init();
+    let app = App::new(endpoint, &config, theme, remote);
+    let app_result = app.run(terminal).await;
+    ratatui::restore();
+    app_result
+}
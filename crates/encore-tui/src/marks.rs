@@ -0,0 +1,59 @@
+use std::collections::BTreeSet;
+
+// rows picked for a bulk operation: Space marks one at a time, V marks
+// everything between where it was pressed and the cursor
+#[derive(Default)]
+pub struct Marks {
+    rows: BTreeSet<usize>,
+    anchor: Option<usize>,
+}
+
+impl Marks {
+    pub fn toggle(&mut self, row: usize) {
+        if !self.rows.remove(&row) {
+            self.rows.insert(row);
+        }
+    }
+
+    // the first press starts a range at `cursor`, the second one keeps it
+    pub fn visual(&mut self, cursor: usize) {
+        match self.anchor.take() {
+            Some(anchor) => self.rows.extend(range(anchor, cursor)),
+            None => self.anchor = Some(cursor),
+        }
+    }
+
+    pub fn contains(&self, row: usize, cursor: Option<usize>) -> bool {
+        let in_range = match (self.anchor, cursor) {
+            (Some(anchor), Some(cursor)) => range(anchor, cursor).contains(&row),
+            _ => false,
+        };
+        in_range || self.rows.contains(&row)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty() && self.anchor.is_none()
+    }
+
+    pub fn clear(&mut self) {
+        self.rows.clear();
+        self.anchor = None;
+    }
+
+    // the marked rows in order, or just the cursor when nothing is marked;
+    // an operation uses the marks up
+    pub fn take(&mut self, cursor: Option<usize>) -> Vec<usize> {
+        if let (Some(anchor), Some(cursor)) = (self.anchor.take(), cursor) {
+            self.rows.extend(range(anchor, cursor));
+        }
+
+        match std::mem::take(&mut self.rows) {
+            rows if rows.is_empty() => cursor.into_iter().collect(),
+            rows => rows.into_iter().collect(),
+        }
+    }
+}
+
+fn range(a: usize, b: usize) -> std::ops::RangeInclusive<usize> {
+    a.min(b)..=a.max(b)
+}
@@ -0,0 +1,223 @@
+use ratatui::{
+    layout::{Constraint, Flex, Layout, Rect},
+    text::{Line, Span},
+    widgets::{List, ListItem, ListState},
+    Frame,
+};
+
+use crate::{marks::Marks, nav::Rows, theme::Theme};
+
+// a single column of entries with a cursor, shared by the browsing tabs
+pub struct Menu<T> {
+    pub items: Vec<T>,
+    pub state: ListState,
+    pub marks: Marks,
+    height: usize,
+}
+
+impl<T> Default for Menu<T> {
+    fn default() -> Self {
+        Self {
+            items: Vec::new(),
+            state: ListState::default(),
+            marks: Marks::default(),
+            height: 0,
+        }
+    }
+}
+
+impl<T> Menu<T> {
+    pub fn new(items: Vec<T>) -> Self {
+        let mut menu = Self::default();
+        menu.set(items);
+        menu
+    }
+
+    pub fn set(&mut self, items: Vec<T>) {
+        self.items = items;
+        self.marks.clear();
+        let selected = match self.state.selected() {
+            _ if self.items.is_empty() => None,
+            Some(selected) => Some(selected.min(self.items.len() - 1)),
+            None => Some(0),
+        };
+        self.state.select(selected);
+    }
+
+    pub fn selected(&self) -> Option<&T> {
+        self.items.get(self.state.selected()?)
+    }
+
+    // the marked items, or the one under the cursor
+    pub fn take_marked(&mut self) -> Vec<&T> {
+        let rows = self.marks.take(self.state.selected());
+        rows.into_iter()
+            .filter_map(|row| self.items.get(row))
+            .collect()
+    }
+}
+
+impl<T> Rows for Menu<T> {
+    fn rows(&self) -> usize {
+        self.items.len()
+    }
+
+    fn cursor(&self) -> Option<usize> {
+        self.state.selected()
+    }
+
+    fn set_cursor(&mut self, row: usize) {
+        self.state.select(Some(row));
+    }
+
+    fn view(&self) -> (usize, usize) {
+        (self.state.offset(), self.height)
+    }
+
+    fn marks(&mut self) -> &mut Marks {
+        &mut self.marks
+    }
+}
+
+// menus opened one below the other while walking down a tree; each level is
+// only listed once it is opened
+pub struct Stack<T> {
+    pub levels: Vec<Menu<T>>,
+    requested: bool,
+}
+
+impl<T> Default for Stack<T> {
+    fn default() -> Self {
+        Self {
+            levels: Vec::new(),
+            requested: false,
+        }
+    }
+}
+
+impl<T> Stack<T> {
+    // true the first time the top level is needed after a clear
+    pub fn wants_top(&mut self) -> bool {
+        let wants = self.levels.is_empty() && !self.requested;
+        self.requested = true;
+        wants
+    }
+
+    pub fn open(&mut self, items: Vec<T>) {
+        self.levels.push(Menu::new(items));
+    }
+
+    // the top level stays put
+    pub fn close(&mut self) {
+        if self.levels.len() > 1 {
+            self.levels.pop();
+        }
+    }
+
+    // back to the top level in one go
+    pub fn close_all(&mut self) {
+        self.levels.truncate(1);
+    }
+
+    pub fn clear(&mut self) {
+        self.levels.clear();
+        self.requested = false;
+    }
+
+    pub fn current(&mut self) -> Option<&mut Menu<T>> {
+        self.levels.last_mut()
+    }
+
+    pub fn selected(&self) -> Option<&T> {
+        self.levels.last()?.selected()
+    }
+
+    // the way down to the open level: what was opened on each level above it
+    pub fn path(&self) -> impl Iterator<Item = &T> {
+        let above = self.levels.len().saturating_sub(1);
+        self.levels[..above].iter().filter_map(Menu::selected)
+    }
+}
+
+const SEPARATOR: &str = " ▸ ";
+
+// where a stack is, from `root` down to the open level, on the first row of
+// `area`; the levels nearest the top give way first when it does not fit.
+// leaves the rest of `area`
+pub fn breadcrumbs(
+    root: &str,
+    crumbs: Vec<String>,
+    theme: &Theme,
+    frame: &mut Frame,
+    area: Rect,
+) -> Rect {
+    let [area, rest] = Layout::vertical([Constraint::Length(1), Constraint::Fill(1)]).areas(area);
+    let width = |crumbs: &[String]| {
+        let names: usize = crumbs.iter().map(|crumb| crumb.chars().count()).sum();
+        root.chars().count() + names + crumbs.len() * SEPARATOR.chars().count()
+    };
+
+    // the separator and an ellipsis stand in for what was left out
+    let fits = |skipped: usize| {
+        let ellipsis = if skipped > 0 {
+            SEPARATOR.chars().count() + 1
+        } else {
+            0
+        };
+        width(&crumbs[skipped..]) + ellipsis <= area.width.into()
+    };
+    let mut skipped = 0;
+    while skipped < crumbs.len() && !fits(skipped) {
+        skipped += 1;
+    }
+
+    let last = crumbs.len();
+    let mut spans = vec![Span::styled(root, theme.muted)];
+    if skipped > 0 {
+        spans.push(Span::styled(SEPARATOR, theme.muted));
+        spans.push(Span::styled("…", theme.muted));
+    }
+    for (i, crumb) in crumbs.into_iter().enumerate().skip(skipped) {
+        let style = match i + 1 == last {
+            true => theme.title,
+            false => theme.muted,
+        };
+        spans.push(Span::styled(SEPARATOR, theme.muted));
+        spans.push(Span::styled(crumb, style));
+    }
+    if last == 0 {
+        spans[0].style = theme.title;
+    }
+    frame.render_widget(Line::from(spans), area);
+    rest
+}
+
+pub fn draw<'a, T>(
+    menu: &'a mut Menu<T>,
+    label: impl Fn(&'a T) -> Line<'a>,
+    empty: &'static str,
+    theme: &Theme,
+    frame: &mut Frame,
+    area: Rect,
+) {
+    menu.height = area.height.into();
+    if menu.items.is_empty() {
+        let [area] = Layout::vertical([Constraint::Length(1)])
+            .flex(Flex::Center)
+            .areas(area);
+        frame.render_widget(Line::styled(empty, theme.muted).centered(), area);
+        return;
+    }
+
+    let cursor = menu.state.selected();
+    let marks = &menu.marks;
+    let items = menu.items.iter().enumerate().map(|(row, item)| {
+        let item = ListItem::new(label(item));
+        match marks.contains(row, cursor) {
+            true => item.style(theme.marked),
+            false => item,
+        }
+    });
+    let list = List::new(items).highlight_style(theme.selected);
+    frame.render_stateful_widget(list, area, &mut menu.state);
+}
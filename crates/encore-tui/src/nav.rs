@@ -0,0 +1,106 @@
+use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use crate::marks::Marks;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Motion {
+    Lines(isize),
+    Pages(isize),
+    HalfPages(isize),
+    Top,
+    Bottom,
+    // the first, middle and last row on screen
+    ScreenTop,
+    ScreenMiddle,
+    ScreenBottom,
+}
+
+// a list with a cursor and marks, as far as moving between rows goes
+pub trait Rows {
+    fn rows(&self) -> usize;
+    fn cursor(&self) -> Option<usize>;
+    fn set_cursor(&mut self, row: usize);
+    // the first row shown and how many fit, as of the last draw
+    fn view(&self) -> (usize, usize);
+    fn marks(&mut self) -> &mut Marks;
+}
+
+// the arrow keys and their vi counterparts; `g` waits for a second `g`
+#[derive(Default)]
+pub struct Keys {
+    pending_g: bool,
+}
+
+impl Keys {
+    pub fn motion(&mut self, key: &KeyEvent) -> Option<Motion> {
+        let pending_g = std::mem::take(&mut self.pending_g);
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+
+        Some(match key.code {
+            KeyCode::Char('d') if ctrl => Motion::HalfPages(1),
+            KeyCode::Char('u') if ctrl => Motion::HalfPages(-1),
+            _ if ctrl => return None,
+            KeyCode::Up | KeyCode::Char('k') => Motion::Lines(-1),
+            KeyCode::Down | KeyCode::Char('j') => Motion::Lines(1),
+            KeyCode::PageUp => Motion::Pages(-1),
+            KeyCode::PageDown => Motion::Pages(1),
+            KeyCode::Home => Motion::Top,
+            KeyCode::Char('g') if pending_g => Motion::Top,
+            KeyCode::Char('g') => {
+                self.pending_g = true;
+                return None;
+            }
+            KeyCode::End | KeyCode::Char('G') => Motion::Bottom,
+            KeyCode::Char('H') => Motion::ScreenTop,
+            KeyCode::Char('M') => Motion::ScreenMiddle,
+            KeyCode::Char('L') => Motion::ScreenBottom,
+            _ => return None,
+        })
+    }
+
+    // a lone `g` still counts as handled so it does not fall through
+    pub fn is_pending(&self) -> bool {
+        self.pending_g
+    }
+}
+
+// moves the cursor, stopping at either end
+pub fn go(list: &mut dyn Rows, motion: Motion) {
+    let Some(cursor) = list.cursor() else {
+        return;
+    };
+
+    let last = list.rows().saturating_sub(1);
+    let (top, height) = list.view();
+    let height = height.max(1);
+    let bottom = (top + height - 1).min(last);
+
+    let row = match motion {
+        Motion::Lines(n) => cursor.saturating_add_signed(n),
+        Motion::Pages(n) => cursor.saturating_add_signed(n * height as isize),
+        Motion::HalfPages(n) => cursor.saturating_add_signed(n * (height as isize / 2).max(1)),
+        Motion::Top => 0,
+        Motion::Bottom => last,
+        Motion::ScreenTop => top,
+        Motion::ScreenMiddle => (top + bottom) / 2,
+        Motion::ScreenBottom => bottom,
+    };
+    list.set_cursor(row.min(last));
+}
+
+// Space marks the row and moves on, V starts or ends a range
+pub fn mark(list: &mut dyn Rows, key: KeyCode) -> bool {
+    let Some(cursor) = list.cursor() else {
+        return matches!(key, KeyCode::Char(' ' | 'V'));
+    };
+
+    match key {
+        KeyCode::Char(' ') => {
+            list.marks().toggle(cursor);
+            go(list, Motion::Lines(1));
+        }
+        KeyCode::Char('V') => list.marks().visual(cursor),
+        _ => return false,
+    }
+    true
+}
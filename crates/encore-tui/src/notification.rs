@@ -0,0 +1,120 @@
+use encore_core::config;
+#[cfg(feature = "notifications")]
+use encore_core::notification::{self, Note, Picture};
+use encore_mpd::CurrentSong;
+use image::RgbaImage;
+#[cfg(feature = "notifications")]
+use image::{imageops::FilterType, DynamicImage};
+#[cfg(feature = "notifications")]
+use tokio::sync::mpsc::{unbounded_channel, UnboundedSender};
+
+// big enough for any notification server, small enough for the bus
+#[cfg(feature = "notifications")]
+const THUMBNAIL: u32 = 128;
+
+// tells the desktop what started playing, with the cover when there is one
+#[derive(Default)]
+pub struct Notifier {
+    #[cfg(feature = "notifications")]
+    tx: Option<UnboundedSender<Note>>,
+    #[cfg(feature = "notifications")]
+    cover: bool,
+    // the song's note until its cover is in
+    #[cfg(feature = "notifications")]
+    waiting: Option<(String, Note)>,
+}
+
+#[cfg(feature = "notifications")]
+impl Notifier {
+    pub fn start(config: &config::Notifications) -> Self {
+        if !config.enabled {
+            return Self::default();
+        }
+
+        let (tx, rx) = unbounded_channel();
+        tokio::spawn(notification::serve(rx, config.every));
+        Self::sending(tx, config.cover)
+    }
+
+    // notes go to `tx`, which the caller serves
+    pub fn sending(tx: UnboundedSender<Note>, cover: bool) -> Self {
+        Self {
+            tx: Some(tx),
+            cover,
+            waiting: None,
+        }
+    }
+
+    // `cover` when one is on its way for the song
+    pub fn song(&mut self, song: &CurrentSong, cover: bool) {
+        if self.tx.is_none() {
+            return;
+        }
+
+        let summary = song
+            .title
+            .clone()
+            .or_else(|| song.name.clone())
+            .unwrap_or_else(|| song.uri.rsplit('/').next().unwrap_or_default().into());
+        let by = [&song.artist, &song.album].into_iter().flatten();
+        let body = by.map(String::as_str).collect::<Vec<_>>().join(" — ");
+        let note = Note {
+            summary,
+            body,
+            picture: None,
+        };
+
+        self.waiting = None;
+        match cover && self.cover {
+            true => self.waiting = Some((song.uri.clone(), note)),
+            false => self.send(note),
+        }
+    }
+
+    pub fn cover(&mut self, uri: &str, cover: Option<&RgbaImage>) {
+        if self.waiting.as_ref().is_some_and(|(song, _)| song == uri) {
+            if let Some((_, mut note)) = self.waiting.take() {
+                note.picture = cover.map(thumbnail);
+                self.send(note);
+            }
+        }
+    }
+
+    // the cover is not coming after all
+    pub fn flush(&mut self) {
+        if let Some((_, note)) = self.waiting.take() {
+            self.send(note);
+        }
+    }
+
+    fn send(&self, note: Note) {
+        if let Some(tx) = &self.tx {
+            let _ = tx.send(note);
+        }
+    }
+}
+
+#[cfg(feature = "notifications")]
+fn thumbnail(cover: &RgbaImage) -> Picture {
+    let cover = DynamicImage::ImageRgba8(cover.clone());
+    let small = cover.resize(THUMBNAIL, THUMBNAIL, FilterType::Triangle);
+    let small = small.into_rgba8();
+    Picture {
+        width: small.width(),
+        height: small.height(),
+        rgba: small.into_raw(),
+    }
+}
+
+#[cfg(not(feature = "notifications"))]
+impl Notifier {
+    pub fn start(_: &config::Notifications) -> Self {
+        Self::default()
+    }
+
+    pub fn song(&mut self, _: &CurrentSong, _: bool) {}
+
+    pub fn cover(&mut self, _: &str, _: Option<&RgbaImage>) {}
+
+    pub fn flush(&mut self) {}
+}
@@ -0,0 +1,182 @@
+use encore_mpd::{CommandList, Output};
+use ratatui::{
+    layout::{Constraint, Flex, Layout},
+    text::{Line, Span},
+    widgets::{Block, Clear, List, ListItem, ListState},
+    Frame,
+};
+
+use crate::theme::Theme;
+
+// one row per output, then one per attribute it has
+#[derive(Clone, Copy)]
+enum Row {
+    Output(usize),
+    Attribute(usize, usize),
+}
+
+// the server's audio outputs, switched on and off and set up in place;
+// read again whenever the server says they changed
+pub struct Outputs {
+    list: Vec<Output>,
+    state: ListState,
+    // the new value of the attribute under the cursor
+    editing: Option<String>,
+    // the server told of a change since the list was read
+    stale: bool,
+}
+
+impl Outputs {
+    pub fn new(list: Vec<Output>) -> Self {
+        Self {
+            list,
+            state: ListState::default().with_selected(Some(0)),
+            editing: None,
+            stale: false,
+        }
+    }
+
+    // the cursor stays on its row as long as there is one
+    pub fn set(&mut self, list: Vec<Output>) {
+        self.list = list;
+        self.stale = false;
+        let last = self.rows().len().saturating_sub(1);
+        let at = self.state.selected().unwrap_or(0).min(last);
+        self.state.select(Some(at));
+    }
+
+    pub fn changed(&mut self) {
+        self.stale = true;
+    }
+
+    // true once per change
+    pub fn wanted(&mut self) -> bool {
+        std::mem::take(&mut self.stale)
+    }
+
+    fn rows(&self) -> Vec<Row> {
+        let mut rows = Vec::new();
+        for (i, output) in self.list.iter().enumerate() {
+            rows.push(Row::Output(i));
+            rows.extend((0..output.attributes.len()).map(|j| Row::Attribute(i, j)));
+        }
+        rows
+    }
+
+    fn row(&self) -> Option<Row> {
+        self.rows().get(self.state.selected()?).copied()
+    }
+
+    pub fn step(&mut self, down: bool) {
+        let last = self.rows().len().saturating_sub(1);
+        let at = self.state.selected().unwrap_or(0);
+        let at = if down {
+            (at + 1).min(last)
+        } else {
+            at.saturating_sub(1)
+        };
+        self.state.select(Some(at));
+    }
+
+    // switches the output under the cursor, or starts editing the attribute
+    pub fn enter(&mut self) -> Option<CommandList> {
+        match self.row()? {
+            Row::Output(i) => {
+                let mut list = CommandList::new();
+                list.push(b"toggleoutput", &[&self.list[i].id.to_string()])
+                    .ok()?;
+                Some(list)
+            }
+            Row::Attribute(i, j) => {
+                self.editing = Some(self.list[i].attributes[j].1.clone());
+                None
+            }
+        }
+    }
+
+    pub fn input(&mut self) -> Option<&mut String> {
+        self.editing.as_mut()
+    }
+
+    pub fn cancel(&mut self) {
+        self.editing = None;
+    }
+
+    // what sets the edited attribute, and its name
+    pub fn save(&mut self) -> Option<(String, CommandList)> {
+        let value = self.editing.take()?;
+        let Row::Attribute(i, j) = self.row()? else {
+            return None;
+        };
+
+        let output = &self.list[i];
+        let name = output.attributes[j].0.clone();
+        let mut list = CommandList::new();
+        let id = output.id.to_string();
+        list.push(b"outputset", &[&id, &name, value.trim()]).ok()?;
+        Some((name, list))
+    }
+}
+
+pub fn draw(outputs: &mut Outputs, theme: &Theme, frame: &mut Frame) {
+    let area = frame.area();
+    let rows = outputs.rows();
+    let height = rows.len().max(1) as u16 + 2;
+    let [popup] = Layout::horizontal([Constraint::Max(60)])
+        .flex(Flex::Center)
+        .areas(area);
+    let [popup] = Layout::vertical([Constraint::Max(height)])
+        .flex(Flex::Center)
+        .areas(popup);
+
+    let hint = match outputs.editing {
+        Some(_) => " Enter saves, Esc cancels ",
+        None => " Enter switches or edits, Esc closes ",
+    };
+    let block = Block::bordered()
+        .border_style(theme.border)
+        .title(" Outputs ")
+        .title_bottom(hint);
+
+    let at = outputs.state.selected();
+    let items: Vec<_> = rows
+        .iter()
+        .enumerate()
+        .map(|(row, &kind)| match kind {
+            Row::Output(i) => {
+                let output = &outputs.list[i];
+                let (mark, style) = match output.enabled {
+                    true => ("[x]", theme.flag_on),
+                    false => ("[ ]", theme.flag_off),
+                };
+                ListItem::new(Line::from(vec![
+                    Span::styled(mark, style),
+                    Span::raw(format!(" {}", output.name)),
+                    Span::styled(format!("  {}", output.plugin), theme.muted),
+                ]))
+            }
+            Row::Attribute(i, j) => {
+                let (name, value) = &outputs.list[i].attributes[j];
+                let value = match &outputs.editing {
+                    Some(input) if at == Some(row) => format!("{input}█"),
+                    _ => value.clone(),
+                };
+                ListItem::new(Line::from(vec![
+                    Span::styled(format!("    {name} "), theme.key),
+                    Span::raw(value),
+                ]))
+            }
+        })
+        .collect();
+    let empty = items.is_empty();
+    let list = List::new(items)
+        .block(block)
+        .highlight_style(theme.selected);
+
+    frame.render_widget(Clear, popup);
+    frame.render_stateful_widget(list, popup, &mut outputs.state);
+    if empty {
+        let none = Line::styled("The server has no outputs", theme.muted);
+        frame.render_widget(none, Block::bordered().inner(popup));
+    }
+}
@@ -0,0 +1,95 @@
+use encore_core::config::{self, Split};
+use ratatui::layout::{Constraint, Layout, Rect};
+
+// percentage points per resize key press
+const STEP: u16 = 5;
+const SMALLEST: u16 = 10;
+const LARGEST: u16 = 80;
+
+// how the screen below the header is shared between the lists, the cover
+// and the visualizer
+pub struct Panes {
+    pub art: bool,
+    pub split: Split,
+    // percent of the screen, sized to fit the cover until resized
+    art_size: Option<u16>,
+    visualizer_size: u16,
+    // what the cover took as of the last frame, resizing starts from there
+    art_share: u16,
+}
+
+impl Panes {
+    pub fn new(config: &config::Layout) -> Self {
+        Self {
+            art: config.art,
+            split: config.split,
+            art_size: config.art_size.map(|size| size.clamp(SMALLEST, LARGEST)),
+            visualizer_size: config.visualizer_size.clamp(SMALLEST, LARGEST),
+            art_share: 0,
+        }
+    }
+
+    pub fn flip(&mut self) {
+        self.split = match self.split {
+            Split::Horizontal => Split::Vertical,
+            Split::Vertical => Split::Horizontal,
+        };
+    }
+
+    pub fn resize_art(&mut self, grow: bool) {
+        let size = self.art_size.unwrap_or(self.art_share);
+        self.art_size = Some(resize(size, grow));
+    }
+
+    pub fn resize_visualizer(&mut self, grow: bool) {
+        self.visualizer_size = resize(self.visualizer_size, grow);
+    }
+
+    // the lists and the cover's pane, which is empty while hidden or
+    // without a cover to show
+    pub fn split_art(&mut self, area: Rect, cover: bool) -> (Rect, Rect) {
+        let layout = match self.split {
+            Split::Horizontal => Layout::horizontal,
+            Split::Vertical => Layout::vertical,
+        };
+        let pane = match (self.art && cover, self.art_size, self.split) {
+            (false, ..) => Constraint::Length(0),
+            (true, Some(size), _) => Constraint::Percentage(size),
+            // a square cover in cells twice as tall as they are wide
+            (true, None, Split::Horizontal) => {
+                Constraint::Length((area.height * 2).min(area.width / 3))
+            }
+            (true, None, Split::Vertical) => {
+                Constraint::Length((area.width / 2).min(area.height / 3))
+            }
+        };
+        let gap = if pane == Constraint::Length(0) { 0 } else { 1 };
+        let [lists, pane] = layout([Constraint::Fill(1), pane]).spacing(gap).areas(area);
+
+        let (part, whole) = match self.split {
+            Split::Horizontal => (pane.width, area.width),
+            Split::Vertical => (pane.height, area.height),
+        };
+        if part > 0 {
+            self.art_share = (part as u32 * 100 / whole.max(1) as u32) as u16;
+        }
+        (lists, pane)
+    }
+
+    // the lists and the visualizer below them
+    pub fn split_visualizer(&self, area: Rect) -> (Rect, Rect) {
+        let height = (area.height * self.visualizer_size / 100).max(4);
+        let [lists, pane] = Layout::vertical([Constraint::Fill(1), Constraint::Length(height)])
+            .spacing(1)
+            .areas(area);
+        (lists, pane)
+    }
+}
+
+fn resize(size: u16, grow: bool) -> u16 {
+    match grow {
+        true => size.saturating_add(STEP),
+        false => size.saturating_sub(STEP),
+    }
+    .clamp(SMALLEST, LARGEST)
+}
@@ -0,0 +1,125 @@
+use ratatui::{
+    layout::{Constraint, Flex, Layout},
+    text::{Line, Span},
+    widgets::{Block, Clear, List, ListItem, ListState},
+    Frame,
+};
+
+use crate::theme::Theme;
+
+// the server's partitions, each with its own queue and player; read again
+// whenever the server says they changed
+pub struct Partitions {
+    list: Vec<String>,
+    state: ListState,
+    // the name of the one being created
+    naming: Option<String>,
+    // the server told of a change since the list was read
+    stale: bool,
+}
+
+impl Partitions {
+    // the cursor starts on the partition in use
+    pub fn new(list: Vec<String>, current: &str) -> Self {
+        let at = list.iter().position(|name| name == current).unwrap_or(0);
+        Self {
+            list,
+            state: ListState::default().with_selected(Some(at)),
+            naming: None,
+            stale: false,
+        }
+    }
+
+    pub fn set(&mut self, list: Vec<String>) {
+        self.list = list;
+        self.stale = false;
+        let last = self.list.len().saturating_sub(1);
+        let at = self.state.selected().unwrap_or(0).min(last);
+        self.state.select(Some(at));
+    }
+
+    pub fn changed(&mut self) {
+        self.stale = true;
+    }
+
+    // true once per change
+    pub fn wanted(&mut self) -> bool {
+        std::mem::take(&mut self.stale)
+    }
+
+    pub fn selected(&self) -> Option<&String> {
+        self.list.get(self.state.selected()?)
+    }
+
+    pub fn step(&mut self, down: bool) {
+        let last = self.list.len().saturating_sub(1);
+        let at = self.state.selected().unwrap_or(0);
+        let at = if down {
+            (at + 1).min(last)
+        } else {
+            at.saturating_sub(1)
+        };
+        self.state.select(Some(at));
+    }
+
+    pub fn name(&mut self) {
+        self.naming = Some(String::new());
+    }
+
+    pub fn input(&mut self) -> Option<&mut String> {
+        self.naming.as_mut()
+    }
+
+    // the name typed, unless there was none
+    pub fn named(&mut self) -> Option<String> {
+        let name = self.naming.take()?;
+        let name = name.trim();
+        (!name.is_empty()).then(|| name.to_string())
+    }
+
+    pub fn cancel(&mut self) {
+        self.naming = None;
+    }
+}
+
+pub fn draw(partitions: &mut Partitions, current: &str, theme: &Theme, frame: &mut Frame) {
+    let area = frame.area();
+    let rows = partitions.list.len() + usize::from(partitions.naming.is_some());
+    let height = rows.max(1) as u16 + 2;
+    let [popup] = Layout::horizontal([Constraint::Max(48)])
+        .flex(Flex::Center)
+        .areas(area);
+    let [popup] = Layout::vertical([Constraint::Max(height)])
+        .flex(Flex::Center)
+        .areas(popup);
+
+    let hint = match partitions.naming {
+        Some(_) => " Enter creates, Esc cancels ",
+        None => " Enter switches, n new, d deletes ",
+    };
+    let block = Block::bordered()
+        .border_style(theme.border)
+        .title(" Partitions ")
+        .title_bottom(hint);
+
+    let mut items: Vec<_> = partitions
+        .list
+        .iter()
+        .map(|name| match name == current {
+            true => ListItem::new(Line::styled(format!("● {name}"), theme.current)),
+            false => ListItem::new(format!("  {name}")),
+        })
+        .collect();
+    if let Some(name) = &partitions.naming {
+        items.push(ListItem::new(Line::from(vec![
+            Span::styled("  New: ", theme.key),
+            Span::raw(format!("{name}█")),
+        ])));
+    }
+    let list = List::new(items)
+        .block(block)
+        .highlight_style(theme.selected);
+
+    frame.render_widget(Clear, popup);
+    frame.render_stateful_widget(list, popup, &mut partitions.state);
+}
@@ -0,0 +1,33 @@
+use encore_core::perf::Perf;
+use ratatui::{
+    layout::Rect,
+    widgets::{Block, Clear, Paragraph},
+    Frame,
+};
+
+use crate::theme::Theme;
+
+pub fn draw(perf: &Perf, theme: &Theme, frame: &mut Frame) {
+    let area = frame.area();
+    let width = 28.min(area.width);
+    let height = 6.min(area.height);
+    let area = Rect::new(area.right() - width, area.y, width, height);
+
+    let latency = match perf.mpd_latency {
+        Some(latency) => format!("{:.1}ms", latency.as_secs_f64() * 1000.0),
+        None => "-".into(),
+    };
+    let text = format!(
+        "frame   {:.2}ms\nevents  {}/s\nmpd rtt {}\ntasks   {}",
+        perf.frame_time.as_secs_f64() * 1000.0,
+        perf.events_per_sec,
+        latency,
+        perf.pending,
+    );
+
+    frame.render_widget(Clear, area);
+    frame.render_widget(
+        Paragraph::new(text).block(Block::bordered().border_style(theme.border).title("perf")),
+        area,
+    );
+}
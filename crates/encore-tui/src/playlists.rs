@@ -0,0 +1,51 @@
+use encore_mpd::Track;
+use ratatui::{layout::Rect, text::Line, Frame};
+
+use crate::{
+    menu::{self, Stack},
+    queue,
+    theme::Theme,
+};
+
+#[derive(Clone, Debug)]
+pub enum Item {
+    Playlist(String),
+    Track(Box<Track>),
+}
+
+// the saved playlists, then the tracks of the one being viewed
+pub type Playlists = Stack<Item>;
+
+// the playlist under the cursor, or the one being viewed
+pub fn name(playlists: &Playlists) -> Option<&str> {
+    match playlists.levels.first()?.selected()? {
+        Item::Playlist(name) => Some(name),
+        Item::Track(_) => None,
+    }
+}
+
+fn label(item: &Item) -> Line<'_> {
+    match item {
+        Item::Playlist(name) => Line::from(name.as_str()),
+        Item::Track(track) => {
+            let time = queue::duration(track.time);
+            Line::from(format!("{}  {time}", queue::title(track)))
+        }
+    }
+}
+
+pub fn draw(playlists: &mut Playlists, theme: &Theme, frame: &mut Frame, area: Rect) {
+    let empty = match playlists.levels.len() {
+        0 | 1 => "No saved playlists, s saves the queue",
+        _ => "This playlist is empty",
+    };
+
+    let crumbs = playlists.path().map(|item| match item {
+        Item::Playlist(name) => name.clone(),
+        Item::Track(track) => queue::title(track).to_string(),
+    });
+    let area = menu::breadcrumbs("Playlists", crumbs.collect(), theme, frame, area);
+    if let Some(level) = playlists.current() {
+        menu::draw(level, label, empty, theme, frame, area);
+    }
+}
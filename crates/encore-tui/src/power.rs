@@ -0,0 +1,138 @@
+#[cfg(all(unix, feature = "dbus"))]
+use encore_core::power::{IdleInhibitor, SuspendGuard};
+use encore_core::{config, connection::Connection};
+#[cfg(all(unix, feature = "dbus"))]
+use encore_mpd::PlayerState;
+use eyre::Result;
+#[cfg(all(unix, feature = "dbus"))]
+use eyre::WrapErr;
+
+// pauses playback before the system suspends, and plays again on waking;
+// keeps the system awake while playing
+#[derive(Default)]
+pub struct Power {
+    #[cfg(all(unix, feature = "dbus"))]
+    config: config::Power,
+    #[cfg(all(unix, feature = "dbus"))]
+    suspend: Option<SuspendGuard>,
+    #[cfg(all(unix, feature = "dbus"))]
+    idle: Option<IdleInhibitor>,
+    // as last told, so the bus only hears of changes
+    #[cfg(all(unix, feature = "dbus"))]
+    playing: Option<bool>,
+    // the config changed since the locks were taken
+    #[cfg(all(unix, feature = "dbus"))]
+    stale: bool,
+}
+
+#[cfg(all(unix, feature = "dbus"))]
+impl Power {
+    pub fn new(config: config::Power) -> Self {
+        Self {
+            config,
+            ..Self::default()
+        }
+    }
+
+    pub async fn start(&mut self) -> Result<()> {
+        let config = self.config;
+        if config.inhibit_idle {
+            self.idle = Some(IdleInhibitor::default());
+        }
+        if config.pause_on_suspend {
+            let guard = SuspendGuard::new(config.pause_on_suspend, config.resume_on_wake);
+            let guard = guard.await.wrap_err("Not pausing on suspend")?;
+            self.suspend = Some(guard);
+        }
+        Ok(())
+    }
+
+    // another server may have a profile of its own
+    pub fn configure(&mut self, config: config::Power) {
+        if config != self.config {
+            self.config = config;
+            self.stale = true;
+        }
+    }
+
+    // a failed lock gives up on it rather than retrying on every loop
+    pub async fn update(&mut self, playing: bool) -> Result<()> {
+        if std::mem::take(&mut self.stale) {
+            *self = Self::new(self.config);
+            self.start().await?;
+        }
+        if self.playing.replace(playing) == Some(playing) {
+            return Ok(());
+        }
+
+        let state = match playing {
+            true => PlayerState::Play,
+            false => PlayerState::Pause,
+        };
+        if let Some(guard) = &mut self.suspend {
+            if let Err(e) = guard.update(&state).await {
+                self.suspend = None;
+                return Err(e).wrap_err("Not pausing on suspend");
+            }
+        }
+        if let Some(idle) = &mut self.idle {
+            if let Err(e) = idle.update(&state).await {
+                self.idle = None;
+                return Err(e).wrap_err("Not keeping the system awake");
+            }
+        }
+        Ok(())
+    }
+
+    // while the system is kept awake
+    pub fn is_awake(&self) -> bool {
+        self.idle.as_ref().is_some_and(IdleInhibitor::is_active)
+    }
+
+    // true right before the system suspends, false once it woke up
+    pub async fn sleeping(&mut self) -> Option<bool> {
+        match &mut self.suspend {
+            Some(guard) => guard.next().await,
+            None => std::future::pending().await,
+        }
+    }
+
+    pub async fn handle(&mut self, sleeping: bool, client: Option<&mut Connection>) -> Result<()> {
+        // the pause may not have been seen before the suspend, so the
+        // state is told afresh
+        self.playing = None;
+        match &mut self.suspend {
+            Some(guard) => guard.handle(sleeping, client).await,
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(not(all(unix, feature = "dbus")))]
+impl Power {
+    pub fn new(_: config::Power) -> Self {
+        Self::default()
+    }
+
+    pub async fn start(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    pub fn configure(&mut self, _: config::Power) {}
+
+    pub async fn update(&mut self, _: bool) -> Result<()> {
+        Ok(())
+    }
+
+    pub fn is_awake(&self) -> bool {
+        false
+    }
+
+    pub async fn sleeping(&mut self) -> Option<bool> {
+        std::future::pending().await
+    }
+
+    pub async fn handle(&mut self, _: bool, _: Option<&mut Connection>) -> Result<()> {
+        Ok(())
+    }
+}
@@ -0,0 +1,25 @@
+use std::time::Duration;
+
+use ratatui::{layout::Rect, symbols, widgets::LineGauge, Frame};
+
+use crate::{queue, theme::Theme};
+
+pub fn draw(elapsed: Duration, total: Option<u16>, theme: &Theme, frame: &mut Frame, area: Rect) {
+    let elapsed = elapsed.as_secs().min(u16::MAX.into()) as u16;
+    let (ratio, label) = match total {
+        Some(total) if total > 0 => (
+            f64::from(elapsed.min(total)) / f64::from(total),
+            format!("{} / {}", queue::duration(elapsed), queue::duration(total)),
+        ),
+        // streams have no length, so only the elapsed time is shown
+        _ => (0.0, queue::duration(elapsed)),
+    };
+
+    let gauge = LineGauge::default()
+        .ratio(ratio)
+        .label(label)
+        .line_set(symbols::line::THICK)
+        .filled_style(theme.progress)
+        .unfilled_style(theme.gauge);
+    frame.render_widget(gauge, area);
+}
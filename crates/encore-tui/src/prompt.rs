@@ -0,0 +1,127 @@
+use ratatui::{
+    layout::Rect,
+    text::{Line, Span},
+    Frame,
+};
+
+use crate::{bulk::Source, command::Sort, theme::Theme};
+
+// a question on the bottom line that takes the keyboard until answered
+pub enum Prompt {
+    Save(String),
+    AddTo(String, Vec<Source>),
+    Confirm(Confirm),
+    // what is rated, and the songs
+    Rate(String, Vec<String>),
+    // the sort menu, a key for each order
+    Sort,
+    // a stream URL, and the schemes the server plays
+    Stream(String, Vec<String>, Option<Note>),
+    // `:` and a command, with what Tab or Enter had to say about it
+    Command(String, Option<Note>),
+}
+
+// what waits for a y
+pub enum Confirm {
+    Clear,
+    Delete(String),
+    DeletePartition(String),
+    // queue ids
+    Remove(Vec<u32>),
+}
+
+pub enum Note {
+    Choices(Vec<String>),
+    Error(String),
+}
+
+impl Prompt {
+    pub fn add_to(sources: Vec<Source>) -> Option<Self> {
+        (!sources.is_empty()).then(|| Prompt::AddTo(String::new(), sources))
+    }
+
+    // the text being typed, for the prompts that take any
+    pub fn input(&mut self) -> Option<&mut String> {
+        match self {
+            Prompt::Save(name)
+            | Prompt::AddTo(name, _)
+            | Prompt::Stream(name, ..)
+            | Prompt::Command(name, _) => Some(name),
+            Prompt::Confirm(_) | Prompt::Rate(..) | Prompt::Sort => None,
+        }
+    }
+}
+
+// http or https and something after it, of a scheme the server plays
+pub fn stream(url: &str, handlers: &[String]) -> Result<String, String> {
+    let url = url.trim();
+    let scheme = ["http://", "https://"]
+        .into_iter()
+        .find(|scheme| url.len() > scheme.len() && url.starts_with(scheme));
+    let Some(scheme) = scheme else {
+        return Err("Expected an http:// or https:// URL".into());
+    };
+    if !handlers.iter().any(|handler| handler == scheme) {
+        return Err(format!("The server does not play {scheme} streams"));
+    }
+    Ok(url.into())
+}
+
+pub fn draw(prompt: &Prompt, theme: &Theme, frame: &mut Frame, area: Rect) {
+    let line = match prompt {
+        Prompt::Save(name) => Line::from(vec![
+            Span::styled("Save queue as: ", theme.title),
+            Span::raw(format!("{name}█")),
+        ]),
+        Prompt::AddTo(name, sources) => Line::from(vec![
+            Span::styled(format!("Add {} to playlist: ", sources.len()), theme.title),
+            Span::raw(format!("{name}█")),
+        ]),
+        Prompt::Confirm(confirm) => {
+            let question = match confirm {
+                Confirm::Clear => "Clear the queue? ".into(),
+                Confirm::Delete(name) => format!("Delete playlist \"{name}\"? "),
+                Confirm::DeletePartition(name) => format!("Delete partition \"{name}\"? "),
+                Confirm::Remove(ids) => format!("Remove {} tracks from the queue? ", ids.len()),
+            };
+            Line::from(vec![
+                Span::styled(question, theme.title),
+                Span::styled("y/n", theme.muted),
+            ])
+        }
+        Prompt::Rate(what, _) => Line::from(vec![
+            Span::styled(format!("Rate {what}: "), theme.title),
+            Span::styled("1-5, 0 clears", theme.muted),
+        ]),
+        Prompt::Sort => {
+            let mut spans = vec![Span::styled("Sort the queue by: ", theme.title)];
+            for sort in Sort::ALL {
+                spans.push(Span::styled(format!("{} ", sort.key()), theme.key));
+                spans.push(Span::raw(format!("{}  ", sort.name())));
+            }
+            Line::from(spans)
+        }
+        Prompt::Stream(url, _, note) => Line::from(vec![
+            Span::styled("Add stream: ", theme.title),
+            Span::raw(format!("{url}█")),
+            self::note(note, theme),
+        ]),
+        Prompt::Command(input, note) => Line::from(vec![
+            Span::styled(":", theme.title),
+            Span::raw(format!("{input}█")),
+            self::note(note, theme),
+        ]),
+    };
+
+    frame.render_widget(line, area);
+}
+
+fn note<'a>(note: &'a Option<Note>, theme: &Theme) -> Span<'a> {
+    match note {
+        Some(Note::Choices(choices)) => {
+            Span::styled(format!("  {}", choices.join("  ")), theme.muted)
+        }
+        Some(Note::Error(error)) => Span::styled(format!("  {error}"), theme.error),
+        None => Span::raw(""),
+    }
+}
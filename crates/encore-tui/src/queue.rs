@@ -0,0 +1,528 @@
+use std::{future::pending, time::Duration};
+
+use encore_core::config::{self, Align, Column, Tag, Width};
+use encore_mpd::{CommandList, Track};
+use ratatui::{
+    layout::{Alignment, Constraint, Flex, Layout, Rect},
+    style::Style,
+    text::{Line, Span},
+    widgets::{Cell, Row, Table, TableState},
+    Frame,
+};
+use tokio::time::{sleep_until, Instant};
+
+use crate::{
+    fuzzy, highlight,
+    marks::Marks,
+    nav::Rows,
+    ratings::{self, Ratings},
+    theme::Theme,
+};
+
+// rows moved in quick succession reach the server once the keys rest
+pub const BATCH: Duration = Duration::from_millis(250);
+
+// the rows the queue needs before it gives one to the totals
+const FOOTER_MIN: u16 = 6;
+
+pub struct Queue {
+    pub tracks: Vec<Track>,
+    pub state: TableState,
+    pub marks: Marks,
+    // move the cursor along as playback advances
+    pub follow: bool,
+    pub filter: Option<Filter>,
+    pub columns: Vec<Column>,
+    pub ratings: Ratings,
+    // every track's length added up, in seconds
+    total: u64,
+    center: bool,
+    height: usize,
+    // moves shown here but not sent yet, as track id and the row it went to
+    moves: Vec<(u32, usize)>,
+    due: Option<Instant>,
+}
+
+// narrows the rows down to the tracks that fuzzily match what was typed
+#[derive(Default)]
+pub struct Filter {
+    pub input: String,
+    pub typing: bool,
+    // positions of the matching tracks, in queue order
+    rows: Vec<usize>,
+}
+
+impl Default for Queue {
+    fn default() -> Self {
+        Self {
+            tracks: Vec::new(),
+            state: TableState::default(),
+            marks: Marks::default(),
+            follow: true,
+            filter: None,
+            columns: config::Queue::default().columns,
+            ratings: Ratings::default(),
+            total: 0,
+            center: false,
+            height: 0,
+            moves: Vec::new(),
+            due: None,
+        }
+    }
+}
+
+impl Queue {
+    pub fn new(columns: Vec<Column>) -> Self {
+        Self {
+            columns,
+            ..Self::default()
+        }
+    }
+
+    pub fn set(&mut self, tracks: Vec<Track>) {
+        self.tracks = tracks;
+        self.total = self.tracks.iter().map(|track| u64::from(track.time)).sum();
+        self.marks.clear();
+
+        // a refresh from before the moves went out would undo them
+        for &(id, to) in &self.moves {
+            if let Some(from) = self.tracks.iter().position(|track| track.id == Some(id)) {
+                let track = self.tracks.remove(from);
+                self.tracks.insert(to.min(self.tracks.len()), track);
+                self.state.select(Some(to.min(self.tracks.len() - 1)));
+            }
+        }
+
+        self.rematch();
+        let rows = self.rows();
+        let selected = match self.state.selected() {
+            _ if rows == 0 => None,
+            Some(selected) => Some(selected.min(rows - 1)),
+            None => Some(0),
+        };
+        self.state.select(selected);
+    }
+
+    pub fn set_ratings(&mut self, stickers: Vec<(String, String)>) {
+        self.ratings.set(stickers);
+        self.rematch();
+        let rows = self.rows();
+        if self.state.selected().is_some_and(|row| row >= rows) {
+            self.state.select(rows.checked_sub(1));
+        }
+    }
+
+    // once a rating column or filter needs them
+    pub fn wants_ratings(&mut self) -> bool {
+        let column = self.columns.iter().any(|column| column.tag == Tag::Rating);
+        let filter = self.filter.as_ref().and_then(|f| rated(&f.input)).is_some();
+        (column || filter) && self.ratings.wanted()
+    }
+
+    // the filter applied again to changed tracks, the cursor stays
+    fn rematch(&mut self) {
+        if let Some(filter) = &mut self.filter {
+            filter.rows = matches(&self.tracks, &self.ratings, &filter.input)
+                .map(|(pos, _)| pos)
+                .collect();
+        }
+    }
+
+    // the position in the queue of a row on screen and the other way round,
+    // they differ while filtering
+    pub fn pos(&self, row: usize) -> Option<usize> {
+        match &self.filter {
+            Some(filter) => filter.rows.get(row).copied(),
+            None => (row < self.tracks.len()).then_some(row),
+        }
+    }
+
+    pub fn row(&self, pos: usize) -> Option<usize> {
+        match &self.filter {
+            Some(filter) => filter.rows.iter().position(|row| *row == pos),
+            None => (pos < self.tracks.len()).then_some(pos),
+        }
+    }
+
+    pub fn selected(&self) -> Option<usize> {
+        self.pos(self.state.selected()?)
+    }
+
+    // the positions of the marked tracks, or the one under the cursor
+    pub fn take_marked(&mut self) -> Vec<usize> {
+        let rows = self.marks.take(self.state.selected());
+        rows.into_iter().filter_map(|row| self.pos(row)).collect()
+    }
+
+    // puts the cursor on `pos` and the row in the middle of the next frame
+    pub fn reveal(&mut self, pos: usize) {
+        if let Some(row) = self.row(pos) {
+            self.state.select(Some(row));
+            self.center = true;
+        }
+    }
+
+    // narrows the rows to what the filter input matches now, with the
+    // cursor on the best match
+    pub fn refilter(&mut self) {
+        let Some(filter) = &mut self.filter else {
+            return;
+        };
+
+        let matches: Vec<_> = matches(&self.tracks, &self.ratings, &filter.input).collect();
+        let best = matches.iter().enumerate().max_by_key(|(row, (_, score))| {
+            // the earlier track wins a tie
+            (*score, std::cmp::Reverse(*row))
+        });
+        let best = best.map(|(row, _)| row);
+        filter.rows = matches.into_iter().map(|(pos, _)| pos).collect();
+
+        self.marks.clear();
+        self.state.select(best);
+        self.center = true;
+    }
+
+    // drops the filter, the cursor stays on its track
+    pub fn unfilter(&mut self) {
+        let pos = self.selected();
+        self.filter = None;
+        self.marks.clear();
+        match pos {
+            Some(pos) => self.reveal(pos),
+            None => self.state.select((!self.tracks.is_empty()).then_some(0)),
+        }
+    }
+
+    // n and N go round the matches
+    pub fn next_match(&mut self, forward: bool) {
+        let (Some(_), Some(cursor)) = (&self.filter, self.state.selected()) else {
+            return;
+        };
+        let rows = self.rows();
+        let row = match forward {
+            true => (cursor + 1) % rows,
+            false => (cursor + rows - 1) % rows,
+        };
+        self.state.select(Some(row));
+    }
+
+    // swaps the track under the cursor with its neighbour, the cursor stays
+    // on the track
+    pub fn shift(&mut self, up: bool) {
+        // rows next to each other on screen need not be in the queue
+        let Some(from) = self.state.selected().filter(|_| self.filter.is_none()) else {
+            return;
+        };
+        let to = match up {
+            true => from.checked_sub(1),
+            false => Some(from + 1).filter(|to| *to < self.tracks.len()),
+        };
+        let (Some(to), Some(id)) = (to, self.tracks[from].id) else {
+            return;
+        };
+
+        self.tracks.swap(from, to);
+        self.marks.clear();
+        self.state.select(Some(to));
+
+        match self.moves.last_mut() {
+            Some((last, row)) if *last == id => *row = to,
+            _ => self.moves.push((id, to)),
+        }
+        self.due = Some(Instant::now() + BATCH);
+    }
+
+    // one `moveid` per track moved, once the keys have rested
+    pub async fn shifted(&mut self) -> Option<CommandList> {
+        let Some(due) = self.due else {
+            return pending().await;
+        };
+
+        sleep_until(due).await;
+        self.due = None;
+
+        let mut list = CommandList::new();
+        for (id, to) in std::mem::take(&mut self.moves) {
+            list.push(b"moveid", &[&id.to_string(), &to.to_string()])
+                .ok()?;
+        }
+        Some(list)
+    }
+}
+
+impl Rows for Queue {
+    fn rows(&self) -> usize {
+        match &self.filter {
+            Some(filter) => filter.rows.len(),
+            None => self.tracks.len(),
+        }
+    }
+
+    fn cursor(&self) -> Option<usize> {
+        self.state.selected()
+    }
+
+    fn set_cursor(&mut self, row: usize) {
+        self.state.select(Some(row));
+    }
+
+    fn view(&self) -> (usize, usize) {
+        (self.state.offset(), self.height)
+    }
+
+    fn marks(&mut self) -> &mut Marks {
+        &mut self.marks
+    }
+}
+
+// the matching tracks with how well they match, by position; a word like
+// `*4` keeps only the tracks rated 4 or more
+fn matches<'a>(
+    tracks: &'a [Track],
+    ratings: &'a Ratings,
+    input: &'a str,
+) -> impl Iterator<Item = (usize, u32)> + 'a {
+    let rated = rated(input);
+    let pattern = pattern(input);
+
+    tracks.iter().enumerate().filter_map(move |(pos, track)| {
+        if let Some(least) = rated {
+            ratings.get(&track.file).filter(|rating| *rating >= least)?;
+        }
+
+        let [title, artist, album] = fields(track);
+        let text = format!("{title} {artist} {album}");
+        fuzzy::score(&pattern, &text).map(|score| (pos, score))
+    })
+}
+
+// what the filter looks for in the text, without the ratings
+fn pattern(input: &str) -> String {
+    let words = input
+        .split_whitespace()
+        .filter(|word| least(word).is_none());
+    words.collect::<Vec<_>>().join(" ")
+}
+
+// what the filter matches a track by
+fn fields(track: &Track) -> [&str; 3] {
+    let artist = track.artist.as_deref().unwrap_or_default();
+    let album = track.album.as_deref().unwrap_or_default();
+    [title(track), artist, album]
+}
+
+// where the pattern matched, by char in each of the fields
+fn hits(track: &Track, pattern: &str) -> [Vec<usize>; 3] {
+    let mut hits = [Vec::new(), Vec::new(), Vec::new()];
+    let fields = fields(track);
+    let Some(positions) = fuzzy::positions(pattern, &fields.join(" ")) else {
+        return hits;
+    };
+
+    // each field starts one past the end of the one before, for the space
+    let mut start = 0;
+    for (field, hits) in fields.iter().zip(&mut hits) {
+        let end = start + field.chars().count();
+        let within = positions.iter().filter(|&&at| at >= start && at < end);
+        hits.extend(within.map(|at| at - start));
+        start = end + 1;
+    }
+    hits
+}
+
+fn rated(input: &str) -> Option<u8> {
+    input.split_whitespace().find_map(least)
+}
+
+fn least(word: &str) -> Option<u8> {
+    word.strip_prefix('*')?.parse().ok()
+}
+
+pub fn title(track: &Track) -> &str {
+    let file = track.file.rsplit('/').next().unwrap_or(&track.file);
+    track
+        .title
+        .as_deref()
+        .or(track.name.as_deref())
+        .unwrap_or(file)
+}
+
+pub fn duration(secs: u16) -> String {
+    format!("{}:{:02}", secs / 60, secs % 60)
+}
+
+pub fn tracks(n: usize) -> String {
+    match n {
+        1 => "1 track".into(),
+        n => format!("{n} tracks"),
+    }
+}
+
+// whole minutes, and hours once there are any
+pub fn length(secs: u64) -> String {
+    let minutes = secs / 60;
+    match minutes / 60 {
+        0 => format!("{minutes}m"),
+        hours => format!("{hours}h {:02}m", minutes % 60),
+    }
+}
+
+// what is left of the playing song and everything after it
+fn remaining(tracks: &[Track], current: usize, elapsed: Duration) -> u64 {
+    let Some(playing) = tracks.get(current) else {
+        return 0;
+    };
+    let rest = u64::from(playing.time).saturating_sub(elapsed.as_secs());
+    let after: u64 = tracks[current + 1..]
+        .iter()
+        .map(|t| u64::from(t.time))
+        .sum();
+    rest + after
+}
+
+fn cell<'a>(
+    track: &'a Track,
+    column: &Column,
+    ratings: &Ratings,
+    hits: &[Vec<usize>; 3],
+    matched: Style,
+) -> Cell<'a> {
+    let alignment = match column.align {
+        Align::Left => Alignment::Left,
+        Align::Center => Alignment::Center,
+        Align::Right => Alignment::Right,
+    };
+    let field = match column.tag {
+        Tag::Title => Some(0),
+        Tag::Artist => Some(1),
+        Tag::Album => Some(2),
+        _ => None,
+    };
+    if let Some(field) = field.filter(|&field| !hits[field].is_empty()) {
+        let text = fields(track)[field];
+        let spans = highlight::spans(text, &hits[field], Style::new(), matched);
+        return Cell::from(Line::from(spans).alignment(alignment));
+    }
+
+    let tag = |tag: &Option<String>| tag.clone().unwrap_or_default();
+    let text = match column.tag {
+        Tag::Track => tag(&track.track)
+            .split('/')
+            .next()
+            .unwrap_or_default()
+            .into(),
+        Tag::Title => title(track).to_string(),
+        Tag::Artist => tag(&track.artist),
+        Tag::AlbumArtist => tag(&track.album_artist),
+        Tag::Album => tag(&track.album),
+        Tag::Disc => tag(&track.disc),
+        Tag::Date => tag(&track.date),
+        Tag::Genre => tag(&track.genre),
+        Tag::Duration => duration(track.time),
+        Tag::File => track.file.clone(),
+        Tag::Rating => ratings
+            .get(&track.file)
+            .map(ratings::stars)
+            .unwrap_or_default(),
+    };
+    Cell::from(Line::from(text).alignment(alignment))
+}
+
+pub fn draw(
+    queue: &mut Queue,
+    current: Option<usize>,
+    elapsed: Duration,
+    theme: &Theme,
+    frame: &mut Frame,
+    area: Rect,
+) {
+    // a short screen keeps its rows for the tracks
+    let area = match queue.tracks.len() {
+        0 => area,
+        _ if area.height < FOOTER_MIN => area,
+        len => {
+            let [area, footer] =
+                Layout::vertical([Constraint::Fill(1), Constraint::Length(1)]).areas(area);
+            let mut totals = format!("{} — {} total", tracks(len), length(queue.total));
+            if let Some(current) = current {
+                let left = remaining(&queue.tracks, current, elapsed);
+                totals.push_str(&format!(", {} remaining", length(left)));
+            }
+            frame.render_widget(Line::styled(totals, theme.muted), footer);
+            area
+        }
+    };
+
+    let area = match &queue.filter {
+        Some(filter) => {
+            let [area, bar] =
+                Layout::vertical([Constraint::Fill(1), Constraint::Length(1)]).areas(area);
+            let mut spans = vec![
+                Span::styled("/ ", theme.muted),
+                Span::raw(filter.input.as_str()),
+            ];
+            if filter.typing {
+                spans.push(Span::raw("█"));
+            }
+            let count = format!("  {} of {}", filter.rows.len(), queue.tracks.len());
+            spans.push(Span::styled(count, theme.muted));
+            frame.render_widget(Line::from(spans), bar);
+            area
+        }
+        None => area,
+    };
+
+    if queue.rows() == 0 {
+        let empty = match queue.filter {
+            Some(_) => "No matches",
+            None => "The queue is empty",
+        };
+        let [area] = Layout::vertical([Constraint::Length(1)])
+            .flex(Flex::Center)
+            .areas(area);
+        frame.render_widget(Line::styled(empty, theme.muted).centered(), area);
+        return;
+    }
+
+    queue.height = area.height.into();
+    if std::mem::take(&mut queue.center) {
+        if let Some(selected) = queue.state.selected() {
+            let half = area.height as usize / 2;
+            *queue.state.offset_mut() = selected.saturating_sub(half);
+        }
+    }
+
+    let cursor = queue.state.selected();
+    let pattern = queue.filter.as_ref().map(|filter| pattern(&filter.input));
+    let rows = (0..queue.rows()).filter_map(|row| Some((row, queue.pos(row)?)));
+    let rows = rows.map(|(row, pos)| {
+        let track = &queue.tracks[pos];
+        let hits = match &pattern {
+            Some(pattern) if !pattern.is_empty() => hits(track, pattern),
+            _ => Default::default(),
+        };
+        let cells = queue.columns.iter();
+        let cell = |column| cell(track, column, &queue.ratings, &hits, theme.matched);
+        let cells = Row::new(cells.map(cell));
+
+        let mut style = Style::new();
+        if queue.marks.contains(row, cursor) {
+            style = style.patch(theme.marked);
+        }
+        if Some(pos) == current {
+            style = style.patch(theme.current);
+        }
+        cells.style(style)
+    });
+
+    let widths = queue.columns.iter().map(|column| match column.width {
+        Width::Cells(cells) => Constraint::Length(cells),
+        Width::Percent(percent) => Constraint::Percentage(percent),
+        Width::Fill(share) => Constraint::Fill(share),
+    });
+    let table = Table::new(rows, widths)
+        .column_spacing(2)
+        .highlight_style(theme.selected);
+
+    frame.render_stateful_widget(table, area, &mut queue.state);
+}
@@ -0,0 +1,61 @@
+use std::collections::HashMap;
+
+use encore_mpd::CommandList;
+
+// the sticker ratings are kept in, as 1 to 5
+pub const STICKER: &str = "rating";
+
+pub const MOST: u8 = 5;
+
+// the ratings of every rated song, read once and again whenever a sticker
+// changes
+#[derive(Default)]
+pub struct Ratings {
+    by_file: HashMap<String, u8>,
+    requested: bool,
+}
+
+impl Ratings {
+    pub fn get(&self, file: &str) -> Option<u8> {
+        self.by_file.get(file).copied()
+    }
+
+    // true once until cleared, a server without stickers is not asked again
+    pub fn wanted(&mut self) -> bool {
+        !std::mem::replace(&mut self.requested, true)
+    }
+
+    // values other clients wrote that are not a number of stars are left out
+    pub fn set(&mut self, stickers: Vec<(String, String)>) {
+        self.by_file = stickers
+            .into_iter()
+            .filter_map(|(file, value)| {
+                let rating = value.parse().ok().filter(|r| (1..=MOST).contains(r))?;
+                Some((file, rating))
+            })
+            .collect();
+    }
+
+    pub fn clear(&mut self) {
+        self.by_file.clear();
+        self.requested = false;
+    }
+}
+
+pub fn stars(rating: u8) -> String {
+    let rating = rating.min(MOST) as usize;
+    "★".repeat(rating) + &"☆".repeat(MOST as usize - rating)
+}
+
+// no rating takes the sticker off
+pub fn rate(uris: &[String], rating: Option<u8>) -> Option<CommandList> {
+    let mut list = CommandList::new();
+    for uri in uris {
+        match rating {
+            Some(rating) => list.push(b"sticker set song", &[uri, STICKER, &rating.to_string()]),
+            None => list.push(b"sticker delete song", &[uri, STICKER]),
+        }
+        .ok()?;
+    }
+    (!list.is_empty()).then_some(list)
+}
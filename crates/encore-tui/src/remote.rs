@@ -0,0 +1,81 @@
+#[cfg(feature = "http-remote")]
+use encore_core::remote_control;
+#[cfg(feature = "http-remote")]
+pub use encore_core::remote_control::Request;
+use encore_core::{config, connection::Connection};
+#[cfg(feature = "http-remote")]
+use encore_mpd::MpdError;
+use eyre::{eyre, Result};
+use tokio::sync::mpsc;
+
+#[cfg(not(feature = "http-remote"))]
+pub enum Request {}
+
+#[derive(Default)]
+pub struct Remote {
+    rx: Option<mpsc::Receiver<Request>>,
+}
+
+impl Remote {
+    // a remote control asked for but not served is an error rather than
+    // something to find out from the phone
+    #[cfg(feature = "http-remote")]
+    pub async fn start(config: config::RemoteControl) -> Result<Self> {
+        let Some(addr) = config.addr else {
+            return Ok(Self::default());
+        };
+        let Some(token) = config.token.filter(|token| !token.is_empty()) else {
+            return Err(eyre!(
+                "Set remote_control.token to serve the remote control"
+            ));
+        };
+
+        let listener = remote_control::bind(addr).await?;
+        let (tx, rx) = mpsc::channel(8);
+        tokio::spawn(remote_control::serve(listener, token, tx));
+        Ok(Self { rx: Some(rx) })
+    }
+
+    #[cfg(not(feature = "http-remote"))]
+    pub async fn start(config: config::RemoteControl) -> Result<Self> {
+        match config.addr {
+            Some(addr) => Err(eyre!(
+                "Not serving the remote control on {addr}, encore was built without http-remote"
+            )),
+            None => Ok(Self::default()),
+        }
+    }
+
+    pub async fn recv(&mut self) -> Option<Request> {
+        match &mut self.rx {
+            Some(rx) => rx.recv().await,
+            None => std::future::pending().await,
+        }
+    }
+}
+
+// the HTTP client hears of every failure, the caller only of those that
+// leave the connection unusable
+#[cfg(feature = "http-remote")]
+pub async fn handle(request: Request, client: Option<&mut Connection>) -> Result<()> {
+    // dropping the reply without an answer reports the server as unavailable
+    let Some(client) = client else {
+        return Ok(());
+    };
+
+    match remote_control::execute(client, request.command).await {
+        Err(e) if !e.is::<MpdError>() => {
+            let _ = request.reply.send(Err(eyre!("{e}")));
+            Err(e)
+        }
+        reply => {
+            let _ = request.reply.send(reply);
+            Ok(())
+        }
+    }
+}
+
+#[cfg(not(feature = "http-remote"))]
+pub async fn handle(request: Request, _: Option<&mut Connection>) -> Result<()> {
+    match request {}
+}
@@ -0,0 +1,62 @@
+use std::time::Duration;
+
+use tokio::time::{sleep_until, Instant};
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Pane {
+    Progress,
+    Lyrics,
+    Visualizer,
+}
+
+struct Entry {
+    pane: Pane,
+    every: Duration,
+    next: Instant,
+}
+
+// each pane refreshes on its own cadence, so a fast pane only wakes the
+// loop for itself instead of everything ticking at the fastest rate
+#[derive(Default)]
+pub struct Scheduler {
+    entries: Vec<Entry>,
+}
+
+impl Scheduler {
+    pub fn set(&mut self, pane: Pane, every: Option<Duration>) {
+        let pos = self.entries.iter().position(|entry| entry.pane == pane);
+        match (pos, every) {
+            (Some(pos), None) => {
+                self.entries.swap_remove(pos);
+            }
+            (Some(pos), Some(every)) if self.entries[pos].every != every => {
+                self.entries[pos].every = every;
+                self.entries[pos].next = Instant::now() + every;
+            }
+            (None, Some(every)) => self.entries.push(Entry {
+                pane,
+                every,
+                next: Instant::now() + every,
+            }),
+            _ => {}
+        }
+    }
+
+    pub async fn wait(&mut self) -> Vec<Pane> {
+        let Some(next) = self.entries.iter().map(|entry| entry.next).min() else {
+            return std::future::pending().await;
+        };
+
+        sleep_until(next).await;
+
+        let now = Instant::now();
+        self.entries
+            .iter_mut()
+            .filter(|entry| entry.next <= now)
+            .map(|entry| {
+                entry.next = now + entry.every;
+                entry.pane
+            })
+            .collect()
+    }
+}
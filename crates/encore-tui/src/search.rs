@@ -0,0 +1,140 @@
+use std::{future::pending, time::Duration};
+
+use encore_core::filter::{Chip, Query};
+use encore_mpd::Track;
+use ratatui::{
+    layout::{Constraint, Layout, Rect},
+    style::Style,
+    text::{Line, Span},
+    Frame,
+};
+use tokio::time::{sleep_until, Instant};
+
+use crate::{
+    highlight,
+    menu::{self, Menu},
+    queue,
+    theme::Theme,
+};
+
+// typing is only sent to the server once it pauses for this long
+pub const DEBOUNCE: Duration = Duration::from_millis(300);
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Focus {
+    #[default]
+    Input,
+    Results,
+}
+
+#[derive(Default)]
+pub struct Search {
+    pub input: String,
+    pub query: Query,
+    pub results: Menu<Track>,
+    pub focus: Focus,
+    due: Option<Instant>,
+}
+
+impl Search {
+    // the chips so far plus whatever is being typed
+    pub fn filter(&self) -> Option<String> {
+        let mut query = self.query.clone();
+        query.chips.extend(Chip::parse(&self.input));
+        query.compile()
+    }
+
+    pub fn changed(&mut self) {
+        self.due = Some(Instant::now() + DEBOUNCE);
+    }
+
+    // Enter turns the input into a chip, Backspace on an empty input takes
+    // the last one back
+    pub fn commit(&mut self) -> bool {
+        let added = self.query.add(&self.input);
+        if added {
+            self.input.clear();
+        }
+        added
+    }
+
+    pub fn uncommit(&mut self) {
+        if let Some(chip) = self.query.chips.pop() {
+            self.input = chip.label();
+            self.changed();
+        }
+    }
+
+    // the filter to run once typing has settled, none when it was cleared
+    pub async fn settled(&mut self) -> Option<String> {
+        let Some(due) = self.due else {
+            return pending().await;
+        };
+
+        sleep_until(due).await;
+        self.due = None;
+
+        let filter = self.filter();
+        if filter.is_none() {
+            self.results.set(Vec::new());
+        }
+        filter
+    }
+}
+
+pub fn draw(search: &mut Search, theme: &Theme, frame: &mut Frame, area: Rect) {
+    let [bar, _, results] = Layout::vertical([
+        Constraint::Length(1),
+        Constraint::Length(1),
+        Constraint::Fill(1),
+    ])
+    .areas(area);
+
+    let mut spans = vec![Span::styled("/ ", theme.muted)];
+    for chip in &search.query.chips {
+        spans.push(Span::styled(format!(" {} ", chip.label()), theme.chip));
+        spans.push(Span::raw(" "));
+    }
+    spans.push(Span::raw(search.input.as_str()));
+    if search.focus == Focus::Input {
+        spans.push(Span::raw("█"));
+    }
+    frame.render_widget(Line::from(spans), bar);
+
+    let empty = match search.filter() {
+        None => "Type to search, artist: album: and so on narrow it to a tag",
+        Some(_) if search.due.is_some() => "Searching…",
+        Some(_) => "No matches",
+    };
+    let mut chips = search.query.chips.clone();
+    chips.extend(Chip::parse(&search.input));
+    let label = |track| label(track, &chips, theme);
+    menu::draw(&mut search.results, label, empty, theme, frame, results);
+}
+
+fn label<'a>(track: &'a Track, chips: &[Chip], theme: &Theme) -> Line<'a> {
+    let title = queue::title(track);
+    let artist = track.artist.as_deref().unwrap_or_default();
+    let found = [hits(title, chips, "title"), hits(artist, chips, "artist")];
+    let mut spans = highlight::spans(title, &found[0], Style::new(), theme.matched);
+    spans.push(Span::raw("  "));
+    let by = highlight::spans(artist, &found[1], theme.muted, theme.matched);
+    spans.extend(by);
+    spans.push(Span::raw(format!("  {}", queue::duration(track.time))));
+    Line::from(spans)
+}
+
+// where the terms searched for in every tag, or in this one, show in it
+fn hits(text: &str, chips: &[Chip], tag: &str) -> Vec<usize> {
+    let terms = chips.iter().filter_map(|chip| match chip {
+        Chip::Text(value) => Some(value),
+        Chip::Contains { tag: of, value } | Chip::Equals { tag: of, value } if of == tag => {
+            Some(value)
+        }
+        _ => None,
+    });
+    let mut hits: Vec<_> = terms.flat_map(|term| highlight::find(text, term)).collect();
+    hits.sort_unstable();
+    hits.dedup();
+    hits
+}
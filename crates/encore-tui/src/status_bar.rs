@@ -0,0 +1,78 @@
+use encore_core::connection::Endpoint;
+use ratatui::{
+    layout::Rect,
+    style::Style,
+    text::{Line, Span},
+    Frame,
+};
+
+use crate::{connecting::Connecting, theme::Theme};
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Link {
+    Connected,
+    Reconnecting,
+    // the server refused at least one retry and we are backing off
+    Offline,
+}
+
+impl Link {
+    fn style(self, theme: &Theme) -> Style {
+        match self {
+            Link::Connected => theme.connected,
+            Link::Reconnecting => theme.reconnecting,
+            Link::Offline => theme.offline,
+        }
+    }
+}
+
+// what is told of the server once connected
+pub struct Connected<'a> {
+    // the one commands go to, once the status told
+    pub partition: Option<&'a str>,
+    // the system is kept from sleeping while playing
+    pub awake: bool,
+}
+
+pub fn draw(
+    link: Link,
+    connecting: &Connecting,
+    endpoint: &Endpoint,
+    connected: Connected,
+    theme: &Theme,
+    frame: &mut Frame,
+    area: Rect,
+) {
+    let mut spans = vec![Span::styled("● ", link.style(theme))];
+
+    match link {
+        Link::Connected => {
+            spans.push(Span::raw(endpoint.to_string()));
+            if let Some(partition) = connected.partition {
+                spans.push(Span::styled(
+                    format!("  partition {partition}"),
+                    theme.muted,
+                ));
+            }
+            if connected.awake {
+                spans.push(Span::styled("  keeping awake", theme.muted));
+            }
+        }
+        Link::Reconnecting => spans.push(Span::raw(format!("Reconnecting to {endpoint}…"))),
+        Link::Offline => spans.push(Span::raw(format!("Offline, retrying {endpoint}"))),
+    }
+
+    // only the root cause of the context chain fits on one line
+    if let (Link::Reconnecting | Link::Offline, Some(error)) = (link, &connecting.error) {
+        let cause = error.rsplit(": ").next().unwrap_or(error);
+        spans.push(Span::raw("  "));
+        spans.push(Span::styled(cause.to_string(), theme.error));
+    }
+
+    if link != Link::Connected {
+        spans.push(Span::raw("  "));
+        spans.push(Span::styled("e: edit target", theme.muted));
+    }
+
+    frame.render_widget(Line::from(spans), area);
+}
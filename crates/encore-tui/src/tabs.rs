@@ -0,0 +1,65 @@
+use ratatui::{layout::Rect, widgets::Tabs, Frame};
+
+use crate::theme::Theme;
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Tab {
+    #[default]
+    Queue,
+    Library,
+    Files,
+    Playlists,
+    Search,
+    Lyrics,
+}
+
+impl Tab {
+    pub const ALL: [Tab; 6] = [
+        Tab::Queue,
+        Tab::Library,
+        Tab::Files,
+        Tab::Playlists,
+        Tab::Search,
+        Tab::Lyrics,
+    ];
+
+    pub fn title(self) -> &'static str {
+        match self {
+            Tab::Queue => "Queue",
+            Tab::Library => "Library",
+            Tab::Files => "Files",
+            Tab::Playlists => "Playlists",
+            Tab::Search => "Search",
+            Tab::Lyrics => "Lyrics",
+        }
+    }
+
+    // `1` for the first tab and so on
+    pub fn from_key(c: char) -> Option<Self> {
+        let index = c.to_digit(10)?.checked_sub(1)?;
+        Self::ALL.get(index as usize).copied()
+    }
+
+    pub fn cycle(self, forward: bool) -> Self {
+        let index = Self::ALL.iter().position(|tab| *tab == self).unwrap_or(0);
+        let len = Self::ALL.len();
+        let index = if forward { index + 1 } else { index + len - 1 };
+        Self::ALL[index % len]
+    }
+}
+
+pub fn draw(current: Tab, theme: &Theme, frame: &mut Frame, area: Rect) {
+    let titles = Tab::ALL
+        .iter()
+        .enumerate()
+        .map(|(i, tab)| format!("{} {}", i + 1, tab.title()));
+
+    let selected = Tab::ALL.iter().position(|tab| *tab == current);
+    let tabs = Tabs::new(titles)
+        .select(selected.unwrap_or(0))
+        .style(theme.tab)
+        .highlight_style(theme.tab_active)
+        .padding("", "")
+        .divider("  ");
+    frame.render_widget(tabs, area);
+}
@@ -0,0 +1,245 @@
+use std::{collections::BTreeMap, path::Path, str::FromStr};
+
+use encore_core::config::{self, Config, Spec};
+use eyre::{eyre, Result, WrapErr};
+use ratatui::style::{Color, Modifier, Style, Stylize};
+
+macro_rules! elements {
+    ($($(#[$doc:meta])* $name:ident,)*) => {
+        // what every part of the screen looks like, by what it means rather
+        // than where it is drawn
+        #[derive(Clone, Debug)]
+        pub struct Theme {
+            $($(#[$doc])* pub $name: Style,)*
+        }
+
+        impl Theme {
+            fn element(&mut self, name: &str) -> Option<&mut Style> {
+                match name {
+                    $(stringify!($name) => Some(&mut self.$name),)*
+                    _ => None,
+                }
+            }
+        }
+    };
+}
+
+elements! {
+    // hints, empty lists and whatever is secondary
+    muted,
+    // headings and the playing song's title
+    title,
+    error,
+    // the playing song in the queue, the line being sung
+    current,
+    // the row under the cursor
+    selected,
+    marked,
+    directory,
+    playlist,
+    // keys in the help
+    key,
+    tab,
+    tab_active,
+    // overlays
+    border,
+    // the filled part of the gauges, and the rest
+    progress,
+    volume,
+    level,
+    gauge,
+    wave,
+    flag_on,
+    flag_off,
+    flag_oneshot,
+    connected,
+    reconnecting,
+    offline,
+    // search terms kept as chips
+    chip,
+    // what a filter or search found in a row
+    matched,
+    // notes that come and go in the corner
+    toast,
+    toast_error,
+}
+
+const BUILT_IN: [&str; 4] = ["default", "mono", "gruvbox", "nord"];
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            muted: Style::new().dim(),
+            title: Style::new().bold(),
+            error: Style::new().red(),
+            current: Style::new().bold().yellow(),
+            selected: Style::new().reversed(),
+            marked: Style::new().magenta(),
+            directory: Style::new().bold().blue(),
+            playlist: Style::new().italic().dim(),
+            key: Style::new().yellow(),
+            tab: Style::new().dim(),
+            tab_active: Style::new().not_dim().bold().reversed(),
+            border: Style::new(),
+            progress: Style::new().yellow(),
+            volume: Style::new().cyan(),
+            level: Style::new().green(),
+            gauge: Style::new().dim(),
+            wave: Style::new().cyan(),
+            flag_on: Style::new().green().bold(),
+            flag_off: Style::new().dim(),
+            flag_oneshot: Style::new().yellow().bold(),
+            connected: Style::new().green(),
+            reconnecting: Style::new().yellow(),
+            offline: Style::new().red(),
+            chip: Style::new().reversed(),
+            matched: Style::new().bold().underlined(),
+            toast: Style::new().reversed(),
+            toast_error: Style::new().reversed().red(),
+        }
+    }
+}
+
+impl Theme {
+    // the built-in or file theme the config names, restyled by its tables
+    pub fn load(config: &Config) -> Result<Self> {
+        let mut theme = match config.theme.name.as_deref() {
+            None => Self::default(),
+            Some(name) => match Self::built_in(name) {
+                Some(theme) => theme,
+                None => {
+                    let path = config.path.clone().or_else(Config::path);
+                    let dir = path.as_deref().and_then(Path::parent);
+                    let dir =
+                        dir.ok_or_else(|| eyre!("No config directory to find theme `{name}` in"))?;
+                    let path = dir.join("themes").join(format!("{name}.toml"));
+                    let known = BUILT_IN.join(", ");
+                    let styles = config::load_theme(&path).wrap_err_with(|| {
+                        format!("Theme `{name}` is neither built in ({known}) nor a file")
+                    })?;
+                    let mut theme = Self::default();
+                    theme.restyle(&styles)?;
+                    theme
+                }
+            },
+        };
+
+        theme.restyle(&config.theme.styles)?;
+        Ok(theme)
+    }
+
+    fn built_in(name: &str) -> Option<Self> {
+        let rgb = |hex: u32| Color::from_u32(hex);
+        Some(match name {
+            "default" => Self::default(),
+            // modifiers only, for terminals with odd palettes
+            "mono" => Self {
+                error: Style::new().bold().underlined(),
+                current: Style::new().bold(),
+                marked: Style::new().italic().underlined(),
+                directory: Style::new().bold(),
+                key: Style::new().bold(),
+                progress: Style::new(),
+                volume: Style::new(),
+                level: Style::new(),
+                wave: Style::new(),
+                flag_on: Style::new().bold(),
+                flag_oneshot: Style::new().bold().italic(),
+                connected: Style::new(),
+                reconnecting: Style::new().italic(),
+                offline: Style::new().bold(),
+                toast_error: Style::new().reversed().bold(),
+                ..Self::default()
+            },
+            "gruvbox" => Self {
+                muted: Style::new().fg(rgb(0x928374)),
+                error: Style::new().fg(rgb(0xfb4934)),
+                current: Style::new().bold().fg(rgb(0xfabd2f)),
+                selected: Style::new().bg(rgb(0x504945)),
+                marked: Style::new().fg(rgb(0xd3869b)),
+                directory: Style::new().bold().fg(rgb(0x83a598)),
+                playlist: Style::new().italic().fg(rgb(0x928374)),
+                key: Style::new().fg(rgb(0xfe8019)),
+                tab: Style::new().fg(rgb(0x928374)),
+                tab_active: Style::new().bold().fg(rgb(0x282828)).bg(rgb(0xfabd2f)),
+                border: Style::new().fg(rgb(0x665c54)),
+                progress: Style::new().fg(rgb(0xfabd2f)),
+                volume: Style::new().fg(rgb(0x8ec07c)),
+                level: Style::new().fg(rgb(0xb8bb26)),
+                gauge: Style::new().fg(rgb(0x504945)),
+                wave: Style::new().fg(rgb(0x8ec07c)),
+                flag_on: Style::new().bold().fg(rgb(0xb8bb26)),
+                flag_off: Style::new().fg(rgb(0x665c54)),
+                flag_oneshot: Style::new().bold().fg(rgb(0xfe8019)),
+                connected: Style::new().fg(rgb(0xb8bb26)),
+                reconnecting: Style::new().fg(rgb(0xfabd2f)),
+                offline: Style::new().fg(rgb(0xfb4934)),
+                chip: Style::new().fg(rgb(0x282828)).bg(rgb(0x83a598)),
+                matched: Style::new().bold().fg(rgb(0xfe8019)),
+                toast: Style::new().fg(rgb(0x282828)).bg(rgb(0xebdbb2)),
+                toast_error: Style::new().fg(rgb(0x282828)).bg(rgb(0xfb4934)),
+                ..Self::default()
+            },
+            "nord" => Self {
+                muted: Style::new().fg(rgb(0x616e88)),
+                error: Style::new().fg(rgb(0xbf616a)),
+                current: Style::new().bold().fg(rgb(0x88c0d0)),
+                selected: Style::new().bg(rgb(0x434c5e)),
+                marked: Style::new().fg(rgb(0xb48ead)),
+                directory: Style::new().bold().fg(rgb(0x81a1c1)),
+                playlist: Style::new().italic().fg(rgb(0x616e88)),
+                key: Style::new().fg(rgb(0x88c0d0)),
+                tab: Style::new().fg(rgb(0x616e88)),
+                tab_active: Style::new().bold().fg(rgb(0x2e3440)).bg(rgb(0x88c0d0)),
+                border: Style::new().fg(rgb(0x4c566a)),
+                progress: Style::new().fg(rgb(0x88c0d0)),
+                volume: Style::new().fg(rgb(0x81a1c1)),
+                level: Style::new().fg(rgb(0xa3be8c)),
+                gauge: Style::new().fg(rgb(0x434c5e)),
+                wave: Style::new().fg(rgb(0x8fbcbb)),
+                flag_on: Style::new().bold().fg(rgb(0xa3be8c)),
+                flag_off: Style::new().fg(rgb(0x4c566a)),
+                flag_oneshot: Style::new().bold().fg(rgb(0xebcb8b)),
+                connected: Style::new().fg(rgb(0xa3be8c)),
+                reconnecting: Style::new().fg(rgb(0xebcb8b)),
+                offline: Style::new().fg(rgb(0xbf616a)),
+                chip: Style::new().fg(rgb(0x2e3440)).bg(rgb(0x81a1c1)),
+                matched: Style::new().bold().fg(rgb(0xebcb8b)),
+                toast: Style::new().fg(rgb(0x2e3440)).bg(rgb(0xd8dee9)),
+                toast_error: Style::new().fg(rgb(0x2e3440)).bg(rgb(0xbf616a)),
+                ..Self::default()
+            },
+            _ => return None,
+        })
+    }
+
+    // a restyled element starts over instead of adding to the old style
+    fn restyle(&mut self, styles: &BTreeMap<String, Spec>) -> Result<()> {
+        for (name, spec) in styles {
+            let style = self
+                .element(name)
+                .ok_or_else(|| eyre!("Unknown theme element `{name}`"))?;
+            *style = parse(spec).map_err(|e| eyre!("Invalid style for `{name}`: {e}"))?;
+        }
+        Ok(())
+    }
+}
+
+fn parse(spec: &Spec) -> Result<Style, String> {
+    let color = |color: &Option<String>| match color {
+        Some(name) => Color::from_str(name)
+            .map(Some)
+            .map_err(|_| format!("unknown color `{name}`")),
+        None => Ok(None),
+    };
+
+    let mut style = Style::new();
+    style.fg = color(&spec.fg)?;
+    style.bg = color(&spec.bg)?;
+    for name in &spec.modifiers {
+        let modifier = Modifier::from_name(&name.to_uppercase())
+            .ok_or_else(|| format!("unknown modifier `{name}`"))?;
+        style = style.add_modifier(modifier);
+    }
+    Ok(style)
+}
@@ -0,0 +1,91 @@
+use std::{collections::VecDeque, future::pending, time::Duration};
+
+use ratatui::{
+    layout::Rect,
+    text::Line,
+    widgets::{Clear, Paragraph},
+    Frame,
+};
+use tokio::time::{sleep_until, Instant};
+
+use crate::theme::Theme;
+
+const INFO: Duration = Duration::from_secs(3);
+// errors take longer to read, and matter more
+const ERROR: Duration = Duration::from_secs(6);
+// the oldest give way beyond that
+const MOST: usize = 4;
+
+struct Toast {
+    text: String,
+    error: bool,
+    until: Instant,
+}
+
+// short notes stacked in the bottom right corner that go away on their own
+#[derive(Default)]
+pub struct Toasts {
+    shown: VecDeque<Toast>,
+}
+
+impl Toasts {
+    pub fn info(&mut self, text: impl Into<String>) {
+        self.push(text.into(), false);
+    }
+
+    pub fn error(&mut self, text: impl Into<String>) {
+        self.push(text.into(), true);
+    }
+
+    // the same note again only keeps it up longer
+    fn push(&mut self, text: String, error: bool) {
+        self.shown.retain(|toast| toast.text != text);
+        let lasts = if error { ERROR } else { INFO };
+        self.shown.push_back(Toast {
+            text,
+            error,
+            until: Instant::now() + lasts,
+        });
+        if self.shown.len() > MOST {
+            self.shown.pop_front();
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.shown.is_empty()
+    }
+
+    // wakes the loop to take down the ones that ran out
+    pub async fn expired(&mut self) {
+        let Some(until) = self.shown.iter().map(|toast| toast.until).min() else {
+            return pending().await;
+        };
+
+        sleep_until(until).await;
+        let now = Instant::now();
+        self.shown.retain(|toast| toast.until > now);
+    }
+}
+
+// newest at the bottom, right above the status bar
+pub fn draw(toasts: &Toasts, theme: &Theme, frame: &mut Frame) {
+    let area = frame.area();
+    let mut bottom = area.bottom().saturating_sub(1);
+    for toast in toasts.shown.iter().rev() {
+        if bottom <= area.y {
+            break;
+        }
+
+        let width = (toast.text.chars().count() as u16 + 2).min(area.width);
+        bottom -= 1;
+        let at = Rect::new(area.right() - width, bottom, width, 1);
+        let style = if toast.error {
+            theme.toast_error
+        } else {
+            theme.toast
+        };
+        let text = Paragraph::new(Line::from(format!(" {} ", toast.text))).style(style);
+        frame.render_widget(Clear, at);
+        frame.render_widget(text, at);
+    }
+}
@@ -0,0 +1,111 @@
+use std::time::SystemTime;
+
+use encore_core::trash::Trashed;
+use ratatui::{
+    layout::{Constraint, Flex, Layout},
+    text::{Line, Span},
+    widgets::{Block, Clear, List, ListItem, ListState},
+    Frame,
+};
+
+use crate::theme::Theme;
+
+// the playlists deleted from here, newest first, to put back on the server;
+// read again whenever the server's playlists change
+pub struct Bin {
+    list: Vec<Trashed>,
+    state: ListState,
+    // the server told of a change since the list was read
+    stale: bool,
+}
+
+impl Bin {
+    pub fn new(list: Vec<Trashed>) -> Self {
+        Self {
+            list,
+            state: ListState::default().with_selected(Some(0)),
+            stale: false,
+        }
+    }
+
+    pub fn set(&mut self, list: Vec<Trashed>) {
+        self.list = list;
+        self.stale = false;
+        let last = self.list.len().saturating_sub(1);
+        let at = self.state.selected().unwrap_or(0).min(last);
+        self.state.select(Some(at));
+    }
+
+    pub fn changed(&mut self) {
+        self.stale = true;
+    }
+
+    // true once per change
+    pub fn wanted(&mut self) -> bool {
+        std::mem::take(&mut self.stale)
+    }
+
+    pub fn selected(&self) -> Option<&Trashed> {
+        self.list.get(self.state.selected()?)
+    }
+
+    pub fn step(&mut self, down: bool) {
+        let last = self.list.len().saturating_sub(1);
+        let at = self.state.selected().unwrap_or(0);
+        let at = if down {
+            (at + 1).min(last)
+        } else {
+            at.saturating_sub(1)
+        };
+        self.state.select(Some(at));
+    }
+}
+
+// in the largest whole unit
+pub fn ago(deleted: SystemTime) -> String {
+    let secs = deleted.elapsed().unwrap_or_default().as_secs();
+    match secs {
+        0..60 => "just now".into(),
+        60..3600 => format!("{}m ago", secs / 60),
+        3600..86400 => format!("{}h ago", secs / 3600),
+        _ => format!("{}d ago", secs / 86400),
+    }
+}
+
+pub fn draw(bin: &mut Bin, theme: &Theme, frame: &mut Frame) {
+    let area = frame.area();
+    let height = bin.list.len().max(1) as u16 + 2;
+    let [popup] = Layout::horizontal([Constraint::Max(48)])
+        .flex(Flex::Center)
+        .areas(area);
+    let [popup] = Layout::vertical([Constraint::Max(height)])
+        .flex(Flex::Center)
+        .areas(popup);
+
+    let block = Block::bordered()
+        .border_style(theme.border)
+        .title(" Trash ")
+        .title_bottom(" Enter restores, Esc closes ");
+
+    let items: Vec<_> = bin
+        .list
+        .iter()
+        .map(|trashed| {
+            ListItem::new(Line::from(vec![
+                Span::raw(format!("  {}", trashed.name)),
+                Span::styled(format!("  {}", ago(trashed.deleted)), theme.muted),
+            ]))
+        })
+        .collect();
+    let empty = items.is_empty();
+    let list = List::new(items)
+        .block(block)
+        .highlight_style(theme.selected);
+
+    frame.render_widget(Clear, popup);
+    frame.render_stateful_widget(list, popup, &mut bin.state);
+    if empty {
+        let none = Line::styled("No playlist was deleted", theme.muted);
+        frame.render_widget(none, Block::bordered().inner(popup));
+    }
+}
@@ -0,0 +1,91 @@
+use std::{
+    env, fs,
+    io::{self, Write},
+    path::PathBuf,
+    process::{Child, ChildStdin, Command, Stdio},
+    sync::Arc,
+};
+
+use image::{ImageFormat, RgbaImage};
+use ratatui::layout::Rect;
+use serde_json::json;
+
+const PROGRAM: &str = "ueberzugpp";
+const IDENTIFIER: &str = "encore";
+
+// an `ueberzugpp layer` child that draws the cover in a window of its own
+// over the terminal, told what to do one JSON object per line
+pub struct Ueberzug {
+    child: Child,
+    stdin: ChildStdin,
+    // it reads pictures from disk, so the cover is written out first
+    path: PathBuf,
+    written: Option<Arc<RgbaImage>>,
+}
+
+impl Ueberzug {
+    pub fn spawn() -> io::Result<Self> {
+        let mut child = Command::new(PROGRAM)
+            .args(["layer", "--silent"])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()?;
+        let stdin = child.stdin.take().ok_or(io::ErrorKind::BrokenPipe)?;
+        let path = env::temp_dir().join(format!("encore-cover-{}.png", std::process::id()));
+
+        Ok(Self {
+            child,
+            stdin,
+            path,
+            written: None,
+        })
+    }
+
+    // whether it can be started at all
+    pub fn found() -> bool {
+        let path = env::var_os("PATH").unwrap_or_default();
+        env::split_paths(&path).any(|dir| dir.join(PROGRAM).is_file())
+    }
+
+    pub fn show(&mut self, cover: &Arc<RgbaImage>, at: Rect) -> io::Result<()> {
+        let fresh = self
+            .written
+            .as_ref()
+            .is_some_and(|written| Arc::ptr_eq(written, cover));
+        if !fresh {
+            cover
+                .write_to(&mut fs::File::create(&self.path)?, ImageFormat::Png)
+                .map_err(io::Error::other)?;
+            self.written = Some(cover.clone());
+        }
+
+        self.send(json!({
+            "action": "add",
+            "identifier": IDENTIFIER,
+            "x": at.x,
+            "y": at.y,
+            "max_width": at.width,
+            "max_height": at.height,
+            "path": self.path,
+        }))
+    }
+
+    pub fn hide(&mut self) -> io::Result<()> {
+        self.send(json!({ "action": "remove", "identifier": IDENTIFIER }))
+    }
+
+    fn send(&mut self, command: serde_json::Value) -> io::Result<()> {
+        writeln!(self.stdin, "{command}")?;
+        self.stdin.flush()
+    }
+}
+
+impl Drop for Ueberzug {
+    fn drop(&mut self) {
+        let _ = self.hide();
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+        let _ = fs::remove_file(&self.path);
+    }
+}
@@ -0,0 +1,197 @@
+use std::time::Duration;
+
+use encore_core::{
+    config::{self, Mode, Rgb},
+    fifo::Fifo,
+    spectrum::Spectrum,
+};
+use ratatui::{
+    layout::{Constraint, Flex, Layout, Rect},
+    style::Color,
+    symbols::{self, Marker},
+    text::Line,
+    widgets::{
+        canvas::{self, Canvas},
+        LineGauge,
+    },
+    Frame,
+};
+
+use crate::theme::Theme;
+
+// about thirty frames a second, smooth enough without busying the loop
+pub const EVERY: Duration = Duration::from_millis(33);
+
+// decibels below full scale where the level meter bottoms out
+const FLOOR: f32 = 60.0;
+
+const EIGHTHS: [&str; 8] = ["▁", "▂", "▃", "▄", "▅", "▆", "▇", "█"];
+
+// what MPD is playing as a waveform or spectrum over a level meter, read
+// from its fifo output once the pane is first shown
+pub struct Visualizer {
+    config: config::Visualizer,
+    fifo: Option<Fifo>,
+    pub shown: bool,
+    pub mode: Mode,
+    spectrum: Spectrum,
+    // as many bars as fit the pane as of the last draw
+    fit: usize,
+}
+
+impl Visualizer {
+    pub fn new(config: config::Visualizer) -> Self {
+        Self {
+            mode: config.mode,
+            spectrum: Spectrum::new(config.bars, config.smoothing),
+            fit: config.bars,
+            config,
+            fifo: None,
+            shown: false,
+        }
+    }
+
+    pub fn switch(&mut self) {
+        self.mode = match self.mode {
+            Mode::Wave => Mode::Spectrum,
+            Mode::Spectrum => Mode::Wave,
+        };
+    }
+
+    // the spectrum falls at the same pace however often the screen is drawn
+    pub fn tick(&mut self) {
+        let Some(fifo) = self.fifo.as_ref().filter(|_| self.mode == Mode::Spectrum) else {
+            return;
+        };
+
+        if self.spectrum.bars.len() != self.fit {
+            self.spectrum = Spectrum::new(self.fit, self.config.smoothing);
+        }
+        self.spectrum
+            .update(&fifo.latest(usize::MAX), fifo.format.rate);
+    }
+
+    pub fn toggle(&mut self) {
+        self.shown = !self.shown;
+        if let (true, None, Some(path)) = (self.shown, &self.fifo, &self.config.fifo) {
+            self.fifo = Some(Fifo::open(path.clone(), self.config.format));
+        }
+    }
+}
+
+pub fn draw(
+    visualizer: &mut Visualizer,
+    playing: bool,
+    theme: &Theme,
+    frame: &mut Frame,
+    area: Rect,
+) {
+    let Some(fifo) = &visualizer.fifo else {
+        let [area] = Layout::vertical([Constraint::Length(1)])
+            .flex(Flex::Center)
+            .areas(area);
+        let hint = Line::styled("Set visualizer.fifo to MPD's fifo output", theme.muted);
+        frame.render_widget(hint.centered(), area);
+        return;
+    };
+
+    // what was read before a pause would otherwise hang there frozen
+    let width = area.width as usize * 2;
+    let samples = match playing {
+        true => fifo.latest(width.max(1024)),
+        false => Vec::new(),
+    };
+
+    let [top, meter] = Layout::vertical([Constraint::Fill(1), Constraint::Length(1)]).areas(area);
+    let gauge = LineGauge::default()
+        .ratio(level(&samples))
+        .label("level")
+        .line_set(symbols::line::THICK)
+        .filled_style(theme.level)
+        .unfilled_style(theme.gauge);
+    frame.render_widget(gauge, meter);
+
+    if visualizer.mode == Mode::Spectrum {
+        visualizer.fit = visualizer.config.bars.clamp(1, top.width.max(1).into());
+        if playing {
+            bars(
+                &visualizer.spectrum.bars,
+                &visualizer.config.gradient,
+                frame,
+                top,
+            );
+        }
+        return;
+    }
+
+    let shown = &samples[samples.len().saturating_sub(width)..];
+    let wave = theme.wave.fg.unwrap_or(Color::Reset);
+    let canvas = Canvas::default()
+        .marker(Marker::Braille)
+        .x_bounds([0.0, width.saturating_sub(1) as f64])
+        .y_bounds([-1.0, 1.0])
+        .paint(|ctx| {
+            let points = shown.iter().enumerate().map(|(x, y)| (x as f64, *y as f64));
+            let mut points = points.peekable();
+            while let (Some((x1, y1)), Some(&(x2, y2))) = (points.next(), points.peek()) {
+                ctx.draw(&canvas::Line::new(x1, y1, x2, y2, wave));
+            }
+        });
+    frame.render_widget(canvas, top);
+}
+
+// bars spread over the width with a gap where there is room, coloured by
+// height and topped with partial blocks
+fn bars(bars: &[f32], gradient: &[Rgb], frame: &mut Frame, area: Rect) {
+    if bars.is_empty() || area.is_empty() {
+        return;
+    }
+
+    let slot = area.width as usize / bars.len();
+    let width = if slot > 2 { slot - 1 } else { slot.max(1) };
+    let left = area.x + (area.width - (slot * bars.len()) as u16) / 2;
+    let buffer = frame.buffer_mut();
+    for (i, bar) in bars.iter().enumerate() {
+        let eighths = (bar * area.height as f32 * 8.0).round() as usize;
+        for row in 0..area.height {
+            let filled = eighths.saturating_sub(row as usize * 8).min(8);
+            if filled == 0 {
+                break;
+            }
+
+            let color = color(gradient, (row as f32 + 0.5) / area.height as f32);
+            for dx in 0..width {
+                let x = left + (i * slot + dx) as u16;
+                let y = area.bottom() - 1 - row;
+                buffer[(x, y)].set_symbol(EIGHTHS[filled - 1]).set_fg(color);
+            }
+        }
+    }
+}
+
+// `at` from 0 to 1 along stops spaced evenly
+fn color(gradient: &[Rgb], at: f32) -> Color {
+    let rgb = |Rgb(r, g, b): Rgb| Color::Rgb(r, g, b);
+    match gradient {
+        [] => Color::Reset,
+        [only] => rgb(*only),
+        _ => {
+            let at = at.clamp(0.0, 1.0) * (gradient.len() - 1) as f32;
+            let i = (at as usize).min(gradient.len() - 2);
+            let (Rgb(r1, g1, b1), Rgb(r2, g2, b2)) = (gradient[i], gradient[i + 1]);
+            let mix = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * (at - i as f32)) as u8;
+            Color::Rgb(mix(r1, r2), mix(g1, g2), mix(b1, b2))
+        }
+    }
+}
+
+// loudness on a decibel scale from the floor up to full scale, as 0..1
+fn level(samples: &[f32]) -> f64 {
+    if samples.is_empty() {
+        return 0.0;
+    }
+
+    let power = samples.iter().map(|s| s * s).sum::<f32>() / samples.len() as f32;
+    let db = 10.0 * power.max(f32::MIN_POSITIVE).log10();
+    ((db + FLOOR) / FLOOR).clamp(0.0, 1.0).into()
+}
@@ -0,0 +1,21 @@
+use ratatui::{layout::Rect, symbols, text::Line, widgets::LineGauge, Frame};
+
+use crate::theme::Theme;
+
+pub const WIDTH: u16 = 16;
+
+pub fn draw(volume: Option<u8>, theme: &Theme, frame: &mut Frame, area: Rect) {
+    // volume -1 means there is no mixer to control
+    let Some(volume) = volume else {
+        frame.render_widget(Line::styled("vol --", theme.muted).right_aligned(), area);
+        return;
+    };
+
+    let gauge = LineGauge::default()
+        .ratio(f64::from(volume.min(100)) / 100.0)
+        .label(format!("vol {volume:>3}%"))
+        .line_set(symbols::line::THICK)
+        .filled_style(theme.volume)
+        .unfilled_style(theme.gauge);
+    frame.render_widget(gauge, area);
+}
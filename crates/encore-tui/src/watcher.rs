@@ -0,0 +1,49 @@
+use std::path::PathBuf;
+
+use encore_core::config::Config;
+#[cfg(feature = "watch")]
+use encore_core::watch::{self, DirWatcher};
+use eyre::Result;
+
+// what changed on disk of what is shown or was read at start
+#[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(not(feature = "watch"), allow(dead_code))]
+pub enum Change {
+    Lyrics,
+    Art(PathBuf),
+    Config(PathBuf),
+}
+
+#[derive(Default)]
+pub struct Watcher {
+    #[cfg(feature = "watch")]
+    dirs: Option<DirWatcher>,
+}
+
+impl Watcher {
+    // the lyrics and music directories, and the config file
+    #[cfg(feature = "watch")]
+    pub fn start(config: &Config) -> Result<Self> {
+        let path = config.path.clone().or_else(Config::path);
+        let lyrics = config.lyrics_dir.as_deref();
+        let dirs = DirWatcher::new(lyrics, config.music_dir.as_deref(), path.as_deref())?;
+        Ok(Self { dirs: Some(dirs) })
+    }
+
+    #[cfg(not(feature = "watch"))]
+    pub fn start(_: &Config) -> Result<Self> {
+        Ok(Self::default())
+    }
+
+    pub async fn recv(&mut self) -> Option<Change> {
+        #[cfg(feature = "watch")]
+        if let Some(dirs) = &mut self.dirs {
+            return dirs.recv().await.map(|change| match change {
+                watch::Change::Lyrics(_) => Change::Lyrics,
+                watch::Change::Art(path) => Change::Art(path),
+                watch::Change::Config(path) => Change::Config(path),
+            });
+        }
+        std::future::pending().await
+    }
+}
@@ -1,14 +0,0 @@
-use eyre::Result;
-use app::App;
-
-mod app;
-mod mpd;
-
-#[tokio::main(flavor = "multi_thread", worker_threads = 4)]
-async fn main() -> Result<()> {
-    color_eyre::install()?;
-    let terminal = ratatui::init();
-    let app_result = App::default().run(terminal).await;
-    ratatui::restore();
-    app_result
-}